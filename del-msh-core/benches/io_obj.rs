@@ -0,0 +1,63 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use del_msh_core::io_obj::{ParallelLoadOptions, WavefrontObj};
+
+/// text of a grid mesh with the texture coordinates and normals
+fn grid_obj(num_div: usize) -> String {
+    use std::fmt::Write;
+    let mut txt = String::new();
+    for iy in 0..=num_div {
+        for ix in 0..=num_div {
+            let (x, y) = (ix as f32 / num_div as f32, iy as f32 / num_div as f32);
+            writeln!(txt, "v {} {} {}", x, y, (x * 10.).sin() * 0.1).unwrap();
+            writeln!(txt, "vt {} {}", x, y).unwrap();
+            writeln!(txt, "vn 0 0 1").unwrap();
+        }
+    }
+    for iy in 0..num_div {
+        for ix in 0..num_div {
+            let i0 = iy * (num_div + 1) + ix + 1;
+            let (i1, i2, i3) = (i0 + 1, i0 + num_div + 2, i0 + num_div + 1);
+            writeln!(txt, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", i0, i1, i2).unwrap();
+            writeln!(txt, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", i0, i2, i3).unwrap();
+        }
+    }
+    txt
+}
+
+fn io_obj(c: &mut Criterion) {
+    let txt = grid_obj(300);
+    let mut group = c.benchmark_group("io_obj");
+    group.sample_size(10);
+    group.bench_function("load_from_reader", |b| {
+        b.iter(|| {
+            let mut obj = WavefrontObj::<u32, f32>::new();
+            obj.load_from_reader(std::io::Cursor::new(&txt)).unwrap();
+            obj
+        })
+    });
+    group.bench_function("load_from_bytes_parallel", |b| {
+        b.iter(|| {
+            let mut obj = WavefrontObj::<u32, f32>::new();
+            obj.load_from_bytes_parallel(txt.as_bytes(), &ParallelLoadOptions::default())
+                .unwrap();
+            obj
+        })
+    });
+    group.bench_function("load_from_bytes_parallel_skip_uv_nrm", |b| {
+        let options = ParallelLoadOptions {
+            skip_uv: true,
+            skip_nrm: true,
+            ..Default::default()
+        };
+        b.iter(|| {
+            let mut obj = WavefrontObj::<u32, f32>::new();
+            obj.load_from_bytes_parallel(txt.as_bytes(), &options)
+                .unwrap();
+            obj
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, io_obj);
+criterion_main!(benches);
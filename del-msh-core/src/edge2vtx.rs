@@ -262,6 +262,42 @@ pub fn silhouette_for_triangle_mesh(
     edge2vtx_contour
 }
 
+/// edges adjacent to only one triangle (`edge2tri` is `usize::MAX` for the missing one)
+pub fn boundary_for_triangle_mesh(edge2vtx: &[usize], edge2tri: &[usize]) -> Vec<usize> {
+    let mut edge2vtx_boundary = vec![];
+    for (node2vtx, node2tri) in edge2vtx.chunks(2).zip(edge2tri.chunks(2)) {
+        if node2tri[0] == usize::MAX || node2tri[1] == usize::MAX {
+            edge2vtx_boundary.extend_from_slice(node2vtx);
+        }
+    }
+    edge2vtx_boundary
+}
+
+/// edges where the angle between the normals of the two adjacent triangles is larger than `min_angle` (radian)
+pub fn crease_for_triangle_mesh(
+    tri2vtx: &[usize],
+    vtx2xyz: &[f32],
+    edge2vtx: &[usize],
+    edge2tri: &[usize],
+    min_angle: f32,
+) -> Vec<usize> {
+    use del_geo_core::vec3;
+    let mut edge2vtx_crease = vec![];
+    for (node2vtx, node2tri) in edge2vtx.chunks(2).zip(edge2tri.chunks(2)) {
+        let (i0_tri, i1_tri) = (node2tri[0], node2tri[1]);
+        if i0_tri == usize::MAX || i1_tri == usize::MAX {
+            continue;
+        }
+        let nrm0 = crate::trimesh3::to_tri3(tri2vtx, vtx2xyz, i0_tri).unit_normal();
+        let nrm1 = crate::trimesh3::to_tri3(tri2vtx, vtx2xyz, i1_tri).unit_normal();
+        let angle = vec3::dot(&nrm0, &nrm1).clamp(-1., 1.).acos();
+        if angle > min_angle {
+            edge2vtx_crease.extend_from_slice(node2vtx);
+        }
+    }
+    edge2vtx_crease
+}
+
 #[test]
 pub fn test_contour() {
     let (tri2vtx, vtx2xyz)
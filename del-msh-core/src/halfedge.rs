@@ -0,0 +1,384 @@
+//! half-edge data structure of triangle and polygon meshes
+
+/// half-edge structure of an oriented manifold polygon mesh possibly with boundaries.
+/// The half-edges of the element `i_elem` are `elem2idx[i_elem]..elem2idx[i_elem+1]` in the order
+/// of its corners, so that `he2vtx` is the `idx2vtx` of the polygon mesh
+#[derive(Clone, Debug)]
+pub struct HalfEdge {
+    pub elem2idx: Vec<usize>,
+    /// vertex where the half-edge starts
+    pub he2vtx: Vec<usize>,
+    pub he2elem: Vec<usize>,
+    /// half-edge in the opposite direction. `usize::MAX` on the boundary
+    pub he2opp: Vec<usize>,
+    /// half-edge starting from the vertex. It is the boundary half-edge for the vertex on the
+    /// boundary, and `usize::MAX` for the vertex not used by any element
+    pub vtx2he: Vec<usize>,
+}
+
+impl HalfEdge {
+    pub fn from_tri2vtx(tri2vtx: &[usize], num_vtx: usize) -> anyhow::Result<Self> {
+        let tri2idx: Vec<usize> = (0..=tri2vtx.len() / 3).map(|i_tri| i_tri * 3).collect();
+        Self::from_polygon_mesh(&tri2idx, tri2vtx, num_vtx)
+    }
+
+    /// build the half-edge structure. Returns the error describing the first degenerate element,
+    /// non-manifold edge, inconsistently oriented pair of elements or non-manifold vertex
+    pub fn from_polygon_mesh(
+        elem2idx: &[usize],
+        idx2vtx: &[usize],
+        num_vtx: usize,
+    ) -> anyhow::Result<Self> {
+        let num_he = idx2vtx.len();
+        anyhow::ensure!(
+            elem2idx.first() == Some(&0) && elem2idx.last() == Some(&num_he),
+            "elem2idx should start with 0 and end with the number of indices {}",
+            num_he
+        );
+        if let Some(i_elem) = (0..elem2idx.len() - 1).find(|&i| elem2idx[i] > elem2idx[i + 1]) {
+            anyhow::bail!("elem2idx decreases at the element {}", i_elem);
+        }
+        let num_elem = elem2idx.len() - 1;
+        let mut he2elem = vec![0; num_he];
+        for i_elem in 0..num_elem {
+            let (idx0, idx1) = (elem2idx[i_elem], elem2idx[i_elem + 1]);
+            let elem2vtx = &idx2vtx[idx0..idx1];
+            anyhow::ensure!(
+                elem2vtx.len() >= 3,
+                "element {} has only {} vertices",
+                i_elem,
+                elem2vtx.len()
+            );
+            for (i_node, &i_vtx) in elem2vtx.iter().enumerate() {
+                anyhow::ensure!(
+                    i_vtx < num_vtx,
+                    "element {} has the vertex {} out of range",
+                    i_elem,
+                    i_vtx
+                );
+                anyhow::ensure!(
+                    !elem2vtx[..i_node].contains(&i_vtx),
+                    "element {} {:?} has the vertex {} twice",
+                    i_elem,
+                    elem2vtx,
+                    i_vtx
+                );
+            }
+            he2elem[idx0..idx1].fill(i_elem);
+        }
+        let mut he = HalfEdge {
+            elem2idx: elem2idx.to_vec(),
+            he2vtx: idx2vtx.to_vec(),
+            he2elem,
+            he2opp: vec![usize::MAX; num_he],
+            vtx2he: vec![usize::MAX; num_vtx],
+        };
+        // half-edges starting from each vertex
+        let (vtx2jdx, jdx2he) = {
+            let mut vtx2jdx = vec![0; num_vtx + 1];
+            for &i_vtx in idx2vtx {
+                vtx2jdx[i_vtx + 1] += 1;
+            }
+            for i_vtx in 0..num_vtx {
+                vtx2jdx[i_vtx + 1] += vtx2jdx[i_vtx];
+            }
+            let mut jdx2he = vec![0; num_he];
+            let mut vtx2cnt = vtx2jdx.clone();
+            for (i_he, &i_vtx) in idx2vtx.iter().enumerate() {
+                jdx2he[vtx2cnt[i_vtx]] = i_he;
+                vtx2cnt[i_vtx] += 1;
+            }
+            (vtx2jdx, jdx2he)
+        };
+        let vtx2out = |i_vtx: usize| &jdx2he[vtx2jdx[i_vtx]..vtx2jdx[i_vtx + 1]];
+        for i_he in 0..num_he {
+            let (i0, i1) = (he.origin(i_he), he.target(i_he));
+            let same: Vec<usize> = vtx2out(i0)
+                .iter()
+                .copied()
+                .filter(|&j_he| he.target(j_he) == i1)
+                .collect();
+            let opp: Vec<usize> = vtx2out(i1)
+                .iter()
+                .copied()
+                .filter(|&j_he| he.target(j_he) == i0)
+                .collect();
+            let elems = || -> Vec<usize> {
+                same.iter()
+                    .chain(opp.iter())
+                    .map(|&j_he| he.he2elem[j_he])
+                    .collect()
+            };
+            anyhow::ensure!(
+                same.len() + opp.len() <= 2,
+                "non-manifold edge ({}, {}) shared by the elements {:?}",
+                i0,
+                i1,
+                elems()
+            );
+            anyhow::ensure!(
+                same.len() == 1,
+                "elements {:?} have inconsistent orientations at the edge ({}, {})",
+                elems(),
+                i0,
+                i1
+            );
+            if let Some(&j_he) = opp.first() {
+                he.he2opp[i_he] = j_he;
+            }
+        }
+        // starting half-edge of the vertices
+        for i_vtx in 0..num_vtx {
+            let out = vtx2out(i_vtx);
+            let Some(&i_he0) = out.first() else {
+                continue;
+            };
+            let boundary: Vec<usize> = out
+                .iter()
+                .copied()
+                .filter(|&i_he| he.he2opp[i_he] == usize::MAX)
+                .collect();
+            anyhow::ensure!(
+                boundary.len() <= 1,
+                "non-manifold vertex {} where {} fans of elements meet",
+                i_vtx,
+                boundary.len()
+            );
+            he.vtx2he[i_vtx] = boundary.first().copied().unwrap_or(i_he0);
+            let num_around = he.halfedges_around_vertex(i_vtx).count();
+            anyhow::ensure!(
+                num_around == out.len(),
+                "non-manifold vertex {} where the elements {:?} do not form a single fan",
+                i_vtx,
+                out.iter().map(|&i_he| he.he2elem[i_he]).collect::<Vec<_>>()
+            );
+        }
+        Ok(he)
+    }
+
+    pub fn num_vtx(&self) -> usize {
+        self.vtx2he.len()
+    }
+
+    pub fn num_elem(&self) -> usize {
+        self.elem2idx.len() - 1
+    }
+
+    pub fn num_halfedge(&self) -> usize {
+        self.he2vtx.len()
+    }
+
+    /// next half-edge in the same element
+    pub fn next(&self, i_he: usize) -> usize {
+        let i_elem = self.he2elem[i_he];
+        if i_he + 1 == self.elem2idx[i_elem + 1] {
+            self.elem2idx[i_elem]
+        } else {
+            i_he + 1
+        }
+    }
+
+    /// previous half-edge in the same element
+    pub fn prev(&self, i_he: usize) -> usize {
+        let i_elem = self.he2elem[i_he];
+        if i_he == self.elem2idx[i_elem] {
+            self.elem2idx[i_elem + 1] - 1
+        } else {
+            i_he - 1
+        }
+    }
+
+    /// half-edge in the opposite direction. `None` on the boundary
+    pub fn opposite(&self, i_he: usize) -> Option<usize> {
+        let j_he = self.he2opp[i_he];
+        (j_he != usize::MAX).then_some(j_he)
+    }
+
+    pub fn origin(&self, i_he: usize) -> usize {
+        self.he2vtx[i_he]
+    }
+
+    pub fn target(&self, i_he: usize) -> usize {
+        self.he2vtx[self.next(i_he)]
+    }
+
+    pub fn is_boundary_halfedge(&self, i_he: usize) -> bool {
+        self.he2opp[i_he] == usize::MAX
+    }
+
+    pub fn is_boundary_vertex(&self, i_vtx: usize) -> bool {
+        let i_he = self.vtx2he[i_vtx];
+        i_he != usize::MAX && self.is_boundary_halfedge(i_he)
+    }
+
+    /// half-edges of the element in the order of its corners
+    pub fn halfedges_of_element(&self, i_elem: usize) -> std::ops::Range<usize> {
+        self.elem2idx[i_elem]..self.elem2idx[i_elem + 1]
+    }
+
+    pub fn vertices_of_element(&self, i_elem: usize) -> &[usize] {
+        &self.he2vtx[self.halfedges_of_element(i_elem)]
+    }
+
+    /// elements sharing an edge with the element. `usize::MAX` for the boundary edges
+    pub fn elements_around_element(&self, i_elem: usize) -> impl Iterator<Item = usize> + '_ {
+        self.halfedges_of_element(i_elem).map(|i_he| {
+            self.opposite(i_he)
+                .map_or(usize::MAX, |j_he| self.he2elem[j_he])
+        })
+    }
+
+    /// half-edges starting from the vertex in the clockwise order (when the elements are
+    /// counter-clockwise), beginning with the boundary half-edge for the vertex on the boundary
+    pub fn halfedges_around_vertex(&self, i_vtx: usize) -> impl Iterator<Item = usize> + '_ {
+        let i_he0 = self.vtx2he[i_vtx];
+        let mut i_he = i_he0;
+        std::iter::from_fn(move || {
+            if i_he == usize::MAX {
+                return None;
+            }
+            let res = i_he;
+            i_he = self.he2opp[self.prev(i_he)];
+            if i_he == i_he0 {
+                i_he = usize::MAX;
+            }
+            Some(res)
+        })
+    }
+
+    /// vertices connected to the vertex by the edges. The vertex on the boundary has one more
+    /// neighbor than the half-edges starting from it
+    pub fn vertices_around_vertex(&self, i_vtx: usize) -> impl Iterator<Item = usize> + '_ {
+        let last = match self.is_boundary_vertex(i_vtx) {
+            true => {
+                let i_he_last = self.halfedges_around_vertex(i_vtx).last().unwrap();
+                Some(self.origin(self.prev(i_he_last)))
+            }
+            false => None,
+        };
+        self.halfedges_around_vertex(i_vtx)
+            .map(|i_he| self.target(i_he))
+            .chain(last)
+    }
+
+    pub fn elements_around_vertex(&self, i_vtx: usize) -> impl Iterator<Item = usize> + '_ {
+        self.halfedges_around_vertex(i_vtx)
+            .map(|i_he| self.he2elem[i_he])
+    }
+
+    /// boundary half-edges following the given boundary half-edge along the boundary loop,
+    /// starting with itself
+    pub fn boundary_walk(&self, i_he0: usize) -> impl Iterator<Item = usize> + '_ {
+        assert!(self.is_boundary_halfedge(i_he0));
+        let mut i_he = i_he0;
+        std::iter::from_fn(move || {
+            if i_he == usize::MAX {
+                return None;
+            }
+            let res = i_he;
+            // the boundary half-edge starting from the target vertex
+            i_he = self.vtx2he[self.target(i_he)];
+            if i_he == i_he0 {
+                i_he = usize::MAX;
+            }
+            Some(res)
+        })
+    }
+
+    /// half-edges of the boundary loops
+    /// # Returns
+    /// (loop2idx, idx2he)
+    pub fn boundary_loops(&self) -> (Vec<usize>, Vec<usize>) {
+        let mut loop2idx = vec![0];
+        let mut idx2he = vec![];
+        let mut he2flag = vec![false; self.num_halfedge()];
+        for i_he0 in 0..self.num_halfedge() {
+            if !self.is_boundary_halfedge(i_he0) || he2flag[i_he0] {
+                continue;
+            }
+            for i_he in self.boundary_walk(i_he0) {
+                he2flag[i_he] = true;
+                idx2he.push(i_he);
+            }
+            loop2idx.push(idx2he.len());
+        }
+        (loop2idx, idx2he)
+    }
+
+    /// # Returns
+    /// (elem2idx, idx2vtx)
+    pub fn to_polygon_mesh(&self) -> (Vec<usize>, Vec<usize>) {
+        (self.elem2idx.clone(), self.he2vtx.clone())
+    }
+
+    pub fn to_tri2vtx(&self) -> anyhow::Result<Vec<usize>> {
+        let i_elem =
+            (0..self.num_elem()).find(|&i_elem| self.vertices_of_element(i_elem).len() != 3);
+        if let Some(i_elem) = i_elem {
+            return Err(anyhow::anyhow!("element {} is not a triangle", i_elem));
+        }
+        Ok(self.he2vtx.clone())
+    }
+}
+
+#[test]
+fn test_halfedge() -> anyhow::Result<()> {
+    // quad grid with a boundary
+    let (quad2vtx, vtx2xy) = crate::quadmesh::from_grid::<f32>(4, 3);
+    let num_vtx = vtx2xy.len() / 2;
+    let quad2idx: Vec<usize> = (0..=quad2vtx.len() / 4).map(|i| i * 4).collect();
+    let he = HalfEdge::from_polygon_mesh(&quad2idx, &quad2vtx, num_vtx)?;
+    assert_eq!(he.to_polygon_mesh(), (quad2idx.clone(), quad2vtx.clone()));
+    assert!(he.to_tri2vtx().is_err());
+    for i_he in 0..he.num_halfedge() {
+        assert_eq!(he.prev(he.next(i_he)), i_he);
+        assert_eq!(he.origin(he.next(i_he)), he.target(i_he));
+        if let Some(j_he) = he.opposite(i_he) {
+            assert_eq!(he.opposite(j_he), Some(i_he));
+            assert_eq!(he.origin(j_he), he.target(i_he));
+        }
+    }
+    let (loop2idx, idx2he) = he.boundary_loops();
+    assert_eq!(loop2idx, vec![0, 14]);
+    for (i_he, j_he) in idx2he.iter().zip(idx2he.iter().cycle().skip(1)) {
+        assert_eq!(he.target(*i_he), he.origin(*j_he));
+    }
+    for i_vtx in 0..num_vtx {
+        let num_elem = he.elements_around_vertex(i_vtx).count();
+        let num_neighbor = he.vertices_around_vertex(i_vtx).count();
+        match he.is_boundary_vertex(i_vtx) {
+            true => assert_eq!(num_neighbor, num_elem + 1),
+            false => assert_eq!((num_elem, num_neighbor), (4, 4)),
+        }
+    }
+    // closed triangle mesh
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::torus_zup::<usize, f32>(1.0, 0.3, 16, 8);
+    let he = HalfEdge::from_tri2vtx(&tri2vtx, vtx2xyz.len() / 3)?;
+    assert_eq!(he.to_tri2vtx()?, tri2vtx);
+    assert!(he.boundary_loops().1.is_empty());
+    assert!(he.he2opp.iter().all(|&j_he| j_he != usize::MAX));
+    let (vtx2idx, idx2vtx) = crate::vtx2vtx::from_uniform_mesh(&tri2vtx, 3, he.num_vtx(), false);
+    for i_vtx in 0..he.num_vtx() {
+        let mut vtxs0: Vec<usize> = he.vertices_around_vertex(i_vtx).collect();
+        let mut vtxs1 = idx2vtx[vtx2idx[i_vtx]..vtx2idx[i_vtx + 1]].to_vec();
+        vtxs0.sort();
+        vtxs1.sort();
+        assert_eq!(vtxs0, vtxs1);
+    }
+    // non-manifold inputs
+    let err = HalfEdge::from_tri2vtx(&[0, 1, 2, 1, 0, 3, 0, 1, 4], 5).unwrap_err();
+    assert!(format!("{}", err).contains("non-manifold edge (0, 1)"));
+    let err = HalfEdge::from_tri2vtx(&[0, 1, 2, 0, 1, 3], 4).unwrap_err();
+    assert!(format!("{}", err).contains("inconsistent orientations"));
+    let err = HalfEdge::from_tri2vtx(&[0, 1, 2, 0, 3, 4], 5).unwrap_err();
+    assert!(format!("{}", err).contains("non-manifold vertex 0"));
+    assert!(HalfEdge::from_tri2vtx(&[0, 1, 1], 2).is_err());
+    // broken elem2idx
+    let idx2vtx = [0, 1, 2, 0, 2, 3];
+    assert!(HalfEdge::from_polygon_mesh(&[], &idx2vtx, 4).is_err());
+    assert!(HalfEdge::from_polygon_mesh(&[0, 3, 7], &idx2vtx, 4).is_err());
+    assert!(HalfEdge::from_polygon_mesh(&[0, 3], &idx2vtx, 4).is_err());
+    assert!(HalfEdge::from_polygon_mesh(&[0, 4, 3, 6], &idx2vtx, 4).is_err());
+    assert!(HalfEdge::from_polygon_mesh(&[0, 3, 6], &idx2vtx, 4).is_ok());
+    Ok(())
+}
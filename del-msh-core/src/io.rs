@@ -0,0 +1,814 @@
+//! format-agnostic loading and saving of surface meshes. The format is chosen from the magic
+//! bytes and the extension of the file. Other crates can add formats with [`register`]
+
+use anyhow::Context;
+use std::path::Path;
+
+/// surface mesh in a format-agnostic container. Optional attributes are empty if absent
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub vtx2xyz: Vec<f32>,
+    /// triangles. Polygons are split into triangle fans
+    pub tri2vtx: Vec<usize>,
+    /// polygon faces before the triangulation. Empty if the mesh is given as triangles
+    pub elem2idx: Vec<usize>,
+    pub idx2vtx: Vec<usize>,
+    pub vtx2nrm: Vec<f32>,
+    /// texture coordinates with the origin at the bottom-left
+    pub vtx2uv: Vec<f32>,
+    /// colors in [0,1]
+    pub vtx2rgb: Vec<f32>,
+    /// group of each triangle. Empty if the mesh is not grouped
+    pub tri2group: Vec<usize>,
+    /// names of the groups. A group without a name in the file has an empty name
+    pub group2name: Vec<String>,
+}
+
+impl Mesh {
+    pub fn from_tri_mesh(tri2vtx: Vec<usize>, vtx2xyz: Vec<f32>) -> Self {
+        Mesh {
+            vtx2xyz,
+            tri2vtx,
+            ..Default::default()
+        }
+    }
+
+    /// the triangles are the fans of the polygons
+    pub fn from_polygon_mesh(elem2idx: Vec<usize>, idx2vtx: Vec<usize>, vtx2xyz: Vec<f32>) -> Self {
+        let (tri2vtx, _) = triangle_fans(&elem2idx, &idx2vtx);
+        Mesh {
+            vtx2xyz,
+            tri2vtx,
+            elem2idx,
+            idx2vtx,
+            ..Default::default()
+        }
+    }
+
+    pub fn num_vtx(&self) -> usize {
+        self.vtx2xyz.len() / 3
+    }
+
+    pub fn num_tri(&self) -> usize {
+        self.tri2vtx.len() / 3
+    }
+
+    /// polygon faces, or the triangles if the mesh does not have polygons
+    /// * Returns (elem2idx, idx2vtx)
+    pub fn polygons(&self) -> (Vec<usize>, Vec<usize>) {
+        if !self.elem2idx.is_empty() {
+            return (self.elem2idx.clone(), self.idx2vtx.clone());
+        }
+        let elem2idx = (0..self.num_tri() + 1).map(|i_tri| i_tri * 3).collect();
+        (elem2idx, self.tri2vtx.clone())
+    }
+
+    /// group of each polygon in [`Mesh::polygons`]. Empty if the mesh is not grouped
+    pub fn elem2group(&self) -> Vec<usize> {
+        if self.elem2idx.is_empty() || self.tri2group.is_empty() {
+            return self.tri2group.clone();
+        }
+        let (_, tri2elem) = triangle_fans(&self.elem2idx, &self.idx2vtx);
+        let mut elem2group = vec![0; self.elem2idx.len() - 1];
+        for (&i_elem, &i_group) in tri2elem.iter().zip(self.tri2group.iter()) {
+            elem2group[i_elem] = i_group;
+        }
+        elem2group
+    }
+
+    /// set the groups given for each polygon in [`Mesh::polygons`]
+    fn set_elem2group(&mut self, elem2group: &[usize]) {
+        if self.elem2idx.is_empty() {
+            self.tri2group = elem2group.to_vec();
+            return;
+        }
+        let (_, tri2elem) = triangle_fans(&self.elem2idx, &self.idx2vtx);
+        self.tri2group = tri2elem.iter().map(|&i_elem| elem2group[i_elem]).collect();
+    }
+}
+
+/// split the polygons into triangle fans
+/// * Returns (tri2vtx, tri2elem)
+fn triangle_fans(elem2idx: &[usize], idx2vtx: &[usize]) -> (Vec<usize>, Vec<usize>) {
+    let mut tri2vtx = Vec::with_capacity(idx2vtx.len());
+    let mut tri2elem = vec![];
+    for (i_elem, idx) in elem2idx.windows(2).enumerate() {
+        let (idx0, idx1) = (idx[0], idx[1]);
+        for idx in idx0 + 1..idx1.max(idx0 + 2) - 1 {
+            tri2vtx.extend_from_slice(&[idx2vtx[idx0], idx2vtx[idx], idx2vtx[idx + 1]]);
+            tri2elem.push(i_elem);
+        }
+    }
+    (tri2vtx, tri2elem)
+}
+
+/// group index of each element from the distinct ids (e.g., physical tags) in ascending order
+/// * Returns (elem2group, group2id)
+fn groups_from_ids(elem2id: &[usize]) -> (Vec<usize>, Vec<usize>) {
+    let mut group2id = elem2id.to_vec();
+    group2id.sort();
+    group2id.dedup();
+    let elem2group = elem2id
+        .iter()
+        .map(|id| group2id.binary_search(id).unwrap())
+        .collect();
+    (elem2group, group2id)
+}
+
+// ---------------------------
+// format registry
+
+pub type LoadFn = fn(&Path) -> anyhow::Result<Mesh>;
+pub type SaveFn = fn(&Path, &Mesh) -> anyhow::Result<()>;
+
+/// entry of the format registry
+#[derive(Clone, Copy)]
+pub struct Format {
+    pub name: &'static str,
+    /// lowercase extensions without the dot
+    pub extensions: &'static [&'static str],
+    /// test the first bytes of the file. `None` if the format does not have a signature
+    pub magic: Option<fn(&[u8]) -> bool>,
+    pub load: Option<LoadFn>,
+    pub save: Option<SaveFn>,
+}
+
+impl std::fmt::Debug for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Format")
+            .field("name", &self.name)
+            .field("extensions", &self.extensions)
+            .finish()
+    }
+}
+
+/// number of the bytes passed to [`Format::magic`]
+pub const NUM_MAGIC_BYTES: usize = 256;
+
+static REGISTERED_FORMATS: std::sync::RwLock<Vec<Format>> = std::sync::RwLock::new(Vec::new());
+
+/// add a format. Formats registered later take precedence over the earlier ones and the built-in ones
+pub fn register(format: Format) {
+    REGISTERED_FORMATS.write().unwrap().push(format);
+}
+
+/// registered and built-in formats in the order of precedence
+pub fn formats() -> Vec<Format> {
+    let registered = REGISTERED_FORMATS.read().unwrap();
+    registered
+        .iter()
+        .rev()
+        .copied()
+        .chain(builtin_formats())
+        .collect()
+}
+
+/// lowercase extension of the path
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// format for loading the file. The magic bytes are tested first and then the extension
+pub fn format_for_load<P: AsRef<Path>>(path: P) -> anyhow::Result<Format> {
+    use std::io::Read;
+    let path = path.as_ref();
+    let mut head = Vec::with_capacity(NUM_MAGIC_BYTES);
+    std::fs::File::open(path)
+        .with_context(|| format!("file not found: {}", path.display()))?
+        .take(NUM_MAGIC_BYTES as u64)
+        .read_to_end(&mut head)?;
+    let formats: Vec<Format> = formats().into_iter().filter(|f| f.load.is_some()).collect();
+    if let Some(format) = formats
+        .iter()
+        .find(|f| f.magic.is_some_and(|magic| magic(&head)))
+    {
+        return Ok(*format);
+    }
+    let ext = extension(path);
+    formats
+        .into_iter()
+        .find(|f| {
+            ext.as_deref()
+                .is_some_and(|ext| f.extensions.contains(&ext))
+        })
+        .with_context(|| format!("unknown mesh format: {}", path.display()))
+}
+
+/// format for saving the file, chosen from the extension
+pub fn format_for_save<P: AsRef<Path>>(path: P) -> anyhow::Result<Format> {
+    let path = path.as_ref();
+    let ext = extension(path).with_context(|| format!("no extension: {}", path.display()))?;
+    formats()
+        .into_iter()
+        .find(|f| f.save.is_some() && f.extensions.contains(&ext.as_str()))
+        .with_context(|| format!("cannot save mesh in \"{}\" format", ext))
+}
+
+/// load a mesh in any of the registered formats
+pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Mesh> {
+    let path = path.as_ref();
+    let format = format_for_load(path)?;
+    (format.load.unwrap())(path)
+        .with_context(|| format!("failed to load {} as {}", path.display(), format.name))
+}
+
+/// save a mesh in the format given by the extension
+pub fn save<P: AsRef<Path>>(path: P, mesh: &Mesh) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let format = format_for_save(path)?;
+    (format.save.unwrap())(path, mesh)
+        .with_context(|| format!("failed to save {} as {}", path.display(), format.name))
+}
+
+// ---------------------------
+// built-in formats
+
+pub fn builtin_formats() -> Vec<Format> {
+    vec![
+        Format {
+            name: "Wavefront OBJ",
+            extensions: &["obj"],
+            magic: None,
+            load: Some(load_obj),
+            save: Some(save_obj),
+        },
+        Format {
+            name: "OFF",
+            extensions: &["off"],
+            magic: Some(|head| {
+                first_token(head).is_some_and(|t| t.ends_with(b"OFF") && t.len() <= 7)
+            }),
+            load: Some(load_off),
+            save: Some(save_off),
+        },
+        Format {
+            name: "PLY",
+            extensions: &["ply"],
+            magic: Some(|head| head.starts_with(b"ply")),
+            load: Some(load_ply),
+            save: Some(save_ply),
+        },
+        Format {
+            name: "STL",
+            extensions: &["stl"],
+            // binary STL may also start with `solid`, so the text of a facet needs to follow
+            magic: Some(|head| {
+                first_token(head) == Some(b"solid")
+                    && head
+                        .iter()
+                        .all(|c| c.is_ascii_graphic() || c.is_ascii_whitespace())
+                    && head
+                        .split(|c| c.is_ascii_whitespace())
+                        .any(|token| token == b"facet" || token == b"endsolid")
+            }),
+            load: Some(load_stl),
+            save: Some(save_stl),
+        },
+        Format {
+            name: "glTF",
+            extensions: &["gltf", "glb"],
+            magic: Some(|head| head.starts_with(b"glTF")),
+            load: Some(load_gltf),
+            save: Some(save_gltf),
+        },
+        Format {
+            name: "Gmsh MSH",
+            extensions: &["msh"],
+            magic: Some(|head| first_token(head) == Some(b"$MeshFormat")),
+            load: Some(load_msh),
+            save: Some(save_msh),
+        },
+        Format {
+            name: "Medit",
+            extensions: &["mesh"],
+            magic: Some(|head| first_token(head) == Some(b"MeshVersionFormatted")),
+            load: Some(load_medit),
+            save: Some(save_medit),
+        },
+        Format {
+            name: "Nastran",
+            extensions: &["nas", "bdf"],
+            magic: None,
+            load: Some(load_nas),
+            save: Some(save_nas),
+        },
+        Format {
+            name: "legacy VTK",
+            extensions: &["vtk"],
+            magic: Some(|head| head.starts_with(b"# vtk DataFile")),
+            load: Some(load_vtk),
+            save: Some(save_vtk),
+        },
+    ]
+}
+
+/// first whitespace-separated token of the text skipping the `#` comments
+fn first_token(head: &[u8]) -> Option<&[u8]> {
+    head.split(|&c| c == b'\n')
+        .map(|line| line.split(|&c| c == b'#').next().unwrap())
+        .flat_map(|line| line.split(|c| c.is_ascii_whitespace()))
+        .find(|token| !token.is_empty())
+}
+
+fn load_obj(path: &Path) -> anyhow::Result<Mesh> {
+    let mut obj = crate::io_obj::WavefrontObj::<usize, f32>::new();
+    obj.load(path)?;
+    // the vertices are split where the corners have different texture coordinates or normals
+    let num_xyz = obj.vtx2xyz.len() / 3;
+    let has_uv = !obj.vtx2uv.is_empty();
+    let has_nrm = !obj.vtx2nrm.is_empty();
+    let corner2key: Vec<[usize; 3]> = (0..obj.idx2vtx_xyz.len())
+        .map(|idx| {
+            let i_uv = if has_uv {
+                obj.idx2vtx_uv[idx]
+            } else {
+                usize::MAX
+            };
+            let i_nrm = if has_nrm {
+                obj.idx2vtx_nrm[idx]
+            } else {
+                usize::MAX
+            };
+            [obj.idx2vtx_xyz[idx], i_uv, i_nrm]
+        })
+        .collect();
+    let is_shared = corner2key
+        .iter()
+        .all(|key| key[1..].iter().all(|&i| i == usize::MAX || i == key[0]));
+    let (idx2vtx, uni2key) = if is_shared {
+        // keep the vertices as they are in the file
+        let uni2key: Vec<[usize; 3]> = (0..num_xyz)
+            .map(|i_xyz| {
+                let i_uv = if i_xyz < obj.vtx2uv.len() / 2 {
+                    i_xyz
+                } else {
+                    usize::MAX
+                };
+                let i_nrm = if i_xyz < obj.vtx2nrm.len() / 3 {
+                    i_xyz
+                } else {
+                    usize::MAX
+                };
+                [i_xyz, i_uv, i_nrm]
+            })
+            .collect();
+        (obj.idx2vtx_xyz.clone(), uni2key)
+    } else {
+        let mut key2uni = std::collections::HashMap::<[usize; 3], usize>::new();
+        let mut uni2key = vec![];
+        let mut idx2vtx = vec![];
+        for key in corner2key {
+            let i_uni = *key2uni.entry(key).or_insert_with(|| {
+                uni2key.push(key);
+                uni2key.len() - 1
+            });
+            idx2vtx.push(i_uni);
+        }
+        (idx2vtx, uni2key)
+    };
+    let attribute = |vtx2val: &[f32], num_dim: usize, i_key: usize| -> Vec<f32> {
+        uni2key
+            .iter()
+            .flat_map(|key| match key[i_key] {
+                usize::MAX => vec![0.; num_dim],
+                i => vtx2val[i * num_dim..(i + 1) * num_dim].to_vec(),
+            })
+            .collect()
+    };
+    let mut mesh =
+        Mesh::from_polygon_mesh(obj.elem2idx.clone(), idx2vtx, attribute(&obj.vtx2xyz, 3, 0));
+    if !obj.vtx2rgb.is_empty() {
+        mesh.vtx2rgb = attribute(&obj.vtx2rgb, 3, 0);
+    }
+    if has_uv {
+        mesh.vtx2uv = attribute(&obj.vtx2uv, 2, 1);
+    }
+    if has_nrm {
+        mesh.vtx2nrm = attribute(&obj.vtx2nrm, 3, 2);
+    }
+    // the group `0` is the default group of the faces before any `g`
+    if obj.group2name.len() > 1 {
+        let mut elem2group = obj.elem2group;
+        let mut group2name = obj.group2name;
+        if elem2group.iter().all(|&i_group| i_group != 0) {
+            elem2group.iter_mut().for_each(|i_group| *i_group -= 1);
+            group2name.remove(0);
+        }
+        mesh.set_elem2group(&elem2group);
+        mesh.group2name = group2name;
+    }
+    Ok(mesh)
+}
+
+fn save_obj(path: &Path, mesh: &Mesh) -> anyhow::Result<()> {
+    let mut obj = crate::io_obj::WavefrontObj::<usize, f32>::new();
+    let (elem2idx, idx2vtx) = mesh.polygons();
+    obj.vtx2xyz = mesh.vtx2xyz.clone();
+    obj.vtx2rgb = mesh.vtx2rgb.clone();
+    obj.vtx2uv = mesh.vtx2uv.clone();
+    obj.vtx2nrm = mesh.vtx2nrm.clone();
+    if !mesh.vtx2uv.is_empty() {
+        obj.idx2vtx_uv = idx2vtx.clone();
+    }
+    if !mesh.vtx2nrm.is_empty() {
+        obj.idx2vtx_nrm = idx2vtx.clone();
+    }
+    if !mesh.tri2group.is_empty() {
+        // the group `0` is reserved for the default group
+        obj.elem2group = mesh.elem2group().iter().map(|&i| i + 1).collect();
+        let num_group = mesh.tri2group.iter().max().map_or(0, |&i| i + 1);
+        obj.group2name = std::iter::once("_default".to_string())
+            .chain(
+                (0..num_group).map(|i_group| match mesh.group2name.get(i_group) {
+                    Some(name) if !name.is_empty() => name.clone(),
+                    _ => format!("group{}", i_group),
+                }),
+            )
+            .collect();
+    }
+    obj.elem2idx = elem2idx;
+    obj.idx2vtx_xyz = idx2vtx;
+    obj.save(path)
+}
+
+fn load_off(path: &Path) -> anyhow::Result<Mesh> {
+    let off = crate::io_off::load::<_, usize, f32>(path)?;
+    let mut mesh = Mesh::from_polygon_mesh(off.elem2idx, off.idx2vtx, off.vtx2xyz);
+    mesh.vtx2nrm = off.vtx2nrm;
+    mesh.vtx2uv = off.vtx2uv;
+    mesh.vtx2rgb = off
+        .vtx2rgba
+        .chunks(4)
+        .flat_map(|rgba| [rgba[0], rgba[1], rgba[2]])
+        .collect();
+    Ok(mesh)
+}
+
+fn save_off(path: &Path, mesh: &Mesh) -> anyhow::Result<()> {
+    let (elem2idx, idx2vtx) = mesh.polygons();
+    let mut off = crate::io_off::OffMesh::new(elem2idx, idx2vtx, mesh.vtx2xyz.clone());
+    off.vtx2nrm = mesh.vtx2nrm.clone();
+    off.vtx2uv = mesh.vtx2uv.clone();
+    off.vtx2rgba = mesh
+        .vtx2rgb
+        .chunks(3)
+        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 1.])
+        .collect();
+    off.save(path, crate::io_off::Format::Ascii)
+}
+
+fn load_ply(path: &Path) -> anyhow::Result<Mesh> {
+    use crate::io_ply::ScalarType;
+    let ply = crate::io_ply::load(path)?;
+    let (elem2idx, idx2vtx, vtx2xyz) = ply.polygon_mesh::<usize, f32>()?;
+    let mut mesh = Mesh::from_polygon_mesh(elem2idx, idx2vtx, vtx2xyz);
+    let vtx = ply.element("vertex").unwrap();
+    if vtx.has_property("nx") {
+        mesh.vtx2nrm = vtx.interleaved(&["nx", "ny", "nz"])?;
+    }
+    for names in [["u", "v"], ["s", "t"], ["texture_u", "texture_v"]] {
+        if vtx.has_property(names[0]) {
+            mesh.vtx2uv = vtx.interleaved(&names)?;
+            break;
+        }
+    }
+    if let Some(red) = vtx.property("red") {
+        let scale = match red.values.scalar_type() {
+            ScalarType::Float | ScalarType::Double => 1.,
+            ScalarType::UShort | ScalarType::Short => 1. / 65535.,
+            _ => 1. / 255.,
+        };
+        let vtx2rgb: Vec<f32> = vtx.interleaved(&["red", "green", "blue"])?;
+        mesh.vtx2rgb = vtx2rgb.iter().map(|&v| v * scale).collect();
+    }
+    Ok(mesh)
+}
+
+fn save_ply(path: &Path, mesh: &Mesh) -> anyhow::Result<()> {
+    use crate::io_ply::{Element, Property, ScalarType};
+    let vtx2rgb: Vec<u8> = mesh
+        .vtx2rgb
+        .iter()
+        .map(|&v| (v.clamp(0., 1.) * 255.).round() as u8)
+        .collect();
+    let mut ply = crate::io_ply::from_tri2vtx_vtx2xyz(
+        crate::io_ply::Format::BinaryLittleEndian,
+        &mesh.tri2vtx,
+        &mesh.vtx2xyz,
+        (!vtx2rgb.is_empty()).then_some(vtx2rgb.as_slice()),
+        (!mesh.vtx2nrm.is_empty()).then_some(mesh.vtx2nrm.as_slice()),
+        &[],
+    )?;
+    if !mesh.vtx2uv.is_empty() {
+        let vtx = &mut ply.elements[0];
+        for (i_dim, name) in ["u", "v"].iter().enumerate() {
+            let vals: Vec<f32> = mesh.vtx2uv.iter().skip(i_dim).step_by(2).copied().collect();
+            vtx.properties.push(Property::new_scalar(name, vals));
+        }
+    }
+    if !mesh.elem2idx.is_empty() {
+        let idx2vtx: Vec<i32> = mesh.idx2vtx.iter().map(|&i| i as i32).collect();
+        let face = Property::new_list(
+            "vertex_indices",
+            ScalarType::UChar,
+            mesh.elem2idx.clone(),
+            idx2vtx,
+        );
+        ply.elements[1] = Element::new("face", mesh.elem2idx.len() - 1, vec![face]);
+    }
+    ply.save(path)
+}
+
+fn load_stl(path: &Path) -> anyhow::Result<Mesh> {
+    let (tri2vtx, vtx2xyz) = crate::io_stl::load_tri_mesh(path)?;
+    Ok(Mesh::from_tri_mesh(tri2vtx, vtx2xyz))
+}
+
+fn save_stl(path: &Path, mesh: &Mesh) -> anyhow::Result<()> {
+    crate::io_stl::save_tri2vtx_vtx2xyz_binary(path, &mesh.tri2vtx, &mesh.vtx2xyz)
+}
+
+fn load_gltf(path: &Path) -> anyhow::Result<Mesh> {
+    let gltf = crate::io_gltf::load::<_, usize, f32>(path)?;
+    Ok(Mesh {
+        vtx2xyz: gltf.vtx2xyz,
+        tri2vtx: gltf.tri2vtx,
+        vtx2nrm: gltf.vtx2nrm,
+        vtx2uv: gltf.vtx2uv,
+        vtx2rgb: gltf.vtx2rgb,
+        tri2group: gltf.tri2group,
+        ..Default::default()
+    })
+}
+
+fn save_gltf(path: &Path, mesh: &Mesh) -> anyhow::Result<()> {
+    let mut gltf = crate::io_gltf::GltfMesh::<usize, f32>::new();
+    gltf.vtx2xyz = mesh.vtx2xyz.clone();
+    gltf.tri2vtx = mesh.tri2vtx.clone();
+    gltf.vtx2nrm = mesh.vtx2nrm.clone();
+    gltf.vtx2uv = mesh.vtx2uv.clone();
+    gltf.vtx2rgb = mesh.vtx2rgb.clone();
+    gltf.tri2group = mesh.tri2group.clone();
+    gltf.save(path)
+}
+
+fn load_msh(path: &Path) -> anyhow::Result<Mesh> {
+    use crate::io_msh::GmshElementType;
+    let msh = crate::io_msh::load::<_, usize, f32>(path)?;
+    let mut elem2idx = vec![0];
+    let mut idx2vtx = vec![];
+    let mut elem2physical = vec![];
+    for (i_elem, elem_type) in msh.elem2type.iter().enumerate() {
+        if !matches!(elem_type, GmshElementType::Triangle | GmshElementType::Quad) {
+            continue;
+        }
+        idx2vtx.extend_from_slice(&msh.idx2vtx[msh.elem2idx[i_elem]..msh.elem2idx[i_elem + 1]]);
+        elem2idx.push(idx2vtx.len());
+        elem2physical.push(msh.elem2physical[i_elem]);
+    }
+    let mut mesh = Mesh::from_polygon_mesh(elem2idx, idx2vtx, msh.vtx2xyz);
+    if elem2physical.iter().any(|&i| i != 0) {
+        let (elem2group, group2physical) = groups_from_ids(&elem2physical);
+        mesh.set_elem2group(&elem2group);
+        mesh.group2name = group2physical
+            .iter()
+            .map(|&tag| {
+                msh.physical_names
+                    .iter()
+                    .find(|(dim, t, _)| *dim == 2 && *t == tag)
+                    .map_or(String::new(), |(_, _, name)| name.clone())
+            })
+            .collect();
+    }
+    Ok(mesh)
+}
+
+fn save_msh(path: &Path, mesh: &Mesh) -> anyhow::Result<()> {
+    use crate::io_msh::{GmshElementType, GmshMesh, Version};
+    let mut msh = GmshMesh::<usize, f32>::from_uniform_mesh(
+        GmshElementType::Triangle,
+        &mesh.tri2vtx,
+        &mesh.vtx2xyz,
+        3,
+    );
+    if !mesh.tri2group.is_empty() {
+        // the physical tags start from one because zero means no physical group
+        msh.elem2physical = mesh.tri2group.iter().map(|&i_group| i_group + 1).collect();
+        msh.physical_names = mesh
+            .group2name
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| !name.is_empty())
+            .map(|(i_group, name)| (2, i_group + 1, name.clone()))
+            .collect();
+    }
+    msh.save(path, Version::V4)
+}
+
+fn load_medit(path: &Path) -> anyhow::Result<Mesh> {
+    let medit = crate::io_medit::load_mesh::<_, usize, f32>(path)?;
+    let vtx2xyz = match medit.num_dim {
+        3 => medit.vtx2xyz,
+        _ => medit
+            .vtx2xyz
+            .chunks(2)
+            .flat_map(|xy| [xy[0], xy[1], 0.])
+            .collect(),
+    };
+    let mut elem2idx: Vec<usize> = (0..medit.tri2vtx.len() / 3 + 1).map(|i| i * 3).collect();
+    let mut idx2vtx = medit.tri2vtx;
+    for quad in medit.quad2vtx.chunks(4) {
+        idx2vtx.extend_from_slice(quad);
+        elem2idx.push(idx2vtx.len());
+    }
+    let elem2ref: Vec<usize> = medit.tri2ref.into_iter().chain(medit.quad2ref).collect();
+    let mut mesh = if medit.quad2vtx.is_empty() {
+        Mesh::from_tri_mesh(idx2vtx, vtx2xyz)
+    } else {
+        Mesh::from_polygon_mesh(elem2idx, idx2vtx, vtx2xyz)
+    };
+    if elem2ref.iter().any(|&i| i != 0) {
+        let (elem2group, group2ref) = groups_from_ids(&elem2ref);
+        mesh.set_elem2group(&elem2group);
+        mesh.group2name = group2ref.iter().map(|i_ref| i_ref.to_string()).collect();
+    }
+    Ok(mesh)
+}
+
+fn save_medit(path: &Path, mesh: &Mesh) -> anyhow::Result<()> {
+    let mut medit = crate::io_medit::MeditMesh::<usize, f32>::new(3);
+    medit.vtx2xyz = mesh.vtx2xyz.clone();
+    medit.tri2vtx = mesh.tri2vtx.clone();
+    medit.tri2ref = match mesh.tri2group.is_empty() {
+        true => vec![0; mesh.num_tri()],
+        false => mesh.tri2group.iter().map(|&i_group| i_group + 1).collect(),
+    };
+    medit.save(path)
+}
+
+fn load_nas(path: &Path) -> anyhow::Result<Mesh> {
+    let (tri2vtx, vtx2xyz) = crate::io_nas::load_tri_mesh(path)?;
+    Ok(Mesh::from_tri_mesh(tri2vtx, vtx2xyz))
+}
+
+fn save_nas(path: &Path, mesh: &Mesh) -> anyhow::Result<()> {
+    crate::io_nas::save_tri_mesh(path, &mesh.tri2vtx, &mesh.vtx2xyz)
+}
+
+fn load_vtk(path: &Path) -> anyhow::Result<Mesh> {
+    use crate::io_vtk::{VtkAttribute, VtkElementType};
+    let vtk = crate::io_vtk::load_legacy::<_, usize, f32>(path)?;
+    let mut elem2idx = vec![0];
+    let mut idx2vtx = vec![];
+    for (i_elem, elem_type) in vtk.elem2type.iter().enumerate() {
+        use VtkElementType::*;
+        if !matches!(elem_type, TRIANGLE | QUAD | POLYGON) {
+            continue;
+        }
+        idx2vtx.extend_from_slice(&vtk.idx2vtx[vtk.elem2idx[i_elem]..vtk.elem2idx[i_elem + 1]]);
+        elem2idx.push(idx2vtx.len());
+    }
+    let mut mesh = Mesh::from_polygon_mesh(elem2idx, idx2vtx, vtk.vtx2xyz);
+    for data in &vtk.point_data {
+        match (data.attribute, data.num_comp) {
+            (VtkAttribute::Normals, 3) => mesh.vtx2nrm = data.values.clone(),
+            (VtkAttribute::TextureCoordinates, 2) => mesh.vtx2uv = data.values.clone(),
+            (VtkAttribute::ColorScalars, 3) => mesh.vtx2rgb = data.values.clone(),
+            _ => {}
+        }
+    }
+    Ok(mesh)
+}
+
+fn save_vtk(path: &Path, mesh: &Mesh) -> anyhow::Result<()> {
+    use crate::io_vtk::{VtkAttribute, VtkElementType};
+    use std::io::Write;
+    let file =
+        std::fs::File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+    let mut file = std::io::BufWriter::new(file);
+    crate::io_vtk::write_vtk_points(&mut file, "mesh", &mesh.vtx2xyz, 3)?;
+    let (elem2idx, idx2vtx) = mesh.polygons();
+    let elem2type: Vec<VtkElementType> = elem2idx
+        .windows(2)
+        .map(|idx| match idx[1] - idx[0] {
+            3 => VtkElementType::TRIANGLE,
+            4 => VtkElementType::QUAD,
+            _ => VtkElementType::POLYGON,
+        })
+        .collect();
+    crate::io_vtk::write_vtk_cells_mixed(&mut file, &elem2idx, &idx2vtx, &elem2type)?;
+    let attributes = [
+        (VtkAttribute::Normals, "normals", &mesh.vtx2nrm, 3),
+        (VtkAttribute::TextureCoordinates, "uv", &mesh.vtx2uv, 2),
+        (VtkAttribute::ColorScalars, "rgb", &mesh.vtx2rgb, 3),
+    ];
+    if attributes.iter().any(|(_, _, vals, _)| !vals.is_empty()) {
+        writeln!(file, "POINT_DATA {}", mesh.num_vtx())?;
+    }
+    for (attribute, name, vals, num_comp) in attributes {
+        if !vals.is_empty() {
+            crate::io_vtk::write_vtk_data_point_attribute(
+                &mut file, attribute, name, vals, num_comp,
+            )?;
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_load_save() -> anyhow::Result<()> {
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(1., 8, 8);
+    let mut mesh = Mesh::from_tri_mesh(tri2vtx, vtx2xyz);
+    mesh.tri2group = (0..mesh.num_tri()).map(|i_tri| i_tri % 2).collect();
+    mesh.group2name = vec!["even".to_string(), "odd".to_string()];
+    for ext in [
+        "obj", "off", "ply", "stl", "glb", "msh", "mesh", "nas", "vtk",
+    ] {
+        let path = format!("../target/io_load_save.{}", ext);
+        save(&path, &mesh)?;
+        let mesh1 = load(&path)?;
+        assert_eq!(mesh1.num_tri(), mesh.num_tri(), "{}", ext);
+        // the order of the triangles may change (e.g., by the primitives of glTF)
+        let tri2key = |m: &Mesh| -> Vec<(String, [i32; 9])> {
+            let mut tri2key: Vec<_> = (0..m.num_tri())
+                .map(|i_tri| {
+                    let group = match ext {
+                        "obj" | "msh" => m.group2name[m.tri2group[i_tri]].clone(),
+                        "glb" | "mesh" => m.tri2group[i_tri].to_string(),
+                        _ => String::new(),
+                    };
+                    let xyz: [i32; 9] = std::array::from_fn(|i| {
+                        let i_vtx = m.tri2vtx[i_tri * 3 + i / 3];
+                        (m.vtx2xyz[i_vtx * 3 + i % 3] * 1.0e4).round() as i32
+                    });
+                    (group, xyz)
+                })
+                .collect();
+            tri2key.sort();
+            tri2key
+        };
+        assert_eq!(tri2key(&mesh1), tri2key(&mesh), "{}", ext);
+    }
+    // magic bytes take precedence over the extension
+    std::fs::copy(
+        "../target/io_load_save.ply",
+        "../target/io_load_save_ply.dat",
+    )?;
+    assert_eq!(
+        format_for_load("../target/io_load_save_ply.dat")?.name,
+        "PLY"
+    );
+    assert!(format_for_save("../target/io_load_save.xyz123").is_err());
+    // downstream formats take precedence over the built-in ones
+    register(Format {
+        name: "custom",
+        extensions: &["xyz123"],
+        magic: None,
+        load: Some(|_| Ok(Mesh::from_tri_mesh(vec![0, 1, 2], vec![0.; 9]))),
+        save: Some(|path, mesh| {
+            std::fs::write(path, format!("{}", mesh.num_tri()))?;
+            Ok(())
+        }),
+    });
+    save("../target/io_load_save.xyz123", &mesh)?;
+    assert_eq!(load("../target/io_load_save.xyz123")?.num_tri(), 1);
+    // polygons
+    let mesh = Mesh::from_polygon_mesh(
+        vec![0, 4, 7],
+        vec![0, 1, 2, 3, 1, 4, 2],
+        vec![0., 0., 0., 1., 0., 0., 1., 1., 0., 0., 1., 0., 2., 0., 0.],
+    );
+    assert_eq!(mesh.num_tri(), 3);
+    for ext in ["obj", "off", "ply", "vtk"] {
+        let path = format!("../target/io_load_save_polygon.{}", ext);
+        save(&path, &mesh)?;
+        let mesh1 = load(&path)?;
+        assert_eq!(mesh1.polygons(), mesh.polygons(), "{}", ext);
+    }
+    // attributes of the vertices
+    let mut mesh = mesh;
+    mesh.vtx2uv = vec![0., 0., 1., 0., 1., 1., 0., 1., 2., 0.];
+    mesh.vtx2rgb = vec![
+        1., 0., 0., 0., 1., 0., 0., 0., 1., 1., 1., 1., 0.5, 0.5, 0.5,
+    ];
+    save("../target/io_load_save_attribute.vtk", &mesh)?;
+    let mesh1 = load("../target/io_load_save_attribute.vtk")?;
+    assert_eq!(mesh1.vtx2uv, mesh.vtx2uv);
+    assert_eq!(mesh1.vtx2rgb, mesh.vtx2rgb);
+    assert!(mesh1.vtx2nrm.is_empty());
+    // ASCII STL is found by the text even without the extension
+    crate::io_stl::save_tri2vtx_vtx2xyz_ascii(
+        "../target/io_load_save_stl.dat",
+        &mesh.tri2vtx,
+        &mesh.vtx2xyz,
+    )?;
+    assert_eq!(
+        format_for_load("../target/io_load_save_stl.dat")?.name,
+        "STL"
+    );
+    assert_eq!(load("../target/io_load_save_stl.dat")?.num_tri(), 3);
+    Ok(())
+}
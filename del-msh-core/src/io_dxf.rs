@@ -0,0 +1,908 @@
+//! methods for ASCII DXF files of 2D drawings (e.g., for laser cutters)
+
+use anyhow::Context;
+use std::io::Write;
+
+/// polyline on a layer. A closed polyline does not repeat the first point at the end
+#[derive(Clone, Debug, PartialEq)]
+pub struct DxfPolyline {
+    pub layer: String,
+    pub vtx2xy: Vec<f32>,
+    pub is_closed: bool,
+}
+
+/// pairs of the group code and the value
+fn read_group_pairs<R: std::io::BufRead>(reader: R) -> anyhow::Result<Vec<(i32, String)>> {
+    let mut lines = reader.lines().enumerate();
+    let mut pairs = vec![];
+    while let Some((i_line, code)) = lines.next() {
+        let code = code?;
+        let code = code.trim();
+        if i_line == 0 && code.starts_with("AutoCAD Binary DXF") {
+            return Err(anyhow::anyhow!("binary DXF is not supported"));
+        }
+        if code.is_empty() {
+            continue;
+        }
+        let code = code
+            .parse::<i32>()
+            .with_context(|| format!("line {}: invalid group code \"{}\"", i_line + 1, code))?;
+        let Some((_, value)) = lines.next() else {
+            return Err(anyhow::anyhow!("line {}: missing value", i_line + 1));
+        };
+        pairs.push((code, value?.trim().to_string()));
+    }
+    Ok(pairs)
+}
+
+/// group codes and values of an entity (without the leading `0`)
+struct Entity<'a> {
+    name: &'a str,
+    pairs: &'a [(i32, String)],
+}
+
+impl Entity<'_> {
+    fn string(&self, code: i32) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn real(&self, code: i32) -> anyhow::Result<Option<f64>> {
+        self.string(code)
+            .map(|v| v.parse::<f64>())
+            .transpose()
+            .with_context(|| format!("invalid value of group code {} in {}", code, self.name))
+    }
+
+    fn real_or(&self, code: i32, default: f64) -> anyhow::Result<f64> {
+        Ok(self.real(code)?.unwrap_or(default))
+    }
+
+    fn int_or(&self, code: i32, default: i64) -> anyhow::Result<i64> {
+        self.string(code)
+            .map(|v| v.parse::<i64>())
+            .transpose()
+            .with_context(|| format!("invalid value of group code {} in {}", code, self.name))
+            .map(|v| v.unwrap_or(default))
+    }
+
+    /// all the values of the group code in the order of appearance
+    fn reals(&self, code: i32) -> anyhow::Result<Vec<f64>> {
+        self.pairs
+            .iter()
+            .filter(|(c, _)| *c == code)
+            .map(|(_, v)| v.parse::<f64>())
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("invalid value of group code {} in {}", code, self.name))
+    }
+
+    /// points given by the group codes `code_x` and `code_x + 10`
+    fn points(&self, code_x: i32) -> anyhow::Result<Vec<[f64; 2]>> {
+        let xs = self.reals(code_x)?;
+        let ys = self.reals(code_x + 10)?;
+        anyhow::ensure!(
+            xs.len() == ys.len(),
+            "numbers of x and y coordinates differ in {}",
+            self.name
+        );
+        Ok(xs.into_iter().zip(ys).map(|(x, y)| [x, y]).collect())
+    }
+
+    fn layer(&self) -> String {
+        self.string(8).unwrap_or("0").to_string()
+    }
+
+    /// whether the extrusion direction is `(0,0,-1)`, where the x-coordinate of the object
+    /// coordinate system is flipped
+    fn is_mirrored(&self) -> anyhow::Result<bool> {
+        Ok(self.real_or(230, 1.)? < 0.)
+    }
+}
+
+/// number of the segments to approximate the arc within the tolerance of the sagitta
+fn num_division_arc(radius: f64, angle: f64, tolerance: f64) -> usize {
+    // the sagitta of the step is radius * (1 - cos(step / 2)) = 2 * radius * sin(step / 4)^2
+    let step = if tolerance < radius {
+        4. * (tolerance / (2. * radius)).sqrt().asin()
+    } else {
+        std::f64::consts::FRAC_PI_2
+    };
+    // the lower bound avoids the infinite division for a tiny tolerance
+    let step = step.clamp(1.0e-6, std::f64::consts::FRAC_PI_2);
+    ((angle.abs() / step).ceil() as usize).max(1)
+}
+
+/// points on the arc excluding the start point
+fn push_arc(
+    xys: &mut Vec<[f64; 2]>,
+    center: [f64; 2],
+    radius: f64,
+    angle_start: f64,
+    angle: f64,
+    tolerance: f64,
+) {
+    let num_div = num_division_arc(radius, angle, tolerance);
+    for i_div in 1..=num_div {
+        let theta = angle_start + angle * (i_div as f64) / (num_div as f64);
+        xys.push([
+            center[0] + radius * theta.cos(),
+            center[1] + radius * theta.sin(),
+        ]);
+    }
+}
+
+/// points from `p0` to `p1` (excluding `p0`) along the arc with the `bulge`, which is the tangent of
+/// the quarter of the included angle. The arc is counterclockwise if the bulge is positive
+fn push_bulge(xys: &mut Vec<[f64; 2]>, p0: [f64; 2], p1: [f64; 2], bulge: f64, tolerance: f64) {
+    let d = [p1[0] - p0[0], p1[1] - p0[1]];
+    let chord = (d[0] * d[0] + d[1] * d[1]).sqrt();
+    if bulge.abs() < 1.0e-10 || chord == 0. {
+        xys.push(p1);
+        return;
+    }
+    let angle = 4. * bulge.atan();
+    // signed distance from the middle of the chord to the center (to the left of the chord)
+    let h = 0.5 * chord / (0.5 * angle).tan();
+    let center = [
+        (p0[0] + p1[0]) * 0.5 - d[1] / chord * h,
+        (p0[1] + p1[1]) * 0.5 + d[0] / chord * h,
+    ];
+    let radius = ((p0[0] - center[0]).powi(2) + (p0[1] - center[1]).powi(2)).sqrt();
+    let angle_start = (p0[1] - center[1]).atan2(p0[0] - center[0]);
+    push_arc(xys, center, radius, angle_start, angle, tolerance);
+    *xys.last_mut().unwrap() = p1; // avoid the round-off error at the end
+}
+
+/// vertices with the bulges of the following segments
+fn polyline_with_bulges(
+    vtx2xy: &[[f64; 2]],
+    vtx2bulge: &[f64],
+    is_closed: bool,
+    tolerance: f64,
+) -> Vec<[f64; 2]> {
+    let num_vtx = vtx2xy.len();
+    let mut xys = vec![];
+    if num_vtx == 0 {
+        return xys;
+    }
+    xys.push(vtx2xy[0]);
+    let num_seg = if is_closed { num_vtx } else { num_vtx - 1 };
+    for i_seg in 0..num_seg {
+        let p1 = vtx2xy[(i_seg + 1) % num_vtx];
+        let bulge = vtx2bulge.get(i_seg).copied().unwrap_or(0.);
+        push_bulge(&mut xys, vtx2xy[i_seg], p1, bulge, tolerance);
+    }
+    if is_closed {
+        xys.pop(); // the first point
+    }
+    xys
+}
+
+/// point on the NURBS curve by the de Boor's algorithm
+fn nurbs_point(degree: usize, knots: &[f64], ctrl2xyw: &[[f64; 3]], t: f64) -> [f64; 2] {
+    let num_ctrl = ctrl2xyw.len();
+    // knot span such that knots[k] <= t < knots[k+1]
+    let k = (degree..num_ctrl)
+        .rev()
+        .find(|&k| knots[k] <= t)
+        .unwrap_or(degree);
+    let mut d: Vec<[f64; 3]> = (0..=degree)
+        .map(|j| {
+            let [x, y, w] = ctrl2xyw[j + k - degree];
+            [x * w, y * w, w]
+        })
+        .collect();
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let i = j + k - degree;
+            let denom = knots[i + degree + 1 - r] - knots[i];
+            let alpha = if denom == 0. {
+                0.
+            } else {
+                (t - knots[i]) / denom
+            };
+            d[j] = std::array::from_fn(|l| (1. - alpha) * d[j - 1][l] + alpha * d[j][l]);
+        }
+    }
+    [d[degree][0] / d[degree][2], d[degree][1] / d[degree][2]]
+}
+
+/// points on the curve in (t0, t1] split until the middle point is within the tolerance
+#[allow(clippy::too_many_arguments)]
+fn push_nurbs_adaptive(
+    xys: &mut Vec<[f64; 2]>,
+    degree: usize,
+    knots: &[f64],
+    ctrl2xyw: &[[f64; 3]],
+    (t0, p0): (f64, [f64; 2]),
+    (t1, p1): (f64, [f64; 2]),
+    tolerance: f64,
+    depth: usize,
+) {
+    let tm = (t0 + t1) * 0.5;
+    let pm = nurbs_point(degree, knots, ctrl2xyw, tm);
+    let d = [p1[0] - p0[0], p1[1] - p0[1]];
+    let len = (d[0] * d[0] + d[1] * d[1]).sqrt();
+    let dist = if len == 0. {
+        ((pm[0] - p0[0]).powi(2) + (pm[1] - p0[1]).powi(2)).sqrt()
+    } else {
+        ((pm[0] - p0[0]) * d[1] - (pm[1] - p0[1]) * d[0]).abs() / len
+    };
+    if depth > 0 && (dist <= tolerance || depth >= 16) {
+        xys.push(p1);
+        return;
+    }
+    let (m, e) = ((tm, pm), (t1, p1));
+    push_nurbs_adaptive(
+        xys,
+        degree,
+        knots,
+        ctrl2xyw,
+        (t0, p0),
+        m,
+        tolerance,
+        depth + 1,
+    );
+    push_nurbs_adaptive(xys, degree, knots, ctrl2xyw, m, e, tolerance, depth + 1);
+}
+
+fn spline(entity: &Entity, tolerance: f64) -> anyhow::Result<Vec<[f64; 2]>> {
+    let ctrl2xy = entity.points(10)?;
+    if ctrl2xy.is_empty() {
+        // spline only with the fit points is approximated by the polyline through them
+        return entity.points(11);
+    }
+    let degree = entity.int_or(71, 3)?.max(1) as usize;
+    let num_ctrl = ctrl2xy.len();
+    anyhow::ensure!(num_ctrl > degree, "too few control points in SPLINE");
+    let weights = entity.reals(41)?;
+    let ctrl2xyw: Vec<[f64; 3]> = ctrl2xy
+        .iter()
+        .enumerate()
+        .map(|(i, xy)| [xy[0], xy[1], weights.get(i).copied().unwrap_or(1.)])
+        .collect();
+    let mut knots = entity.reals(40)?;
+    if knots.len() != num_ctrl + degree + 1 {
+        // clamped uniform knots
+        let num_span = num_ctrl - degree;
+        knots = (0..num_ctrl + degree + 1)
+            .map(|i| (i.clamp(degree, num_ctrl) - degree) as f64 / num_span as f64)
+            .collect();
+    }
+    let mut xys = vec![];
+    let t_start = knots[degree];
+    xys.push(nurbs_point(degree, &knots, &ctrl2xyw, t_start));
+    // split at the knots to capture the features of each span
+    for i_knot in degree..num_ctrl {
+        let (t0, t1) = (knots[i_knot], knots[i_knot + 1]);
+        if t1 <= t0 {
+            continue;
+        }
+        let p0 = *xys.last().unwrap();
+        let p1 = nurbs_point(degree, &knots, &ctrl2xyw, t1);
+        push_nurbs_adaptive(
+            &mut xys,
+            degree,
+            &knots,
+            &ctrl2xyw,
+            (t0, p0),
+            (t1, p1),
+            tolerance,
+            0,
+        );
+    }
+    Ok(xys)
+}
+
+/// remove the last point if it coincides with the first one
+fn remove_duplicated_end(xys: &mut Vec<[f64; 2]>, eps: f64) {
+    if xys.len() > 1 {
+        let (p0, p1) = (xys[0], xys[xys.len() - 1]);
+        if (p0[0] - p1[0]).abs() <= eps && (p0[1] - p1[1]).abs() <= eps {
+            xys.pop();
+        }
+    }
+}
+
+/// read the LWPOLYLINE, POLYLINE, LINE, ARC, CIRCLE and SPLINE entities in the ENTITIES section
+/// * `tolerance` - maximum distance between the curves and their polyline approximations
+pub fn read<R: std::io::BufRead>(reader: R, tolerance: f32) -> anyhow::Result<Vec<DxfPolyline>> {
+    anyhow::ensure!(tolerance > 0., "tolerance {} is not positive", tolerance);
+    let tolerance = tolerance as f64;
+    let pairs = read_group_pairs(reader)?;
+    // split into the entities at the group code `0`
+    let mut entities = vec![];
+    let mut is_in_entities = false;
+    let mut i_pair = 0;
+    while i_pair < pairs.len() {
+        let (code, name) = &pairs[i_pair];
+        let i_start = i_pair + 1;
+        i_pair += 1;
+        while i_pair < pairs.len() && pairs[i_pair].0 != 0 {
+            i_pair += 1;
+        }
+        if *code != 0 {
+            continue;
+        }
+        let entity = Entity {
+            name,
+            pairs: &pairs[i_start..i_pair],
+        };
+        match name.as_str() {
+            "SECTION" => is_in_entities = entity.string(2) == Some("ENTITIES"),
+            "ENDSEC" => is_in_entities = false,
+            _ if is_in_entities => entities.push(entity),
+            _ => {}
+        }
+    }
+    //
+    let mut polylines = vec![];
+    let mut i_entity = 0;
+    while i_entity < entities.len() {
+        let entity = &entities[i_entity];
+        i_entity += 1;
+        let mut is_closed = false;
+        let mut xys = match entity.name {
+            "LINE" => {
+                let p0 = [entity.real_or(10, 0.)?, entity.real_or(20, 0.)?];
+                let p1 = [entity.real_or(11, 0.)?, entity.real_or(21, 0.)?];
+                vec![p0, p1]
+            }
+            "ARC" | "CIRCLE" => {
+                let center = [entity.real_or(10, 0.)?, entity.real_or(20, 0.)?];
+                let radius = entity.real(40)?.context("no radius")?;
+                let (angle_start, angle) = if entity.name == "CIRCLE" {
+                    is_closed = true;
+                    (0., 2. * std::f64::consts::PI)
+                } else {
+                    let a0 = entity.real_or(50, 0.)?.to_radians();
+                    let a1 = entity.real_or(51, 360.)?.to_radians();
+                    let angle = (a1 - a0).rem_euclid(2. * std::f64::consts::PI);
+                    let angle = if angle == 0. {
+                        2. * std::f64::consts::PI
+                    } else {
+                        angle
+                    };
+                    (a0, angle)
+                };
+                let p0 = [
+                    center[0] + radius * angle_start.cos(),
+                    center[1] + radius * angle_start.sin(),
+                ];
+                let mut xys = vec![p0];
+                push_arc(&mut xys, center, radius, angle_start, angle, tolerance);
+                if is_closed {
+                    xys.pop();
+                }
+                xys
+            }
+            "LWPOLYLINE" => {
+                is_closed = entity.int_or(70, 0)? & 1 == 1;
+                // the bulge (42) follows the coordinates of its vertex
+                let mut vtx2xy = vec![];
+                let mut vtx2bulge = vec![];
+                for (code, value) in entity.pairs {
+                    let value = || {
+                        value
+                            .parse::<f64>()
+                            .with_context(|| format!("invalid value \"{}\" in LWPOLYLINE", value))
+                    };
+                    match code {
+                        10 => {
+                            vtx2xy.push([value()?, 0.]);
+                            vtx2bulge.push(0.);
+                        }
+                        20 => vtx2xy.last_mut().context("y before x")?[1] = value()?,
+                        42 => *vtx2bulge.last_mut().context("bulge before x")? = value()?,
+                        _ => {}
+                    }
+                }
+                polyline_with_bulges(&vtx2xy, &vtx2bulge, is_closed, tolerance)
+            }
+            "POLYLINE" => {
+                let flag = entity.int_or(70, 0)?;
+                is_closed = flag & 1 == 1;
+                let mut vtx2xy = vec![];
+                let mut vtx2bulge = vec![];
+                while i_entity < entities.len() && entities[i_entity].name == "VERTEX" {
+                    let vertex = &entities[i_entity];
+                    i_entity += 1;
+                    // skip the spline frame control points and the face records
+                    if vertex.int_or(70, 0)? & (16 | 128) != 0 {
+                        continue;
+                    }
+                    vtx2xy.push([vertex.real_or(10, 0.)?, vertex.real_or(20, 0.)?]);
+                    vtx2bulge.push(vertex.real_or(42, 0.)?);
+                }
+                if i_entity < entities.len() && entities[i_entity].name == "SEQEND" {
+                    i_entity += 1;
+                }
+                if flag & (16 | 64) != 0 {
+                    continue; // polygon mesh or polyface mesh
+                }
+                polyline_with_bulges(&vtx2xy, &vtx2bulge, is_closed, tolerance)
+            }
+            "SPLINE" => {
+                is_closed = entity.int_or(70, 0)? & 1 == 1;
+                let mut xys = spline(entity, tolerance).context("SPLINE")?;
+                if is_closed {
+                    remove_duplicated_end(&mut xys, tolerance * 1.0e-3);
+                }
+                xys
+            }
+            _ => continue,
+        };
+        if entity.is_mirrored()? {
+            xys.iter_mut().for_each(|xy| xy[0] = -xy[0]);
+        }
+        if xys.len() < 2 {
+            continue;
+        }
+        polylines.push(DxfPolyline {
+            layer: entity.layer(),
+            vtx2xy: xys
+                .iter()
+                .flat_map(|xy| [xy[0] as f32, xy[1] as f32])
+                .collect(),
+            is_closed,
+        });
+    }
+    Ok(polylines)
+}
+
+pub fn load<P: AsRef<std::path::Path>>(
+    path: P,
+    tolerance: f32,
+) -> anyhow::Result<Vec<DxfPolyline>> {
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    read(std::io::BufReader::new(file), tolerance)
+}
+
+/// chain the open polylines on the same layer whose end points are within `eps` into closed loops.
+/// The closed polylines are kept, and the chains that cannot be closed are discarded.
+/// All the loops are made counterclockwise
+pub fn polyloops_from_polylines(polylines: &[DxfPolyline], eps: f32) -> Vec<DxfPolyline> {
+    let mut loops: Vec<DxfPolyline> = polylines.iter().filter(|p| p.is_closed).cloned().collect();
+    let mut layers: Vec<&str> = polylines.iter().map(|p| p.layer.as_str()).collect();
+    layers.sort();
+    layers.dedup();
+    let cell =
+        |xy: &[f32]| -> (i64, i64) { ((xy[0] / eps).floor() as i64, (xy[1] / eps).floor() as i64) };
+    for layer in layers {
+        let chains: Vec<&[f32]> = polylines
+            .iter()
+            .filter(|p| !p.is_closed && p.layer == layer && p.vtx2xy.len() >= 4)
+            .map(|p| p.vtx2xy.as_slice())
+            .collect();
+        // grid of the end points. The end point `i_chain * 2 + i_end`
+        let end2xy = |i_end: usize| -> &[f32] {
+            let xys = chains[i_end / 2];
+            match i_end % 2 {
+                0 => &xys[..2],
+                _ => &xys[xys.len() - 2..],
+            }
+        };
+        let mut cell2ends = std::collections::HashMap::<(i64, i64), Vec<usize>>::new();
+        for i_end in 0..chains.len() * 2 {
+            cell2ends
+                .entry(cell(end2xy(i_end)))
+                .or_default()
+                .push(i_end);
+        }
+        let find_end = |xy: &[f32], chain2flag: &[bool]| -> Option<usize> {
+            let (ix, iy) = cell(xy);
+            for jx in ix - 1..=ix + 1 {
+                for jy in iy - 1..=iy + 1 {
+                    let Some(ends) = cell2ends.get(&(jx, jy)) else {
+                        continue;
+                    };
+                    for &i_end in ends {
+                        let q = end2xy(i_end);
+                        if !chain2flag[i_end / 2]
+                            && (q[0] - xy[0]).abs() <= eps
+                            && (q[1] - xy[1]).abs() <= eps
+                        {
+                            return Some(i_end);
+                        }
+                    }
+                }
+            }
+            None
+        };
+        let mut chain2flag = vec![false; chains.len()];
+        for i_chain_start in 0..chains.len() {
+            if chain2flag[i_chain_start] {
+                continue;
+            }
+            chain2flag[i_chain_start] = true;
+            let mut vtx2xy = chains[i_chain_start].to_vec();
+            let is_closed = loop {
+                let n = vtx2xy.len();
+                let (head, tail) = (&vtx2xy[..2], &vtx2xy[n - 2..]);
+                if n > 4 && (head[0] - tail[0]).abs() <= eps && (head[1] - tail[1]).abs() <= eps {
+                    vtx2xy.truncate(n - 2);
+                    break true;
+                }
+                let Some(i_end) = find_end(tail, &chain2flag) else {
+                    break false;
+                };
+                chain2flag[i_end / 2] = true;
+                let xys = chains[i_end / 2];
+                // skip the first point which coincides with the tail
+                match i_end % 2 {
+                    0 => vtx2xy.extend_from_slice(&xys[2..]),
+                    _ => {
+                        for xy in xys.chunks(2).rev().skip(1) {
+                            vtx2xy.extend_from_slice(xy);
+                        }
+                    }
+                }
+            };
+            if is_closed && vtx2xy.len() >= 6 {
+                loops.push(DxfPolyline {
+                    layer: layer.to_string(),
+                    vtx2xy,
+                    is_closed: true,
+                });
+            }
+        }
+    }
+    for polyloop in loops.iter_mut() {
+        if crate::polyloop2::area_(&polyloop.vtx2xy) < 0. {
+            polyloop.vtx2xy = crate::polyloop::flip(&polyloop.vtx2xy, 2);
+        }
+    }
+    loops
+}
+
+/// closed loops in the DXF file (e.g., for `trimesh2_dynamic::meshing_from_polyloop2`)
+/// * `tolerance` - tolerance of the tessellation, which is also used to connect the end points
+pub fn load_polyloops<P: AsRef<std::path::Path>>(
+    path: P,
+    tolerance: f32,
+) -> anyhow::Result<Vec<DxfPolyline>> {
+    let polylines = load(path, tolerance)?;
+    Ok(polyloops_from_polylines(&polylines, tolerance))
+}
+
+// ---------------------------
+// writer
+
+/// polylines of the polyloops (e.g., `vtxl2xy`) on a layer
+pub fn polylines_from_polyloops(layer: &str, loop2vtxl2xy: &[Vec<f32>]) -> Vec<DxfPolyline> {
+    loop2vtxl2xy
+        .iter()
+        .map(|vtxl2xy| DxfPolyline {
+            layer: layer.to_string(),
+            vtx2xy: vtxl2xy.clone(),
+            is_closed: true,
+        })
+        .collect()
+}
+
+/// polylines of two points for the edges
+pub fn polylines_from_edge2vtx(
+    layer: &str,
+    edge2vtx: &[usize],
+    vtx2xy: &[f32],
+) -> Vec<DxfPolyline> {
+    edge2vtx
+        .chunks(2)
+        .map(|node2vtx| DxfPolyline {
+            layer: layer.to_string(),
+            vtx2xy: node2vtx
+                .iter()
+                .flat_map(|&i_vtx| [vtx2xy[i_vtx * 2], vtx2xy[i_vtx * 2 + 1]])
+                .collect(),
+            is_closed: false,
+        })
+        .collect()
+}
+
+/// write DXF (R12) where the open polylines of two points are written as LINE and the others as POLYLINE
+pub fn write<W: Write>(writer: &mut W, polylines: &[DxfPolyline]) -> anyhow::Result<()> {
+    writeln!(
+        writer,
+        "0\nSECTION\n2\nHEADER\n9\n$ACADVER\n1\nAC1009\n0\nENDSEC"
+    )?;
+    // layer table
+    let mut layers: Vec<&str> = polylines.iter().map(|p| p.layer.as_str()).collect();
+    layers.sort();
+    layers.dedup();
+    writeln!(
+        writer,
+        "0\nSECTION\n2\nTABLES\n0\nTABLE\n2\nLAYER\n70\n{}",
+        layers.len()
+    )?;
+    for layer in &layers {
+        writeln!(
+            writer,
+            "0\nLAYER\n2\n{}\n70\n0\n62\n7\n6\nCONTINUOUS",
+            layer
+        )?;
+    }
+    writeln!(writer, "0\nENDTAB\n0\nENDSEC")?;
+    //
+    writeln!(writer, "0\nSECTION\n2\nENTITIES")?;
+    for polyline in polylines {
+        let xys = &polyline.vtx2xy;
+        if !polyline.is_closed && xys.len() == 4 {
+            writeln!(writer, "0\nLINE\n8\n{}", polyline.layer)?;
+            writeln!(writer, "10\n{}\n20\n{}\n30\n0", xys[0], xys[1])?;
+            writeln!(writer, "11\n{}\n21\n{}\n31\n0", xys[2], xys[3])?;
+            continue;
+        }
+        let flag = if polyline.is_closed { 1 } else { 0 };
+        writeln!(
+            writer,
+            "0\nPOLYLINE\n8\n{}\n66\n1\n70\n{}",
+            polyline.layer, flag
+        )?;
+        writeln!(writer, "10\n0\n20\n0\n30\n0")?;
+        for xy in xys.chunks(2) {
+            writeln!(writer, "0\nVERTEX\n8\n{}", polyline.layer)?;
+            writeln!(writer, "10\n{}\n20\n{}\n30\n0", xy[0], xy[1])?;
+        }
+        writeln!(writer, "0\nSEQEND\n8\n{}", polyline.layer)?;
+    }
+    writeln!(writer, "0\nENDSEC\n0\nEOF")?;
+    Ok(())
+}
+
+pub fn save<P: AsRef<std::path::Path>>(path: P, polylines: &[DxfPolyline]) -> anyhow::Result<()> {
+    let file = std::fs::File::create(path.as_ref())
+        .with_context(|| format!("cannot create {}", path.as_ref().display()))?;
+    let mut file = std::io::BufWriter::new(file);
+    write(&mut file, polylines)?;
+    file.flush()?;
+    Ok(())
+}
+
+pub fn save_polyloops<P: AsRef<std::path::Path>>(
+    path: P,
+    loop2vtxl2xy: &[Vec<f32>],
+) -> anyhow::Result<()> {
+    save(path, &polylines_from_polyloops("0", loop2vtxl2xy))
+}
+
+pub fn save_edge2vtx_vtx2xy<P: AsRef<std::path::Path>>(
+    path: P,
+    edge2vtx: &[usize],
+    vtx2xy: &[f32],
+) -> anyhow::Result<()> {
+    save(path, &polylines_from_edge2vtx("0", edge2vtx, vtx2xy))
+}
+
+#[test]
+fn test_read_write() -> anyhow::Result<()> {
+    let pi = std::f32::consts::PI;
+    // - square with a rounded side (LWPOLYLINE with a bulge) on the layer "outer"
+    // - unit square made of LINEs in random order and direction
+    // - "D" shape made of an ARC and a LINE
+    // - circle, and closed B-spline through the corners of a square (degree one)
+    let txt = "999
+comment
+0
+SECTION
+2
+HEADER
+0
+ENDSEC
+0
+SECTION
+2
+ENTITIES
+0
+LWPOLYLINE
+8
+outer
+90
+4
+70
+1
+10
+0
+20
+0
+10
+4
+20
+0
+42
+1
+10
+4
+20
+4
+10
+0
+20
+4
+0
+LINE
+8
+cut
+10
+1
+20
+1
+11
+2
+21
+1
+0
+LINE
+8
+cut
+10
+1
+20
+2
+11
+2
+21
+2
+0
+LINE
+8
+cut
+10
+1
+20
+2
+11
+1
+21
+1
+0
+LINE
+8
+cut
+10
+2
+20
+1
+11
+2
+21
+2
+0
+ARC
+8
+cut
+10
+3
+20
+3
+40
+0.5
+50
+270
+51
+90
+0
+LINE
+8
+cut
+10
+3
+20
+3.5
+11
+3
+21
+2.5
+0
+CIRCLE
+8
+hole
+10
+10
+20
+10
+40
+2
+0
+SPLINE
+8
+spline
+70
+1
+71
+1
+72
+7
+73
+5
+40
+0
+40
+0
+40
+1
+40
+2
+40
+3
+40
+4
+40
+4
+10
+0
+20
+0
+10
+1
+20
+0
+10
+1
+20
+1
+10
+0
+20
+1
+10
+0
+20
+0
+0
+ENDSEC
+0
+EOF
+";
+    let tolerance = 1.0e-3;
+    assert!(read(std::io::BufReader::new(txt.as_bytes()), 0.).is_err());
+    let polylines = read(std::io::BufReader::new(txt.as_bytes()), tolerance)?;
+    assert_eq!(polylines.len(), 9);
+    let loops = polyloops_from_polylines(&polylines, 1.0e-4);
+    assert_eq!(loops.len(), 5);
+    let area = |layer: &str| -> Vec<f32> {
+        let mut areas: Vec<f32> = loops
+            .iter()
+            .filter(|l| l.layer == layer)
+            .map(|l| crate::polyloop2::area_(&l.vtx2xy))
+            .collect();
+        areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        areas
+    };
+    // the bulge of one makes the half circle bulging to the right of (4,0)-(4,4)
+    let area_outer = area("outer");
+    assert!(
+        (area_outer[0] - (16. + 2. * pi)).abs() < 0.05,
+        "{:?}",
+        area_outer
+    );
+    let area_cut = area("cut");
+    assert_eq!(area_cut.len(), 2);
+    assert!((area_cut[0] - 0.125 * pi).abs() < 0.01, "{:?}", area_cut);
+    assert!((area_cut[1] - 1.).abs() < 1.0e-5, "{:?}", area_cut);
+    assert!((area("hole")[0] - 4. * pi).abs() < 0.05);
+    assert!((area("spline")[0] - 1.).abs() < 1.0e-5);
+    // the tessellation is within the tolerance
+    let circle = &loops.iter().find(|l| l.layer == "hole").unwrap().vtx2xy;
+    for xy in circle.chunks(2) {
+        let r = ((xy[0] - 10.).powi(2) + (xy[1] - 10.).powi(2)).sqrt();
+        assert!((r - 2.).abs() < 1.0e-4);
+    }
+    for (p0, p1) in circle.chunks(2).zip(circle.chunks(2).cycle().skip(1)) {
+        let m = [(p0[0] + p1[0]) * 0.5 - 10., (p0[1] + p1[1]) * 0.5 - 10.];
+        assert!(2. - (m[0] * m[0] + m[1] * m[1]).sqrt() <= tolerance * 1.01);
+    }
+    // write and read
+    let mut polylines1 = polylines_from_polyloops("loop", std::slice::from_ref(circle));
+    polylines1.extend(polylines_from_edge2vtx(
+        "edge",
+        &[0, 1, 1, 2],
+        &[0., 0., 1., 0., 1., 1.],
+    ));
+    save("../target/io_dxf.dxf", &polylines1)?;
+    let polylines2 = load("../target/io_dxf.dxf", tolerance)?;
+    assert_eq!(polylines1, polylines2);
+    Ok(())
+}
@@ -0,0 +1,1384 @@
+//! methods for glTF 2.0 files (`.gltf` with `.bin`, and `.glb`) of triangle meshes
+
+use anyhow::Context;
+use num_traits::AsPrimitive;
+use std::io::Write;
+
+/// triangle mesh in a glTF asset. Optional vertex attributes are empty if absent
+#[derive(Clone, Debug)]
+pub struct GltfMesh<Index, Real> {
+    pub vtx2xyz: Vec<Real>,
+    pub vtx2nrm: Vec<Real>,
+    /// texture coordinates with the origin at the bottom-left (same as Wavefront OBJ)
+    pub vtx2uv: Vec<Real>,
+    pub vtx2rgb: Vec<Real>,
+    pub tri2vtx: Vec<Index>,
+    /// primitive of each triangle. Empty if there is only one primitive
+    pub tri2group: Vec<usize>,
+    /// absolute vertex coordinates of each morph target
+    pub target2vtx2xyz: Vec<Vec<Real>>,
+    /// time of the keyframes. The first keyframe is the base shape and the `i+1`-th is the `i`-th morph target
+    pub keyframe2time: Vec<f32>,
+}
+
+impl<Index, Real> GltfMesh<Index, Real> {
+    pub fn new() -> Self {
+        GltfMesh {
+            vtx2xyz: vec![],
+            vtx2nrm: vec![],
+            vtx2uv: vec![],
+            vtx2rgb: vec![],
+            tri2vtx: vec![],
+            tri2group: vec![],
+            target2vtx2xyz: vec![],
+            keyframe2time: vec![],
+        }
+    }
+
+    /// animation of the vertex coordinates. The frames share the connectivity
+    pub fn from_frames(tri2vtx: Vec<Index>, frame2vtx2xyz: Vec<Vec<Real>>, dt: f32) -> Self {
+        let mut mesh = Self::new();
+        let num_frame = frame2vtx2xyz.len();
+        let mut frames = frame2vtx2xyz.into_iter();
+        mesh.vtx2xyz = frames.next().unwrap_or_default();
+        mesh.tri2vtx = tri2vtx;
+        mesh.target2vtx2xyz = frames.collect();
+        if num_frame > 1 {
+            mesh.keyframe2time = (0..num_frame).map(|i_frame| i_frame as f32 * dt).collect();
+        }
+        mesh
+    }
+}
+
+impl<Index, Real> Default for GltfMesh<Index, Real> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ---------------------------
+// JSON
+
+#[derive(Clone, Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn object(items: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            items
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(items) => items.iter().find(|item| item.0 == key).map(|item| &item.1),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(v) if *v >= 0. && v.fract() == 0. => Some(*v as usize),
+            _ => None,
+        }
+    }
+
+    /// JSON text. Error if a number is not finite
+    fn stringify(&self) -> anyhow::Result<String> {
+        use std::fmt::Write;
+        let mut s = String::new();
+        write!(s, "{}", self).map_err(|_| anyhow::anyhow!("non-finite number in JSON"))?;
+        Ok(s)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// elements of an array. Empty if not an array
+    fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(v) => v,
+            _ => &[],
+        }
+    }
+
+    /// array element of the key. Empty if not found
+    fn array_of(&self, key: &str) -> &[Json] {
+        self.get(key).map_or(&[], |v| v.as_array())
+    }
+
+    fn usize_of(&self, key: &str) -> anyhow::Result<usize> {
+        self.get(key)
+            .and_then(|v| v.as_usize())
+            .with_context(|| format!("missing \"{}\"", key))
+    }
+
+    fn f64s_of<const N: usize>(&self, key: &str) -> Option<[f64; N]> {
+        let values = self.get(key)?.as_array();
+        if values.len() != N {
+            return None;
+        }
+        let mut res = [0f64; N];
+        for (r, v) in res.iter_mut().zip(values) {
+            *r = v.as_f64()?;
+        }
+        Some(res)
+    }
+
+    fn parse(s: &str) -> anyhow::Result<Json> {
+        let mut parser = JsonParser {
+            buf: s.as_bytes(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.buf.len() {
+            return Err(anyhow::anyhow!(
+                "JSON: trailing characters at {}",
+                parser.pos
+            ));
+        }
+        Ok(value)
+    }
+}
+
+/// fails for the non-finite numbers, which JSON cannot represent
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(v) => write!(f, "{}", v),
+            Json::Number(v) if !v.is_finite() => Err(std::fmt::Error),
+            Json::Number(v) => write!(f, "{}", v),
+            Json::String(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, v) in values.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(items) => {
+                write!(f, "{{")?;
+                for (i, (key, v)) in items.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", Json::String(key.clone()), v)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.buf.len() && self.buf[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> anyhow::Result<()> {
+        self.skip_whitespace();
+        if self.buf.get(self.pos) != Some(&c) {
+            return Err(anyhow::anyhow!(
+                "JSON: expected '{}' at {}",
+                c as char,
+                self.pos
+            ));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> anyhow::Result<Json> {
+        if !self.buf[self.pos..].starts_with(word.as_bytes()) {
+            return Err(anyhow::anyhow!("JSON: unexpected token at {}", self.pos));
+        }
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    fn value(&mut self) -> anyhow::Result<Json> {
+        self.skip_whitespace();
+        let Some(&c) = self.buf.get(self.pos) else {
+            return Err(anyhow::anyhow!("JSON: unexpected end"));
+        };
+        match c {
+            b'{' => {
+                self.pos += 1;
+                let mut items = vec![];
+                self.skip_whitespace();
+                if self.buf.get(self.pos) == Some(&b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(items));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    items.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.buf.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(items));
+                        }
+                        _ => {
+                            return Err(anyhow::anyhow!(
+                                "JSON: expected ',' or '}}' at {}",
+                                self.pos
+                            ))
+                        }
+                    }
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut values = vec![];
+                self.skip_whitespace();
+                if self.buf.get(self.pos) == Some(&b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.buf.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(values));
+                        }
+                        _ => {
+                            return Err(anyhow::anyhow!(
+                                "JSON: expected ',' or ']' at {}",
+                                self.pos
+                            ))
+                        }
+                    }
+                }
+            }
+            b'"' => Ok(Json::String(self.string()?)),
+            b't' => self.keyword("true", Json::Bool(true)),
+            b'f' => self.keyword("false", Json::Bool(false)),
+            b'n' => self.keyword("null", Json::Null),
+            _ => {
+                let start = self.pos;
+                while self.pos < self.buf.len()
+                    && matches!(
+                        self.buf[self.pos],
+                        b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'
+                    )
+                {
+                    self.pos += 1;
+                }
+                let s = std::str::from_utf8(&self.buf[start..self.pos])?;
+                let v = s
+                    .parse::<f64>()
+                    .with_context(|| format!("JSON: invalid number at {}", start))?;
+                Ok(Json::Number(v))
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> anyhow::Result<u32> {
+        let Some(s) = self.buf.get(self.pos..self.pos + 4) else {
+            return Err(anyhow::anyhow!("JSON: unexpected end"));
+        };
+        self.pos += 4;
+        Ok(u32::from_str_radix(std::str::from_utf8(s)?, 16)?)
+    }
+
+    fn string(&mut self) -> anyhow::Result<String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::<u8>::new();
+        loop {
+            let Some(&c) = self.buf.get(self.pos) else {
+                return Err(anyhow::anyhow!("JSON: unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&e) = self.buf.get(self.pos) else {
+                        return Err(anyhow::anyhow!("JSON: unterminated string"));
+                    };
+                    self.pos += 1;
+                    let c = match e {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        b'r' => '\r',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            if (0xD800..0xDC00).contains(&code)
+                                && self.buf[self.pos..].starts_with(b"\\u")
+                            {
+                                let pos = self.pos;
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                if (0xDC00..0xE000).contains(&low) {
+                                    code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                                } else {
+                                    // unpaired high surrogate. The next escape is read by itself
+                                    self.pos = pos;
+                                }
+                            }
+                            char::from_u32(code).unwrap_or('\u{FFFD}')
+                        }
+                        e => e as char,
+                    };
+                    let mut tmp = [0u8; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut tmp).as_bytes());
+                }
+                c => bytes.push(c),
+            }
+        }
+        Ok(String::from_utf8(bytes)?)
+    }
+}
+
+fn decode_base64(s: &str) -> anyhow::Result<Vec<u8>> {
+    let mut res = Vec::<u8>::with_capacity(s.len() / 4 * 3);
+    let mut n = 0u32;
+    let mut num_bit = 0;
+    for c in s.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => return Err(anyhow::anyhow!("invalid base64 character '{}'", c as char)),
+        };
+        n = n << 6 | v as u32;
+        num_bit += 6;
+        if num_bit >= 8 {
+            num_bit -= 8;
+            res.push((n >> num_bit) as u8);
+        }
+    }
+    Ok(res)
+}
+
+// ---------------------------
+// export
+
+/// binary buffer and its views and accessors
+struct BufferBuilder {
+    bin: Vec<u8>,
+    buffer_views: Vec<Json>,
+    accessors: Vec<Json>,
+}
+
+impl BufferBuilder {
+    fn push_view(&mut self, data: Vec<u8>, target: Option<usize>) -> usize {
+        let mut view = vec![
+            ("buffer", Json::Number(0.)),
+            ("byteOffset", Json::Number(self.bin.len() as f64)),
+            ("byteLength", Json::Number(data.len() as f64)),
+        ];
+        if let Some(target) = target {
+            view.push(("target", Json::Number(target as f64)));
+        }
+        self.bin.extend(data);
+        self.buffer_views.push(Json::object(view));
+        self.buffer_views.len() - 1
+    }
+
+    /// float accessor. `min` and `max` are required for positions and animation inputs
+    fn push_f32(
+        &mut self,
+        values: &[f32],
+        num_comp: usize,
+        target: Option<usize>,
+        with_bound: bool,
+    ) -> usize {
+        let data = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let i_view = self.push_view(data, target);
+        let count = values.len() / num_comp;
+        let mut accessor = vec![
+            ("bufferView", Json::Number(i_view as f64)),
+            ("componentType", Json::Number(5126.)),
+            ("count", Json::Number(count as f64)),
+            ("type", Json::String(type_name(num_comp).to_string())),
+        ];
+        if with_bound && count > 0 {
+            let mut min = vec![f32::INFINITY; num_comp];
+            let mut max = vec![f32::NEG_INFINITY; num_comp];
+            for item in values.chunks(num_comp) {
+                for i_comp in 0..num_comp {
+                    min[i_comp] = min[i_comp].min(item[i_comp]);
+                    max[i_comp] = max[i_comp].max(item[i_comp]);
+                }
+            }
+            let to_json =
+                |v: Vec<f32>| Json::Array(v.into_iter().map(|v| Json::Number(v as f64)).collect());
+            accessor.push(("min", to_json(min)));
+            accessor.push(("max", to_json(max)));
+        }
+        self.accessors.push(Json::object(accessor));
+        self.accessors.len() - 1
+    }
+
+    fn push_indices(&mut self, indices: &[u32]) -> usize {
+        let data = indices.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let i_view = self.push_view(data, Some(34963)); // ELEMENT_ARRAY_BUFFER
+        self.accessors.push(Json::object(vec![
+            ("bufferView", Json::Number(i_view as f64)),
+            ("componentType", Json::Number(5125.)),
+            ("count", Json::Number(indices.len() as f64)),
+            ("type", Json::String("SCALAR".to_string())),
+        ]));
+        self.accessors.len() - 1
+    }
+}
+
+fn type_name(num_comp: usize) -> &'static str {
+    match num_comp {
+        1 => "SCALAR",
+        2 => "VEC2",
+        3 => "VEC3",
+        _ => "VEC4",
+    }
+}
+
+impl<Index, Real> GltfMesh<Index, Real>
+where
+    Index: AsPrimitive<usize>,
+    Real: AsPrimitive<f32>,
+{
+    /// JSON document and the binary buffer it refers to
+    fn to_json_and_bin(&self, buffer_uri: Option<&str>) -> anyhow::Result<(Json, Vec<u8>)> {
+        let num_vtx = self.vtx2xyz.len() / 3;
+        anyhow::ensure!(num_vtx > 0, "the mesh has no vertex");
+        anyhow::ensure!(
+            num_vtx <= u32::MAX as usize,
+            "too many vertices for the 32-bit indices"
+        );
+        let to_f32 = |v: &[Real]| -> Vec<f32> { v.iter().map(|v| v.as_()).collect() };
+        let mut builder = BufferBuilder {
+            bin: vec![],
+            buffer_views: vec![],
+            accessors: vec![],
+        };
+        const ARRAY_BUFFER: Option<usize> = Some(34962);
+        let mut attributes = vec![(
+            "POSITION",
+            builder.push_f32(&to_f32(&self.vtx2xyz), 3, ARRAY_BUFFER, true),
+        )];
+        if !self.vtx2nrm.is_empty() {
+            let i_accessor = builder.push_f32(&to_f32(&self.vtx2nrm), 3, ARRAY_BUFFER, false);
+            attributes.push(("NORMAL", i_accessor));
+        }
+        if !self.vtx2uv.is_empty() {
+            // the origin of glTF's texture coordinates is the top-left
+            let vtx2uv: Vec<f32> = self
+                .vtx2uv
+                .chunks(2)
+                .flat_map(|uv| [uv[0].as_(), 1f32 - uv[1].as_()])
+                .collect();
+            attributes.push((
+                "TEXCOORD_0",
+                builder.push_f32(&vtx2uv, 2, ARRAY_BUFFER, false),
+            ));
+        }
+        if !self.vtx2rgb.is_empty() {
+            let i_accessor = builder.push_f32(&to_f32(&self.vtx2rgb), 3, ARRAY_BUFFER, false);
+            attributes.push(("COLOR_0", i_accessor));
+        }
+        for (name, vtx2val, num_comp) in [
+            ("NORMAL", &self.vtx2nrm, 3),
+            ("TEXCOORD_0", &self.vtx2uv, 2),
+            ("COLOR_0", &self.vtx2rgb, 3),
+        ] {
+            if !vtx2val.is_empty() && vtx2val.len() != num_vtx * num_comp {
+                return Err(anyhow::anyhow!(
+                    "the size of {} does not match the vertices",
+                    name
+                ));
+            }
+        }
+        let attributes = Json::object(
+            attributes
+                .into_iter()
+                .map(|(name, i)| (name, Json::Number(i as f64)))
+                .collect(),
+        );
+        //
+        let mut targets = vec![];
+        for vtx2xyz in &self.target2vtx2xyz {
+            if vtx2xyz.len() != self.vtx2xyz.len() {
+                return Err(anyhow::anyhow!(
+                    "the size of a morph target does not match the vertices"
+                ));
+            }
+            let displacement: Vec<f32> = vtx2xyz
+                .iter()
+                .zip(self.vtx2xyz.iter())
+                .map(|(&p, &q)| p.as_() - q.as_())
+                .collect();
+            let i_accessor = builder.push_f32(&displacement, 3, ARRAY_BUFFER, true);
+            targets.push(Json::object(vec![(
+                "POSITION",
+                Json::Number(i_accessor as f64),
+            )]));
+        }
+        //
+        let num_group = self.tri2group.iter().map(|&i| i + 1).max().unwrap_or(1);
+        let mut group2indices = vec![Vec::<u32>::new(); num_group];
+        for (i_tri, node2vtx) in self.tri2vtx.chunks(3).enumerate() {
+            let i_group = self.tri2group.get(i_tri).copied().unwrap_or(0);
+            group2indices[i_group].extend(node2vtx.iter().map(|&i_vtx| i_vtx.as_() as u32));
+        }
+        let mut primitives = vec![];
+        for indices in group2indices.iter().filter(|v| !v.is_empty()) {
+            let mut primitive = vec![
+                ("attributes", attributes.clone()),
+                (
+                    "indices",
+                    Json::Number(builder.push_indices(indices) as f64),
+                ),
+                ("mode", Json::Number(4.)),
+            ];
+            if !targets.is_empty() {
+                primitive.push(("targets", Json::Array(targets.clone())));
+            }
+            primitives.push(Json::object(primitive));
+        }
+        let mut mesh = vec![("primitives", Json::Array(primitives))];
+        if !targets.is_empty() {
+            mesh.push((
+                "weights",
+                Json::Array(vec![Json::Number(0.); targets.len()]),
+            ));
+        }
+        //
+        let mut animations = vec![];
+        if !self.keyframe2time.is_empty() {
+            let num_target = targets.len();
+            if self.keyframe2time.len() != num_target + 1 {
+                return Err(anyhow::anyhow!(
+                    "the number of keyframes should be the number of the morph targets plus one"
+                ));
+            }
+            let input = builder.push_f32(&self.keyframe2time, 1, None, true);
+            // the i-th keyframe shows only the (i-1)-th morph target
+            let mut weights = vec![0f32; (num_target + 1) * num_target];
+            for i_target in 0..num_target {
+                weights[(i_target + 1) * num_target + i_target] = 1f32;
+            }
+            let output = builder.push_f32(&weights, 1, None, false);
+            animations.push(Json::object(vec![
+                (
+                    "channels",
+                    Json::Array(vec![Json::object(vec![
+                        ("sampler", Json::Number(0.)),
+                        (
+                            "target",
+                            Json::object(vec![
+                                ("node", Json::Number(0.)),
+                                ("path", Json::String("weights".to_string())),
+                            ]),
+                        ),
+                    ])]),
+                ),
+                (
+                    "samplers",
+                    Json::Array(vec![Json::object(vec![
+                        ("input", Json::Number(input as f64)),
+                        ("output", Json::Number(output as f64)),
+                        ("interpolation", Json::String("LINEAR".to_string())),
+                    ])]),
+                ),
+            ]));
+        }
+        //
+        let mut buffer = vec![("byteLength", Json::Number(builder.bin.len() as f64))];
+        if let Some(uri) = buffer_uri {
+            buffer.push(("uri", Json::String(uri.to_string())));
+        }
+        let mut doc = vec![
+            (
+                "asset",
+                Json::object(vec![
+                    ("version", Json::String("2.0".to_string())),
+                    ("generator", Json::String("del-msh".to_string())),
+                ]),
+            ),
+            ("scene", Json::Number(0.)),
+            (
+                "scenes",
+                Json::Array(vec![Json::object(vec![(
+                    "nodes",
+                    Json::Array(vec![Json::Number(0.)]),
+                )])]),
+            ),
+            (
+                "nodes",
+                Json::Array(vec![Json::object(vec![("mesh", Json::Number(0.))])]),
+            ),
+            ("meshes", Json::Array(vec![Json::object(mesh)])),
+            ("buffers", Json::Array(vec![Json::object(buffer)])),
+            ("bufferViews", Json::Array(builder.buffer_views)),
+            ("accessors", Json::Array(builder.accessors)),
+        ];
+        if !animations.is_empty() {
+            doc.push(("animations", Json::Array(animations)));
+        }
+        Ok((Json::object(doc), builder.bin))
+    }
+
+    /// write `.gltf` file and the binary buffer as the `.bin` file next to it
+    pub fn save_gltf<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let path_bin = path.with_extension("bin");
+        let uri = path_bin
+            .file_name()
+            .and_then(|s| s.to_str())
+            .with_context(|| format!("invalid path {}", path.display()))?;
+        let (json, bin) = self.to_json_and_bin(Some(uri))?;
+        std::fs::write(path, json.stringify()?)
+            .with_context(|| format!("cannot create {}", path.display()))?;
+        std::fs::write(&path_bin, bin)
+            .with_context(|| format!("cannot create {}", path_bin.display()))?;
+        Ok(())
+    }
+
+    /// write binary glTF
+    pub fn write_glb<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let (json, mut bin) = self.to_json_and_bin(None)?;
+        let mut json = json.stringify()?.into_bytes();
+        // chunks are padded to four bytes
+        json.resize(json.len().div_ceil(4) * 4, b' ');
+        bin.resize(bin.len().div_ceil(4) * 4, 0);
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        writer.write_all(b"glTF")?;
+        writer.write_all(&2u32.to_le_bytes())?;
+        writer.write_all(&(length as u32).to_le_bytes())?;
+        writer.write_all(&(json.len() as u32).to_le_bytes())?;
+        writer.write_all(b"JSON")?;
+        writer.write_all(&json)?;
+        writer.write_all(&(bin.len() as u32).to_le_bytes())?;
+        writer.write_all(b"BIN\0")?;
+        writer.write_all(&bin)?;
+        Ok(())
+    }
+
+    /// write `.glb` or `.gltf` depending on the extension
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let is_glb = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("glb"));
+        if !is_glb {
+            return self.save_gltf(path);
+        }
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("cannot create {}", path.display()))?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write_glb(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// save triangle mesh as `.glb` or `.gltf`
+pub fn save_tri2vtx_vtx2xyz<P, Index, Real>(
+    path: P,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    Index: AsPrimitive<usize>,
+    Real: AsPrimitive<f32>,
+{
+    let mut mesh = GltfMesh::new();
+    mesh.tri2vtx = tri2vtx.to_vec();
+    mesh.vtx2xyz = vtx2xyz.to_vec();
+    mesh.save(path)
+}
+
+// ---------------------------
+// import
+
+/// values of an accessor as `f64` with the number of components
+fn read_accessor(
+    json: &Json,
+    buffers: &[Vec<u8>],
+    i_accessor: usize,
+) -> anyhow::Result<(Vec<f64>, usize)> {
+    let accessor = json
+        .array_of("accessors")
+        .get(i_accessor)
+        .with_context(|| format!("accessor {} not found", i_accessor))?;
+    if accessor.get("sparse").is_some() {
+        return Err(anyhow::anyhow!("sparse accessor is not supported"));
+    }
+    let count = accessor.usize_of("count")?;
+    let num_comp = match accessor.get("type").and_then(|v| v.as_str()) {
+        Some("SCALAR") => 1,
+        Some("VEC2") => 2,
+        Some("VEC3") => 3,
+        Some("VEC4") | Some("MAT2") => 4,
+        Some("MAT3") => 9,
+        Some("MAT4") => 16,
+        _ => return Err(anyhow::anyhow!("unknown type of accessor {}", i_accessor)),
+    };
+    let Some(i_view) = accessor.get("bufferView").and_then(|v| v.as_usize()) else {
+        // zeros of any count could be requested without the data backing them
+        return Err(anyhow::anyhow!(
+            "accessor {} without buffer view is not supported",
+            i_accessor
+        ));
+    };
+    let component_type = accessor.usize_of("componentType")?;
+    let normalized = accessor.get("normalized") == Some(&Json::Bool(true));
+    let comp_size = match component_type {
+        5120 | 5121 => 1,
+        5122 | 5123 => 2,
+        5125 | 5126 => 4,
+        _ => return Err(anyhow::anyhow!("unknown component type {}", component_type)),
+    };
+    let view = json
+        .array_of("bufferViews")
+        .get(i_view)
+        .with_context(|| format!("buffer view {} not found", i_view))?;
+    let buffer = buffers
+        .get(view.usize_of("buffer")?)
+        .with_context(|| format!("buffer of view {} not found", i_view))?;
+    let offset = view
+        .get("byteOffset")
+        .and_then(|v| v.as_usize())
+        .unwrap_or(0)
+        .checked_add(
+            accessor
+                .get("byteOffset")
+                .and_then(|v| v.as_usize())
+                .unwrap_or(0),
+        )
+        .context("byte offset overflows")?;
+    let elem_size = comp_size * num_comp;
+    let stride = view
+        .get("byteStride")
+        .and_then(|v| v.as_usize())
+        .unwrap_or(elem_size);
+    anyhow::ensure!(
+        stride >= elem_size,
+        "byte stride {} is smaller than the element",
+        stride
+    );
+    // the last element is in the buffer, so the count is bounded by the size of the buffer
+    let end = match count {
+        0 => Some(offset),
+        _ => (count - 1)
+            .checked_mul(stride)
+            .and_then(|v| v.checked_add(offset))
+            .and_then(|v| v.checked_add(elem_size)),
+    };
+    anyhow::ensure!(
+        end.is_some_and(|end| end <= buffer.len()),
+        "accessor {} exceeds the buffer",
+        i_accessor
+    );
+    let mut values = Vec::<f64>::with_capacity(count * num_comp);
+    for i_item in 0..count {
+        for i_comp in 0..num_comp {
+            let pos = offset + i_item * stride + i_comp * comp_size;
+            let Some(b) = buffer.get(pos..pos + comp_size) else {
+                return Err(anyhow::anyhow!(
+                    "accessor {} exceeds the buffer",
+                    i_accessor
+                ));
+            };
+            let v = match component_type {
+                5120 => {
+                    let v = b[0] as i8 as f64;
+                    if normalized {
+                        (v / 127.).max(-1.)
+                    } else {
+                        v
+                    }
+                }
+                5121 => {
+                    let v = b[0] as f64;
+                    if normalized {
+                        v / 255.
+                    } else {
+                        v
+                    }
+                }
+                5122 => {
+                    let v = i16::from_le_bytes([b[0], b[1]]) as f64;
+                    if normalized {
+                        (v / 32767.).max(-1.)
+                    } else {
+                        v
+                    }
+                }
+                5123 => {
+                    let v = u16::from_le_bytes([b[0], b[1]]) as f64;
+                    if normalized {
+                        v / 65535.
+                    } else {
+                        v
+                    }
+                }
+                5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            };
+            values.push(v);
+        }
+    }
+    Ok((values, num_comp))
+}
+
+/// column-major 4x4 matrix of a node
+fn node_transform(node: &Json) -> [f64; 16] {
+    if let Some(m) = node.f64s_of::<16>("matrix") {
+        return m;
+    }
+    let t = node.f64s_of::<3>("translation").unwrap_or([0.; 3]);
+    let [x, y, z, w] = node.f64s_of::<4>("rotation").unwrap_or([0., 0., 0., 1.]);
+    let s = node.f64s_of::<3>("scale").unwrap_or([1.; 3]);
+    let r = [
+        [
+            1. - 2. * (y * y + z * z),
+            2. * (x * y - z * w),
+            2. * (x * z + y * w),
+        ],
+        [
+            2. * (x * y + z * w),
+            1. - 2. * (x * x + z * z),
+            2. * (y * z - x * w),
+        ],
+        [
+            2. * (x * z - y * w),
+            2. * (y * z + x * w),
+            1. - 2. * (x * x + y * y),
+        ],
+    ];
+    let mut m = [0f64; 16];
+    for i in 0..3 {
+        for j in 0..3 {
+            m[i + 4 * j] = r[i][j] * s[j];
+        }
+        m[12 + i] = t[i];
+    }
+    m[15] = 1.;
+    m
+}
+
+fn mult_mat4(a: &[f64; 16], b: &[f64; 16]) -> [f64; 16] {
+    let mut c = [0f64; 16];
+    for i in 0..4 {
+        for j in 0..4 {
+            c[i + 4 * j] = (0..4).map(|k| a[i + 4 * k] * b[k + 4 * j]).sum();
+        }
+    }
+    c
+}
+
+fn transform_vec3(m: &[f64; 16], v: &[f64]) -> [f64; 3] {
+    std::array::from_fn(|i| m[i] * v[0] + m[i + 4] * v[1] + m[i + 8] * v[2])
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// gather the triangles of the nodes in the scene with their world transformation
+fn read_json<Index, Real>(json: &Json, buffers: &[Vec<u8>]) -> anyhow::Result<GltfMesh<Index, Real>>
+where
+    Index: Copy + 'static,
+    Real: Copy + 'static,
+    usize: AsPrimitive<Index>,
+    f64: AsPrimitive<Real>,
+{
+    let nodes = json.array_of("nodes");
+    let root_nodes: Vec<usize> = {
+        let scenes = json.array_of("scenes");
+        let i_scene = json.get("scene").and_then(|v| v.as_usize()).unwrap_or(0);
+        if let Some(scene) = scenes.get(i_scene) {
+            scene
+                .array_of("nodes")
+                .iter()
+                .filter_map(|v| v.as_usize())
+                .collect()
+        } else {
+            // without scenes, the nodes that are not children are the roots
+            let mut is_child = vec![false; nodes.len()];
+            for node in nodes {
+                for i_child in node
+                    .array_of("children")
+                    .iter()
+                    .filter_map(|v| v.as_usize())
+                {
+                    if i_child < nodes.len() {
+                        is_child[i_child] = true;
+                    }
+                }
+            }
+            (0..nodes.len()).filter(|&i| !is_child[i]).collect()
+        }
+    };
+    let mut node2world = vec![];
+    let mut stack: Vec<(usize, [f64; 16])> = root_nodes
+        .iter()
+        .rev()
+        .map(|&i_node| {
+            let mut identity = [0f64; 16];
+            (0..4).for_each(|i| identity[i * 5] = 1.);
+            (i_node, identity)
+        })
+        .collect();
+    while let Some((i_node, parent)) = stack.pop() {
+        if node2world.len() > nodes.len() {
+            return Err(anyhow::anyhow!("cyclic node hierarchy"));
+        }
+        let node = nodes
+            .get(i_node)
+            .with_context(|| format!("node {} not found", i_node))?;
+        let world = mult_mat4(&parent, &node_transform(node));
+        node2world.push((i_node, world));
+        for i_child in node
+            .array_of("children")
+            .iter()
+            .rev()
+            .filter_map(|v| v.as_usize())
+        {
+            stack.push((i_child, world));
+        }
+    }
+    //
+    let mut res = GltfMesh::<Index, Real>::new();
+    let mut vtx2xyz = Vec::<f64>::new();
+    let mut target2vtx2xyz = Vec::<Vec<f64>>::new();
+    let mut num_group = 0;
+    let mut node_with_targets = None;
+    for &(i_node, world) in &node2world {
+        let Some(i_mesh) = nodes[i_node].get("mesh").and_then(|v| v.as_usize()) else {
+            continue;
+        };
+        let mesh = json
+            .array_of("meshes")
+            .get(i_mesh)
+            .with_context(|| format!("mesh {} not found", i_mesh))?;
+        let cols = [0, 1, 2].map(|j| [world[4 * j], world[4 * j + 1], world[4 * j + 2]]);
+        let cof = [
+            cross(cols[1], cols[2]),
+            cross(cols[2], cols[0]),
+            cross(cols[0], cols[1]),
+        ];
+        let det = (0..3).map(|i| cols[0][i] * cof[0][i]).sum::<f64>();
+        // primitives sharing the same attributes share the vertices
+        let mut attributes2offset = Vec::<(&Json, &Json, usize)>::new();
+        for primitive in mesh.array_of("primitives") {
+            if primitive
+                .get("mode")
+                .and_then(|v| v.as_usize())
+                .unwrap_or(4)
+                != 4
+            {
+                continue; // only triangles
+            }
+            let attributes = primitive
+                .get("attributes")
+                .context("missing \"attributes\"")?;
+            let targets = primitive.get("targets").unwrap_or(&Json::Null);
+            let target_list = targets.as_array();
+            let vtx_offset = if let Some(&(_, _, offset)) = attributes2offset
+                .iter()
+                .find(|v| v.0 == attributes && v.1 == targets)
+            {
+                offset
+            } else {
+                let offset = vtx2xyz.len() / 3;
+                let (xyz, _) = read_accessor(json, buffers, attributes.usize_of("POSITION")?)?;
+                let num_vtx = xyz.len() / 3;
+                for p in xyz.chunks(3) {
+                    let q = transform_vec3(&world, p);
+                    vtx2xyz.extend((0..3).map(|i| q[i] + world[12 + i]));
+                }
+                if let Some(i_accessor) = attributes.get("NORMAL").and_then(|v| v.as_usize()) {
+                    let (nrm, _) = read_accessor(json, buffers, i_accessor)?;
+                    // normals are transformed by the cofactor matrix
+                    res.vtx2nrm.resize(offset * 3, 0f64.as_());
+                    for n in nrm.chunks(3) {
+                        let m: [f64; 3] = std::array::from_fn(|i| {
+                            cof[0][i] * n[0] + cof[1][i] * n[1] + cof[2][i] * n[2]
+                        });
+                        let len = (m[0] * m[0] + m[1] * m[1] + m[2] * m[2]).sqrt();
+                        let scale = if len > 0. { det.signum() / len } else { 0. };
+                        res.vtx2nrm.extend(m.iter().map(|&v| (v * scale).as_()));
+                    }
+                }
+                if let Some(i_accessor) = attributes.get("TEXCOORD_0").and_then(|v| v.as_usize()) {
+                    let (uv, _) = read_accessor(json, buffers, i_accessor)?;
+                    res.vtx2uv.resize(offset * 2, 0f64.as_());
+                    res.vtx2uv.extend(
+                        uv.chunks(2)
+                            .flat_map(|uv| [uv[0].as_(), (1. - uv[1]).as_()]),
+                    );
+                }
+                if let Some(i_accessor) = attributes.get("COLOR_0").and_then(|v| v.as_usize()) {
+                    let (rgb, num_comp) = read_accessor(json, buffers, i_accessor)?;
+                    res.vtx2rgb.resize(offset * 3, 0f64.as_());
+                    res.vtx2rgb.extend(
+                        rgb.chunks(num_comp)
+                            .flat_map(|c| [c[0].as_(), c[1].as_(), c[2].as_()]),
+                    );
+                }
+                if !target_list.is_empty() {
+                    node_with_targets.get_or_insert(i_node);
+                }
+                if target2vtx2xyz.len() < target_list.len() {
+                    target2vtx2xyz.resize(target_list.len(), vtx2xyz[..offset * 3].to_vec());
+                }
+                for (i_target, vtx2xyz_target) in target2vtx2xyz.iter_mut().enumerate() {
+                    let base = &vtx2xyz[offset * 3..];
+                    let i_accessor = target_list
+                        .get(i_target)
+                        .and_then(|t| t.get("POSITION"))
+                        .and_then(|v| v.as_usize());
+                    if let Some(i_accessor) = i_accessor {
+                        let (disp, _) = read_accessor(json, buffers, i_accessor)?;
+                        for (p, d) in base.chunks(3).zip(disp.chunks(3)) {
+                            let d = transform_vec3(&world, d);
+                            vtx2xyz_target.extend((0..3).map(|i| p[i] + d[i]));
+                        }
+                    } else {
+                        vtx2xyz_target.extend_from_slice(base);
+                    }
+                }
+                for vtx2val in [&mut res.vtx2nrm, &mut res.vtx2rgb] {
+                    if !vtx2val.is_empty() {
+                        vtx2val.resize((offset + num_vtx) * 3, 0f64.as_());
+                    }
+                }
+                if !res.vtx2uv.is_empty() {
+                    res.vtx2uv.resize((offset + num_vtx) * 2, 0f64.as_());
+                }
+                attributes2offset.push((attributes, targets, offset));
+                offset
+            };
+            let num_vtx = vtx2xyz.len() / 3;
+            let indices: Vec<usize> = match primitive.get("indices").and_then(|v| v.as_usize()) {
+                Some(i_accessor) => read_accessor(json, buffers, i_accessor)?
+                    .0
+                    .iter()
+                    .map(|&i| i as usize + vtx_offset)
+                    .collect(),
+                None => (vtx_offset..num_vtx).collect(),
+            };
+            for node2vtx in indices.chunks_exact(3) {
+                if node2vtx.iter().any(|&i_vtx| i_vtx >= num_vtx) {
+                    return Err(anyhow::anyhow!("index out of range in mesh {}", i_mesh));
+                }
+                // mirroring transformation flips the orientation
+                let node2vtx = if det < 0. {
+                    [node2vtx[0], node2vtx[2], node2vtx[1]]
+                } else {
+                    [node2vtx[0], node2vtx[1], node2vtx[2]]
+                };
+                res.tri2vtx.extend(node2vtx.map(|i_vtx| i_vtx.as_()));
+                res.tri2group.push(num_group);
+            }
+            num_group += 1;
+        }
+    }
+    // the vertices added after a shorter morph target keep the base coordinates
+    for vtx2xyz_target in target2vtx2xyz.iter_mut() {
+        let num = vtx2xyz_target.len();
+        vtx2xyz_target.extend_from_slice(&vtx2xyz[num..]);
+    }
+    if num_group <= 1 {
+        res.tri2group.clear();
+    }
+    res.vtx2xyz = vtx2xyz.iter().map(|&v| v.as_()).collect();
+    res.target2vtx2xyz = target2vtx2xyz
+        .iter()
+        .map(|v| v.iter().map(|&v| v.as_()).collect())
+        .collect();
+    // keyframes of the morph weights
+    if let Some(i_node) = node_with_targets {
+        for animation in json.array_of("animations") {
+            let channel = animation.array_of("channels").iter().find(|c| {
+                c.get("target").is_some_and(|t| {
+                    t.get("path").and_then(|v| v.as_str()) == Some("weights")
+                        && t.get("node").and_then(|v| v.as_usize()) == Some(i_node)
+                })
+            });
+            let Some(channel) = channel else {
+                continue;
+            };
+            let sampler = animation
+                .array_of("samplers")
+                .get(channel.usize_of("sampler")?)
+                .context("sampler not found")?;
+            let (times, _) = read_accessor(json, buffers, sampler.usize_of("input")?)?;
+            res.keyframe2time = times.iter().map(|&t| t as f32).collect();
+            break;
+        }
+    }
+    Ok(res)
+}
+
+/// buffers referred by the document. `bin` is the binary chunk of GLB
+fn load_buffers(
+    json: &Json,
+    mut bin: Option<Vec<u8>>,
+    dir: Option<&std::path::Path>,
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut buffers = vec![];
+    for (i_buffer, buffer) in json.array_of("buffers").iter().enumerate() {
+        let data = match buffer.get("uri").and_then(|v| v.as_str()) {
+            None => bin
+                .take()
+                .with_context(|| format!("buffer {} has no data", i_buffer))?,
+            Some(uri) if uri.starts_with("data:") => {
+                let (_, data) = uri.split_once(";base64,").context("unsupported data uri")?;
+                decode_base64(data)?
+            }
+            Some(uri) => {
+                let dir = dir.context("external buffer cannot be resolved")?;
+                let path = dir.join(uri.replace("%20", " "));
+                std::fs::read(&path)
+                    .with_context(|| format!("file not found: {}", path.display()))?
+            }
+        };
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+fn read_bytes<Index, Real>(
+    bytes: &[u8],
+    dir: Option<&std::path::Path>,
+) -> anyhow::Result<GltfMesh<Index, Real>>
+where
+    Index: Copy + 'static,
+    Real: Copy + 'static,
+    usize: AsPrimitive<Index>,
+    f64: AsPrimitive<Real>,
+{
+    if !bytes.starts_with(b"glTF") {
+        let json = Json::parse(std::str::from_utf8(bytes)?)?;
+        let buffers = load_buffers(&json, None, dir)?;
+        return read_json(&json, &buffers);
+    }
+    let u32_at = |pos: usize| -> anyhow::Result<usize> {
+        let b = bytes.get(pos..pos + 4).context("truncated GLB")?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+    if u32_at(4)? != 2 {
+        return Err(anyhow::anyhow!("unsupported GLB version {}", u32_at(4)?));
+    }
+    let length = u32_at(8)?.min(bytes.len());
+    let mut json = None;
+    let mut bin = None;
+    let mut pos = 12;
+    while pos + 8 <= length {
+        let chunk_length = u32_at(pos)?;
+        let chunk = bytes
+            .get(pos + 8..pos + 8 + chunk_length)
+            .context("truncated GLB")?;
+        match &bytes[pos + 4..pos + 8] {
+            b"JSON" => json = Some(Json::parse(std::str::from_utf8(chunk)?)?),
+            b"BIN\0" => bin = Some(chunk.to_vec()),
+            _ => {} // unknown chunk
+        }
+        pos += 8 + chunk_length;
+    }
+    let json = json.context("GLB without JSON chunk")?;
+    let buffers = load_buffers(&json, bin, dir)?;
+    read_json(&json, &buffers)
+}
+
+/// read binary glTF
+pub fn read_glb<R: std::io::Read, Index, Real>(
+    mut reader: R,
+) -> anyhow::Result<GltfMesh<Index, Real>>
+where
+    Index: Copy + 'static,
+    Real: Copy + 'static,
+    usize: AsPrimitive<Index>,
+    f64: AsPrimitive<Real>,
+{
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    read_bytes(&bytes, None)
+}
+
+/// load `.gltf` or `.glb` with all the triangle primitives in the scene flattened
+pub fn load<P, Index, Real>(path: P) -> anyhow::Result<GltfMesh<Index, Real>>
+where
+    P: AsRef<std::path::Path>,
+    Index: Copy + 'static,
+    Real: Copy + 'static,
+    usize: AsPrimitive<Index>,
+    f64: AsPrimitive<Real>,
+{
+    let path = path.as_ref();
+    let bytes =
+        std::fs::read(path).with_context(|| format!("file not found: {}", path.display()))?;
+    read_bytes(&bytes, path.parent())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io_gltf::{GltfMesh, Json};
+
+    #[test]
+    fn json() -> anyhow::Result<()> {
+        let s = r#" {"a": [1, -2.5e1, true, null], "b": {"c": "x\"é😀"}, "d": []} "#;
+        let json = Json::parse(s)?;
+        assert_eq!(json.get("a").unwrap().as_array()[1], Json::Number(-25.));
+        assert_eq!(
+            json.get("b").unwrap().get("c").unwrap().as_str(),
+            Some("x\"\u{e9}\u{1f600}")
+        );
+        assert_eq!(Json::parse(&json.stringify()?)?, json);
+        assert!(Json::Array(vec![Json::Number(f64::INFINITY)])
+            .stringify()
+            .is_err());
+        assert!(Json::parse("{\"a\": 1,}").is_err());
+        // unpaired high surrogate
+        let json = Json::parse(r#"{"a": "\uD800\u0041"}"#)?;
+        assert_eq!(json.get("a").unwrap().as_str(), Some("\u{FFFD}A"));
+        assert_eq!(crate::io_gltf::decode_base64("Zm9vYmE=")?, b"fooba");
+        Ok(())
+    }
+
+    #[test]
+    fn save_load() -> anyhow::Result<()> {
+        let (tri2vtx, vtx2xyz) =
+            crate::trimesh3_primitive::torus_zup::<usize, f32>(1.0, 0.3, 16, 8);
+        let num_vtx = vtx2xyz.len() / 3;
+        let mut mesh = GltfMesh::<usize, f32>::new();
+        mesh.vtx2xyz = vtx2xyz.clone();
+        mesh.vtx2uv = (0..num_vtx * 2).map(|i| (i % 7) as f32 * 0.125).collect();
+        mesh.vtx2rgb = (0..num_vtx * 3).map(|i| (i % 5) as f32 * 0.25).collect();
+        mesh.tri2group = (0..tri2vtx.len() / 3).map(|i_tri| i_tri % 2).collect();
+        mesh.tri2vtx = tri2vtx.clone();
+        for path in [
+            "../target/io_gltf_torus.gltf",
+            "../target/io_gltf_torus.glb",
+        ] {
+            mesh.save(path)?;
+            let mesh1 = crate::io_gltf::load::<_, usize, f32>(path)?;
+            assert_eq!(mesh1.vtx2xyz, mesh.vtx2xyz);
+            assert_eq!(mesh1.vtx2rgb, mesh.vtx2rgb);
+            assert_eq!(mesh1.vtx2uv, mesh.vtx2uv);
+            assert!(mesh1.vtx2nrm.is_empty());
+            // triangles are sorted by the primitive
+            assert_eq!(mesh1.tri2vtx.len(), tri2vtx.len());
+            assert_eq!(&mesh1.tri2vtx[..3], &tri2vtx[..3]);
+            assert_eq!(&mesh1.tri2vtx[3..6], &tri2vtx[6..9]);
+            assert_eq!(mesh1.tri2group[mesh1.tri2group.len() - 1], 1);
+        }
+        // the empty mesh cannot be written
+        let mut buf = vec![];
+        assert!(GltfMesh::<usize, f32>::new().write_glb(&mut buf).is_err());
+        // animation with morph targets
+        let frame2vtx2xyz: Vec<Vec<f32>> = (0..4)
+            .map(|i_frame| {
+                vtx2xyz
+                    .iter()
+                    .map(|&v| v * (1.0 + 0.25 * i_frame as f32))
+                    .collect()
+            })
+            .collect();
+        let anim = GltfMesh::from_frames(tri2vtx.clone(), frame2vtx2xyz.clone(), 0.5);
+        anim.save("../target/io_gltf_anim.glb")?;
+        let anim1 = crate::io_gltf::load::<_, usize, f64>("../target/io_gltf_anim.glb")?;
+        assert_eq!(anim1.keyframe2time, vec![0.0, 0.5, 1.0, 1.5]);
+        assert_eq!(anim1.target2vtx2xyz.len(), 3);
+        for (vtx2xyz0, vtx2xyz1) in frame2vtx2xyz[1..].iter().zip(anim1.target2vtx2xyz.iter()) {
+            for (&v0, &v1) in vtx2xyz0.iter().zip(vtx2xyz1.iter()) {
+                assert!((v0 as f64 - v1).abs() < 1.0e-5);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn node_transform() -> anyhow::Result<()> {
+        // triangle embedded in a data uri, instanced by a mirrored and translated node
+        let bin: Vec<u8> = [0f32, 0., 0., 1., 0., 0., 0., 1., 0.]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let uri = String::from_utf8(crate::io_vtk::encode_base64(&bin))?;
+        let doc = format!(
+            r#"{{"asset":{{"version":"2.0"}},
+"nodes":[{{"children":[1],"translation":[0,0,1]}},{{"mesh":0,"scale":[-1,1,1]}}],
+"meshes":[{{"primitives":[{{"attributes":{{"POSITION":0}}}}]}}],
+"accessors":[{{"bufferView":0,"componentType":5126,"count":3,"type":"VEC3"}}],
+"bufferViews":[{{"buffer":0,"byteLength":36}}],
+"buffers":[{{"byteLength":36,"uri":"data:application/octet-stream;base64,{}"}}]}}"#,
+            uri
+        );
+        let mesh = crate::io_gltf::read_bytes::<usize, f32>(doc.as_bytes(), None)?;
+        assert_eq!(mesh.vtx2xyz, vec![0., 0., 1., -1., 0., 1., 0., 1., 1.]);
+        assert_eq!(mesh.tri2vtx, vec![0, 2, 1]);
+        assert!(mesh.tri2group.is_empty());
+        // counts beyond the buffer
+        for count in ["4", "18446744073709551615"] {
+            let doc = doc.replace("\"count\":3", &format!("\"count\":{}", count));
+            assert!(crate::io_gltf::read_bytes::<usize, f32>(doc.as_bytes(), None).is_err());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+//! methods for ASPRS LAS (`.las`) point cloud files. The compressed LAZ is not supported
+
+use crate::vtx2point::PointCloud;
+use anyhow::Context;
+use num_traits::AsPrimitive;
+use std::io::{Read, Write};
+
+const NUM_BYTE_HEADER_V12: usize = 227;
+
+fn u16_at(b: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([b[i], b[i + 1]])
+}
+
+fn u32_at(b: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes(b[i..i + 4].try_into().unwrap())
+}
+
+fn i32_at(b: &[u8], i: usize) -> i32 {
+    i32::from_le_bytes(b[i..i + 4].try_into().unwrap())
+}
+
+fn f64_at(b: &[u8], i: usize) -> f64 {
+    f64::from_le_bytes(b[i..i + 8].try_into().unwrap())
+}
+
+/// read the LAS file of the version 1.0 to 1.4 and the point data record format 0 to 3 or 6 to 8.
+/// The 16-bit colors are converted to 8-bit
+pub fn read<R: Read, Real>(mut reader: R) -> anyhow::Result<PointCloud<Real>>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    anyhow::ensure!(
+        data.len() >= NUM_BYTE_HEADER_V12 && &data[0..4] == b"LASF",
+        "not a LAS file"
+    );
+    let version_minor = data[25];
+    let header_size = u16_at(&data, 94) as usize;
+    let offset_to_points = u32_at(&data, 96) as usize;
+    let point_format = data[104];
+    let record_length = u16_at(&data, 105) as usize;
+    let mut num_pnt = u32_at(&data, 107) as usize;
+    if version_minor >= 4 && header_size >= 375 && data.len() >= 255 {
+        // legacy count is zero for more than 2^32 points or the formats 6 to 10
+        num_pnt = usize::try_from(u64::from_le_bytes(data[247..255].try_into()?))?;
+    }
+    anyhow::ensure!(
+        point_format & 0x80 == 0,
+        "compressed LAZ (point format {}) is not supported",
+        point_format
+    );
+    let scale = [f64_at(&data, 131), f64_at(&data, 139), f64_at(&data, 147)];
+    let offset = [f64_at(&data, 155), f64_at(&data, 163), f64_at(&data, 171)];
+    // byte offset of the color and the minimum length of the record
+    let (offset_rgb, min_record_length) = match point_format {
+        0 => (None, 20),
+        1 => (None, 28),
+        2 => (Some(20), 26),
+        3 => (Some(28), 34),
+        4 => (None, 57),
+        5 => (Some(28), 63),
+        6 => (None, 30),
+        7 => (Some(30), 36),
+        8 => (Some(30), 38),
+        9 => (None, 59),
+        10 => (Some(30), 67),
+        _ => return Err(anyhow::anyhow!("unknown point format {}", point_format)),
+    };
+    anyhow::ensure!(
+        record_length >= min_record_length,
+        "record length {} is shorter than {} for the point format {}",
+        record_length,
+        min_record_length,
+        point_format
+    );
+    anyhow::ensure!(
+        offset_to_points <= data.len(),
+        "offset to the point data is beyond the end of the file"
+    );
+    anyhow::ensure!(
+        num_pnt
+            .checked_mul(record_length)
+            .is_some_and(|len| len <= data.len() - offset_to_points),
+        "too few point records"
+    );
+    let records: Vec<&[u8]> = data[offset_to_points..]
+        .chunks(record_length)
+        .take(num_pnt)
+        .collect();
+    let mut cloud = PointCloud::<Real>::new(Vec::with_capacity(num_pnt * 3));
+    let mut vtx2rgb16 = vec![];
+    for rec in &records {
+        for i_dim in 0..3 {
+            let v = i32_at(rec, i_dim * 4) as f64 * scale[i_dim] + offset[i_dim];
+            cloud.vtx2xyz.push(v.as_());
+        }
+        cloud.vtx2intensity.push((u16_at(rec, 12) as f64).as_());
+        if let Some(o) = offset_rgb {
+            vtx2rgb16.extend([u16_at(rec, o), u16_at(rec, o + 2), u16_at(rec, o + 4)]);
+        }
+    }
+    // the colors are 16-bit in the specification, but some writers store 8-bit values
+    let is_16bit = vtx2rgb16.iter().any(|&c| c > 255);
+    cloud.vtx2rgb = vtx2rgb16
+        .iter()
+        .map(|&c| if is_16bit { (c >> 8) as u8 } else { c as u8 })
+        .collect();
+    Ok(cloud)
+}
+
+pub fn load<P, Real>(path: P) -> anyhow::Result<PointCloud<Real>>
+where
+    P: AsRef<std::path::Path>,
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    read(std::io::BufReader::new(file))
+}
+
+/// write the LAS 1.2 file with the point format 2 if the points have colors and 0 otherwise.
+/// The coordinates are quantized with the scale of a billionth of the extent, and the intensity
+/// is clamped to [0, 65535]. The normals are not written
+pub fn write<W: Write, Real>(writer: &mut W, cloud: &PointCloud<Real>) -> anyhow::Result<()>
+where
+    Real: num_traits::Float + AsPrimitive<f64>,
+{
+    let num_pnt = cloud.num_vtx();
+    anyhow::ensure!(num_pnt <= u32::MAX as usize, "too many points for LAS 1.2");
+    let has_rgb = !cloud.vtx2rgb.is_empty();
+    let (point_format, record_length) = if has_rgb { (2u8, 26u16) } else { (0u8, 20u16) };
+    let mut min = [f64::MAX; 3];
+    let mut max = [f64::MIN; 3];
+    for xyz in cloud.vtx2xyz.chunks(3) {
+        for i_dim in 0..3 {
+            min[i_dim] = min[i_dim].min(xyz[i_dim].as_());
+            max[i_dim] = max[i_dim].max(xyz[i_dim].as_());
+        }
+    }
+    if num_pnt == 0 {
+        (min, max) = ([0.; 3], [0.; 3]);
+    }
+    let scale = std::array::from_fn::<f64, 3, _>(|i_dim| {
+        let extent = max[i_dim] - min[i_dim];
+        if extent > 0. {
+            extent / 1.0e9
+        } else {
+            1.0e-9
+        }
+    });
+    //
+    let mut h = Vec::<u8>::with_capacity(NUM_BYTE_HEADER_V12);
+    h.extend(b"LASF");
+    h.extend([0u8; 4]); // file source id, global encoding
+    h.extend([0u8; 16]); // project id
+    h.extend([1u8, 2u8]); // version
+    let mut name = [0u8; 32];
+    name[..7].copy_from_slice(b"del-msh");
+    h.extend(name); // system identifier
+    h.extend(name); // generating software
+    h.extend([0u8; 4]); // creation day and year
+    h.extend((NUM_BYTE_HEADER_V12 as u16).to_le_bytes());
+    h.extend((NUM_BYTE_HEADER_V12 as u32).to_le_bytes()); // offset to point data
+    h.extend(0u32.to_le_bytes()); // number of variable length records
+    h.push(point_format);
+    h.extend(record_length.to_le_bytes());
+    h.extend((num_pnt as u32).to_le_bytes());
+    h.extend((num_pnt as u32).to_le_bytes()); // by return 1
+    h.extend([0u8; 16]); // by return 2 to 5
+    scale.iter().for_each(|v| h.extend(v.to_le_bytes()));
+    min.iter().for_each(|v| h.extend(v.to_le_bytes()));
+    for i_dim in 0..3 {
+        h.extend(max[i_dim].to_le_bytes());
+        h.extend(min[i_dim].to_le_bytes());
+    }
+    assert_eq!(h.len(), NUM_BYTE_HEADER_V12);
+    writer.write_all(&h)?;
+    //
+    let mut rec = Vec::<u8>::with_capacity(record_length as usize);
+    for i_pnt in 0..num_pnt {
+        rec.clear();
+        for i_dim in 0..3 {
+            let v: f64 = cloud.vtx2xyz[i_pnt * 3 + i_dim].as_();
+            let v = ((v - min[i_dim]) / scale[i_dim]).round() as i32;
+            rec.extend(v.to_le_bytes());
+        }
+        let intensity = cloud
+            .vtx2intensity
+            .get(i_pnt)
+            .map_or(0., |v| v.as_().round().clamp(0., 65535.)) as u16;
+        rec.extend(intensity.to_le_bytes());
+        rec.push(0b0000_1001); // return number 1 of 1
+        rec.extend([1u8, 0, 0]); // classification unclassified, scan angle, user data
+        rec.extend(0u16.to_le_bytes()); // point source id
+        if has_rgb {
+            for &c in &cloud.vtx2rgb[i_pnt * 3..i_pnt * 3 + 3] {
+                rec.extend((c as u16 * 257).to_le_bytes());
+            }
+        }
+        writer.write_all(&rec)?;
+    }
+    Ok(())
+}
+
+pub fn save<P, Real>(path: P, cloud: &PointCloud<Real>) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    Real: num_traits::Float + AsPrimitive<f64>,
+{
+    let file = std::fs::File::create(path.as_ref())
+        .with_context(|| format!("cannot create {}", path.as_ref().display()))?;
+    let mut file = std::io::BufWriter::new(file);
+    write(&mut file, cloud)?;
+    file.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_read_write() -> anyhow::Result<()> {
+    let num_pnt = 100;
+    let mut cloud = PointCloud::<f64>::new(
+        (0..num_pnt * 3)
+            .map(|i| 1000. + (i as f64 * 0.37).sin() * 10.)
+            .collect(),
+    );
+    cloud.vtx2intensity = (0..num_pnt).map(|i| (i * 100) as f64).collect();
+    cloud.vtx2rgb = (0..num_pnt * 3).map(|i| (i * 7 % 256) as u8).collect();
+    let path = "../target/io_las.las";
+    save(path, &cloud)?;
+    let cloud1 = load::<_, f64>(path)?;
+    assert_eq!(cloud1.vtx2rgb, cloud.vtx2rgb);
+    assert_eq!(cloud1.vtx2intensity, cloud.vtx2intensity);
+    for (a, b) in cloud.vtx2xyz.iter().zip(cloud1.vtx2xyz.iter()) {
+        assert!((a - b).abs() < 1.0e-7, "{} {}", a, b);
+    }
+    // without colors
+    cloud.vtx2rgb.clear();
+    let mut buf = vec![];
+    write(&mut buf, &cloud)?;
+    assert_eq!(buf.len(), NUM_BYTE_HEADER_V12 + num_pnt * 20);
+    let cloud1 = read::<_, f32>(buf.as_slice())?;
+    assert!(cloud1.vtx2rgb.is_empty());
+    // broken headers
+    for (i_byte, bytes) in [
+        (105, 0u16.to_le_bytes().to_vec()),     // record length
+        (105, 12u16.to_le_bytes().to_vec()),    // record length
+        (96, u32::MAX.to_le_bytes().to_vec()),  // offset to points
+        (107, u32::MAX.to_le_bytes().to_vec()), // number of points
+    ] {
+        let mut buf = buf.clone();
+        buf[i_byte..i_byte + bytes.len()].copy_from_slice(&bytes);
+        assert!(read::<_, f32>(buf.as_slice()).is_err());
+    }
+    // LAZ
+    buf[104] |= 0x80;
+    assert!(read::<_, f32>(buf.as_slice()).is_err());
+    Ok(())
+}
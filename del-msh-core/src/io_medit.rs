@@ -0,0 +1,598 @@
+//! methods for INRIA Medit files (`.mesh` and `.sol`, ASCII)
+
+use anyhow::Context;
+use num_traits::AsPrimitive;
+use std::io::Write;
+
+/// mesh in Medit `.mesh` file. Indices are zero-based and the reference ids work as group arrays
+#[derive(Clone, Debug)]
+pub struct MeditMesh<Index, Real> {
+    /// two or three
+    pub num_dim: usize,
+    pub vtx2xyz: Vec<Real>,
+    pub vtx2ref: Vec<usize>,
+    pub edge2vtx: Vec<Index>,
+    pub edge2ref: Vec<usize>,
+    pub tri2vtx: Vec<Index>,
+    pub tri2ref: Vec<usize>,
+    pub quad2vtx: Vec<Index>,
+    pub quad2ref: Vec<usize>,
+    pub tet2vtx: Vec<Index>,
+    pub tet2ref: Vec<usize>,
+    pub hex2vtx: Vec<Index>,
+    pub hex2ref: Vec<usize>,
+    /// vertices in `Corners`
+    pub corners: Vec<Index>,
+    /// edges in `Ridges`
+    pub ridges: Vec<Index>,
+    /// vertices in `RequiredVertices`
+    pub required_vertices: Vec<Index>,
+}
+
+impl<Index, Real> MeditMesh<Index, Real> {
+    pub fn new(num_dim: usize) -> Self {
+        MeditMesh {
+            num_dim,
+            vtx2xyz: vec![],
+            vtx2ref: vec![],
+            edge2vtx: vec![],
+            edge2ref: vec![],
+            tri2vtx: vec![],
+            tri2ref: vec![],
+            quad2vtx: vec![],
+            quad2ref: vec![],
+            tet2vtx: vec![],
+            tet2ref: vec![],
+            hex2vtx: vec![],
+            hex2ref: vec![],
+            corners: vec![],
+            ridges: vec![],
+            required_vertices: vec![],
+        }
+    }
+}
+
+/// type of the field in `.sol` file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolType {
+    Scalar = 1,
+    Vector = 2,
+    /// symmetric tensor (e.g., metric) stored as its upper triangle
+    Tensor = 3,
+}
+
+impl SolType {
+    pub fn num_comp(&self, num_dim: usize) -> usize {
+        match self {
+            SolType::Scalar => 1,
+            SolType::Vector => num_dim,
+            SolType::Tensor => num_dim * (num_dim + 1) / 2,
+        }
+    }
+}
+
+/// per-vertex fields in `SolAtVertices` of `.sol` file
+#[derive(Clone, Debug)]
+pub struct MeditSol<Real> {
+    pub num_dim: usize,
+    pub num_vtx: usize,
+    /// type and values (`num_vtx * num_comp`) of each field
+    pub fields: Vec<(SolType, Vec<Real>)>,
+}
+
+/// whitespace-separated tokens with comments removed
+struct Tokens {
+    tokens: Vec<(usize, String)>,
+    pos: usize,
+}
+
+impl Tokens {
+    fn new<R: std::io::BufRead>(reader: R) -> anyhow::Result<Self> {
+        let mut tokens = vec![];
+        for (i_line, line) in reader.lines().enumerate() {
+            let line = line?;
+            let content = line.split('#').next().unwrap();
+            for token in content.split_whitespace() {
+                tokens.push((i_line + 1, token.to_string()));
+            }
+        }
+        Ok(Tokens { tokens, pos: 0 })
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        let token = self.tokens.get(self.pos)?;
+        self.pos += 1;
+        Some(&token.1)
+    }
+
+    /// line number of the current token
+    fn i_line(&self) -> usize {
+        self.tokens
+            .get(self.pos.saturating_sub(1))
+            .map_or(0, |t| t.0)
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self) -> anyhow::Result<T> {
+        let Some(token) = self.next() else {
+            return Err(anyhow::anyhow!("unexpected end of file"));
+        };
+        token
+            .parse::<T>()
+            .ok()
+            .with_context(|| format!("cannot parse \"{}\"", token))
+    }
+
+    /// one-based index in the file to zero-based index
+    fn index<Index>(&mut self, num: usize) -> anyhow::Result<Index>
+    where
+        Index: Copy + 'static,
+        usize: AsPrimitive<Index>,
+    {
+        let i: usize = self.parse()?;
+        if i == 0 || i > num {
+            return Err(anyhow::anyhow!("index {} is out of range", i));
+        }
+        Ok((i - 1).as_())
+    }
+}
+
+fn read_elements<Index>(
+    tokens: &mut Tokens,
+    num_node: usize,
+    num_vtx: usize,
+    elem2vtx: &mut Vec<Index>,
+    elem2ref: &mut Vec<usize>,
+) -> anyhow::Result<()>
+where
+    Index: Copy + 'static,
+    usize: AsPrimitive<Index>,
+{
+    let num_elem: usize = tokens.parse()?;
+    for _ in 0..num_elem {
+        for _ in 0..num_node {
+            elem2vtx.push(tokens.index(num_vtx)?);
+        }
+        elem2ref.push(tokens.parse()?);
+    }
+    Ok(())
+}
+
+fn read_indices<Index>(
+    tokens: &mut Tokens,
+    num: usize,
+    indices: &mut Vec<Index>,
+) -> anyhow::Result<()>
+where
+    Index: Copy + 'static,
+    usize: AsPrimitive<Index>,
+{
+    let num_index: usize = tokens.parse()?;
+    for _ in 0..num_index {
+        indices.push(tokens.index(num)?);
+    }
+    Ok(())
+}
+
+/// number of tokens per entry of the keywords that are read but discarded
+fn num_token_of_skipped_keyword(keyword: &str, num_dim: usize) -> Option<usize> {
+    let num = match keyword {
+        "Normals" | "Tangents" => num_dim,
+        "NormalAtVertices" | "TangentAtVertices" => 2,
+        "NormalAtTriangleVertices" | "TangentAtEdgeVertices" => 3,
+        "NormalAtQuadrilateralVertices" => 3,
+        "RequiredEdges" | "RequiredTriangles" | "RequiredQuadrilaterals" | "RequiredTetrahedra" => {
+            1
+        }
+        "Pyramids" => 6,
+        "Prisms" => 7,
+        _ => return None,
+    };
+    Some(num)
+}
+
+fn read_version_and_dimension(tokens: &mut Tokens, keyword: &str) -> anyhow::Result<Option<usize>> {
+    match keyword {
+        "MeshVersionFormatted" => {
+            let _version: usize = tokens.parse()?;
+            Ok(None)
+        }
+        "Dimension" => {
+            let num_dim: usize = tokens.parse()?;
+            if num_dim != 2 && num_dim != 3 {
+                return Err(anyhow::anyhow!("unsupported dimension {}", num_dim));
+            }
+            Ok(Some(num_dim))
+        }
+        _ => Err(anyhow::anyhow!("unknown keyword \"{}\"", keyword)),
+    }
+}
+
+/// read Medit `.mesh` file
+pub fn read_mesh<R: std::io::BufRead, Index, Real>(
+    reader: R,
+) -> anyhow::Result<MeditMesh<Index, Real>>
+where
+    Index: Copy + 'static,
+    Real: std::str::FromStr,
+    usize: AsPrimitive<Index>,
+{
+    let mut tokens = Tokens::new(reader)?;
+    let mut mesh = MeditMesh::<Index, Real>::new(3);
+    while let Some(keyword) = tokens.next() {
+        let keyword = keyword.to_string();
+        let i_line = tokens.i_line();
+        let res: anyhow::Result<bool> = (|| {
+            let num_vtx = mesh.vtx2ref.len();
+            match keyword.as_str() {
+                "End" => return Ok(true),
+                "Vertices" => {
+                    let num_vtx: usize = tokens.parse()?;
+                    for _ in 0..num_vtx {
+                        for _ in 0..mesh.num_dim {
+                            mesh.vtx2xyz.push(tokens.parse()?);
+                        }
+                        mesh.vtx2ref.push(tokens.parse()?);
+                    }
+                }
+                "Edges" => read_elements(
+                    &mut tokens,
+                    2,
+                    num_vtx,
+                    &mut mesh.edge2vtx,
+                    &mut mesh.edge2ref,
+                )?,
+                "Triangles" => read_elements(
+                    &mut tokens,
+                    3,
+                    num_vtx,
+                    &mut mesh.tri2vtx,
+                    &mut mesh.tri2ref,
+                )?,
+                "Quadrilaterals" => read_elements(
+                    &mut tokens,
+                    4,
+                    num_vtx,
+                    &mut mesh.quad2vtx,
+                    &mut mesh.quad2ref,
+                )?,
+                "Tetrahedra" => read_elements(
+                    &mut tokens,
+                    4,
+                    num_vtx,
+                    &mut mesh.tet2vtx,
+                    &mut mesh.tet2ref,
+                )?,
+                "Hexahedra" => read_elements(
+                    &mut tokens,
+                    8,
+                    num_vtx,
+                    &mut mesh.hex2vtx,
+                    &mut mesh.hex2ref,
+                )?,
+                "Corners" => read_indices(&mut tokens, num_vtx, &mut mesh.corners)?,
+                "RequiredVertices" => {
+                    read_indices(&mut tokens, num_vtx, &mut mesh.required_vertices)?
+                }
+                "Ridges" => {
+                    let num_edge = mesh.edge2ref.len();
+                    read_indices(&mut tokens, num_edge, &mut mesh.ridges)?
+                }
+                _ => {
+                    if let Some(num_token) = num_token_of_skipped_keyword(&keyword, mesh.num_dim) {
+                        let num: usize = tokens.parse()?;
+                        let num_token = num.checked_mul(num_token).context("too many entries")?;
+                        for _ in 0..num_token {
+                            tokens.next().context("unexpected end of file")?;
+                        }
+                    } else if let Some(num_dim) = read_version_and_dimension(&mut tokens, &keyword)?
+                    {
+                        mesh.num_dim = num_dim;
+                    }
+                }
+            }
+            Ok(false)
+        })();
+        let is_end = res.with_context(|| format!("line {}: {}", i_line, keyword))?;
+        if is_end {
+            break;
+        }
+    }
+    Ok(mesh)
+}
+
+pub fn load_mesh<P, Index, Real>(path: P) -> anyhow::Result<MeditMesh<Index, Real>>
+where
+    P: AsRef<std::path::Path>,
+    Index: Copy + 'static,
+    Real: std::str::FromStr,
+    usize: AsPrimitive<Index>,
+{
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    read_mesh(std::io::BufReader::new(file))
+}
+
+fn write_elements<W: Write, Index>(
+    writer: &mut W,
+    keyword: &str,
+    num_node: usize,
+    elem2vtx: &[Index],
+    elem2ref: &[usize],
+) -> anyhow::Result<()>
+where
+    Index: AsPrimitive<usize>,
+{
+    let num_elem = elem2vtx.len() / num_node;
+    if num_elem == 0 {
+        return Ok(());
+    }
+    writeln!(writer, "\n{}\n{}", keyword, num_elem)?;
+    for (i_elem, node2vtx) in elem2vtx.chunks(num_node).enumerate() {
+        for &i_vtx in node2vtx {
+            write!(writer, "{} ", i_vtx.as_() + 1)?;
+        }
+        writeln!(writer, "{}", elem2ref.get(i_elem).copied().unwrap_or(0))?;
+    }
+    Ok(())
+}
+
+fn write_indices<W: Write, Index>(
+    writer: &mut W,
+    keyword: &str,
+    indices: &[Index],
+) -> anyhow::Result<()>
+where
+    Index: AsPrimitive<usize>,
+{
+    if indices.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "\n{}\n{}", keyword, indices.len())?;
+    for &i in indices {
+        writeln!(writer, "{}", i.as_() + 1)?;
+    }
+    Ok(())
+}
+
+impl<Index, Real> MeditMesh<Index, Real>
+where
+    Index: AsPrimitive<usize>,
+    Real: std::fmt::Display,
+{
+    pub fn write<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        writeln!(writer, "MeshVersionFormatted 2")?;
+        writeln!(writer, "\nDimension {}", self.num_dim)?;
+        let num_vtx = self.vtx2xyz.len() / self.num_dim;
+        writeln!(writer, "\nVertices\n{}", num_vtx)?;
+        for (i_vtx, xyz) in self.vtx2xyz.chunks(self.num_dim).enumerate() {
+            for v in xyz {
+                write!(writer, "{} ", v)?;
+            }
+            writeln!(writer, "{}", self.vtx2ref.get(i_vtx).copied().unwrap_or(0))?;
+        }
+        write_elements(writer, "Edges", 2, &self.edge2vtx, &self.edge2ref)?;
+        write_elements(writer, "Triangles", 3, &self.tri2vtx, &self.tri2ref)?;
+        write_elements(writer, "Quadrilaterals", 4, &self.quad2vtx, &self.quad2ref)?;
+        write_elements(writer, "Tetrahedra", 4, &self.tet2vtx, &self.tet2ref)?;
+        write_elements(writer, "Hexahedra", 8, &self.hex2vtx, &self.hex2ref)?;
+        write_indices(writer, "Corners", &self.corners)?;
+        write_indices(writer, "Ridges", &self.ridges)?;
+        write_indices(writer, "RequiredVertices", &self.required_vertices)?;
+        writeln!(writer, "\nEnd")?;
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path.as_ref())
+            .with_context(|| format!("cannot create {}", path.as_ref().display()))?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// save triangle mesh as Medit `.mesh` file
+pub fn save_tri2vtx_vtx2xyz<P, Index, Real>(
+    path: P,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+    num_dim: usize,
+) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    Index: AsPrimitive<usize> + Copy,
+    Real: std::fmt::Display + Copy,
+{
+    let mut mesh = MeditMesh::new(num_dim);
+    mesh.vtx2xyz = vtx2xyz.to_vec();
+    mesh.tri2vtx = tri2vtx.to_vec();
+    mesh.save(path)
+}
+
+/// read Medit `.sol` file with `SolAtVertices`
+pub fn read_sol<R: std::io::BufRead, Real>(reader: R) -> anyhow::Result<MeditSol<Real>>
+where
+    Real: std::str::FromStr,
+{
+    let mut tokens = Tokens::new(reader)?;
+    let mut sol = MeditSol::<Real> {
+        num_dim: 3,
+        num_vtx: 0,
+        fields: vec![],
+    };
+    while let Some(keyword) = tokens.next() {
+        let keyword = keyword.to_string();
+        let i_line = tokens.i_line();
+        let res: anyhow::Result<bool> = (|| {
+            match keyword.as_str() {
+                "End" => return Ok(true),
+                "SolAtVertices" => {
+                    sol.num_vtx = tokens.parse()?;
+                    let num_field: usize = tokens.parse()?;
+                    let mut field2type = vec![];
+                    for _ in 0..num_field {
+                        let sol_type = match tokens.parse::<usize>()? {
+                            1 => SolType::Scalar,
+                            2 => SolType::Vector,
+                            3 => SolType::Tensor,
+                            t => return Err(anyhow::anyhow!("unknown solution type {}", t)),
+                        };
+                        field2type.push(sol_type);
+                    }
+                    // the values grow as they are read since the count is not trusted
+                    sol.fields = field2type.iter().map(|&t| (t, vec![])).collect();
+                    // values are interleaved for each vertex
+                    for _ in 0..sol.num_vtx {
+                        for (sol_type, values) in sol.fields.iter_mut() {
+                            for _ in 0..sol_type.num_comp(sol.num_dim) {
+                                values.push(tokens.parse()?);
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(num_dim) = read_version_and_dimension(&mut tokens, &keyword)? {
+                        sol.num_dim = num_dim;
+                    }
+                }
+            }
+            Ok(false)
+        })();
+        let is_end = res.with_context(|| format!("line {}: {}", i_line, keyword))?;
+        if is_end {
+            break;
+        }
+    }
+    Ok(sol)
+}
+
+pub fn load_sol<P, Real>(path: P) -> anyhow::Result<MeditSol<Real>>
+where
+    P: AsRef<std::path::Path>,
+    Real: std::str::FromStr,
+{
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    read_sol(std::io::BufReader::new(file))
+}
+
+impl<Real> MeditSol<Real>
+where
+    Real: std::fmt::Display,
+{
+    pub fn write<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        writeln!(writer, "MeshVersionFormatted 2")?;
+        writeln!(writer, "\nDimension {}", self.num_dim)?;
+        writeln!(writer, "\nSolAtVertices\n{}", self.num_vtx)?;
+        write!(writer, "{}", self.fields.len())?;
+        for (sol_type, _) in &self.fields {
+            write!(writer, " {}", *sol_type as usize)?;
+        }
+        writeln!(writer)?;
+        for i_vtx in 0..self.num_vtx {
+            let mut is_first = true;
+            for (sol_type, values) in &self.fields {
+                let num_comp = sol_type.num_comp(self.num_dim);
+                for v in &values[i_vtx * num_comp..(i_vtx + 1) * num_comp] {
+                    if !is_first {
+                        write!(writer, " ")?;
+                    }
+                    write!(writer, "{}", v)?;
+                    is_first = false;
+                }
+            }
+            writeln!(writer)?;
+        }
+        writeln!(writer, "\nEnd")?;
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path.as_ref())
+            .with_context(|| format!("cannot create {}", path.as_ref().display()))?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_mesh_sol() -> anyhow::Result<()> {
+    let txt = "MeshVersionFormatted 2
+Dimension
+3
+# a tetrahedron and its faces
+Vertices
+4
+0 0 0 1
+1 0 0 1
+0 1 0 2
+0 0 1 2
+Edges
+1
+1 2 5
+Triangles
+2
+1 3 2 10
+1 2 4 11
+Tetrahedra
+1
+1 2 3 4 100
+Corners
+1
+4
+Ridges
+1
+1
+NormalAtVertices
+1
+1 1
+Normals
+1
+0 0 1
+End
+";
+    let mesh = read_mesh::<_, usize, f64>(std::io::Cursor::new(txt))?;
+    assert_eq!(mesh.num_dim, 3);
+    assert_eq!(mesh.vtx2ref, vec![1, 1, 2, 2]);
+    assert_eq!(mesh.tri2vtx, vec![0, 2, 1, 0, 1, 3]);
+    assert_eq!(mesh.tri2ref, vec![10, 11]);
+    assert_eq!(mesh.tet2vtx, vec![0, 1, 2, 3]);
+    assert_eq!(mesh.corners, vec![3]);
+    assert_eq!(mesh.ridges, vec![0]);
+    mesh.save("../target/io_medit_tet.mesh")?;
+    let mesh1 = load_mesh::<_, usize, f64>("../target/io_medit_tet.mesh")?;
+    assert_eq!(mesh.vtx2xyz, mesh1.vtx2xyz);
+    assert_eq!(mesh.edge2vtx, mesh1.edge2vtx);
+    assert_eq!(mesh.edge2ref, mesh1.edge2ref);
+    assert_eq!(mesh.tri2ref, mesh1.tri2ref);
+    assert_eq!(mesh.tet2ref, mesh1.tet2ref);
+    assert_eq!(mesh.ridges, mesh1.ridges);
+    //
+    let err = read_mesh::<_, usize, f64>(std::io::Cursor::new(
+        "Dimension 2\nVertices\n1\n0 0 1\nTriangles\n1\n1 2 3 0\n",
+    ))
+    .unwrap_err();
+    assert!(format!("{:#}", err).contains("line 5"));
+    //
+    let sol = MeditSol {
+        num_dim: 2,
+        num_vtx: 2,
+        fields: vec![
+            (SolType::Scalar, vec![0.5f32, 1.5]),
+            (SolType::Tensor, vec![1., 0., 1., 2., 0.5, 2.]),
+        ],
+    };
+    sol.save("../target/io_medit_metric.sol")?;
+    let sol1 = load_sol::<_, f32>("../target/io_medit_metric.sol")?;
+    assert_eq!(sol1.num_dim, 2);
+    assert_eq!(sol1.fields, sol.fields);
+    // counts beyond the data
+    let txt = "Dimension 3\nNormals\n6148914691236517206\n0 0 1\n";
+    assert!(read_mesh::<_, usize, f64>(std::io::Cursor::new(txt)).is_err());
+    let txt = "Dimension 3\nSolAtVertices\n18446744073709551615\n1 3\n0 0 0 0 0 0\n";
+    assert!(read_sol::<_, f64>(std::io::Cursor::new(txt)).is_err());
+    Ok(())
+}
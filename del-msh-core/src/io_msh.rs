@@ -0,0 +1,852 @@
+//! methods for Gmsh MSH files (version 2.2 and 4.1, ASCII and binary)
+
+use anyhow::Context;
+use num_traits::AsPrimitive;
+use std::io::Write;
+
+/// element types of Gmsh (first order only)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GmshElementType {
+    Line = 1,
+    Triangle = 2,
+    Quad = 3,
+    Tetrahedron = 4,
+    Hexahedron = 5,
+    Prism = 6,
+    Pyramid = 7,
+    Point = 15,
+}
+
+impl GmshElementType {
+    pub fn from_id(id: usize) -> Option<Self> {
+        use GmshElementType::*;
+        let elem_type = match id {
+            1 => Line,
+            2 => Triangle,
+            3 => Quad,
+            4 => Tetrahedron,
+            5 => Hexahedron,
+            6 => Prism,
+            7 => Pyramid,
+            15 => Point,
+            _ => return None,
+        };
+        Some(elem_type)
+    }
+
+    pub fn num_node(&self) -> usize {
+        use GmshElementType::*;
+        match self {
+            Point => 1,
+            Line => 2,
+            Triangle => 3,
+            Quad | Tetrahedron => 4,
+            Pyramid => 5,
+            Prism => 6,
+            Hexahedron => 8,
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        use GmshElementType::*;
+        match self {
+            Point => 0,
+            Line => 1,
+            Triangle | Quad => 2,
+            Tetrahedron | Hexahedron | Prism | Pyramid => 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+    V2,
+    V4,
+}
+
+/// mesh in Gmsh MSH file with mixed element types
+#[derive(Clone, Debug)]
+pub struct GmshMesh<Index, Real> {
+    /// three dimensional coordinates
+    pub vtx2xyz: Vec<Real>,
+    /// node tags in the file
+    pub vtx2tag: Vec<usize>,
+    pub elem2idx: Vec<Index>,
+    pub idx2vtx: Vec<Index>,
+    pub elem2type: Vec<GmshElementType>,
+    /// element tags in the file
+    pub elem2tag: Vec<usize>,
+    /// physical tag of the element. Zero if the element does not belong to a physical group
+    pub elem2physical: Vec<usize>,
+    /// tag of the geometrical entity of the element
+    pub elem2entity: Vec<usize>,
+    /// `(dimension, physical tag, name)` in `$PhysicalNames`
+    pub physical_names: Vec<(usize, usize, String)>,
+}
+
+impl<Index, Real> GmshMesh<Index, Real>
+where
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display + 'static,
+    usize: AsPrimitive<Index>,
+    f64: AsPrimitive<Real>,
+{
+    pub fn new() -> Self {
+        GmshMesh {
+            vtx2xyz: vec![],
+            vtx2tag: vec![],
+            elem2idx: vec![Index::zero()],
+            idx2vtx: vec![],
+            elem2type: vec![],
+            elem2tag: vec![],
+            elem2physical: vec![],
+            elem2entity: vec![],
+            physical_names: vec![],
+        }
+    }
+
+    /// mesh with a single element type (e.g., `tri2vtx`). `num_dim` of `vtx2xyz` is two or three.
+    /// All the elements belong to the entity `1` and to no physical group
+    pub fn from_uniform_mesh(
+        elem_type: GmshElementType,
+        elem2vtx: &[Index],
+        vtx2xyz: &[Real],
+        num_dim: usize,
+    ) -> Self {
+        let num_node = elem_type.num_node();
+        let num_elem = elem2vtx.len() / num_node;
+        let num_vtx = vtx2xyz.len() / num_dim;
+        let mut mesh = Self::new();
+        mesh.vtx2xyz = (0..num_vtx)
+            .flat_map(|i_vtx| {
+                let xyz = &vtx2xyz[i_vtx * num_dim..i_vtx * num_dim + num_dim];
+                [xyz[0], xyz[1], xyz.get(2).copied().unwrap_or(Real::zero())]
+            })
+            .collect();
+        mesh.vtx2tag = (1..num_vtx + 1).collect();
+        mesh.elem2idx = (0..num_elem + 1).map(|i| (i * num_node).as_()).collect();
+        mesh.idx2vtx = elem2vtx.to_vec();
+        mesh.elem2type = vec![elem_type; num_elem];
+        mesh.elem2tag = (1..num_elem + 1).collect();
+        mesh.elem2physical = vec![0; num_elem];
+        mesh.elem2entity = vec![1; num_elem];
+        mesh
+    }
+
+    pub fn num_elem(&self) -> usize {
+        self.elem2type.len()
+    }
+
+    /// flattened connectivity of the elements of the given type (e.g., `tri2vtx` for triangles)
+    pub fn elem2vtx_of_type(&self, elem_type: GmshElementType) -> Vec<Index> {
+        let mut elem2vtx = vec![];
+        for (i_elem, &t) in self.elem2type.iter().enumerate() {
+            if t == elem_type {
+                let idx0 = self.elem2idx[i_elem].as_();
+                let idx1 = self.elem2idx[i_elem + 1].as_();
+                elem2vtx.extend_from_slice(&self.idx2vtx[idx0..idx1]);
+            }
+        }
+        elem2vtx
+    }
+
+    /// number of groups and the group index of each element, where the groups are the
+    /// distinct physical tags in ascending order
+    pub fn elem2group(&self) -> (usize, Vec<usize>) {
+        let mut group2physical = self.elem2physical.clone();
+        group2physical.sort();
+        group2physical.dedup();
+        let elem2group = self
+            .elem2physical
+            .iter()
+            .map(|p| group2physical.binary_search(p).unwrap())
+            .collect();
+        (group2physical.len(), elem2group)
+    }
+
+    fn write_header<W: Write>(&self, writer: &mut W, version: &str) -> anyhow::Result<()> {
+        writeln!(writer, "$MeshFormat")?;
+        writeln!(writer, "{} 0 8", version)?;
+        writeln!(writer, "$EndMeshFormat")?;
+        if !self.physical_names.is_empty() {
+            writeln!(writer, "$PhysicalNames")?;
+            writeln!(writer, "{}", self.physical_names.len())?;
+            for (dim, tag, name) in &self.physical_names {
+                writeln!(writer, "{} {} \"{}\"", dim, tag, name)?;
+            }
+            writeln!(writer, "$EndPhysicalNames")?;
+        }
+        Ok(())
+    }
+
+    fn vtx2tag(&self, i_vtx: usize) -> usize {
+        self.vtx2tag.get(i_vtx).copied().unwrap_or(i_vtx + 1)
+    }
+
+    fn elem2tag(&self, i_elem: usize) -> usize {
+        self.elem2tag.get(i_elem).copied().unwrap_or(i_elem + 1)
+    }
+
+    fn elem2vtx(&self, i_elem: usize) -> &[Index] {
+        &self.idx2vtx[self.elem2idx[i_elem].as_()..self.elem2idx[i_elem + 1].as_()]
+    }
+
+    /// write ASCII MSH file
+    pub fn write<W: Write>(&self, writer: &mut W, version: Version) -> anyhow::Result<()> {
+        match version {
+            Version::V2 => self.write_v2(writer),
+            Version::V4 => self.write_v4(writer),
+        }
+    }
+
+    fn write_v2<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        self.write_header(writer, "2.2")?;
+        writeln!(writer, "$Nodes")?;
+        writeln!(writer, "{}", self.vtx2xyz.len() / 3)?;
+        for (i_vtx, xyz) in self.vtx2xyz.chunks(3).enumerate() {
+            let tag = self.vtx2tag(i_vtx);
+            writeln!(writer, "{} {} {} {}", tag, xyz[0], xyz[1], xyz[2])?;
+        }
+        writeln!(writer, "$EndNodes")?;
+        writeln!(writer, "$Elements")?;
+        writeln!(writer, "{}", self.num_elem())?;
+        for i_elem in 0..self.num_elem() {
+            write!(
+                writer,
+                "{} {} 2 {} {}",
+                self.elem2tag(i_elem),
+                self.elem2type[i_elem] as usize,
+                self.elem2physical[i_elem],
+                self.elem2entity[i_elem]
+            )?;
+            for &i_vtx in self.elem2vtx(i_elem) {
+                write!(writer, " {}", self.vtx2tag(i_vtx.as_()))?;
+            }
+            writeln!(writer)?;
+        }
+        writeln!(writer, "$EndElements")?;
+        Ok(())
+    }
+
+    /// elements are written in blocks of the same entity and type
+    fn write_v4<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        self.write_header(writer, "4.1")?;
+        // an entity has only one physical tag, so the elements of an entity with another
+        // physical tag are moved to a new entity
+        let mut dim2max_entity = [0usize; 4];
+        for i_elem in 0..self.num_elem() {
+            let dim = self.elem2type[i_elem].dim();
+            dim2max_entity[dim] = dim2max_entity[dim].max(self.elem2entity[i_elem]);
+        }
+        let mut key2entity = std::collections::HashMap::<(usize, usize, usize), usize>::new();
+        let mut entities_used = std::collections::HashSet::<(usize, usize)>::new();
+        let elem2entity: Vec<usize> = (0..self.num_elem())
+            .map(|i_elem| {
+                let dim = self.elem2type[i_elem].dim();
+                let entity = self.elem2entity[i_elem];
+                let physical = self.elem2physical[i_elem];
+                *key2entity
+                    .entry((dim, entity, physical))
+                    .or_insert_with(|| match entities_used.insert((dim, entity)) {
+                        true => entity,
+                        false => {
+                            dim2max_entity[dim] += 1;
+                            dim2max_entity[dim]
+                        }
+                    })
+            })
+            .collect();
+        // blocks of (dimension, entity tag, element type) in the order of appearance
+        let mut blocks = Vec::<(usize, usize, GmshElementType)>::new();
+        let mut block2elems = Vec::<Vec<usize>>::new();
+        for (i_elem, &entity) in elem2entity.iter().enumerate() {
+            let t = self.elem2type[i_elem];
+            let key = (t.dim(), entity, t);
+            let i_block = match blocks.iter().position(|b| *b == key) {
+                Some(i_block) => i_block,
+                None => {
+                    blocks.push(key);
+                    block2elems.push(vec![]);
+                    blocks.len() - 1
+                }
+            };
+            block2elems[i_block].push(i_elem);
+        }
+        // entities with their bounding box and physical tag
+        let mut dim2entities: [Vec<(usize, [Real; 6], usize)>; 4] = Default::default();
+        for ((dim, entity, _), elems) in blocks.iter().zip(block2elems.iter()) {
+            let (inf, neg_inf) = (Real::infinity(), Real::neg_infinity());
+            let mut aabb = [inf, inf, inf, neg_inf, neg_inf, neg_inf];
+            for &i_elem in elems {
+                for &i_vtx in self.elem2vtx(i_elem) {
+                    let i_vtx: usize = i_vtx.as_();
+                    for k in 0..3 {
+                        let v = self.vtx2xyz[i_vtx * 3 + k];
+                        aabb[k] = aabb[k].min(v);
+                        aabb[k + 3] = aabb[k + 3].max(v);
+                    }
+                }
+            }
+            let physical = self.elem2physical[elems[0]];
+            match dim2entities[*dim].iter_mut().find(|e| e.0 == *entity) {
+                Some(e) => {
+                    for k in 0..3 {
+                        e.1[k] = e.1[k].min(aabb[k]);
+                        e.1[k + 3] = e.1[k + 3].max(aabb[k + 3]);
+                    }
+                }
+                None => dim2entities[*dim].push((*entity, aabb, physical)),
+            }
+        }
+        writeln!(writer, "$Entities")?;
+        writeln!(
+            writer,
+            "{} {} {} {}",
+            dim2entities[0].len(),
+            dim2entities[1].len(),
+            dim2entities[2].len(),
+            dim2entities[3].len()
+        )?;
+        for (dim, entities) in dim2entities.iter().enumerate() {
+            for (tag, aabb, physical) in entities {
+                write!(writer, "{}", tag)?;
+                let num_coord = if dim == 0 { 3 } else { 6 };
+                for v in &aabb[0..num_coord] {
+                    write!(writer, " {}", v)?;
+                }
+                match physical {
+                    0 => write!(writer, " 0")?,
+                    p => write!(writer, " 1 {}", p)?,
+                }
+                if dim > 0 {
+                    write!(writer, " 0")?; // no bounding entity
+                }
+                writeln!(writer)?;
+            }
+        }
+        writeln!(writer, "$EndEntities")?;
+        // all the nodes are put in the entity of the highest dimension
+        let num_vtx = self.vtx2xyz.len() / 3;
+        let (dim_node, entity_node) = blocks
+            .iter()
+            .max_by_key(|b| b.0)
+            .map_or((0, 1), |b| (b.0, b.1));
+        let tags: Vec<usize> = (0..num_vtx).map(|i_vtx| self.vtx2tag(i_vtx)).collect();
+        writeln!(writer, "$Nodes")?;
+        writeln!(
+            writer,
+            "1 {} {} {}",
+            num_vtx,
+            tags.iter().min().unwrap_or(&0),
+            tags.iter().max().unwrap_or(&0)
+        )?;
+        writeln!(writer, "{} {} 0 {}", dim_node, entity_node, num_vtx)?;
+        for tag in &tags {
+            writeln!(writer, "{}", tag)?;
+        }
+        for xyz in self.vtx2xyz.chunks(3) {
+            writeln!(writer, "{} {} {}", xyz[0], xyz[1], xyz[2])?;
+        }
+        writeln!(writer, "$EndNodes")?;
+        let tags: Vec<usize> = (0..self.num_elem()).map(|i| self.elem2tag(i)).collect();
+        writeln!(writer, "$Elements")?;
+        writeln!(
+            writer,
+            "{} {} {} {}",
+            blocks.len(),
+            self.num_elem(),
+            tags.iter().min().unwrap_or(&0),
+            tags.iter().max().unwrap_or(&0)
+        )?;
+        for ((dim, entity, elem_type), elems) in blocks.iter().zip(block2elems.iter()) {
+            writeln!(
+                writer,
+                "{} {} {} {}",
+                dim,
+                entity,
+                *elem_type as usize,
+                elems.len()
+            )?;
+            for &i_elem in elems {
+                write!(writer, "{}", tags[i_elem])?;
+                for &i_vtx in self.elem2vtx(i_elem) {
+                    write!(writer, " {}", self.vtx2tag(i_vtx.as_()))?;
+                }
+                writeln!(writer)?;
+            }
+        }
+        writeln!(writer, "$EndElements")?;
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P, version: Version) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path.as_ref())
+            .with_context(|| format!("cannot create {}", path.as_ref().display()))?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write(&mut file, version)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl<Index, Real> Default for GmshMesh<Index, Real>
+where
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display + 'static,
+    usize: AsPrimitive<Index>,
+    f64: AsPrimitive<Real>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// cursor over the whole file. Values are parsed from whitespace-separated tokens (ASCII)
+/// or decoded as little-endian values (binary)
+struct MshCursor {
+    buf: Vec<u8>,
+    pos: usize,
+    is_binary: bool,
+    /// size of `size_t` in binary files
+    size_t: usize,
+}
+
+impl MshCursor {
+    /// next non-empty line
+    fn line(&mut self) -> Option<String> {
+        while self.pos < self.buf.len() {
+            let end = self.buf[self.pos..]
+                .iter()
+                .position(|&c| c == b'\n')
+                .map_or(self.buf.len(), |i| self.pos + i);
+            let line = String::from_utf8_lossy(&self.buf[self.pos..end])
+                .trim()
+                .to_string();
+            self.pos = (end + 1).min(self.buf.len());
+            if !line.is_empty() {
+                return Some(line);
+            }
+        }
+        None
+    }
+
+    fn expect_line(&mut self, expected: &str) -> anyhow::Result<()> {
+        match self.line() {
+            Some(line) if line == expected => Ok(()),
+            Some(line) => Err(anyhow::anyhow!("expected {}, found {}", expected, line)),
+            None => Err(anyhow::anyhow!("expected {}, found end of file", expected)),
+        }
+    }
+
+    fn token(&mut self) -> anyhow::Result<&str> {
+        while self.pos < self.buf.len() && self.buf[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        let start = self.pos;
+        while self.pos < self.buf.len() && !self.buf[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(anyhow::anyhow!("unexpected end of file"));
+        }
+        Ok(std::str::from_utf8(&self.buf[start..self.pos])?)
+    }
+
+    fn bytes(&mut self, size: usize) -> anyhow::Result<&[u8]> {
+        if self.pos + size > self.buf.len() {
+            return Err(anyhow::anyhow!("unexpected end of binary data"));
+        }
+        self.pos += size;
+        Ok(&self.buf[self.pos - size..self.pos])
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self) -> anyhow::Result<T> {
+        let token = self.token()?;
+        token
+            .parse::<T>()
+            .ok()
+            .with_context(|| format!("cannot parse \"{}\"", token))
+    }
+
+    fn int(&mut self) -> anyhow::Result<i64> {
+        if !self.is_binary {
+            return self.parse::<i64>();
+        }
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into()?) as i64)
+    }
+
+    fn size(&mut self) -> anyhow::Result<usize> {
+        if !self.is_binary {
+            return self.parse::<usize>();
+        }
+        match self.size_t {
+            4 => Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?) as usize),
+            _ => Ok(u64::from_le_bytes(self.bytes(8)?.try_into()?) as usize),
+        }
+    }
+
+    fn real(&mut self) -> anyhow::Result<f64> {
+        if !self.is_binary {
+            return self.parse::<f64>();
+        }
+        Ok(f64::from_le_bytes(self.bytes(8)?.try_into()?))
+    }
+
+    /// skip the section until `$End...`
+    fn skip_section(&mut self, name: &str) -> anyhow::Result<()> {
+        let end = format!("$End{}", &name[1..]);
+        let Some(i) = self.buf[self.pos..]
+            .windows(end.len())
+            .position(|w| w == end.as_bytes())
+        else {
+            return Err(anyhow::anyhow!("missing {}", end));
+        };
+        self.pos += i + end.len();
+        Ok(())
+    }
+}
+
+fn elem_type_from_id(id: i64) -> anyhow::Result<GmshElementType> {
+    usize::try_from(id)
+        .ok()
+        .and_then(GmshElementType::from_id)
+        .ok_or_else(|| anyhow::anyhow!("unsupported element type {}", id))
+}
+
+/// read Gmsh MSH file of version 2.2 or 4.1 (ASCII or binary)
+pub fn read<R: std::io::Read, Index, Real>(mut reader: R) -> anyhow::Result<GmshMesh<Index, Real>>
+where
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display + 'static,
+    usize: AsPrimitive<Index>,
+    f64: AsPrimitive<Real>,
+{
+    let mut buf = vec![];
+    reader.read_to_end(&mut buf)?;
+    let mut cur = MshCursor {
+        buf,
+        pos: 0,
+        is_binary: false,
+        size_t: 8,
+    };
+    let mut mesh = GmshMesh::<Index, Real>::new();
+    let mut version = 0f64;
+    // physical tags of the entities `(dim, tag)` in `$Entities`
+    let mut entity2physical = std::collections::HashMap::<(usize, usize), usize>::new();
+    // tags of the node of each element before resolved to the node index
+    let mut idx2tag = Vec::<usize>::new();
+    while let Some(section) = cur.line() {
+        let res: anyhow::Result<()> = (|| {
+            match section.as_str() {
+                "$MeshFormat" => {
+                    let line = cur.line().unwrap_or_default();
+                    let tokens: Vec<&str> = line.split_whitespace().collect();
+                    if tokens.len() < 3 {
+                        return Err(anyhow::anyhow!("invalid format line \"{}\"", line));
+                    }
+                    version = tokens[0].parse()?;
+                    if !(2.0..3.0).contains(&version) && !(4.0..5.0).contains(&version) {
+                        return Err(anyhow::anyhow!("unsupported version {}", version));
+                    }
+                    cur.is_binary = tokens[1] == "1";
+                    cur.size_t = tokens[2].parse()?;
+                    if cur.is_binary && cur.int()? != 1 {
+                        return Err(anyhow::anyhow!("big-endian binary is not supported"));
+                    }
+                }
+                "$PhysicalNames" => {
+                    let num = cur.line().unwrap_or_default().parse::<usize>()?;
+                    for _ in 0..num {
+                        let line = cur.line().unwrap_or_default();
+                        let mut tokens = line.splitn(3, char::is_whitespace);
+                        let dim = tokens.next().unwrap_or_default().parse::<usize>()?;
+                        let tag = tokens.next().unwrap_or_default().parse::<usize>()?;
+                        let name = tokens.next().unwrap_or_default().trim().trim_matches('"');
+                        mesh.physical_names.push((dim, tag, name.to_string()));
+                    }
+                }
+                "$Entities" => {
+                    let dim2num = [cur.size()?, cur.size()?, cur.size()?, cur.size()?];
+                    for (dim, &num) in dim2num.iter().enumerate() {
+                        for _ in 0..num {
+                            let tag = cur.int()? as usize;
+                            let num_coord = if dim == 0 { 3 } else { 6 };
+                            for _ in 0..num_coord {
+                                cur.real()?;
+                            }
+                            let num_physical = cur.size()?;
+                            for i_physical in 0..num_physical {
+                                let physical = cur.int()?.unsigned_abs() as usize;
+                                if i_physical == 0 {
+                                    entity2physical.insert((dim, tag), physical);
+                                }
+                            }
+                            if dim > 0 {
+                                for _ in 0..cur.size()? {
+                                    cur.int()?;
+                                }
+                            }
+                        }
+                    }
+                }
+                "$Nodes" if version < 3.0 => {
+                    let num_vtx = cur.line().unwrap_or_default().parse::<usize>()?;
+                    for _ in 0..num_vtx {
+                        mesh.vtx2tag.push(cur.int()? as usize);
+                        for _ in 0..3 {
+                            mesh.vtx2xyz.push(cur.real()?.as_());
+                        }
+                    }
+                }
+                "$Nodes" => {
+                    let num_block = cur.size()?;
+                    let _num_vtx = cur.size()?;
+                    let _min_tag = cur.size()?;
+                    let _max_tag = cur.size()?;
+                    for _ in 0..num_block {
+                        let dim = cur.int()? as usize;
+                        let _entity = cur.int()?;
+                        let parametric = cur.int()? != 0;
+                        let num_vtx = cur.size()?;
+                        for _ in 0..num_vtx {
+                            mesh.vtx2tag.push(cur.size()?);
+                        }
+                        for _ in 0..num_vtx {
+                            for _ in 0..3 {
+                                mesh.vtx2xyz.push(cur.real()?.as_());
+                            }
+                            if parametric {
+                                for _ in 0..dim {
+                                    cur.real()?;
+                                }
+                            }
+                        }
+                    }
+                }
+                "$Elements" if version < 3.0 => {
+                    let num_elem = cur.line().unwrap_or_default().parse::<usize>()?;
+                    let push = |mesh: &mut GmshMesh<Index, Real>,
+                                tag: usize,
+                                elem_type: GmshElementType,
+                                tags: &[i64]| {
+                        mesh.elem2tag.push(tag);
+                        mesh.elem2type.push(elem_type);
+                        mesh.elem2physical
+                            .push(tags.first().map_or(0, |&t| t.unsigned_abs() as usize));
+                        mesh.elem2entity
+                            .push(tags.get(1).map_or(0, |&t| t.unsigned_abs() as usize));
+                    };
+                    if cur.is_binary {
+                        let mut i_elem = 0;
+                        while i_elem < num_elem {
+                            let elem_type = elem_type_from_id(cur.int()?)?;
+                            let num_in_block = cur.int()? as usize;
+                            let num_tag = cur.int()? as usize;
+                            for _ in 0..num_in_block {
+                                let tag = cur.int()? as usize;
+                                let tags: Vec<i64> = (0..num_tag)
+                                    .map(|_| cur.int())
+                                    .collect::<anyhow::Result<_>>()?;
+                                for _ in 0..elem_type.num_node() {
+                                    idx2tag.push(cur.int()? as usize);
+                                }
+                                mesh.elem2idx.push(idx2tag.len().as_());
+                                push(&mut mesh, tag, elem_type, &tags);
+                            }
+                            i_elem += num_in_block;
+                        }
+                    } else {
+                        for _ in 0..num_elem {
+                            let tag = cur.size()?;
+                            let elem_type = elem_type_from_id(cur.int()?)?;
+                            let num_tag = cur.size()?;
+                            let tags: Vec<i64> = (0..num_tag)
+                                .map(|_| cur.int())
+                                .collect::<anyhow::Result<_>>()?;
+                            for _ in 0..elem_type.num_node() {
+                                idx2tag.push(cur.size()?);
+                            }
+                            mesh.elem2idx.push(idx2tag.len().as_());
+                            push(&mut mesh, tag, elem_type, &tags);
+                        }
+                    }
+                }
+                "$Elements" => {
+                    let num_block = cur.size()?;
+                    let _num_elem = cur.size()?;
+                    let _min_tag = cur.size()?;
+                    let _max_tag = cur.size()?;
+                    for _ in 0..num_block {
+                        let dim = cur.int()? as usize;
+                        let entity = cur.int()? as usize;
+                        let elem_type = elem_type_from_id(cur.int()?)?;
+                        let num_elem = cur.size()?;
+                        let physical = entity2physical.get(&(dim, entity)).copied().unwrap_or(0);
+                        for _ in 0..num_elem {
+                            mesh.elem2tag.push(cur.size()?);
+                            for _ in 0..elem_type.num_node() {
+                                idx2tag.push(cur.size()?);
+                            }
+                            mesh.elem2idx.push(idx2tag.len().as_());
+                            mesh.elem2type.push(elem_type);
+                            mesh.elem2physical.push(physical);
+                            mesh.elem2entity.push(entity);
+                        }
+                    }
+                }
+                _ => {
+                    if !section.starts_with('$') {
+                        return Err(anyhow::anyhow!("expected a section"));
+                    }
+                    return cur.skip_section(&section);
+                }
+            }
+            let end = format!("$End{}", &section[1..]);
+            cur.expect_line(&end)
+        })();
+        res.with_context(|| format!("while reading {}", section))?;
+    }
+    let tag2vtx: std::collections::HashMap<usize, usize> = mesh
+        .vtx2tag
+        .iter()
+        .enumerate()
+        .map(|(i_vtx, &tag)| (tag, i_vtx))
+        .collect();
+    mesh.idx2vtx = idx2tag
+        .iter()
+        .map(|tag| match tag2vtx.get(tag) {
+            Some(&i_vtx) => Ok(i_vtx.as_()),
+            None => Err(anyhow::anyhow!("undefined node tag {}", tag)),
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(mesh)
+}
+
+pub fn load<P, Index, Real>(path: P) -> anyhow::Result<GmshMesh<Index, Real>>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display + 'static,
+    usize: AsPrimitive<Index>,
+    f64: AsPrimitive<Real>,
+{
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    read(std::io::BufReader::new(file))
+}
+
+#[test]
+fn test_read_write() -> anyhow::Result<()> {
+    let vtxl2xy = vec![0f32, 0., 1., 0., 1., 1., 0., 1.];
+    let (tri2vtx, vtx2xy) =
+        crate::trimesh2_dynamic::meshing_from_polyloop2::<usize, f32>(&vtxl2xy, 0.2, 0.2);
+    let mut mesh =
+        GmshMesh::<usize, f32>::from_uniform_mesh(GmshElementType::Triangle, &tri2vtx, &vtx2xy, 2);
+    // the physical tags differ within an entity
+    mesh.elem2physical = (0..mesh.num_elem()).map(|i| 1 + i % 2).collect();
+    mesh.physical_names = vec![(2, 1, "odd domain".to_string()), (2, 2, "even".to_string())];
+    for version in [Version::V2, Version::V4] {
+        let mut buf = Vec::<u8>::new();
+        mesh.write(&mut buf, version)?;
+        let mesh1 = read::<_, usize, f32>(buf.as_slice())?;
+        assert_eq!(mesh1.vtx2xyz, mesh.vtx2xyz);
+        assert_eq!(mesh1.physical_names, mesh.physical_names);
+        assert_eq!(mesh1.elem2group().0, 2);
+        // v4 groups the elements by the entity
+        let mut elems0: Vec<(usize, &[usize])> = (0..mesh.num_elem())
+            .map(|i| (mesh.elem2physical[i], mesh.elem2vtx(i)))
+            .collect();
+        let mut elems1: Vec<(usize, &[usize])> = (0..mesh1.num_elem())
+            .map(|i| (mesh1.elem2physical[i], mesh1.elem2vtx(i)))
+            .collect();
+        elems0.sort();
+        elems1.sort();
+        assert_eq!(elems0, elems1);
+    }
+    mesh.save("../target/io_msh_square.msh", Version::V4)?;
+    Ok(())
+}
+
+#[test]
+fn test_read_binary() -> anyhow::Result<()> {
+    // v2.2 binary with a quad and a line
+    let mut buf = Vec::<u8>::new();
+    buf.extend(b"$MeshFormat\n2.2 1 8\n");
+    buf.extend(1i32.to_le_bytes());
+    buf.extend(b"\n$EndMeshFormat\n$Nodes\n4\n");
+    for (tag, xyz) in [
+        (10i32, [0., 0., 0.]),
+        (20, [1., 0., 0.]),
+        (30, [1., 1., 0.]),
+        (40, [0., 1., 0.]),
+    ] {
+        buf.extend(tag.to_le_bytes());
+        xyz.iter().for_each(|v: &f64| buf.extend(v.to_le_bytes()));
+    }
+    buf.extend(b"\n$EndNodes\n$Elements\n2\n");
+    for i in [
+        3i32, 1, 2, 7, 5, 1, 10, 20, 30, 40, 1, 1, 2, 8, 3, 2, 10, 20,
+    ] {
+        buf.extend(i.to_le_bytes());
+    }
+    buf.extend(b"\n$EndElements\n");
+    let mesh = read::<_, u32, f64>(buf.as_slice())?;
+    assert_eq!(
+        mesh.elem2type,
+        vec![GmshElementType::Quad, GmshElementType::Line]
+    );
+    assert_eq!(mesh.elem2idx, vec![0, 4, 6]);
+    assert_eq!(mesh.idx2vtx, vec![0, 1, 2, 3, 0, 1]);
+    assert_eq!(mesh.elem2physical, vec![5, 3]);
+    assert_eq!(mesh.elem2entity, vec![1, 2]);
+    // v4.1 binary with a tetrahedron
+    let mut buf = Vec::<u8>::new();
+    buf.extend(b"$MeshFormat\n4.1 1 8\n");
+    buf.extend(1i32.to_le_bytes());
+    buf.extend(b"\n$EndMeshFormat\n$Entities\n");
+    [0u64, 0, 0, 1]
+        .iter()
+        .for_each(|v| buf.extend(v.to_le_bytes()));
+    buf.extend(7i32.to_le_bytes());
+    [0f64, 0., 0., 1., 1., 1.]
+        .iter()
+        .for_each(|v| buf.extend(v.to_le_bytes()));
+    buf.extend(1u64.to_le_bytes());
+    buf.extend(99i32.to_le_bytes());
+    buf.extend(0u64.to_le_bytes());
+    buf.extend(b"\n$EndEntities\n$Nodes\n");
+    [1u64, 4, 1, 4]
+        .iter()
+        .for_each(|v| buf.extend(v.to_le_bytes()));
+    [3i32, 7, 0]
+        .iter()
+        .for_each(|v| buf.extend(v.to_le_bytes()));
+    buf.extend(4u64.to_le_bytes());
+    [1u64, 2, 3, 4]
+        .iter()
+        .for_each(|v| buf.extend(v.to_le_bytes()));
+    [0f64, 0., 0., 1., 0., 0., 0., 1., 0., 0., 0., 1.]
+        .iter()
+        .for_each(|v| buf.extend(v.to_le_bytes()));
+    buf.extend(b"\n$EndNodes\n$Elements\n");
+    [1u64, 1, 1, 1]
+        .iter()
+        .for_each(|v| buf.extend(v.to_le_bytes()));
+    [3i32, 7, 4]
+        .iter()
+        .for_each(|v| buf.extend(v.to_le_bytes()));
+    buf.extend(1u64.to_le_bytes());
+    [1u64, 4, 3, 2, 1]
+        .iter()
+        .for_each(|v| buf.extend(v.to_le_bytes()));
+    buf.extend(b"\n$EndElements\n");
+    let mesh = read::<_, usize, f32>(buf.as_slice())?;
+    assert_eq!(mesh.vtx2xyz.len(), 12);
+    assert_eq!(
+        mesh.elem2vtx_of_type(GmshElementType::Tetrahedron),
+        vec![3, 2, 1, 0]
+    );
+    assert_eq!(mesh.elem2physical, vec![99]);
+    assert_eq!(mesh.elem2group(), (1, vec![0]));
+    Ok(())
+}
@@ -0,0 +1,302 @@
+//! methods for Wavefront MTL (material library) files
+
+use anyhow::Context;
+use std::io::BufRead;
+
+/// texture map statement such as `map_Kd -s 2 2 1 texture.png`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureMap {
+    pub path: String,
+    /// `-o u v w`
+    pub offset: [f32; 3],
+    /// `-s u v w`
+    pub scale: [f32; 3],
+    /// `-t u v w`
+    pub turbulence: [f32; 3],
+    /// `-bm mult` (bump map)
+    pub bump_multiplier: f32,
+    /// `-clamp on|off`
+    pub clamp: bool,
+    /// `-blendu on|off`
+    pub blendu: bool,
+    /// `-blendv on|off`
+    pub blendv: bool,
+    /// `-imfchan r|g|b|m|l|z`
+    pub imfchan: Option<char>,
+    /// remaining options (e.g., `-mm`, `-boost`, `-texres`) as raw tokens
+    pub options: Vec<(String, Vec<String>)>,
+}
+
+impl TextureMap {
+    pub fn new(path: &str) -> Self {
+        TextureMap {
+            path: path.to_string(),
+            offset: [0f32; 3],
+            scale: [1f32; 3],
+            turbulence: [0f32; 3],
+            bump_multiplier: 1f32,
+            clamp: false,
+            blendu: true,
+            blendv: true,
+            imfchan: None,
+            options: vec![],
+        }
+    }
+}
+
+/// material defined by a `newmtl` statement
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub ka: [f32; 3],
+    pub kd: [f32; 3],
+    pub ks: [f32; 3],
+    pub ke: [f32; 3],
+    pub ns: f32,
+    pub ni: f32,
+    /// dissolve. `Tr` is stored as `1 - Tr`
+    pub d: f32,
+    pub illum: usize,
+    pub map_ka: Option<TextureMap>,
+    pub map_kd: Option<TextureMap>,
+    pub map_ks: Option<TextureMap>,
+    pub map_ns: Option<TextureMap>,
+    pub map_d: Option<TextureMap>,
+    pub map_bump: Option<TextureMap>,
+}
+
+impl Material {
+    pub fn new(name: &str) -> Self {
+        Material {
+            name: name.to_string(),
+            ka: [0f32; 3],
+            kd: [0.8f32; 3],
+            ks: [0f32; 3],
+            ke: [0f32; 3],
+            ns: 0f32,
+            ni: 1f32,
+            d: 1f32,
+            illum: 2,
+            map_ka: None,
+            map_kd: None,
+            map_ks: None,
+            map_ns: None,
+            map_d: None,
+            map_bump: None,
+        }
+    }
+}
+
+fn parse_f32(s: &str) -> anyhow::Result<f32> {
+    s.parse::<f32>()
+        .with_context(|| format!("cannot parse \"{}\" as a number", s))
+}
+
+fn parse_on_off(s: &str) -> anyhow::Result<bool> {
+    match s {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(anyhow::anyhow!(
+            "expected \"on\" or \"off\", found \"{}\"",
+            s
+        )),
+    }
+}
+
+/// `Kd r g b` or `Kd r` (the latter sets all the channels)
+fn parse_color(args: &[&str]) -> anyhow::Result<[f32; 3]> {
+    match args {
+        [r] => {
+            let r = parse_f32(r)?;
+            Ok([r; 3])
+        }
+        [r, g, b, ..] => Ok([parse_f32(r)?, parse_f32(g)?, parse_f32(b)?]),
+        _ => Err(anyhow::anyhow!(
+            "expected one or three components for a color"
+        )),
+    }
+}
+
+fn parse_scalar(args: &[&str]) -> anyhow::Result<f32> {
+    let Some(v) = args.first() else {
+        return Err(anyhow::anyhow!("missing value"));
+    };
+    parse_f32(v)
+}
+
+/// up to three numbers following `-o`, `-s` or `-t`. Omitted components keep the default
+fn parse_uvw(args: &[&str], i: &mut usize, uvw: &mut [f32; 3]) -> anyhow::Result<()> {
+    let mut i_dim = 0;
+    while i_dim < 3 && *i < args.len() - 1 {
+        let Ok(v) = args[*i].parse::<f32>() else {
+            break;
+        };
+        uvw[i_dim] = v;
+        i_dim += 1;
+        *i += 1;
+    }
+    if i_dim == 0 {
+        return Err(anyhow::anyhow!("missing value for texture option"));
+    }
+    Ok(())
+}
+
+/// parse the arguments of a `map_*` statement. The tokens after the options form the path
+pub fn parse_texture_map(args: &[&str]) -> anyhow::Result<TextureMap> {
+    let mut tex = TextureMap::new("");
+    let mut i = 0;
+    while i < args.len() && args[i].starts_with('-') {
+        let opt = args[i];
+        i += 1;
+        let num_arg = match opt {
+            "-o" => {
+                parse_uvw(args, &mut i, &mut tex.offset)?;
+                continue;
+            }
+            "-s" => {
+                parse_uvw(args, &mut i, &mut tex.scale)?;
+                continue;
+            }
+            "-t" => {
+                parse_uvw(args, &mut i, &mut tex.turbulence)?;
+                continue;
+            }
+            "-mm" => 2,
+            _ => 1,
+        };
+        if i + num_arg > args.len() {
+            return Err(anyhow::anyhow!("missing value for texture option {}", opt));
+        }
+        let vals = &args[i..i + num_arg];
+        i += num_arg;
+        match opt {
+            "-bm" => tex.bump_multiplier = parse_f32(vals[0])?,
+            "-clamp" => tex.clamp = parse_on_off(vals[0])?,
+            "-blendu" => tex.blendu = parse_on_off(vals[0])?,
+            "-blendv" => tex.blendv = parse_on_off(vals[0])?,
+            "-imfchan" => tex.imfchan = vals[0].chars().next(),
+            _ => tex.options.push((
+                opt.to_string(),
+                vals.iter().map(|s| s.to_string()).collect(),
+            )),
+        }
+    }
+    if i >= args.len() {
+        return Err(anyhow::anyhow!("missing texture path"));
+    }
+    tex.path = args[i..].join(" ");
+    Ok(tex)
+}
+
+/// read materials from a MTL file
+pub fn read<R: BufRead>(reader: R) -> anyhow::Result<Vec<Material>> {
+    let mut mtls = Vec::<Material>::new();
+    for (i_line, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.split('#').next().unwrap().trim();
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+        if keyword == "newmtl" {
+            mtls.push(Material::new(&args.join(" ")));
+            continue;
+        }
+        let Some(mtl) = mtls.last_mut() else {
+            return Err(anyhow::anyhow!(
+                "line {}: \"{}\" appears before \"newmtl\"",
+                i_line + 1,
+                keyword
+            ));
+        };
+        let res: anyhow::Result<()> = (|| {
+            match keyword {
+                "Ka" => mtl.ka = parse_color(args)?,
+                "Kd" => mtl.kd = parse_color(args)?,
+                "Ks" => mtl.ks = parse_color(args)?,
+                "Ke" => mtl.ke = parse_color(args)?,
+                "Ns" => mtl.ns = parse_scalar(args)?,
+                "Ni" => mtl.ni = parse_scalar(args)?,
+                "d" => {
+                    // `d -halo factor` is read as the plain factor
+                    let args = if args.first() == Some(&"-halo") {
+                        &args[1..]
+                    } else {
+                        args
+                    };
+                    mtl.d = parse_scalar(args)?;
+                }
+                "Tr" => mtl.d = 1f32 - parse_scalar(args)?,
+                "illum" => {
+                    let Some(v) = args.first() else {
+                        return Err(anyhow::anyhow!("missing value"));
+                    };
+                    mtl.illum = v.parse::<usize>()?;
+                }
+                "map_Ka" => mtl.map_ka = Some(parse_texture_map(args)?),
+                "map_Kd" => mtl.map_kd = Some(parse_texture_map(args)?),
+                "map_Ks" => mtl.map_ks = Some(parse_texture_map(args)?),
+                "map_Ns" => mtl.map_ns = Some(parse_texture_map(args)?),
+                "map_d" => mtl.map_d = Some(parse_texture_map(args)?),
+                "map_Bump" | "map_bump" | "bump" => mtl.map_bump = Some(parse_texture_map(args)?),
+                _ => {} // unsupported statement
+            }
+            Ok(())
+        })();
+        res.with_context(|| format!("line {}: {}", i_line + 1, line))?;
+    }
+    Ok(mtls)
+}
+
+/// load materials from a MTL file
+pub fn load<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<Vec<Material>> {
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("cannot open {}", path.as_ref().display()))?;
+    read(std::io::BufReader::new(file))
+}
+
+#[test]
+fn test_read() -> anyhow::Result<()> {
+    let txt = "# comment
+newmtl red
+Ka 0.1 0.1 0.1
+Kd 1.0 0.0 0.0
+Ks 0.5
+Ns 10
+Tr 0.25
+illum 2
+map_Kd -s 2 2 -clamp on red texture.png
+map_Bump -bm 0.5 -mm 0 1 bump.png
+
+newmtl glass
+d 0.5
+Ni 1.5
+";
+    let mtls = read(std::io::Cursor::new(txt))?;
+    assert_eq!(mtls.len(), 2);
+    assert_eq!(mtls[0].name, "red");
+    assert_eq!(mtls[0].ka, [0.1; 3]);
+    assert_eq!(mtls[0].kd, [1.0, 0.0, 0.0]);
+    assert_eq!(mtls[0].ks, [0.5; 3]);
+    assert_eq!(mtls[0].ns, 10.0);
+    assert_eq!(mtls[0].d, 0.75);
+    let map_kd = mtls[0].map_kd.as_ref().unwrap();
+    assert_eq!(map_kd.path, "red texture.png");
+    assert_eq!(map_kd.scale, [2.0, 2.0, 1.0]);
+    assert!(map_kd.clamp);
+    let map_bump = mtls[0].map_bump.as_ref().unwrap();
+    assert_eq!(map_bump.path, "bump.png");
+    assert_eq!(map_bump.bump_multiplier, 0.5);
+    assert_eq!(
+        map_bump.options,
+        vec![("-mm".to_string(), vec!["0".to_string(), "1".to_string()])]
+    );
+    assert_eq!(mtls[1].name, "glass");
+    assert_eq!(mtls[1].d, 0.5);
+    assert_eq!(mtls[1].ni, 1.5);
+    assert!(mtls[1].map_kd.is_none());
+    //
+    let err = read(std::io::Cursor::new("newmtl a\nKd 1 x 0\n")).unwrap_err();
+    assert!(format!("{:#}", err).contains("line 2"));
+    Ok(())
+}
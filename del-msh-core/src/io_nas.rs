@@ -1,85 +1,590 @@
+//! methods for Nastran bulk data files (`.nas`, `.bdf`)
+
+use anyhow::Context;
 use num_traits::AsPrimitive;
+use std::io::Write;
+
+/// element cards supported by the reader and the writer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NasElementType {
+    Bar,
+    Tria3,
+    Tria6,
+    Quad4,
+    Tetra4,
+    Tetra10,
+    Hexa8,
+    Hexa20,
+}
+
+impl NasElementType {
+    pub fn card(&self) -> &'static str {
+        match self {
+            NasElementType::Bar => "CBAR",
+            NasElementType::Tria3 => "CTRIA3",
+            NasElementType::Tria6 => "CTRIA6",
+            NasElementType::Quad4 => "CQUAD4",
+            NasElementType::Tetra4 | NasElementType::Tetra10 => "CTETRA",
+            NasElementType::Hexa8 | NasElementType::Hexa20 => "CHEXA",
+        }
+    }
+
+    pub fn num_node(&self) -> usize {
+        match self {
+            NasElementType::Bar => 2,
+            NasElementType::Tria3 => 3,
+            NasElementType::Tria6 => 6,
+            NasElementType::Quad4 => 4,
+            NasElementType::Tetra4 => 4,
+            NasElementType::Tetra10 => 10,
+            NasElementType::Hexa8 => 8,
+            NasElementType::Hexa20 => 20,
+        }
+    }
+}
+
+/// elements of the same type
+#[derive(Clone, Debug)]
+pub struct NasElementBlock<Index> {
+    pub elem_type: NasElementType,
+    pub elem2vtx: Vec<Index>,
+    /// property id (`PID`) of each element. This works as the group of the elements
+    pub elem2pid: Vec<usize>,
+    /// element id (`EID`) of each element
+    pub elem2eid: Vec<usize>,
+}
+
+/// `PSHELL` or `PSOLID` card
+#[derive(Clone, Debug, PartialEq)]
+pub struct NasProperty {
+    pub card: String,
+    pub pid: usize,
+    /// material id (`MID` or `MID1`)
+    pub mid: usize,
+}
 
-fn parse(s: &str) -> f32 {
-    let s = s.trim_start();
-    if let Some(i) = s.rfind('-') {
-        if i != 0 {
-            let mut s = String::from(s);
-            s.insert(i, 'e');
-            let v = s.trim().parse::<f32>().unwrap();
-            //  dbg!(i,&s,&v);
-            return v;
+/// bulk data. Coordinate systems (`CP` of `GRID`) are not applied
+#[derive(Clone, Debug)]
+pub struct NasMesh<Index, Real> {
+    pub vtx2xyz: Vec<Real>,
+    /// grid id of each vertex
+    pub vtx2gid: Vec<usize>,
+    pub blocks: Vec<NasElementBlock<Index>>,
+    pub properties: Vec<NasProperty>,
+}
+
+impl<Index, Real> NasMesh<Index, Real> {
+    pub fn new() -> Self {
+        NasMesh {
+            vtx2xyz: vec![],
+            vtx2gid: vec![],
+            blocks: vec![],
+            properties: vec![],
         }
-    } else if let Some(i) = s.rfind('+') {
-        assert_ne!(i, 0);
-        let mut s = String::from(s);
-        s.insert(i, 'e');
-        let v = s.trim().parse::<f32>().unwrap();
-        //  dbg!(i,&s,&v);
-        return v;
     }
-    s.trim().parse::<f32>().ok().unwrap()
+
+    pub fn block(&self, elem_type: NasElementType) -> Option<&NasElementBlock<Index>> {
+        self.blocks.iter().find(|b| b.elem_type == elem_type)
+    }
+
+    /// add elements with the same property id
+    pub fn push_block(&mut self, elem_type: NasElementType, elem2vtx: Vec<Index>, pid: usize) {
+        let num_elem = elem2vtx.len() / elem_type.num_node();
+        self.blocks.push(NasElementBlock {
+            elem_type,
+            elem2vtx,
+            elem2pid: vec![pid; num_elem],
+            elem2eid: vec![],
+        });
+    }
 }
 
-pub fn load_tri_mesh<P, Index>(path: P) -> (Vec<Index>, Vec<f32>)
+impl<Index, Real> Default for NasMesh<Index, Real> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Nastran real such as `1.5-3` (`1.5e-3`), `.5`, `1.+2` or `1.5D-3`
+fn parse_real<Real: std::str::FromStr>(s: &str) -> anyhow::Result<Real> {
+    let s = s.trim();
+    let mut t = s.replace(['D', 'd'], "E");
+    if let Some(i) = t.rfind(['+', '-']) {
+        let prev = t.as_bytes()[i.saturating_sub(1)];
+        if i != 0 && prev != b'E' && prev != b'e' {
+            t.insert(i, 'E');
+        }
+    }
+    t.parse::<Real>()
+        .ok()
+        .with_context(|| format!("cannot parse \"{}\" as a real", s))
+}
+
+fn parse_int(s: &str) -> anyhow::Result<usize> {
+    let s = s.trim();
+    s.parse::<usize>()
+        .with_context(|| format!("cannot parse \"{}\" as an integer", s))
+}
+
+/// one line of the bulk data split into the fields
+struct Line {
+    keyword: String,
+    fields: Vec<String>,
+    is_continuation: bool,
+}
+
+fn split_line(line: &str) -> Line {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (head, fields): (String, Vec<String>) = if line.contains(',') {
+        // free field. The fields beyond the tenth continue the card on the same line, as if the
+        // tenth and the first field of the next line were the continuation markers
+        let mut tokens = line.split(',').map(|s| s.trim().to_string());
+        let head = tokens.next().unwrap_or_default();
+        let num_field = if head.ends_with('*') { 4 } else { 8 };
+        let fields = tokens
+            .enumerate()
+            .filter(|(i_token, _)| i_token % (num_field + 2) < num_field)
+            .map(|(_, token)| token)
+            .collect();
+        (head, fields)
+    } else {
+        let chars: Vec<char> = line.chars().collect();
+        let field = |start: usize, width: usize| -> String {
+            chars
+                .iter()
+                .skip(start)
+                .take(width)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        };
+        let head = field(0, 8);
+        if head.ends_with('*') {
+            // large field
+            (head, (0..4).map(|i| field(8 + i * 16, 16)).collect())
+        } else {
+            (head, (0..8).map(|i| field(8 + i * 8, 8)).collect())
+        }
+    };
+    let is_continuation = head.is_empty() || head.starts_with('+') || head.starts_with('*');
+    Line {
+        keyword: head.trim_end_matches('*').to_uppercase(),
+        fields,
+        is_continuation,
+    }
+}
+
+fn elem_type_of_card(keyword: &str, num_node: usize) -> Option<NasElementType> {
+    let elem_type = match (keyword, num_node) {
+        ("CBAR", _) => NasElementType::Bar,
+        ("CTRIA3", _) => NasElementType::Tria3,
+        ("CTRIA6", _) => NasElementType::Tria6,
+        ("CQUAD4", _) => NasElementType::Quad4,
+        ("CTETRA", 10) => NasElementType::Tetra10,
+        ("CTETRA", _) => NasElementType::Tetra4,
+        ("CHEXA", 20) => NasElementType::Hexa20,
+        ("CHEXA", _) => NasElementType::Hexa8,
+        _ => return None,
+    };
+    Some(elem_type)
+}
+
+/// read the bulk data. Lines before `BEGIN BULK` are skipped if it exists
+pub fn read<R: std::io::BufRead, Index, Real>(reader: R) -> anyhow::Result<NasMesh<Index, Real>>
 where
-    P: AsRef<std::path::Path>,
-    Index: num_traits::PrimInt + std::str::FromStr + 'static + AsPrimitive<usize>,
-    <Index as std::str::FromStr>::Err: std::fmt::Debug,
+    Index: Copy + 'static,
+    Real: std::str::FromStr + Copy,
     usize: AsPrimitive<Index>,
 {
-    let mut vtx2xyz = vec![0f32; 0];
-    let mut vtx2idx: Vec<Index> = vec![];
-    let mut tri2idx: Vec<Index> = vec![];
-    //
-    let file = std::fs::File::open(path).expect("file not found.");
-    let reader = std::io::BufReader::new(file);
-    use std::io::BufRead;
-    for line in reader.lines().map_while(Result::ok) {
-        // println!("{:?}", line);
-        if line.starts_with("GRID") {
-            assert_eq!(line.len(), 48);
-            let _ = line.get(0..9);
-            let idx = line.get(9..24);
-            let idx = idx.unwrap().trim().parse::<Index>().unwrap();
-            let v0 = line.get(24..32).unwrap();
-            let v1 = line.get(32..40).unwrap();
-            let v2 = line.get(40..48).unwrap();
-            // dbg!(v0, v1, v2);
-            let v0 = parse(v0);
-            let v1 = parse(v1);
-            let v2 = parse(v2);
-            vtx2xyz.push(v0);
-            vtx2xyz.push(v1);
-            vtx2xyz.push(v2);
-            vtx2idx.push(idx);
-        } else if line.starts_with("CTRIA3") {
-            assert_eq!(line.len(), 48);
-            let _ = line.get(0..8); // CTRIA3
-            let _ = line.get(8..16);
-            let _ = line.get(16..24); // I don't know what this number is for
-            let v0 = line.get(24..32).unwrap().trim();
-            let v1 = line.get(32..40).unwrap().trim();
-            let v2 = line.get(40..48).unwrap().trim();
-            let v0 = v0.parse::<Index>().unwrap();
-            let v1 = v1.parse::<Index>().unwrap();
-            let v2 = v2.parse::<Index>().unwrap();
-            tri2idx.push(v0);
-            tri2idx.push(v1);
-            tri2idx.push(v2);
+    let lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
+    let i_begin = lines
+        .iter()
+        .position(|l| l.trim_start().to_uppercase().starts_with("BEGIN BULK"))
+        .map_or(0, |i| i + 1);
+    // logical cards made of the continued lines
+    let mut cards: Vec<(usize, String, Vec<String>)> = vec![];
+    for (i_line, line) in lines.iter().enumerate().skip(i_begin) {
+        let line = line.split('$').next().unwrap();
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.trim_start().to_uppercase().starts_with("ENDDATA") {
+            break;
+        }
+        let line = split_line(line);
+        if line.is_continuation {
+            let Some(card) = cards.last_mut() else {
+                return Err(anyhow::anyhow!(
+                    "line {}: continuation without card",
+                    i_line + 1
+                ));
+            };
+            card.2.extend(line.fields);
+        } else {
+            cards.push((i_line + 1, line.keyword, line.fields));
         }
     }
     //
-    let &num_idx = vtx2idx.iter().max().unwrap();
-    let mut idx2vtx = vec![Index::max_value(); num_idx.as_() + 1];
-    vtx2idx
+    let mut mesh = NasMesh::<Index, Real>::new();
+    let mut elem2gids: Vec<(NasElementType, usize, usize, Vec<usize>)> = vec![];
+    for (i_line, keyword, fields) in cards {
+        let res: anyhow::Result<()> = (|| {
+            let field = |i: usize| fields.get(i).map_or("", |s| s.as_str());
+            match keyword.as_str() {
+                "GRID" => {
+                    mesh.vtx2gid.push(parse_int(field(0))?);
+                    for i_dim in 0..3 {
+                        let s = field(2 + i_dim);
+                        let v = if s.is_empty() { "0.0" } else { s };
+                        mesh.vtx2xyz.push(parse_real(v)?);
+                    }
+                }
+                "CBAR" | "CTRIA3" | "CTRIA6" | "CQUAD4" | "CTETRA" | "CHEXA" => {
+                    let eid = parse_int(field(0))?;
+                    let pid = if field(1).is_empty() {
+                        eid
+                    } else {
+                        parse_int(field(1))?
+                    };
+                    let num_node_max = match keyword.as_str() {
+                        "CBAR" => 2,
+                        "CTRIA3" => 3,
+                        "CTRIA6" => 6,
+                        "CQUAD4" => 4,
+                        "CTETRA" => 10,
+                        _ => 20,
+                    };
+                    let mut gids = vec![];
+                    for i_node in 0..num_node_max {
+                        let s = field(2 + i_node);
+                        if !s.is_empty() {
+                            gids.push(parse_int(s)?);
+                        } else if gids.len() < 3 {
+                            return Err(anyhow::anyhow!("missing grid id"));
+                        }
+                    }
+                    // the optional mid-side nodes of CTETRA and CHEXA
+                    let num_node = match keyword.as_str() {
+                        "CTETRA" | "CHEXA" => gids.len(),
+                        _ => num_node_max,
+                    };
+                    let elem_type = elem_type_of_card(&keyword, num_node).unwrap();
+                    if gids.len() != elem_type.num_node() {
+                        return Err(anyhow::anyhow!(
+                            "{} grid ids for {} nodes",
+                            gids.len(),
+                            elem_type.num_node()
+                        ));
+                    }
+                    elem2gids.push((elem_type, eid, pid, gids));
+                }
+                "PSHELL" | "PSOLID" => {
+                    let mid = if field(1).is_empty() {
+                        0
+                    } else {
+                        parse_int(field(1))?
+                    };
+                    mesh.properties.push(NasProperty {
+                        card: keyword.clone(),
+                        pid: parse_int(field(0))?,
+                        mid,
+                    });
+                }
+                _ => {} // unsupported card
+            }
+            Ok(())
+        })();
+        res.with_context(|| format!("line {}: {}", i_line, keyword))?;
+    }
+    //
+    let gid2vtx: std::collections::HashMap<usize, usize> = mesh
+        .vtx2gid
         .iter()
         .enumerate()
-        .for_each(|(vtx, &idx)| idx2vtx[idx.as_()] = vtx.as_());
-    let mut tri2vtx = vec![Index::zero(); tri2idx.len()];
-    tri2vtx
+        .map(|(i_vtx, &gid)| (gid, i_vtx))
+        .collect();
+    for (elem_type, eid, pid, gids) in elem2gids {
+        let i_block = match mesh.blocks.iter().position(|b| b.elem_type == elem_type) {
+            Some(i_block) => i_block,
+            None => {
+                mesh.blocks.push(NasElementBlock {
+                    elem_type,
+                    elem2vtx: vec![],
+                    elem2pid: vec![],
+                    elem2eid: vec![],
+                });
+                mesh.blocks.len() - 1
+            }
+        };
+        let block = &mut mesh.blocks[i_block];
+        for gid in gids {
+            let i_vtx = gid2vtx
+                .get(&gid)
+                .with_context(|| format!("element {} refers to an unknown grid {}", eid, gid))?;
+            block.elem2vtx.push(i_vtx.as_());
+        }
+        block.elem2pid.push(pid);
+        block.elem2eid.push(eid);
+    }
+    Ok(mesh)
+}
+
+pub fn load<P, Index, Real>(path: P) -> anyhow::Result<NasMesh<Index, Real>>
+where
+    P: AsRef<std::path::Path>,
+    Index: Copy + 'static,
+    Real: std::str::FromStr + Copy,
+    usize: AsPrimitive<Index>,
+{
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    read(std::io::BufReader::new(file))
+}
+
+/// load the triangles (`CTRIA3`, corners of `CTRIA6` and split `CQUAD4`)
+pub fn load_tri_mesh<P, Index, Real>(path: P) -> anyhow::Result<(Vec<Index>, Vec<Real>)>
+where
+    P: AsRef<std::path::Path>,
+    Index: Copy + 'static,
+    Real: std::str::FromStr + Copy,
+    usize: AsPrimitive<Index>,
+{
+    let mesh = load::<P, Index, Real>(path)?;
+    let mut tri2vtx = vec![];
+    for block in &mesh.blocks {
+        let num_node = block.elem_type.num_node();
+        for node2vtx in block.elem2vtx.chunks(num_node) {
+            match block.elem_type {
+                NasElementType::Tria3 | NasElementType::Tria6 => {
+                    tri2vtx.extend_from_slice(&node2vtx[..3]);
+                }
+                NasElementType::Quad4 => {
+                    let v = node2vtx;
+                    tri2vtx.extend_from_slice(&[v[0], v[1], v[2], v[0], v[2], v[3]]);
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok((tri2vtx, mesh.vtx2xyz))
+}
+
+/// real number that fits the field width
+fn format_real(v: f64, width: usize) -> String {
+    let s = format!("{:?}", v);
+    if s.len() <= width && !s.contains('e') {
+        return s;
+    }
+    for precision in (0..width).rev() {
+        let s = format!("{:.*E}", precision, v);
+        if s.len() <= width {
+            return s;
+        }
+    }
+    format!("{:.0E}", v)
+}
+
+impl<Index, Real> NasMesh<Index, Real>
+where
+    Index: AsPrimitive<usize>,
+    Real: AsPrimitive<f64>,
+{
+    /// write the bulk data. `GRID` uses the large field format for more significant digits
+    pub fn write<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let vtx2gid = |i_vtx: usize| self.vtx2gid.get(i_vtx).copied().unwrap_or(i_vtx + 1);
+        writeln!(writer, "BEGIN BULK")?;
+        for (i_vtx, xyz) in self.vtx2xyz.chunks(3).enumerate() {
+            writeln!(
+                writer,
+                "GRID*   {:<16}{:<16}{:<16}{:<16}",
+                vtx2gid(i_vtx),
+                "",
+                format_real(xyz[0].as_(), 16),
+                format_real(xyz[1].as_(), 16),
+            )?;
+            writeln!(writer, "*       {:<16}", format_real(xyz[2].as_(), 16))?;
+        }
+        // the element ids not given are numbered after the largest given one
+        let mut eid_next = self
+            .blocks
+            .iter()
+            .flat_map(|block| block.elem2eid.iter().copied())
+            .max()
+            .map_or(1, |eid| eid + 1);
+        for block in &self.blocks {
+            let num_node = block.elem_type.num_node();
+            for (i_elem, node2vtx) in block.elem2vtx.chunks(num_node).enumerate() {
+                let eid = block.elem2eid.get(i_elem).copied().unwrap_or_else(|| {
+                    eid_next += 1;
+                    eid_next - 1
+                });
+                let pid = block.elem2pid.get(i_elem).copied().unwrap_or(1);
+                let mut fields = vec![eid, pid];
+                fields.extend(node2vtx.iter().map(|i_vtx| vtx2gid(i_vtx.as_())));
+                write!(writer, "{:<8}", block.elem_type.card())?;
+                for (i_field, chunk) in fields.chunks(8).enumerate() {
+                    if i_field != 0 {
+                        write!(writer, "\n+       ")?;
+                    }
+                    for v in chunk {
+                        write!(writer, "{:<8}", v)?;
+                    }
+                }
+                writeln!(writer)?;
+            }
+        }
+        for property in &self.properties {
+            writeln!(
+                writer,
+                "{:<8}{:<8}{:<8}",
+                property.card, property.pid, property.mid
+            )?;
+        }
+        writeln!(writer, "ENDDATA")?;
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path.as_ref())
+            .with_context(|| format!("cannot create {}", path.as_ref().display()))?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// save triangle mesh as `CTRIA3` cards
+pub fn save_tri_mesh<P, Index, Real>(
+    path: P,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    Index: AsPrimitive<usize>,
+    Real: AsPrimitive<f64>,
+{
+    let mut mesh = NasMesh::new();
+    mesh.vtx2xyz = vtx2xyz.to_vec();
+    mesh.push_block(NasElementType::Tria3, tri2vtx.to_vec(), 1);
+    mesh.save(path)
+}
+
+#[test]
+fn test_read_write() -> anyhow::Result<()> {
+    let grid3 = format!(
+        "GRID*   {:>16}{:>16}{:>16}{:>16}\n*       {:>16}\n",
+        3, 0, "0.00000000000", "1.0000000000E+00", "0.0"
+    );
+    let txt = "ID test
+SOL 101
+CEND
+BEGIN BULK
+$ small field
+GRID           1       01.0     2.0     3.0
+GRID           2       01.5-1   0.0     -2.+1
+{grid3}GRID,4,,1.,1.,1.
+GRID,5,,0.,0.,1.
+CTRIA3        10       1       1       2       3
+CQUAD4,11,1,1,2,4,3
+CTETRA        20       2       1       2       3       5
+CBAR          30       3       1       2 0.0     0.0     1.0
+CHEXA         40       2       1       2       3       4       5       1
++              2       3
+PSHELL         1     100
+PSOLID,2,200
+ENDDATA
+GRID           9       0 9.0     9.0     9.0
+"
+    .replace("{grid3}", &grid3);
+    let mesh = read::<_, usize, f64>(std::io::Cursor::new(txt))?;
+    assert_eq!(mesh.vtx2gid, vec![1, 2, 3, 4, 5]);
+    assert_eq!(
+        &mesh.vtx2xyz[..9],
+        &[1.0, 2.0, 3.0, 0.15, 0.0, -20.0, 0.0, 1.0, 0.0]
+    );
+    let tri = mesh.block(NasElementType::Tria3).unwrap();
+    assert_eq!(tri.elem2vtx, vec![0, 1, 2]);
+    assert_eq!(tri.elem2eid, vec![10]);
+    assert_eq!(
+        mesh.block(NasElementType::Quad4).unwrap().elem2vtx,
+        vec![0, 1, 3, 2]
+    );
+    let tet = mesh.block(NasElementType::Tetra4).unwrap();
+    assert_eq!(
+        (tet.elem2vtx.clone(), tet.elem2pid.clone()),
+        (vec![0, 1, 2, 4], vec![2])
+    );
+    assert_eq!(
+        mesh.block(NasElementType::Bar).unwrap().elem2vtx,
+        vec![0, 1]
+    );
+    assert_eq!(
+        mesh.block(NasElementType::Hexa8).unwrap().elem2vtx,
+        vec![0, 1, 2, 3, 4, 0, 1, 2]
+    );
+    assert_eq!(
+        mesh.properties,
+        vec![
+            NasProperty {
+                card: "PSHELL".to_string(),
+                pid: 1,
+                mid: 100
+            },
+            NasProperty {
+                card: "PSOLID".to_string(),
+                pid: 2,
+                mid: 200
+            }
+        ]
+    );
+    //
+    mesh.save("../target/io_nas_test.nas")?;
+    let mesh1 = load::<_, u32, f64>("../target/io_nas_test.nas")?;
+    assert_eq!(mesh1.vtx2xyz, mesh.vtx2xyz);
+    assert_eq!(mesh1.blocks.len(), mesh.blocks.len());
+    for (b0, b1) in mesh.blocks.iter().zip(mesh1.blocks.iter()) {
+        assert_eq!(b0.elem_type, b1.elem_type);
+        assert_eq!(b0.elem2eid, b1.elem2eid);
+        assert_eq!(b0.elem2pid, b1.elem2pid);
+        assert!(b0
+            .elem2vtx
+            .iter()
+            .zip(b1.elem2vtx.iter())
+            .all(|(&i, &j)| i as u32 == j));
+    }
+    assert_eq!(mesh1.properties, mesh.properties);
+    let (tri2vtx, _vtx2xyz) = load_tri_mesh::<_, usize, f32>("../target/io_nas_test.nas")?;
+    assert_eq!(tri2vtx, vec![0, 1, 2, 0, 1, 3, 0, 3, 2]);
+    //
+    let err = read::<_, usize, f64>(std::io::Cursor::new("GRID,1,,1.,x,1.\n")).unwrap_err();
+    assert!(format!("{:#}", err).contains("line 1"));
+    let err = read::<_, usize, f64>(std::io::Cursor::new("CTRIA3,1,1,1,2,3\n")).unwrap_err();
+    assert!(format!("{:#}", err).contains("unknown grid"));
+    // free field line longer than ten fields
+    let mut txt: String = (1..=8)
+        .map(|i| format!("GRID,{},,{}.,0.,0.\n", i, i))
+        .collect();
+    txt += "CHEXA,1,2,1,2,3,4,5,6,+C1,+C1,7,8\n";
+    let mesh2 = read::<_, usize, f64>(std::io::Cursor::new(txt))?;
+    assert_eq!(
+        mesh2.block(NasElementType::Hexa8).unwrap().elem2vtx,
+        (0..8).collect::<Vec<_>>()
+    );
+    // the missing element ids do not collide with the given ones
+    let mut mesh = mesh;
+    mesh.blocks
         .iter_mut()
-        .zip(tri2idx)
-        .for_each(|(vtx, idx)| *vtx = idx2vtx[idx.as_()]);
-    (tri2vtx, vtx2xyz)
+        .find(|block| block.elem_type == NasElementType::Quad4)
+        .unwrap()
+        .elem2eid
+        .clear();
+    mesh.save("../target/io_nas_test_eid.nas")?;
+    let mesh1 = load::<_, usize, f64>("../target/io_nas_test_eid.nas")?;
+    assert_eq!(
+        mesh1.block(NasElementType::Quad4).unwrap().elem2eid,
+        vec![41]
+    );
+    Ok(())
 }
@@ -8,22 +8,37 @@ use std::ops::AddAssign;
 
 pub struct WavefrontObj<Index, Real> {
     pub vtx2xyz: Vec<Real>,
+    /// vertex colors given as `v x y z r g b`. Empty if no vertex has a color
+    pub vtx2rgb: Vec<Real>,
     pub vtx2uv: Vec<Real>,
     pub vtx2nrm: Vec<Real>,
     pub elem2idx: Vec<Index>,
     pub idx2vtx_xyz: Vec<Index>,
+    /// `Index::max_value()` if the corner does not have a texture coordinate
     pub idx2vtx_uv: Vec<Index>,
+    /// `Index::max_value()` if the corner does not have a normal
     pub idx2vtx_nrm: Vec<Index>,
     pub elem2group: Vec<Index>,
+    /// names of the groups. `g` with several names is not split into the groups but read as a
+    /// single group named by the names joined with a space
     pub group2name: Vec<String>,
+    pub elem2object: Vec<Index>,
+    pub object2name: Vec<String>,
+    /// smoothing group given by `s`. Zero means the smoothing is off
+    pub elem2smooth: Vec<Index>,
     pub elem2mtl: Vec<Index>,
     pub mtl_file_name: String,
     pub mtl2name: Vec<String>,
+    /// materials resolved from the MTL file, in the same order as `mtl2name`
+    pub mtl2material: Vec<crate::io_mtl::Material>,
+    /// polylines given by `l`
+    pub polyline2idx: Vec<Index>,
+    pub idx2vtx_polyline: Vec<Index>,
 }
 
 impl<Index, Real> WavefrontObj<Index, Real>
 where
-    Real: std::str::FromStr + std::fmt::Display + Copy + num_traits::Zero,
+    Real: std::str::FromStr + std::fmt::Display + Copy + num_traits::Zero + num_traits::One,
     Index: num_traits::PrimInt + 'static + AddAssign + AsPrimitive<usize> + Copy,
     usize: AsPrimitive<Index>,
     i32: AsPrimitive<Index>,
@@ -31,6 +46,7 @@ where
     pub fn new() -> Self {
         WavefrontObj::<Index, Real> {
             vtx2xyz: Vec::new(),
+            vtx2rgb: Vec::new(),
             vtx2uv: Vec::new(),
             vtx2nrm: Vec::new(),
             elem2idx: Vec::new(),
@@ -39,165 +55,305 @@ where
             idx2vtx_xyz: Vec::new(),
             elem2group: Vec::new(),
             group2name: Vec::new(),
+            elem2object: Vec::new(),
+            object2name: Vec::new(),
+            elem2smooth: Vec::new(),
             mtl_file_name: "".to_string(),
             elem2mtl: Vec::new(),
             mtl2name: Vec::new(),
+            mtl2material: Vec::new(),
+            polyline2idx: Vec::new(),
+            idx2vtx_polyline: Vec::new(),
         }
     }
 
     /// load wavefront obj file into the class
     pub fn load<P: AsRef<std::path::Path>>(&mut self, filename: P) -> anyhow::Result<()> {
-        let mut elem2vtx_xyz0: Vec<i32> = vec![];
-        let mut elem2vtx_uv0: Vec<i32> = vec![];
-        let mut elem2vtx_nrm0: Vec<i32> = vec![];
-        self.elem2group.clear();
-        self.elem2mtl.clear();
+        let f = File::open(filename.as_ref())
+            .with_context(|| format!("file not found: {}", filename.as_ref().display()))?;
+        self.load_from_reader(BufReader::new(f))
+    }
+
+    /// load wavefront obj from any buffered reader (e.g., `std::io::Cursor` for in-memory data)
+    pub fn load_from_reader<R: BufRead>(&mut self, reader: R) -> anyhow::Result<()> {
+        *self = Self::new();
         self.elem2idx = vec![Index::zero()];
+        self.polyline2idx = vec![Index::zero()];
         let mut name2group = std::collections::BTreeMap::<String, usize>::new();
+        let mut name2object = std::collections::BTreeMap::<String, usize>::new();
         let mut name2mtl = std::collections::BTreeMap::<String, usize>::new();
         name2group.insert("_default".to_string(), 0);
+        name2object.insert("_default".to_string(), 0);
         name2mtl.insert("_default".to_string(), 0);
         let mut i_group = 0_usize;
+        let mut i_object = 0_usize;
         let mut i_mtl = 0_usize;
-        let f = File::open(filename).context("file not found")?;
-        let reader = BufReader::new(f);
-        for line in reader.lines() {
-            let line = line.unwrap();
-            if line.is_empty() {
-                continue;
-            }
-            let char0 = line.chars().next();
-            if char0.is_none() {
-                continue;
-            }
-            let char0 = char0.unwrap();
-            let char1 = line.chars().nth(1);
-            if char1.is_none() {
-                continue;
+        let mut i_smooth = 0_usize;
+        let mut lines = reader.lines().enumerate();
+        while let Some((i_line, line)) = lines.next() {
+            let mut line = line?;
+            // join the lines continued by a trailing backslash
+            while line.trim_end().ends_with('\\') {
+                line.truncate(line.trim_end().len() - 1);
+                line.push(' ');
+                let Some((_, next)) = lines.next() else {
+                    break;
+                };
+                line += &next?;
             }
-            let char1 = char1.unwrap();
-            if char0 == '#' {
+            let content = line.split('#').next().unwrap();
+            let tokens: Vec<&str> = content.split_whitespace().collect();
+            let Some((&keyword, args)) = tokens.split_first() else {
                 continue;
-            }
-            if char0 == 'v' && char1 == ' ' {
-                let v: Vec<&str> = line.split_whitespace().collect();
-                let x = v[1].parse::<Real>().ok().unwrap();
-                let y = v[2].parse::<Real>().ok().unwrap();
-                let z = v[3].parse::<Real>().ok().unwrap();
-                self.vtx2xyz.push(x);
-                self.vtx2xyz.push(y);
-                self.vtx2xyz.push(z);
-            }
-            if char0 == 'g' && char1 == ' ' {
-                let v: Vec<&str> = line.split_whitespace().collect();
-                let name = v[1].to_string();
-                match name2group.get(&name) {
-                    None => {
-                        i_group = name2group.len();
-                        name2group.insert(name, i_group);
+            };
+            let res: anyhow::Result<()> = (|| {
+                match keyword {
+                    "v" => {
+                        if args.len() < 3 {
+                            return Err(anyhow::anyhow!("vertex needs three coordinates"));
+                        }
+                        for arg in &args[0..3] {
+                            self.vtx2xyz.push(parse_real(arg)?);
+                        }
+                        if args.len() >= 6 {
+                            let num_vtx = self.vtx2xyz.len() / 3;
+                            // vertices before the first colored one are white
+                            self.vtx2rgb.resize((num_vtx - 1) * 3, Real::one());
+                            for arg in &args[3..6] {
+                                self.vtx2rgb.push(parse_real(arg)?);
+                            }
+                        } else if !self.vtx2rgb.is_empty() {
+                            self.vtx2rgb.extend([Real::one(); 3]);
+                        }
                     }
-                    Some(&v) => {
-                        i_group = v;
+                    "vt" => {
+                        if args.is_empty() {
+                            return Err(anyhow::anyhow!("texture coordinate needs a value"));
+                        }
+                        self.vtx2uv.push(parse_real(args[0])?);
+                        self.vtx2uv.push(match args.get(1) {
+                            Some(v) => parse_real(v)?,
+                            None => Real::zero(),
+                        });
                     }
-                };
-            }
-            if char0 == 'm' && char1 == 't' {
-                let v: Vec<&str> = line.split_whitespace().collect();
-                self.mtl_file_name = v[1].to_string();
-            }
-            if char0 == 'u' && char1 == 's' {
-                let v: Vec<&str> = line.split_whitespace().collect();
-                let name = v[1].to_string();
-                match name2mtl.get(&name) {
-                    None => {
-                        i_mtl = name2mtl.len();
-                        name2mtl.insert(name, i_mtl);
+                    "vn" => {
+                        if args.len() < 3 {
+                            return Err(anyhow::anyhow!("normal needs three components"));
+                        }
+                        for arg in &args[0..3] {
+                            self.vtx2nrm.push(parse_real(arg)?);
+                        }
                     }
-                    Some(&v) => {
-                        i_mtl = v;
+                    "f" => {
+                        if args.len() < 3 {
+                            return Err(anyhow::anyhow!("face needs at least three vertices"));
+                        }
+                        let num_xyz = self.vtx2xyz.len() / 3;
+                        let num_uv = self.vtx2uv.len() / 2;
+                        let num_nrm = self.vtx2nrm.len() / 3;
+                        for arg in args {
+                            let (i_xyz, i_uv, i_nrm) = parse_vertex(arg)?;
+                            self.idx2vtx_xyz.push(resolve_index(i_xyz, num_xyz)?.as_());
+                            self.idx2vtx_uv.push(match i_uv {
+                                Some(i) => resolve_index(i, num_uv)?.as_(),
+                                None => Index::max_value(),
+                            });
+                            self.idx2vtx_nrm.push(match i_nrm {
+                                Some(i) => resolve_index(i, num_nrm)?.as_(),
+                                None => Index::max_value(),
+                            });
+                        }
+                        self.elem2idx.push(self.idx2vtx_xyz.len().as_());
+                        self.elem2group.push(i_group.as_());
+                        self.elem2object.push(i_object.as_());
+                        self.elem2smooth.push(i_smooth.as_());
+                        self.elem2mtl.push(i_mtl.as_());
                     }
-                };
+                    "l" => {
+                        if args.len() < 2 {
+                            return Err(anyhow::anyhow!("polyline needs at least two vertices"));
+                        }
+                        let num_xyz = self.vtx2xyz.len() / 3;
+                        for arg in args {
+                            let (i_xyz, _, _) = parse_vertex(arg)?;
+                            self.idx2vtx_polyline
+                                .push(resolve_index(i_xyz, num_xyz)?.as_());
+                        }
+                        self.polyline2idx.push(self.idx2vtx_polyline.len().as_());
+                    }
+                    "g" => i_group = index_of_name(&mut name2group, args),
+                    "o" => i_object = index_of_name(&mut name2object, args),
+                    "usemtl" => i_mtl = index_of_name(&mut name2mtl, args),
+                    "mtllib" => self.mtl_file_name = args.join(" "),
+                    "s" => {
+                        i_smooth = match args.first() {
+                            None | Some(&"off") => 0,
+                            Some(v) => v.parse::<usize>()?,
+                        };
+                    }
+                    _ => {} // unsupported statement
+                }
+                Ok(())
+            })();
+            res.with_context(|| format!("line {}: {}", i_line + 1, line))?;
+        } // end loop over text
+        self.group2name = names_from_map(&name2group);
+        self.object2name = names_from_map(&name2object);
+        self.mtl2name = names_from_map(&name2mtl);
+        Ok(())
+    }
+
+    /// write everything `load_from_reader` reads. All the groups, objects and materials are
+    /// declared first so that their indices are kept on reload even if they have no element, then
+    /// the switches are emitted where they change between consecutive elements
+    pub fn write<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        if !self.mtl_file_name.is_empty() {
+            writeln!(writer, "mtllib {}", self.mtl_file_name)?;
+        }
+        let has_rgb = !self.vtx2rgb.is_empty();
+        for i_vtx in 0..self.vtx2xyz.len() / 3 {
+            let xyz = &self.vtx2xyz[i_vtx * 3..i_vtx * 3 + 3];
+            if has_rgb {
+                let rgb = &self.vtx2rgb[i_vtx * 3..i_vtx * 3 + 3];
+                writeln!(
+                    writer,
+                    "v {} {} {} {} {} {}",
+                    xyz[0], xyz[1], xyz[2], rgb[0], rgb[1], rgb[2]
+                )?;
+            } else {
+                writeln!(writer, "v {} {} {}", xyz[0], xyz[1], xyz[2])?;
             }
-            if char0 == 'v' && char1 == 'n' {
-                let v: Vec<&str> = line.split_whitespace().collect();
-                let x = v[1].parse::<Real>().ok().unwrap();
-                let y = v[2].parse::<Real>().ok().unwrap();
-                let z = v[3].parse::<Real>().ok().unwrap();
-                self.vtx2nrm.push(x);
-                self.vtx2nrm.push(y);
-                self.vtx2nrm.push(z);
+        }
+        for uv in self.vtx2uv.chunks(2) {
+            writeln!(writer, "vt {} {}", uv[0], uv[1])?;
+        }
+        for nrm in self.vtx2nrm.chunks(3) {
+            writeln!(writer, "vn {} {} {}", nrm[0], nrm[1], nrm[2])?;
+        }
+        let (mut i_object, mut i_group, mut i_smooth, mut i_mtl) = (0, 0, 0, 0);
+        // the entry zero is `_default` the loader starts with
+        for (keyword, idx2name, i_current) in [
+            ("o", &self.object2name, &mut i_object),
+            ("g", &self.group2name, &mut i_group),
+            ("usemtl", &self.mtl2name, &mut i_mtl),
+        ] {
+            for name in idx2name.iter().skip(1) {
+                writeln!(writer, "{} {}", keyword, name)?;
             }
-            if char0 == 'v' && char1 == 't' {
-                let v: Vec<&str> = line.split_whitespace().collect();
-                let u = v[1].parse::<Real>().ok().unwrap();
-                let v = v[2].parse::<Real>().ok().unwrap();
-                self.vtx2uv.push(u);
-                self.vtx2uv.push(v);
+            *i_current = idx2name.len().max(1) - 1;
+        }
+        for i_elem in 0..self.elem2idx.len().max(1) - 1 {
+            if let Some(&j_object) = self.elem2object.get(i_elem) {
+                let j_object: usize = j_object.as_();
+                if j_object != i_object {
+                    writeln!(writer, "o {}", self.object2name[j_object])?;
+                    i_object = j_object;
+                }
             }
-            if char0 == 'f' && char1 == ' ' {
-                let v: Vec<&str> = line.split_whitespace().collect();
-                for v_ in v.iter().skip(1) {
-                    // skip first 'f'
-                    let (ipnt, itex, inrm) = parse_vertex(v_);
-                    elem2vtx_xyz0.push(ipnt);
-                    elem2vtx_uv0.push(itex);
-                    elem2vtx_nrm0.push(inrm);
+            if let Some(&j_group) = self.elem2group.get(i_elem) {
+                let j_group: usize = j_group.as_();
+                if j_group != i_group {
+                    writeln!(writer, "g {}", self.group2name[j_group])?;
+                    i_group = j_group;
                 }
-                self.elem2idx.push(elem2vtx_xyz0.len().as_());
-                self.elem2group.push(i_group.as_());
-                self.elem2mtl.push(i_mtl.as_());
             }
-        } // end loop over text
-        self.group2name = vec!["".to_string(); name2group.len()];
-        for (name, &i_group) in name2group.iter() {
-            self.group2name[i_group].clone_from(name);
-        }
-        self.mtl2name = vec!["".to_string(); name2mtl.len()];
-        for (name, &i_mtl) in name2mtl.iter() {
-            self.mtl2name[i_mtl].clone_from(name);
-        }
-        {
-            // fix veretx_xyz index
-            let nvtx_xyz = self.vtx2xyz.len() / 3;
-            self.idx2vtx_xyz = elem2vtx_xyz0
-                .iter()
-                .map(|i| {
-                    if *i >= 0 {
-                        (*i).as_()
-                    } else {
-                        (nvtx_xyz as i32 + *i).as_()
+            if let Some(&j_smooth) = self.elem2smooth.get(i_elem) {
+                let j_smooth: usize = j_smooth.as_();
+                if j_smooth != i_smooth {
+                    match j_smooth {
+                        0 => writeln!(writer, "s off")?,
+                        _ => writeln!(writer, "s {}", j_smooth)?,
                     }
-                })
-                .collect();
-        }
-        {
-            // fix veretx_uv index
-            let nvtx_uv = self.vtx2uv.len() / 3;
-            self.idx2vtx_uv = elem2vtx_uv0
-                .iter()
-                .map(|i| {
-                    if *i >= 0 {
-                        (*i).as_()
-                    } else {
-                        (nvtx_uv as i32 + *i).as_()
+                    i_smooth = j_smooth;
+                }
+            }
+            if let Some(&j_mtl) = self.elem2mtl.get(i_elem) {
+                let j_mtl: usize = j_mtl.as_();
+                if j_mtl != i_mtl {
+                    writeln!(writer, "usemtl {}", self.mtl2name[j_mtl])?;
+                    i_mtl = j_mtl;
+                }
+            }
+            write!(writer, "f")?;
+            let idx0: usize = self.elem2idx[i_elem].as_();
+            let idx1: usize = self.elem2idx[i_elem + 1].as_();
+            for idx in idx0..idx1 {
+                let i_xyz: usize = self.idx2vtx_xyz[idx].as_();
+                let i_uv = self
+                    .idx2vtx_uv
+                    .get(idx)
+                    .filter(|&&i| i != Index::max_value());
+                let i_nrm = self
+                    .idx2vtx_nrm
+                    .get(idx)
+                    .filter(|&&i| i != Index::max_value());
+                write!(writer, " {}", i_xyz + 1)?;
+                match (i_uv, i_nrm) {
+                    (None, None) => {}
+                    (Some(&i_uv), None) => write!(writer, "/{}", i_uv.as_() + 1)?,
+                    (None, Some(&i_nrm)) => write!(writer, "//{}", i_nrm.as_() + 1)?,
+                    (Some(&i_uv), Some(&i_nrm)) => {
+                        write!(writer, "/{}/{}", i_uv.as_() + 1, i_nrm.as_() + 1)?
                     }
-                })
-                .collect();
+                }
+            }
+            writeln!(writer)?;
         }
-        {
-            // fix veretx_nrm index
-            let nvtx_nrm = self.vtx2nrm.len() / 3;
-            self.idx2vtx_nrm = elem2vtx_nrm0
+        for i_polyline in 0..self.polyline2idx.len().max(1) - 1 {
+            write!(writer, "l")?;
+            let idx0: usize = self.polyline2idx[i_polyline].as_();
+            let idx1: usize = self.polyline2idx[i_polyline + 1].as_();
+            for &i_vtx in &self.idx2vtx_polyline[idx0..idx1] {
+                write!(writer, " {}", i_vtx.as_() + 1)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// save wavefront obj file
+    pub fn save<P: AsRef<std::path::Path>>(&self, filename: P) -> anyhow::Result<()> {
+        let file = File::create(filename.as_ref())
+            .with_context(|| format!("cannot create {}", filename.as_ref().display()))?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// load wavefront obj file and the MTL file referenced by `mtllib` (if any)
+    pub fn load_with_materials<P: AsRef<std::path::Path>>(
+        &mut self,
+        filename: P,
+    ) -> anyhow::Result<()> {
+        self.load(&filename)?;
+        if self.mtl_file_name.is_empty() {
+            self.mtl2material = self
+                .mtl2name
                 .iter()
-                .map(|i| {
-                    if *i >= 0 {
-                        (*i).as_()
-                    } else {
-                        (nvtx_nrm as i32 + *i).as_()
-                    }
-                })
+                .map(|name| crate::io_mtl::Material::new(name))
                 .collect();
+            return Ok(());
         }
+        let dir = filename
+            .as_ref()
+            .parent()
+            .unwrap_or(std::path::Path::new(""));
+        self.load_materials(dir.join(&self.mtl_file_name))
+    }
+
+    /// set `mtl2material` from a MTL file so that `elem2mtl` indexes it directly.
+    /// Names not defined in the file (e.g., `_default`) get the default material
+    pub fn load_materials<P: AsRef<std::path::Path>>(&mut self, path: P) -> anyhow::Result<()> {
+        let mtls = crate::io_mtl::load(path)?;
+        self.mtl2material = self
+            .mtl2name
+            .iter()
+            .map(|name| match mtls.iter().find(|m| &m.name == name) {
+                Some(m) => m.clone(),
+                None => crate::io_mtl::Material::new(name),
+            })
+            .collect();
         Ok(())
     }
 
@@ -216,7 +372,7 @@ where
 
 impl<Index, Real> Default for WavefrontObj<Index, Real>
 where
-    Real: std::str::FromStr + std::fmt::Display + Copy + num_traits::Zero,
+    Real: std::str::FromStr + std::fmt::Display + Copy + num_traits::Zero + num_traits::One,
     Index: num_traits::PrimInt + 'static + AddAssign + AsPrimitive<usize> + Copy,
     usize: AsPrimitive<Index>,
     i32: AsPrimitive<Index>,
@@ -226,6 +382,179 @@ where
     }
 }
 
+/// options of `WavefrontObj::load_parallel`
+#[derive(Clone, Debug, Default)]
+pub struct ParallelLoadOptions {
+    /// number of threads. The available parallelism is used if zero
+    pub num_thread: usize,
+    /// ignore `vt` and the texture indices of the faces. `vtx2uv` and `idx2vtx_uv` become empty
+    pub skip_uv: bool,
+    /// ignore `vn` and the normal indices of the faces. `vtx2nrm` and `idx2vtx_nrm` become empty
+    pub skip_nrm: bool,
+}
+
+impl<Index, Real> WavefrontObj<Index, Real>
+where
+    Real: num_traits::Float + std::str::FromStr + std::fmt::Display + Send + Sync,
+    Index: num_traits::PrimInt + 'static + AddAssign + AsPrimitive<usize> + Send + Sync,
+    usize: AsPrimitive<Index>,
+    i32: AsPrimitive<Index>,
+{
+    /// load wavefront obj file by parsing chunks of the text in parallel.
+    /// The result is identical to `load` except for the skipped texture coordinates and normals.
+    ///
+    /// The whole file is read into memory at once because both passes of
+    /// `load_from_bytes_parallel` go over all the chunks, so the peak memory is the file size plus
+    /// the arrays of the mesh. Use `load`, which streams the file line by line, if the file does
+    /// not fit in memory
+    pub fn load_parallel<P: AsRef<std::path::Path>>(
+        &mut self,
+        filename: P,
+        options: &ParallelLoadOptions,
+    ) -> anyhow::Result<()> {
+        let bytes = std::fs::read(filename.as_ref())
+            .with_context(|| format!("file not found: {}", filename.as_ref().display()))?;
+        self.load_from_bytes_parallel(&bytes, options)
+    }
+
+    /// load wavefront obj from the bytes in two parallel passes over the chunks split at line
+    /// breaks. The first pass counts the items so that the second pass writes them directly
+    /// into the arrays allocated with the exact sizes
+    pub fn load_from_bytes_parallel(
+        &mut self,
+        bytes: &[u8],
+        options: &ParallelLoadOptions,
+    ) -> anyhow::Result<()> {
+        let text = std::str::from_utf8(bytes).context("obj file is not valid UTF-8")?;
+        let num_thread = match options.num_thread {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let chunks = split_at_line_breaks(text, num_thread);
+        let chunk2count: Vec<ObjChunkCount> = std::thread::scope(|s| {
+            let handles: Vec<_> = chunks
+                .iter()
+                .map(|chunk| s.spawn(|| count_chunk(chunk, options)))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        // offsets of the chunks and the total
+        let mut chunk2offset = vec![ObjChunkCount::default()];
+        for count in &chunk2count {
+            let offset = chunk2offset.last().unwrap().plus(count);
+            chunk2offset.push(offset);
+        }
+        let total = chunk2offset.pop().unwrap();
+        //
+        *self = Self::new();
+        self.vtx2xyz = vec![Real::zero(); total.num_xyz * 3];
+        if total.has_rgb {
+            self.vtx2rgb = vec![Real::one(); total.num_xyz * 3];
+        }
+        self.vtx2uv = vec![Real::zero(); total.num_uv * 2];
+        self.vtx2nrm = vec![Real::zero(); total.num_nrm * 3];
+        self.elem2idx = vec![Index::zero(); total.num_elem + 1];
+        self.idx2vtx_xyz = vec![Index::zero(); total.num_idx];
+        if !options.skip_uv {
+            self.idx2vtx_uv = vec![Index::zero(); total.num_idx];
+        }
+        if !options.skip_nrm {
+            self.idx2vtx_nrm = vec![Index::zero(); total.num_idx];
+        }
+        self.elem2group = vec![Index::zero(); total.num_elem];
+        self.elem2object = vec![Index::zero(); total.num_elem];
+        self.elem2smooth = vec![Index::zero(); total.num_elem];
+        self.elem2mtl = vec![Index::zero(); total.num_elem];
+        self.polyline2idx = vec![Index::zero(); total.num_polyline + 1];
+        self.idx2vtx_polyline = vec![Index::zero(); total.num_idx_polyline];
+        //
+        let sizes = |f: &dyn Fn(&ObjChunkCount) -> usize| -> Vec<usize> {
+            chunk2count.iter().map(f).collect()
+        };
+        let num_rgb = if total.has_rgb { 3 } else { 0 };
+        let num_uv_idx = if options.skip_uv { 0 } else { 1 };
+        let num_nrm_idx = if options.skip_nrm { 0 } else { 1 };
+        let mut vtx2xyz = split_mut(&mut self.vtx2xyz, &sizes(&|c| c.num_xyz * 3));
+        let mut vtx2rgb = split_mut(&mut self.vtx2rgb, &sizes(&|c| c.num_xyz * num_rgb));
+        let mut vtx2uv = split_mut(&mut self.vtx2uv, &sizes(&|c| c.num_uv * 2));
+        let mut vtx2nrm = split_mut(&mut self.vtx2nrm, &sizes(&|c| c.num_nrm * 3));
+        let mut elem2idx = split_mut(&mut self.elem2idx[1..], &sizes(&|c| c.num_elem));
+        let mut idx2vtx_xyz = split_mut(&mut self.idx2vtx_xyz, &sizes(&|c| c.num_idx));
+        let mut idx2vtx_uv = split_mut(&mut self.idx2vtx_uv, &sizes(&|c| c.num_idx * num_uv_idx));
+        let mut idx2vtx_nrm =
+            split_mut(&mut self.idx2vtx_nrm, &sizes(&|c| c.num_idx * num_nrm_idx));
+        let mut elem2group = split_mut(&mut self.elem2group, &sizes(&|c| c.num_elem));
+        let mut elem2object = split_mut(&mut self.elem2object, &sizes(&|c| c.num_elem));
+        let mut elem2smooth = split_mut(&mut self.elem2smooth, &sizes(&|c| c.num_elem));
+        let mut elem2mtl = split_mut(&mut self.elem2mtl, &sizes(&|c| c.num_elem));
+        let mut polyline2idx = split_mut(&mut self.polyline2idx[1..], &sizes(&|c| c.num_polyline));
+        let mut idx2vtx_polyline =
+            split_mut(&mut self.idx2vtx_polyline, &sizes(&|c| c.num_idx_polyline));
+        let chunk2out: Vec<ObjChunkOutput<Index, Real>> = (0..chunks.len())
+            .map(|_| ObjChunkOutput {
+                vtx2xyz: vtx2xyz.next().unwrap(),
+                vtx2rgb: vtx2rgb.next().unwrap(),
+                vtx2uv: vtx2uv.next().unwrap(),
+                vtx2nrm: vtx2nrm.next().unwrap(),
+                elem2idx: elem2idx.next().unwrap(),
+                idx2vtx_xyz: idx2vtx_xyz.next().unwrap(),
+                idx2vtx_uv: idx2vtx_uv.next().unwrap(),
+                idx2vtx_nrm: idx2vtx_nrm.next().unwrap(),
+                elem2group: elem2group.next().unwrap(),
+                elem2object: elem2object.next().unwrap(),
+                elem2smooth: elem2smooth.next().unwrap(),
+                elem2mtl: elem2mtl.next().unwrap(),
+                polyline2idx: polyline2idx.next().unwrap(),
+                idx2vtx_polyline: idx2vtx_polyline.next().unwrap(),
+            })
+            .collect();
+        let results: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = chunks
+                .iter()
+                .zip(chunk2offset.iter())
+                .zip(chunk2out)
+                .map(|((chunk, offset), mut out)| {
+                    s.spawn(move || {
+                        parse_chunk(chunk, offset, &mut out, options).map(|state| (out, state))
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        // the groups, objects, smoothing groups and materials of the elements at the beginning
+        // of a chunk are given by the preceding chunks
+        let mut name2group = std::collections::BTreeMap::<String, usize>::new();
+        let mut name2object = std::collections::BTreeMap::<String, usize>::new();
+        let mut name2mtl = std::collections::BTreeMap::<String, usize>::new();
+        name2group.insert("_default".to_string(), 0);
+        name2object.insert("_default".to_string(), 0);
+        name2mtl.insert("_default".to_string(), 0);
+        let (mut i_group, mut i_object, mut i_smooth, mut i_mtl) = (0, 0, 0, 0);
+        let mut mtl_file_name = None;
+        for res in results {
+            let (out, state) = res?;
+            state
+                .group
+                .to_global(out.elem2group, Some(&mut name2group), &mut i_group);
+            state
+                .object
+                .to_global(out.elem2object, Some(&mut name2object), &mut i_object);
+            state.smooth.to_global(out.elem2smooth, None, &mut i_smooth);
+            state
+                .mtl
+                .to_global(out.elem2mtl, Some(&mut name2mtl), &mut i_mtl);
+            if state.mtl_file_name.is_some() {
+                mtl_file_name = state.mtl_file_name;
+            }
+        }
+        self.mtl_file_name = mtl_file_name.unwrap_or_default();
+        self.group2name = names_from_map(&name2group);
+        self.object2name = names_from_map(&name2object);
+        self.mtl2name = names_from_map(&name2mtl);
+        Ok(())
+    }
+}
+
 pub fn load_tri_mesh<P: AsRef<std::path::Path>, Index, Real>(
     filepath: P,
     scale: Option<Real>,
@@ -532,19 +861,756 @@ where
 // -------------------------
 // below: private functions
 
-fn parse_vertex(str_in: &str) -> (i32, i32, i32) {
-    let snums: Vec<&str> = str_in.split('/').collect();
-    let mut nums: [i32; 3] = [0, 0, 0];
-    for i in 0..snums.len() {
-        nums[i] = snums[i].parse::<i32>().unwrap_or(0);
+fn parse_real<Real: std::str::FromStr>(s: &str) -> anyhow::Result<Real> {
+    s.parse::<Real>()
+        .ok()
+        .with_context(|| format!("cannot parse \"{}\" as a number", s))
+}
+
+/// parse `v`, `v/vt`, `v//vn` or `v/vt/vn`. The indices are one-based or negative (relative)
+fn parse_vertex(str_in: &str) -> anyhow::Result<(i64, Option<i64>, Option<i64>)> {
+    let mut snums = str_in.split('/');
+    let parse = |s: Option<&str>| -> anyhow::Result<Option<i64>> {
+        match s {
+            None | Some("") => Ok(None),
+            Some(s) => Ok(Some(s.parse::<i64>().with_context(|| {
+                format!("cannot parse \"{}\" as a vertex index", str_in)
+            })?)),
+        }
+    };
+    let Some(i_xyz) = parse(snums.next())? else {
+        return Err(anyhow::anyhow!("missing vertex index in \"{}\"", str_in));
+    };
+    Ok((i_xyz, parse(snums.next())?, parse(snums.next())?))
+}
+
+/// convert the index in the file into the zero-based index into the items defined so far
+fn resolve_index(i: i64, num: usize) -> anyhow::Result<usize> {
+    let num = num as i64;
+    if i > 0 && i <= num {
+        Ok((i - 1) as usize)
+    } else if i < 0 && -i <= num {
+        Ok((num + i) as usize)
+    } else {
+        Err(anyhow::anyhow!(
+            "index {} is out of range (number of items: {})",
+            i,
+            num
+        ))
+    }
+}
+
+fn index_of_name(name2idx: &mut std::collections::BTreeMap<String, usize>, args: &[&str]) -> usize {
+    let name = if args.is_empty() {
+        "_default".to_string()
+    } else {
+        args.join(" ")
+    };
+    let num_name = name2idx.len();
+    *name2idx.entry(name).or_insert(num_name)
+}
+
+fn names_from_map(name2idx: &std::collections::BTreeMap<String, usize>) -> Vec<String> {
+    let mut idx2name = vec!["".to_string(); name2idx.len()];
+    for (name, &idx) in name2idx.iter() {
+        idx2name[idx].clone_from(name);
+    }
+    idx2name
+}
+
+/// parse the decimal number by the fast path of Clinger if the digits and the power of ten are
+/// exactly representable in `Real`, so that the single multiplication or division is correctly
+/// rounded. Falls back to `str::parse` otherwise
+fn parse_real_fast<Real>(s: &str, max_mantissa: u64, max_exp10: i32) -> anyhow::Result<Real>
+where
+    Real: num_traits::Float + std::str::FromStr,
+{
+    let b = s.as_bytes();
+    let mut i = 0;
+    let is_negative = match b.first() {
+        Some(b'-') => {
+            i += 1;
+            true
+        }
+        Some(b'+') => {
+            i += 1;
+            false
+        }
+        _ => false,
+    };
+    let mut mantissa = 0u64;
+    let mut num_digit = 0; // digits in the mantissa without the leading zeros
+    let mut has_digit = false;
+    let mut exp10 = 0i32;
+    let mut is_fraction = false;
+    while i < b.len() {
+        match b[i] {
+            c @ b'0'..=b'9' => {
+                has_digit = true;
+                if mantissa != 0 || c != b'0' {
+                    num_digit += 1;
+                    if num_digit > 19 {
+                        return parse_real(s);
+                    }
+                }
+                mantissa = mantissa * 10 + (c - b'0') as u64;
+                if is_fraction {
+                    exp10 -= 1;
+                }
+            }
+            b'.' if !is_fraction => is_fraction = true,
+            _ => break,
+        }
+        i += 1;
+    }
+    if i < b.len() && (b[i] == b'e' || b[i] == b'E') {
+        let Ok(e) = s[i + 1..].parse::<i32>() else {
+            return parse_real(s);
+        };
+        exp10 = exp10.saturating_add(e);
+        i = b.len();
+    }
+    if !has_digit || i != b.len() || mantissa > max_mantissa || exp10.abs() > max_exp10 {
+        return parse_real(s);
+    }
+    const POW10: [f64; 23] = [
+        1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+        1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+    ];
+    let m = Real::from(mantissa).unwrap();
+    let p = Real::from(POW10[exp10.unsigned_abs() as usize]).unwrap();
+    let v = if exp10 < 0 { m / p } else { m * p };
+    Ok(if is_negative { -v } else { v })
+}
+
+/// largest mantissa and power of ten that are exactly representable in `Real`
+fn fast_path_limits<Real: num_traits::Float>() -> (u64, i32) {
+    let eps = Real::epsilon().to_f64();
+    if eps == Some(f32::EPSILON as f64) {
+        (1 << 24, 10)
+    } else if eps == Some(f64::EPSILON) {
+        (1 << 53, 22)
+    } else {
+        (0, -1)
+    }
+}
+
+/// numbers of the items in a chunk of the text, or the offsets of the items of a chunk
+#[derive(Clone, Debug, Default)]
+struct ObjChunkCount {
+    num_line: usize,
+    has_rgb: bool,
+    num_xyz: usize,
+    num_uv: usize,
+    num_nrm: usize,
+    num_elem: usize,
+    num_idx: usize,
+    num_polyline: usize,
+    num_idx_polyline: usize,
+}
+
+impl ObjChunkCount {
+    fn plus(&self, other: &Self) -> Self {
+        ObjChunkCount {
+            num_line: self.num_line + other.num_line,
+            has_rgb: self.has_rgb || other.has_rgb,
+            num_xyz: self.num_xyz + other.num_xyz,
+            num_uv: self.num_uv + other.num_uv,
+            num_nrm: self.num_nrm + other.num_nrm,
+            num_elem: self.num_elem + other.num_elem,
+            num_idx: self.num_idx + other.num_idx,
+            num_polyline: self.num_polyline + other.num_polyline,
+            num_idx_polyline: self.num_idx_polyline + other.num_idx_polyline,
+        }
     }
-    (nums[0] - 1, nums[1] - 1, nums[2] - 1)
+}
+
+/// part of the arrays of `WavefrontObj` where a chunk is parsed into
+struct ObjChunkOutput<'a, Index, Real> {
+    vtx2xyz: &'a mut [Real],
+    vtx2rgb: &'a mut [Real],
+    vtx2uv: &'a mut [Real],
+    vtx2nrm: &'a mut [Real],
+    elem2idx: &'a mut [Index],
+    idx2vtx_xyz: &'a mut [Index],
+    idx2vtx_uv: &'a mut [Index],
+    idx2vtx_nrm: &'a mut [Index],
+    elem2group: &'a mut [Index],
+    elem2object: &'a mut [Index],
+    elem2smooth: &'a mut [Index],
+    elem2mtl: &'a mut [Index],
+    polyline2idx: &'a mut [Index],
+    idx2vtx_polyline: &'a mut [Index],
+}
+
+/// switches of the group, object, smoothing group or material in a chunk.
+/// The elements before the first switch inherit the state at the end of the preceding chunks
+#[derive(Default)]
+struct ObjChunkSwitch {
+    /// local index of the names in the order of appearance in the chunk
+    name2idx: std::collections::BTreeMap<String, usize>,
+    current: Option<usize>,
+    num_elem_inherit: usize,
+}
+
+impl ObjChunkSwitch {
+    /// local index (or the smoothing group) of the next element
+    fn next_elem(&mut self) -> usize {
+        match self.current {
+            Some(i) => i,
+            None => {
+                self.num_elem_inherit += 1;
+                0
+            }
+        }
+    }
+
+    /// convert the local indices into the global ones and update the global state
+    /// * `name2idx` - global names. The local value is used as is if `None`
+    fn to_global<Index>(
+        &self,
+        elem2idx: &mut [Index],
+        name2idx: Option<&mut std::collections::BTreeMap<String, usize>>,
+        current: &mut usize,
+    ) where
+        Index: Copy + AsPrimitive<usize>,
+        usize: AsPrimitive<Index>,
+    {
+        let local2global: Option<Vec<usize>> = name2idx.map(|name2idx| {
+            names_from_map(&self.name2idx)
+                .into_iter()
+                .map(|name| {
+                    let num_name = name2idx.len();
+                    *name2idx.entry(name).or_insert(num_name)
+                })
+                .collect()
+        });
+        let map = |i: usize| local2global.as_ref().map_or(i, |l2g| l2g[i]);
+        let (elem2idx_inherit, elem2idx_local) = elem2idx.split_at_mut(self.num_elem_inherit);
+        elem2idx_inherit.fill(current.as_());
+        for i in elem2idx_local.iter_mut() {
+            *i = map(i.as_()).as_();
+        }
+        if let Some(i) = self.current {
+            *current = map(i);
+        }
+    }
+}
+
+/// names and states of a parsed chunk that depend on the preceding chunks
+#[derive(Default)]
+struct ObjChunkState {
+    group: ObjChunkSwitch,
+    object: ObjChunkSwitch,
+    smooth: ObjChunkSwitch,
+    mtl: ObjChunkSwitch,
+    mtl_file_name: Option<String>,
+}
+
+/// lines where the ones continued by a trailing backslash are joined, with the zero-based
+/// number of the first line
+fn joined_lines(text: &str) -> impl Iterator<Item = (usize, std::borrow::Cow<'_, str>)> {
+    let mut lines = text.lines().enumerate();
+    std::iter::from_fn(move || {
+        let (i_line, line) = lines.next()?;
+        if !line.trim_end().ends_with('\\') {
+            return Some((i_line, std::borrow::Cow::Borrowed(line)));
+        }
+        let mut line = line.to_string();
+        while line.trim_end().ends_with('\\') {
+            line.truncate(line.trim_end().len() - 1);
+            line.push(' ');
+            let Some((_, next)) = lines.next() else {
+                break;
+            };
+            line += next;
+        }
+        Some((i_line, std::borrow::Cow::Owned(line)))
+    })
+}
+
+/// split the text into chunks of similar sizes that end at the line breaks not continued by
+/// a backslash
+fn split_at_line_breaks(text: &str, num_chunk: usize) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut i0 = 0;
+    for i_chunk in 1..=num_chunk {
+        let mut i1 = (text.len() * i_chunk / num_chunk).max(i0);
+        while !text.is_char_boundary(i1) {
+            i1 += 1;
+        }
+        loop {
+            let Some(i_break) = text[i1..].find('\n').map(|i| i + i1) else {
+                i1 = text.len();
+                break;
+            };
+            let i_line = text[..i_break].rfind('\n').map_or(0, |i| i + 1);
+            i1 = i_break + 1;
+            if !text[i_line..i_break].trim_end().ends_with('\\') {
+                break;
+            }
+        }
+        if i_chunk == num_chunk {
+            i1 = text.len();
+        }
+        chunks.push(&text[i0..i1]);
+        i0 = i1;
+    }
+    chunks
+}
+
+fn split_mut<'a, T>(mut slice: &'a mut [T], sizes: &[usize]) -> std::vec::IntoIter<&'a mut [T]> {
+    let mut slices = Vec::with_capacity(sizes.len());
+    for &size in sizes {
+        let (head, tail) = std::mem::take(&mut slice).split_at_mut(size);
+        slices.push(head);
+        slice = tail;
+    }
+    slices.into_iter()
+}
+
+/// split the statement into the keyword and the arguments
+fn split_keyword(content: &str) -> Option<(&str, &str)> {
+    let content = content.trim_start();
+    let i_end = content.find(char::is_whitespace).unwrap_or(content.len());
+    match i_end {
+        0 => None,
+        _ => Some((&content[..i_end], &content[i_end..])),
+    }
+}
+
+/// number of the arguments up to `max`. The ASCII text is split without decoding the characters
+fn num_arg(args: &str, is_ascii: bool, max: usize) -> usize {
+    if is_ascii {
+        let is_whitespace = |b: &u8| matches!(b, b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r');
+        let tokens = args.as_bytes().split(is_whitespace);
+        tokens.filter(|t| !t.is_empty()).take(max).count()
+    } else {
+        args.split_whitespace().take(max).count()
+    }
+}
+
+fn count_chunk(text: &str, options: &ParallelLoadOptions) -> ObjChunkCount {
+    let mut count = ObjChunkCount {
+        num_line: text.bytes().filter(|&b| b == b'\n').count(),
+        ..Default::default()
+    };
+    let is_ascii = text.is_ascii();
+    for (_, line) in joined_lines(text) {
+        let content = line.split('#').next().unwrap();
+        let Some((keyword, args)) = split_keyword(content) else {
+            continue;
+        };
+        match keyword {
+            "v" => {
+                count.num_xyz += 1;
+                count.has_rgb |= num_arg(args, is_ascii, 6) == 6;
+            }
+            "vt" if !options.skip_uv => count.num_uv += 1,
+            "vn" if !options.skip_nrm => count.num_nrm += 1,
+            "f" => {
+                count.num_elem += 1;
+                count.num_idx += num_arg(args, is_ascii, usize::MAX);
+            }
+            "l" => {
+                count.num_polyline += 1;
+                count.num_idx_polyline += num_arg(args, is_ascii, usize::MAX);
+            }
+            _ => {}
+        }
+    }
+    count
+}
+
+/// parse the chunk in the same way as `WavefrontObj::load_from_reader`
+/// * `offset` - numbers of the items in the preceding chunks
+fn parse_chunk<Index, Real>(
+    text: &str,
+    offset: &ObjChunkCount,
+    out: &mut ObjChunkOutput<Index, Real>,
+    options: &ParallelLoadOptions,
+) -> anyhow::Result<ObjChunkState>
+where
+    Real: num_traits::Float + std::str::FromStr,
+    Index: num_traits::PrimInt + 'static,
+    usize: AsPrimitive<Index>,
+{
+    let (max_mantissa, max_exp10) = fast_path_limits::<Real>();
+    let parse = |s: &str| parse_real_fast::<Real>(s, max_mantissa, max_exp10);
+    let mut state = ObjChunkState::default();
+    let mut c = ObjChunkCount::default(); // items parsed so far
+    for (i_line, line) in joined_lines(text) {
+        let content = line.split('#').next().unwrap();
+        let mut tokens = content.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let res: anyhow::Result<()> = (|| {
+            match keyword {
+                "v" => {
+                    let mut args = [""; 6];
+                    let mut num_arg = 0;
+                    for arg in tokens.take(6) {
+                        args[num_arg] = arg;
+                        num_arg += 1;
+                    }
+                    if num_arg < 3 {
+                        return Err(anyhow::anyhow!("vertex needs three coordinates"));
+                    }
+                    let range = c.num_xyz * 3..c.num_xyz * 3 + 3;
+                    for (v, arg) in out.vtx2xyz[range.clone()].iter_mut().zip(&args[0..3]) {
+                        *v = parse(arg)?;
+                    }
+                    if num_arg == 6 {
+                        for (v, arg) in out.vtx2rgb[range].iter_mut().zip(&args[3..6]) {
+                            *v = parse(arg)?;
+                        }
+                    }
+                    c.num_xyz += 1;
+                }
+                "vt" if !options.skip_uv => {
+                    let Some(u) = tokens.next() else {
+                        return Err(anyhow::anyhow!("texture coordinate needs a value"));
+                    };
+                    out.vtx2uv[c.num_uv * 2] = parse(u)?;
+                    if let Some(v) = tokens.next() {
+                        out.vtx2uv[c.num_uv * 2 + 1] = parse(v)?;
+                    }
+                    c.num_uv += 1;
+                }
+                "vn" if !options.skip_nrm => {
+                    if tokens.clone().count() < 3 {
+                        return Err(anyhow::anyhow!("normal needs three components"));
+                    }
+                    for (i_dim, arg) in tokens.take(3).enumerate() {
+                        out.vtx2nrm[c.num_nrm * 3 + i_dim] = parse(arg)?;
+                    }
+                    c.num_nrm += 1;
+                }
+                "f" => {
+                    if tokens.clone().count() < 3 {
+                        return Err(anyhow::anyhow!("face needs at least three vertices"));
+                    }
+                    let num_xyz = offset.num_xyz + c.num_xyz;
+                    let num_uv = offset.num_uv + c.num_uv;
+                    let num_nrm = offset.num_nrm + c.num_nrm;
+                    for arg in tokens {
+                        let (i_xyz, i_uv, i_nrm) = parse_vertex(arg)?;
+                        out.idx2vtx_xyz[c.num_idx] = resolve_index(i_xyz, num_xyz)?.as_();
+                        if !options.skip_uv {
+                            out.idx2vtx_uv[c.num_idx] = match i_uv {
+                                Some(i) => resolve_index(i, num_uv)?.as_(),
+                                None => Index::max_value(),
+                            };
+                        }
+                        if !options.skip_nrm {
+                            out.idx2vtx_nrm[c.num_idx] = match i_nrm {
+                                Some(i) => resolve_index(i, num_nrm)?.as_(),
+                                None => Index::max_value(),
+                            };
+                        }
+                        c.num_idx += 1;
+                    }
+                    let i_elem = c.num_elem;
+                    out.elem2idx[i_elem] = (offset.num_idx + c.num_idx).as_();
+                    out.elem2group[i_elem] = state.group.next_elem().as_();
+                    out.elem2object[i_elem] = state.object.next_elem().as_();
+                    out.elem2smooth[i_elem] = state.smooth.next_elem().as_();
+                    out.elem2mtl[i_elem] = state.mtl.next_elem().as_();
+                    c.num_elem += 1;
+                }
+                "l" => {
+                    if tokens.clone().count() < 2 {
+                        return Err(anyhow::anyhow!("polyline needs at least two vertices"));
+                    }
+                    let num_xyz = offset.num_xyz + c.num_xyz;
+                    for arg in tokens {
+                        let (i_xyz, _, _) = parse_vertex(arg)?;
+                        out.idx2vtx_polyline[c.num_idx_polyline] =
+                            resolve_index(i_xyz, num_xyz)?.as_();
+                        c.num_idx_polyline += 1;
+                    }
+                    out.polyline2idx[c.num_polyline] =
+                        (offset.num_idx_polyline + c.num_idx_polyline).as_();
+                    c.num_polyline += 1;
+                }
+                "g" | "o" | "usemtl" => {
+                    let args: Vec<&str> = tokens.collect();
+                    let switch = match keyword {
+                        "g" => &mut state.group,
+                        "o" => &mut state.object,
+                        _ => &mut state.mtl,
+                    };
+                    switch.current = Some(index_of_name(&mut switch.name2idx, &args));
+                }
+                "mtllib" => state.mtl_file_name = Some(tokens.collect::<Vec<_>>().join(" ")),
+                "s" => {
+                    state.smooth.current = Some(match tokens.next() {
+                        None | Some("off") => 0,
+                        Some(v) => v.parse::<usize>()?,
+                    });
+                }
+                _ => {} // unsupported statement
+            }
+            Ok(())
+        })();
+        res.with_context(|| format!("line {}: {}", offset.num_line + i_line + 1, line))?;
+    }
+    Ok(state)
 }
 
 #[test]
 fn test_parse_vertex() {
-    assert_eq!(parse_vertex("1/2/3"), (0, 1, 2));
-    assert_eq!(parse_vertex("1//3"), (0, -1, 2));
-    assert_eq!(parse_vertex("1/2"), (0, 1, -1));
-    assert_eq!(parse_vertex("1"), (0, -1, -1));
+    assert_eq!(parse_vertex("1/2/3").unwrap(), (1, Some(2), Some(3)));
+    assert_eq!(parse_vertex("1//3").unwrap(), (1, None, Some(3)));
+    assert_eq!(parse_vertex("1/2").unwrap(), (1, Some(2), None));
+    assert_eq!(parse_vertex("1").unwrap(), (1, None, None));
+    assert_eq!(parse_vertex("-1/-2").unwrap(), (-1, Some(-2), None));
+    assert!(parse_vertex("a/2").is_err());
+    assert_eq!(resolve_index(-1, 4).unwrap(), 3);
+    assert!(resolve_index(0, 4).is_err());
+    assert!(resolve_index(5, 4).is_err());
+}
+
+#[test]
+fn test_load_from_reader() -> anyhow::Result<()> {
+    let txt = "# comment
+mtllib a.mtl
+o box
+v 0 0 0
+v 1 0 0 1 0 0
+v 1 1 0 \\
+  0 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+g side
+s 1
+usemtl red
+f 1/1 2/2 -2/-1
+s off
+f -4 -2 -1
+o line
+l 1 2 3
+";
+    let mut obj = WavefrontObj::<usize, f32>::new();
+    obj.load_from_reader(std::io::Cursor::new(txt))?;
+    assert_eq!(obj.mtl_file_name, "a.mtl");
+    assert_eq!(obj.vtx2xyz.len(), 12);
+    assert_eq!(
+        obj.vtx2rgb,
+        vec![1., 1., 1., 1., 0., 0., 0., 1., 0., 1., 1., 1.]
+    );
+    assert_eq!(obj.elem2idx, vec![0, 3, 6]);
+    assert_eq!(obj.idx2vtx_xyz, vec![0, 1, 2, 0, 2, 3]);
+    assert_eq!(obj.idx2vtx_uv[0..3], [0, 1, 2]);
+    assert_eq!(obj.idx2vtx_uv[3], usize::MAX);
+    assert_eq!(obj.elem2smooth, vec![1, 0]);
+    assert_eq!(obj.group2name[obj.elem2group[0]], "side");
+    assert_eq!(obj.object2name[obj.elem2object[1]], "box");
+    assert_eq!(obj.mtl2name[obj.elem2mtl[1]], "red");
+    assert_eq!(obj.polyline2idx, vec![0, 3]);
+    assert_eq!(obj.idx2vtx_polyline, vec![0, 1, 2]);
+    //
+    let err = obj
+        .load_from_reader(std::io::Cursor::new("v 0 0 0\nv 1 0 0\nf 1 2 3\n"))
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("line 3"));
+    let err = obj
+        .load_from_reader(std::io::Cursor::new("v 0 0\n"))
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("line 1"));
+    Ok(())
+}
+#[test]
+fn test_load_with_materials() -> anyhow::Result<()> {
+    std::fs::create_dir_all("../target")?;
+    std::fs::write(
+        "../target/io_obj_material.mtl",
+        "newmtl red\nKd 1 0 0\nmap_Kd red.png\n\nnewmtl blue\nKd 0 0 1\n",
+    )?;
+    std::fs::write(
+        "../target/io_obj_material.obj",
+        "mtllib io_obj_material.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\n\
+         f 1 2 3\nusemtl blue\nf 2 4 3\nusemtl red\nf 1 2 4\n",
+    )?;
+    let mut obj = WavefrontObj::<usize, f32>::new();
+    obj.load_with_materials("../target/io_obj_material.obj")?;
+    assert_eq!(obj.elem2mtl, vec![0, 1, 2]);
+    assert_eq!(obj.mtl2material.len(), obj.mtl2name.len());
+    assert_eq!(obj.mtl2material[0].name, "_default");
+    assert_eq!(obj.mtl2material[obj.elem2mtl[1]].kd, [0., 0., 1.]);
+    let mtl = &obj.mtl2material[obj.elem2mtl[2]];
+    assert_eq!(mtl.kd, [1., 0., 0.]);
+    assert_eq!(mtl.map_kd.as_ref().unwrap().path, "red.png");
+    Ok(())
+}
+
+#[test]
+fn test_save_load() -> anyhow::Result<()> {
+    let txt = "mtllib a.mtl
+v 0 0 0 1 0 0
+v 1 0 0 0 1 0
+v 1 1 0 0 0 1
+v 0 1 0 1 1 1
+vt 0 0
+vt 1 0
+vt 1 1
+vn 0 0 1
+o box
+g side top
+s 2
+usemtl red
+f 1/1/1 2/2/1 3/3/1
+usemtl blue
+f 1//1 3//1 4//1
+g empty
+usemtl green
+usemtl red
+g bottom
+s off
+f 4/3 3/2 2/1 1/1
+f 1 2 4
+l 1 2 3 -1
+";
+    let mut obj0 = WavefrontObj::<u32, f64>::new();
+    obj0.load_from_reader(std::io::Cursor::new(txt))?;
+    std::fs::create_dir_all("../target")?;
+    obj0.save("../target/io_obj_save_load.obj")?;
+    let mut obj1 = WavefrontObj::<u32, f64>::new();
+    obj1.load("../target/io_obj_save_load.obj")?;
+    assert_eq!(obj0.vtx2xyz, obj1.vtx2xyz);
+    assert_eq!(obj0.vtx2rgb, obj1.vtx2rgb);
+    assert_eq!(obj0.vtx2uv, obj1.vtx2uv);
+    assert_eq!(obj0.vtx2nrm, obj1.vtx2nrm);
+    assert_eq!(obj0.elem2idx, obj1.elem2idx);
+    assert_eq!(obj0.idx2vtx_xyz, obj1.idx2vtx_xyz);
+    assert_eq!(obj0.idx2vtx_uv, obj1.idx2vtx_uv);
+    assert_eq!(obj0.idx2vtx_nrm, obj1.idx2vtx_nrm);
+    assert_eq!(obj0.elem2group, obj1.elem2group);
+    assert_eq!(obj0.group2name, obj1.group2name);
+    assert_eq!(obj0.elem2object, obj1.elem2object);
+    assert_eq!(obj0.object2name, obj1.object2name);
+    assert_eq!(obj0.elem2smooth, obj1.elem2smooth);
+    assert_eq!(obj0.elem2mtl, obj1.elem2mtl);
+    assert_eq!(obj0.mtl2name, obj1.mtl2name);
+    assert_eq!(obj0.mtl_file_name, obj1.mtl_file_name);
+    assert_eq!(obj0.polyline2idx, obj1.polyline2idx);
+    assert_eq!(obj0.idx2vtx_polyline, obj1.idx2vtx_polyline);
+    // the groups and the materials without element are kept
+    assert_eq!(obj1.group2name, ["_default", "side top", "empty", "bottom"]);
+    assert_eq!(obj1.mtl2name, ["_default", "red", "blue", "green"]);
+    Ok(())
+}
+
+#[test]
+fn test_load_from_bytes_parallel() -> anyhow::Result<()> {
+    use rand::{Rng, SeedableRng};
+    // fast path of the number parsing
+    let mut rng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    let (max_mantissa_f32, max_exp10_f32) = fast_path_limits::<f32>();
+    let (max_mantissa_f64, max_exp10_f64) = fast_path_limits::<f64>();
+    for _ in 0..10000 {
+        let s = match rng.gen_range(0..4) {
+            0 => format!("{}", rng.gen::<f32>() * 1000. - 500.),
+            1 => format!("{:.6}", rng.gen::<f64>() - 0.5),
+            2 => format!("{:e}", rng.gen::<f64>() * 1.0e-5),
+            _ => format!("{}", rng.gen::<i32>()),
+        };
+        let v32: f32 = parse_real_fast(&s, max_mantissa_f32, max_exp10_f32)?;
+        let v64: f64 = parse_real_fast(&s, max_mantissa_f64, max_exp10_f64)?;
+        assert_eq!(v32.to_bits(), s.parse::<f32>()?.to_bits(), "{}", s);
+        assert_eq!(v64.to_bits(), s.parse::<f64>()?.to_bits(), "{}", s);
+    }
+    for s in [
+        "-0",
+        "1.",
+        ".5",
+        "1e+3",
+        "nan",
+        "-inf",
+        "123456789012345678901234",
+    ] {
+        let v: f64 = parse_real_fast(s, max_mantissa_f64, max_exp10_f64)?;
+        assert_eq!(format!("{:?}", v), format!("{:?}", s.parse::<f64>()?));
+    }
+    for s in ["", "-", "1e", "1.2.3", "0x1"] {
+        assert!(parse_real_fast::<f64>(s, max_mantissa_f64, max_exp10_f64).is_err());
+    }
+    // text where the switches and the continued lines fall on the chunk boundaries
+    let mut txt = "mtllib a.mtl\n".to_string();
+    for i in 0..200 {
+        let (x, y) = (rng.gen::<f32>(), rng.gen::<f32>());
+        match i % 3 {
+            0 => txt += &format!("v {} {} {}\n", x, y, i),
+            1 => txt += &format!("v {} {} \\\n {} 0.5 0.5 1\n", x, y, i),
+            _ => txt += &format!("v {} {} {} # comment\r\n", x, y, i),
+        }
+        txt += &format!("vt {} {}\nvn 0 0 1\n", x, y);
+        if i % 17 == 0 {
+            txt += &format!("g group{}\n", i % 5);
+        }
+        if i % 23 == 0 {
+            txt += &format!("usemtl mtl{}\ns {}\n", i % 3, i % 4);
+        }
+        if i % 29 == 0 {
+            txt += &format!("o object{}\n", i % 2);
+        }
+        if i >= 3 {
+            txt += &format!("f -1/-1/-1 -2/-2 {}//{}\n", i - 1, i - 1);
+            txt += &format!("f {} {} {} {}\n", i - 2, i - 1, i, i + 1);
+        }
+        if i % 31 == 30 {
+            txt += "l 1 2 -1\n";
+        }
+    }
+    let mut obj0 = WavefrontObj::<u32, f32>::new();
+    obj0.load_from_reader(std::io::Cursor::new(&txt))?;
+    let mut obj1 = WavefrontObj::<u32, f32>::new();
+    for num_thread in [1, 2, 3, 7, 64] {
+        let options = ParallelLoadOptions {
+            num_thread,
+            ..Default::default()
+        };
+        obj1.load_from_bytes_parallel(txt.as_bytes(), &options)?;
+        assert_eq!(obj0.vtx2xyz, obj1.vtx2xyz);
+        assert_eq!(obj0.vtx2rgb, obj1.vtx2rgb);
+        assert_eq!(obj0.vtx2uv, obj1.vtx2uv);
+        assert_eq!(obj0.vtx2nrm, obj1.vtx2nrm);
+        assert_eq!(obj0.elem2idx, obj1.elem2idx);
+        assert_eq!(obj0.idx2vtx_xyz, obj1.idx2vtx_xyz);
+        assert_eq!(obj0.idx2vtx_uv, obj1.idx2vtx_uv);
+        assert_eq!(obj0.idx2vtx_nrm, obj1.idx2vtx_nrm);
+        assert_eq!(obj0.elem2group, obj1.elem2group);
+        assert_eq!(obj0.group2name, obj1.group2name);
+        assert_eq!(obj0.elem2object, obj1.elem2object);
+        assert_eq!(obj0.object2name, obj1.object2name);
+        assert_eq!(obj0.elem2smooth, obj1.elem2smooth);
+        assert_eq!(obj0.elem2mtl, obj1.elem2mtl);
+        assert_eq!(obj0.mtl2name, obj1.mtl2name);
+        assert_eq!(obj0.mtl_file_name, obj1.mtl_file_name);
+        assert_eq!(obj0.polyline2idx, obj1.polyline2idx);
+        assert_eq!(obj0.idx2vtx_polyline, obj1.idx2vtx_polyline);
+    }
+    // skip the texture coordinates and normals
+    let options = ParallelLoadOptions {
+        num_thread: 4,
+        skip_uv: true,
+        skip_nrm: true,
+    };
+    obj1.load_from_bytes_parallel(txt.as_bytes(), &options)?;
+    assert!(obj1.vtx2uv.is_empty() && obj1.idx2vtx_uv.is_empty());
+    assert!(obj1.vtx2nrm.is_empty() && obj1.idx2vtx_nrm.is_empty());
+    assert_eq!(obj0.idx2vtx_xyz, obj1.idx2vtx_xyz);
+    // the error is reported at the same line
+    let txt = txt.replace("f 100 101 102 103", "f 100 101 1000 103");
+    let err0 = obj0
+        .load_from_reader(std::io::Cursor::new(&txt))
+        .unwrap_err();
+    let err1 = obj1
+        .load_from_bytes_parallel(txt.as_bytes(), &options)
+        .unwrap_err();
+    assert_eq!(format!("{:#}", err0), format!("{:#}", err1));
+    Ok(())
 }
@@ -1,85 +1,569 @@
-//! methods for OFF files
+//! methods for OFF files (including COFF, NOFF, STOFF and binary OFF)
 
-#[allow(clippy::identity_op)]
-pub fn save_tri_mesh<P: AsRef<std::path::Path>, T>(filepath: P, tri2vtx: &[usize], vtx2xyz: &[T])
+use anyhow::Context;
+use num_traits::AsPrimitive;
+use std::io::{BufRead, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Ascii,
+    Binary,
+}
+
+/// polygon mesh in OFF file
+#[derive(Clone, Debug)]
+pub struct OffMesh<Index, Real> {
+    pub vtx2xyz: Vec<Real>,
+    /// per-vertex normals (NOFF). Empty if absent
+    pub vtx2nrm: Vec<Real>,
+    /// per-vertex colors in [0,1] (COFF). Empty if absent
+    pub vtx2rgba: Vec<f32>,
+    /// per-vertex texture coordinates (STOFF). Empty if absent
+    pub vtx2uv: Vec<Real>,
+    pub elem2idx: Vec<Index>,
+    pub idx2vtx: Vec<Index>,
+    /// per-face colors in [0,1]. Empty if no face has a color
+    pub elem2rgba: Vec<f32>,
+}
+
+impl<Index, Real> OffMesh<Index, Real>
 where
-    T: std::fmt::Display,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display + AsPrimitive<f32>,
+    usize: AsPrimitive<Index>,
 {
-    let mut file = std::fs::File::create(filepath).expect("file not found.");
-    use std::io::Write;
-    let num_tri = tri2vtx.len() / 3;
-    let num_vtx = vtx2xyz.len() / 3;
-    writeln!(file, "OFF {} {} 0", num_vtx, num_tri).expect("fail");
-    for i_vtx in 0..num_vtx {
-        writeln!(
-            file,
-            "{} {} {}",
-            vtx2xyz[i_vtx * 3 + 0],
-            vtx2xyz[i_vtx * 3 + 1],
-            vtx2xyz[i_vtx * 3 + 2]
-        )
-        .expect("fail");
-    }
-    for i_tri in 0..num_tri {
-        writeln!(
-            file,
-            "3 {} {} {}",
-            tri2vtx[i_tri * 3 + 0],
-            tri2vtx[i_tri * 3 + 1],
-            tri2vtx[i_tri * 3 + 2]
-        )
-        .expect("fail");
+    pub fn new(elem2idx: Vec<Index>, idx2vtx: Vec<Index>, vtx2xyz: Vec<Real>) -> Self {
+        OffMesh {
+            vtx2xyz,
+            vtx2nrm: vec![],
+            vtx2rgba: vec![],
+            vtx2uv: vec![],
+            elem2idx,
+            idx2vtx,
+            elem2rgba: vec![],
+        }
+    }
+
+    pub fn num_vtx(&self) -> usize {
+        self.vtx2xyz.len() / 3
+    }
+
+    pub fn num_elem(&self) -> usize {
+        self.elem2idx.len().max(1) - 1
+    }
+
+    /// the header keyword such as `OFF`, `COFF` or `STCNOFF`
+    fn keyword(&self) -> String {
+        let mut keyword = String::new();
+        if !self.vtx2uv.is_empty() {
+            keyword += "ST";
+        }
+        if !self.vtx2rgba.is_empty() {
+            keyword += "C";
+        }
+        if !self.vtx2nrm.is_empty() {
+            keyword += "N";
+        }
+        keyword + "OFF"
+    }
+
+    /// values written for a vertex in the order of `x y z [nx ny nz] [r g b a] [s t]`
+    fn vtx_values(&self, i_vtx: usize) -> Vec<f32> {
+        let mut vals: Vec<f32> = self.vtx2xyz[i_vtx * 3..i_vtx * 3 + 3]
+            .iter()
+            .map(|v| v.as_())
+            .collect();
+        if !self.vtx2nrm.is_empty() {
+            vals.extend(
+                self.vtx2nrm[i_vtx * 3..i_vtx * 3 + 3]
+                    .iter()
+                    .map(|v| v.as_()),
+            );
+        }
+        if !self.vtx2rgba.is_empty() {
+            vals.extend_from_slice(&self.vtx2rgba[i_vtx * 4..i_vtx * 4 + 4]);
+        }
+        if !self.vtx2uv.is_empty() {
+            vals.extend(
+                self.vtx2uv[i_vtx * 2..i_vtx * 2 + 2]
+                    .iter()
+                    .map(|v| v.as_()),
+            );
+        }
+        vals
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W, format: Format) -> anyhow::Result<()> {
+        let num_vtx = self.num_vtx();
+        let num_elem = self.num_elem();
+        let keyword = self.keyword();
+        match format {
+            Format::Ascii => {
+                writeln!(writer, "{}", keyword)?;
+                writeln!(writer, "{} {} 0", num_vtx, num_elem)?;
+                for i_vtx in 0..num_vtx {
+                    // write the coordinates in the type of `Real` to keep the precision
+                    let xyz = &self.vtx2xyz[i_vtx * 3..i_vtx * 3 + 3];
+                    write!(writer, "{} {} {}", xyz[0], xyz[1], xyz[2])?;
+                    if !self.vtx2nrm.is_empty() {
+                        let nrm = &self.vtx2nrm[i_vtx * 3..i_vtx * 3 + 3];
+                        write!(writer, " {} {} {}", nrm[0], nrm[1], nrm[2])?;
+                    }
+                    if !self.vtx2rgba.is_empty() {
+                        let rgba = &self.vtx2rgba[i_vtx * 4..i_vtx * 4 + 4];
+                        // `{:?}` keeps the decimal point so that colors are not read as integers in [0,255]
+                        write!(
+                            writer,
+                            " {:?} {:?} {:?} {:?}",
+                            rgba[0], rgba[1], rgba[2], rgba[3]
+                        )?;
+                    }
+                    if !self.vtx2uv.is_empty() {
+                        let uv = &self.vtx2uv[i_vtx * 2..i_vtx * 2 + 2];
+                        write!(writer, " {} {}", uv[0], uv[1])?;
+                    }
+                    writeln!(writer)?;
+                }
+                for i_elem in 0..num_elem {
+                    let idx0: usize = self.elem2idx[i_elem].as_();
+                    let idx1: usize = self.elem2idx[i_elem + 1].as_();
+                    write!(writer, "{}", idx1 - idx0)?;
+                    for &i_vtx in &self.idx2vtx[idx0..idx1] {
+                        write!(writer, " {}", i_vtx.as_())?;
+                    }
+                    if !self.elem2rgba.is_empty() {
+                        let rgba = &self.elem2rgba[i_elem * 4..i_elem * 4 + 4];
+                        // `{:?}` keeps the decimal point so that colors are not read as integers in [0,255]
+                        write!(
+                            writer,
+                            " {:?} {:?} {:?} {:?}",
+                            rgba[0], rgba[1], rgba[2], rgba[3]
+                        )?;
+                    }
+                    writeln!(writer)?;
+                }
+            }
+            Format::Binary => {
+                writeln!(writer, "{} BINARY", keyword)?;
+                for n in [num_vtx, num_elem, 0] {
+                    writer.write_all(&(n as i32).to_be_bytes())?;
+                }
+                for i_vtx in 0..num_vtx {
+                    for v in self.vtx_values(i_vtx) {
+                        writer.write_all(&v.to_be_bytes())?;
+                    }
+                }
+                for i_elem in 0..num_elem {
+                    let idx0: usize = self.elem2idx[i_elem].as_();
+                    let idx1: usize = self.elem2idx[i_elem + 1].as_();
+                    writer.write_all(&((idx1 - idx0) as i32).to_be_bytes())?;
+                    for &i_vtx in &self.idx2vtx[idx0..idx1] {
+                        writer.write_all(&(i_vtx.as_() as i32).to_be_bytes())?;
+                    }
+                    if self.elem2rgba.is_empty() {
+                        writer.write_all(&0i32.to_be_bytes())?;
+                    } else {
+                        writer.write_all(&4i32.to_be_bytes())?;
+                        for v in &self.elem2rgba[i_elem * 4..i_elem * 4 + 4] {
+                            writer.write_all(&v.to_be_bytes())?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P, format: Format) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path.as_ref())
+            .with_context(|| format!("cannot create {}", path.as_ref().display()))?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write(&mut file, format)?;
+        file.flush()?;
+        Ok(())
     }
 }
 
-/// load OFF file and output triangle mesh
-/// * `file_path` - path to the file
-pub fn load_as_tri_mesh<P: AsRef<std::path::Path>, T>(file_path: P) -> (Vec<usize>, Vec<T>)
+/// vertex attributes declared by the prefix of the header keyword (e.g., `STCNOFF`)
+struct Header {
+    has_uv: bool,
+    has_rgba: bool,
+    has_nrm: bool,
+    is_binary: bool,
+}
+
+fn parse_keyword(tokens: &[&str]) -> anyhow::Result<Header> {
+    let Some(prefix) = tokens.first().and_then(|s| s.strip_suffix("OFF")) else {
+        return Err(anyhow::anyhow!("the file does not start with \"OFF\""));
+    };
+    let (has_uv, prefix) = match prefix.strip_prefix("ST") {
+        Some(p) => (true, p),
+        None => (false, prefix),
+    };
+    let (has_rgba, prefix) = match prefix.strip_prefix('C') {
+        Some(p) => (true, p),
+        None => (false, prefix),
+    };
+    let (has_nrm, prefix) = match prefix.strip_prefix('N') {
+        Some(p) => (true, p),
+        None => (false, prefix),
+    };
+    if !prefix.is_empty() {
+        return Err(anyhow::anyhow!("unsupported OFF variant \"{}\"", tokens[0]));
+    }
+    Ok(Header {
+        has_uv,
+        has_rgba,
+        has_nrm,
+        is_binary: tokens.get(1) == Some(&"BINARY"),
+    })
+}
+
+/// colors given as integers are in [0,255], otherwise in [0,1]. Alpha defaults to one
+fn parse_rgba(tokens: &[&str]) -> anyhow::Result<[f32; 4]> {
+    let mut rgba = [1f32; 4];
+    let is_int = tokens.iter().all(|s| !s.contains(['.', 'e', 'E']));
+    for (i, s) in tokens.iter().enumerate().take(4) {
+        let v = s
+            .parse::<f32>()
+            .with_context(|| format!("cannot parse \"{}\" as a color", s))?;
+        rgba[i] = if is_int { v / 255f32 } else { v };
+    }
+    Ok(rgba)
+}
+
+fn parse<T: std::str::FromStr>(s: &str) -> anyhow::Result<T> {
+    s.parse::<T>()
+        .ok()
+        .with_context(|| format!("cannot parse \"{}\"", s))
+}
+
+/// read OFF file from any buffered reader
+pub fn read<R: BufRead, Index, Real>(mut reader: R) -> anyhow::Result<OffMesh<Index, Real>>
 where
-    T: std::str::FromStr,
-    <T as std::str::FromStr>::Err: std::fmt::Debug,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::str::FromStr + std::fmt::Display + AsPrimitive<f32> + 'static,
+    usize: AsPrimitive<Index>,
+    f32: AsPrimitive<Real>,
 {
-    let file = std::fs::File::open(file_path).expect("file not found.");
-    let mut reader = std::io::BufReader::new(file);
-    use std::io::BufRead;
-    let mut line = String::new();
-    let _ = reader.read_line(&mut line);
-    let strs = line.clone();
-    let strs: Vec<_> = strs.split_whitespace().collect();
-    line.clear();
-    assert_eq!(strs[0], "OFF");
-    use std::str::FromStr;
-    let num_vtx = usize::from_str(strs[1]).unwrap();
-    let num_elem = usize::from_str(strs[2]).unwrap();
-    // dbg!(num_vtx, num_elem);
-    let mut vtx2xyz = Vec::<T>::with_capacity(num_vtx * 3);
+    let mut i_line = 0;
+    // next line with content, with comments removed
+    let mut next_line = |reader: &mut R| -> anyhow::Result<(usize, String)> {
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(anyhow::anyhow!("unexpected end of file"));
+            }
+            i_line += 1;
+            let content = line.split('#').next().unwrap().trim();
+            if !content.is_empty() {
+                return Ok((i_line, content.to_string()));
+            }
+        }
+    };
+    let (i_line_header, line) = next_line(&mut reader)?;
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let header = parse_keyword(&tokens).with_context(|| format!("line {}", i_line_header))?;
+    if header.is_binary {
+        return read_binary(reader, &header);
+    }
+    // the counts are either on the header line or on the next line
+    let counts: Vec<String> = if tokens.len() > 1 {
+        tokens[1..].iter().map(|s| s.to_string()).collect()
+    } else {
+        let (_, line) = next_line(&mut reader)?;
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    };
+    if counts.len() < 2 {
+        return Err(anyhow::anyhow!("missing the number of vertices and faces"));
+    }
+    let num_vtx: usize = parse(&counts[0])?;
+    let num_elem: usize = parse(&counts[1])?;
+    let mut mesh = OffMesh::<Index, Real>::new(vec![Index::zero()], vec![], vec![]);
     for _i_vtx in 0..num_vtx {
-        let _ = reader.read_line(&mut line);
-        let strs = line.clone();
-        let strs: Vec<_> = strs.split_whitespace().collect();
-        line.clear();
-        assert_eq!(strs.len(), 3);
-        let x = T::from_str(strs[0]).unwrap();
-        let y = T::from_str(strs[1]).unwrap();
-        let z = T::from_str(strs[2]).unwrap();
-        vtx2xyz.push(x);
-        vtx2xyz.push(y);
-        vtx2xyz.push(z);
-    }
-    let mut elem2vtx = Vec::<usize>::with_capacity(num_elem * 3);
-    for _i_elem in 0..num_elem {
-        let _ = reader.read_line(&mut line);
-        let strs = line.clone();
-        let strs: Vec<_> = strs.split_whitespace().collect();
-        line.clear();
-        assert_eq!(strs.len(), 4);
-        assert_eq!(strs[0], "3");
-        let i0 = usize::from_str(strs[1]).unwrap();
-        let i1 = usize::from_str(strs[2]).unwrap();
-        let i2 = usize::from_str(strs[3]).unwrap();
-        elem2vtx.push(i0);
-        elem2vtx.push(i1);
-        elem2vtx.push(i2);
-    }
-    (elem2vtx, vtx2xyz)
+        let (i_line, line) = next_line(&mut reader)?;
+        let res: anyhow::Result<()> = (|| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let num_nrm = if header.has_nrm { 3 } else { 0 };
+            let num_uv = if header.has_uv { 2 } else { 0 };
+            if tokens.len() < 3 + num_nrm + num_uv {
+                return Err(anyhow::anyhow!("too few values for a vertex"));
+            }
+            for s in &tokens[0..3] {
+                mesh.vtx2xyz.push(parse(s)?);
+            }
+            for s in &tokens[3..3 + num_nrm] {
+                mesh.vtx2nrm.push(parse(s)?);
+            }
+            if header.has_rgba {
+                let rgba = parse_rgba(&tokens[3 + num_nrm..tokens.len() - num_uv])?;
+                mesh.vtx2rgba.extend_from_slice(&rgba);
+            }
+            for s in &tokens[tokens.len() - num_uv..] {
+                mesh.vtx2uv.push(parse(s)?);
+            }
+            Ok(())
+        })();
+        res.with_context(|| format!("line {}: {}", i_line, line))?;
+    }
+    for i_elem in 0..num_elem {
+        let (i_line, line) = next_line(&mut reader)?;
+        let res: anyhow::Result<()> = (|| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let num_node: usize = parse(tokens[0])?;
+            let idx_color = num_node
+                .checked_add(1)
+                .filter(|&idx| idx <= tokens.len())
+                .context("too few vertex indices for a face")?;
+            for s in &tokens[1..idx_color] {
+                let i_vtx: usize = parse(s)?;
+                if i_vtx >= num_vtx {
+                    return Err(anyhow::anyhow!("vertex index {} is out of range", i_vtx));
+                }
+                mesh.idx2vtx.push(i_vtx.as_());
+            }
+            mesh.elem2idx.push(mesh.idx2vtx.len().as_());
+            let color = &tokens[idx_color..];
+            if color.len() >= 3 {
+                // faces before the first colored one are white
+                mesh.elem2rgba.resize(i_elem * 4, 1f32);
+                mesh.elem2rgba.extend_from_slice(&parse_rgba(color)?);
+            } else if !mesh.elem2rgba.is_empty() {
+                mesh.elem2rgba.extend([1f32; 4]);
+            }
+            Ok(())
+        })();
+        res.with_context(|| format!("line {}: {}", i_line, line))?;
+    }
+    Ok(mesh)
+}
+
+fn read_binary<R: BufRead, Index, Real>(
+    mut reader: R,
+    header: &Header,
+) -> anyhow::Result<OffMesh<Index, Real>>
+where
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display + AsPrimitive<f32> + 'static,
+    usize: AsPrimitive<Index>,
+    f32: AsPrimitive<Real>,
+{
+    let mut buf = [0u8; 4];
+    let mut read_i32 = |reader: &mut R| -> anyhow::Result<i32> {
+        reader
+            .read_exact(&mut buf)
+            .context("unexpected end of binary OFF")?;
+        Ok(i32::from_be_bytes(buf))
+    };
+    let num_vtx = read_i32(&mut reader)?;
+    let num_elem = read_i32(&mut reader)?;
+    let _num_edge = read_i32(&mut reader)?;
+    if num_vtx < 0 || num_elem < 0 {
+        return Err(anyhow::anyhow!("negative number of vertices or faces"));
+    }
+    let (num_vtx, num_elem) = (num_vtx as usize, num_elem as usize);
+    let read_f32 = |reader: &mut R| -> anyhow::Result<f32> {
+        let mut buf = [0u8; 4];
+        reader
+            .read_exact(&mut buf)
+            .context("unexpected end of binary OFF")?;
+        Ok(f32::from_be_bytes(buf))
+    };
+    let mut mesh = OffMesh::<Index, Real>::new(vec![Index::zero()], vec![], vec![]);
+    for _i_vtx in 0..num_vtx {
+        for _ in 0..3 {
+            mesh.vtx2xyz.push(read_f32(&mut reader)?.as_());
+        }
+        if header.has_nrm {
+            for _ in 0..3 {
+                mesh.vtx2nrm.push(read_f32(&mut reader)?.as_());
+            }
+        }
+        if header.has_rgba {
+            for _ in 0..4 {
+                mesh.vtx2rgba.push(read_f32(&mut reader)?);
+            }
+        }
+        if header.has_uv {
+            for _ in 0..2 {
+                mesh.vtx2uv.push(read_f32(&mut reader)?.as_());
+            }
+        }
+    }
+    for i_elem in 0..num_elem {
+        let num_node = read_i32(&mut reader)?;
+        for _ in 0..num_node {
+            let i_vtx = read_i32(&mut reader)?;
+            if i_vtx < 0 || i_vtx as usize >= num_vtx {
+                return Err(anyhow::anyhow!(
+                    "face {}: vertex index {} is out of range",
+                    i_elem,
+                    i_vtx
+                ));
+            }
+            mesh.idx2vtx.push((i_vtx as usize).as_());
+        }
+        mesh.elem2idx.push(mesh.idx2vtx.len().as_());
+        let num_color = read_i32(&mut reader)?;
+        if num_color >= 3 {
+            let mut rgba = [1f32; 4];
+            for v in rgba.iter_mut().take(num_color as usize) {
+                *v = read_f32(&mut reader)?;
+            }
+            // the values beyond RGBA are skipped
+            for _ in 4..num_color {
+                read_f32(&mut reader)?;
+            }
+            mesh.elem2rgba.resize(i_elem * 4, 1f32);
+            mesh.elem2rgba.extend_from_slice(&rgba);
+        } else {
+            // colormap index is not supported
+            for _ in 0..num_color {
+                read_f32(&mut reader)?;
+            }
+            if !mesh.elem2rgba.is_empty() {
+                mesh.elem2rgba.extend([1f32; 4]);
+            }
+        }
+    }
+    Ok(mesh)
+}
+
+/// load OFF file (ASCII or binary) as polygon mesh
+pub fn load<P, Index, Real>(file_path: P) -> anyhow::Result<OffMesh<Index, Real>>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::str::FromStr + std::fmt::Display + AsPrimitive<f32> + 'static,
+    usize: AsPrimitive<Index>,
+    f32: AsPrimitive<Real>,
+{
+    let file = std::fs::File::open(file_path.as_ref())
+        .with_context(|| format!("file not found: {}", file_path.as_ref().display()))?;
+    read(std::io::BufReader::new(file))
+}
+
+/// load OFF file and output triangle mesh. Polygons are split into triangle fans
+/// * `file_path` - path to the file
+pub fn load_as_tri_mesh<P, Index, Real>(file_path: P) -> anyhow::Result<(Vec<Index>, Vec<Real>)>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::str::FromStr + std::fmt::Display + AsPrimitive<f32> + 'static,
+    usize: AsPrimitive<Index>,
+    f32: AsPrimitive<Real>,
+{
+    let mesh = load::<P, Index, Real>(file_path)?;
+    let mut tri2vtx = Vec::<Index>::with_capacity(mesh.idx2vtx.len());
+    for i_elem in 0..mesh.num_elem() {
+        let idx0: usize = mesh.elem2idx[i_elem].as_();
+        let idx1: usize = mesh.elem2idx[i_elem + 1].as_();
+        for idx in idx0 + 1..idx1.max(idx0 + 2) - 1 {
+            tri2vtx.push(mesh.idx2vtx[idx0]);
+            tri2vtx.push(mesh.idx2vtx[idx]);
+            tri2vtx.push(mesh.idx2vtx[idx + 1]);
+        }
+    }
+    Ok((tri2vtx, mesh.vtx2xyz))
+}
+
+pub fn save_tri_mesh<P, Index, Real>(
+    filepath: P,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display + AsPrimitive<f32>,
+    usize: AsPrimitive<Index>,
+{
+    let elem2idx: Vec<Index> = (0..tri2vtx.len() / 3 + 1).map(|i| (i * 3).as_()).collect();
+    save_polygon_mesh(filepath, &elem2idx, tri2vtx, vtx2xyz)
+}
+
+pub fn save_polygon_mesh<P, Index, Real>(
+    filepath: P,
+    elem2idx: &[Index],
+    idx2vtx: &[Index],
+    vtx2xyz: &[Real],
+) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display + AsPrimitive<f32>,
+    usize: AsPrimitive<Index>,
+{
+    let mesh = OffMesh::new(elem2idx.to_vec(), idx2vtx.to_vec(), vtx2xyz.to_vec());
+    mesh.save(filepath, Format::Ascii)
+}
+
+#[test]
+fn test_read_write() -> anyhow::Result<()> {
+    let txt = "# ModelNet style
+COFF
+4 2 0
+
+0 0 0 255 0 0 255
+1 0 0 0 255 0 255
+1 1 0 0 0 255 255 # comment
+0 1 0 255 255 255 255
+4 0 1 2 3 0.5 0.5 0.5
+3 0 2 3
+";
+    let mesh = read::<_, u32, f64>(std::io::Cursor::new(txt))?;
+    assert_eq!(mesh.num_vtx(), 4);
+    assert_eq!(mesh.elem2idx, vec![0, 4, 7]);
+    assert_eq!(mesh.idx2vtx, vec![0, 1, 2, 3, 0, 2, 3]);
+    assert_eq!(mesh.vtx2rgba[0..4], [1., 0., 0., 1.]);
+    assert_eq!(mesh.elem2rgba, vec![0.5, 0.5, 0.5, 1., 1., 1., 1., 1.]);
+    for format in [Format::Ascii, Format::Binary] {
+        let mut buf = Vec::<u8>::new();
+        mesh.write(&mut buf, format)?;
+        let mesh1 = read::<_, u32, f64>(std::io::Cursor::new(buf))?;
+        assert_eq!(mesh.vtx2xyz, mesh1.vtx2xyz);
+        assert_eq!(mesh.vtx2rgba, mesh1.vtx2rgba);
+        assert_eq!(mesh.elem2idx, mesh1.elem2idx);
+        assert_eq!(mesh.idx2vtx, mesh1.idx2vtx);
+        assert_eq!(mesh.elem2rgba, mesh1.elem2rgba);
+    }
+    //
+    let txt = "NOFF 1 1 0\n0 0 0 0 0 1\n3 0 1 2\n";
+    let err = read::<_, usize, f32>(std::io::Cursor::new(txt)).unwrap_err();
+    assert!(format!("{:#}", err).contains("line 3"));
+    // broken counts are errors
+    for txt in [
+        "OFF 99999999999999 1 0\n",
+        "OFF 3 1 0\n0 0 0\n1 0 0\n0 1 0\n18446744073709551615 0 1 2\n",
+    ] {
+        assert!(read::<_, usize, f32>(std::io::Cursor::new(txt)).is_err());
+    }
+    // binary face with five color values followed by another face
+    let mut buf = b"OFF BINARY\n".to_vec();
+    let ints = |buf: &mut Vec<u8>, vals: &[i32]| {
+        buf.extend(vals.iter().flat_map(|v| v.to_be_bytes()));
+    };
+    ints(&mut buf, &[3, 2, 0]);
+    buf.extend(
+        [0f32, 0., 0., 1., 0., 0., 0., 1., 0.]
+            .iter()
+            .flat_map(|v| v.to_be_bytes()),
+    );
+    ints(&mut buf, &[3, 0, 1, 2, 5]);
+    buf.extend(
+        [0.5f32, 0.5, 0.5, 1., 0.]
+            .iter()
+            .flat_map(|v| v.to_be_bytes()),
+    );
+    ints(&mut buf, &[3, 0, 2, 1, 0]);
+    let mesh = read::<_, usize, f32>(std::io::Cursor::new(buf))?;
+    assert_eq!(mesh.idx2vtx, vec![0, 1, 2, 0, 2, 1]);
+    assert_eq!(mesh.elem2rgba, vec![0.5, 0.5, 0.5, 1., 1., 1., 1., 1.]);
+    //
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::sphere_yup::<usize, f32>(1.0, 8, 8);
+    save_tri_mesh("../target/io_off_sphere.off", &tri2vtx, &vtx2xyz)?;
+    let (tri2vtx1, vtx2xyz1) = load_as_tri_mesh::<_, usize, f32>("../target/io_off_sphere.off")?;
+    assert_eq!(tri2vtx, tri2vtx1);
+    assert_eq!(vtx2xyz, vtx2xyz1);
+    Ok(())
 }
@@ -0,0 +1,580 @@
+//! methods for PCL point cloud data (`.pcd`) files in ascii, binary and binary_compressed
+
+use crate::vtx2point::PointCloud;
+use anyhow::Context;
+use num_traits::AsPrimitive;
+use std::io::{BufRead, Read, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Ascii,
+    Binary,
+    /// column-major data compressed by LZF
+    BinaryCompressed,
+}
+
+/// field in `FIELDS` with its `SIZE`, `TYPE` (`I`, `U` or `F`) and `COUNT`
+#[derive(Clone, Debug)]
+struct Field {
+    name: String,
+    size: usize,
+    kind: u8,
+    count: usize,
+}
+
+impl Field {
+    /// value of the field at the head of the little-endian bytes
+    fn value(&self, bytes: &[u8]) -> anyhow::Result<f64> {
+        let b = bytes.get(..self.size).context("truncated field")?;
+        let v = match (self.kind, self.size) {
+            (b'F', 4) => f32::from_le_bytes(b[..4].try_into()?) as f64,
+            (b'F', 8) => f64::from_le_bytes(b[..8].try_into()?),
+            (b'U', 1) => b[0] as f64,
+            (b'U', 2) => u16::from_le_bytes(b[..2].try_into()?) as f64,
+            (b'U', 4) => u32::from_le_bytes(b[..4].try_into()?) as f64,
+            (b'U', 8) => u64::from_le_bytes(b[..8].try_into()?) as f64,
+            (b'I', 1) => b[0] as i8 as f64,
+            (b'I', 2) => i16::from_le_bytes(b[..2].try_into()?) as f64,
+            (b'I', 4) => i32::from_le_bytes(b[..4].try_into()?) as f64,
+            (b'I', 8) => i64::from_le_bytes(b[..8].try_into()?) as f64,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "unsupported type {} of size {} of field \"{}\"",
+                    self.kind as char,
+                    self.size,
+                    self.name
+                ))
+            }
+        };
+        Ok(v)
+    }
+
+    /// packed color `rgb` or `rgba` as the bits of the four bytes
+    fn packed_color(&self, bytes: &[u8]) -> anyhow::Result<[u8; 3]> {
+        anyhow::ensure!(self.size == 4, "size of \"{}\" is not four", self.name);
+        let bits = u32::from_le_bytes(bytes.get(..4).context("truncated field")?.try_into()?);
+        Ok([(bits >> 16) as u8, (bits >> 8) as u8, bits as u8])
+    }
+}
+
+/// decompress the LZF data of the known size
+pub fn lzf_decompress(input: &[u8], size: usize) -> anyhow::Result<Vec<u8>> {
+    // the size is not trusted beyond the largest expansion of LZF (264 bytes from 2 bytes)
+    let mut out = Vec::<u8>::with_capacity(size.min(input.len().saturating_mul(132)));
+    let mut ip = 0;
+    while ip < input.len() {
+        let ctrl = input[ip] as usize;
+        ip += 1;
+        if ctrl < 32 {
+            let len = ctrl + 1;
+            let literal = input.get(ip..ip + len).context("truncated LZF literal")?;
+            out.extend_from_slice(literal);
+            ip += len;
+            continue;
+        }
+        let mut len = ctrl >> 5;
+        if len == 7 {
+            len += *input.get(ip).context("truncated LZF")? as usize;
+            ip += 1;
+        }
+        let offset = ((ctrl & 0x1f) << 8) + *input.get(ip).context("truncated LZF")? as usize + 1;
+        ip += 1;
+        anyhow::ensure!(offset <= out.len(), "invalid LZF back reference");
+        let start = out.len() - offset;
+        for i in 0..len + 2 {
+            out.push(out[start + i]); // may overlap
+        }
+        anyhow::ensure!(out.len() <= size, "LZF data exceeds the size {}", size);
+    }
+    anyhow::ensure!(
+        out.len() == size,
+        "LZF data of size {} while {} is expected",
+        out.len(),
+        size
+    );
+    Ok(out)
+}
+
+/// compress the data by LZF
+pub fn lzf_compress(input: &[u8]) -> Vec<u8> {
+    const MAX_LITERAL: usize = 32;
+    const MAX_OFFSET: usize = 1 << 13;
+    const MAX_MATCH: usize = 7 + 255 + 2;
+    let mut out = vec![];
+    let mut literal = Vec::<u8>::with_capacity(MAX_LITERAL);
+    let flush = |out: &mut Vec<u8>, literal: &mut Vec<u8>| {
+        if !literal.is_empty() {
+            out.push((literal.len() - 1) as u8);
+            out.append(literal);
+        }
+    };
+    let mut hash2pos = vec![usize::MAX; 1 << 14];
+    let hash = |i: usize| -> usize {
+        let v = (input[i] as usize) << 16 | (input[i + 1] as usize) << 8 | input[i + 2] as usize;
+        (v.wrapping_mul(2654435761) >> 8) & ((1 << 14) - 1)
+    };
+    let mut i = 0;
+    while i < input.len() {
+        if i + 2 < input.len() {
+            let h = hash(i);
+            let j = hash2pos[h];
+            hash2pos[h] = i;
+            if j != usize::MAX && i - j <= MAX_OFFSET && input[j..j + 3] == input[i..i + 3] {
+                let mut len = 3;
+                while len < MAX_MATCH && i + len < input.len() && input[j + len] == input[i + len] {
+                    len += 1;
+                }
+                flush(&mut out, &mut literal);
+                let offset = i - j - 1;
+                let len_code = len - 2;
+                if len_code < 7 {
+                    out.push(((len_code << 5) | (offset >> 8)) as u8);
+                } else {
+                    out.push(((7 << 5) | (offset >> 8)) as u8);
+                    out.push((len_code - 7) as u8);
+                }
+                out.push((offset & 0xff) as u8);
+                i += len;
+                continue;
+            }
+        }
+        literal.push(input[i]);
+        if literal.len() == MAX_LITERAL {
+            flush(&mut out, &mut literal);
+        }
+        i += 1;
+    }
+    flush(&mut out, &mut literal);
+    out
+}
+
+/// read the PCD file. The fields `x`, `y`, `z`, `normal_x`, `normal_y`, `normal_z`, `rgb` (or `rgba`)
+/// and `intensity` are loaded and the others are ignored
+pub fn read<R: BufRead, Real>(mut reader: R) -> anyhow::Result<PointCloud<Real>>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    // header
+    let mut fields = Vec::<Field>::new();
+    let mut num_pnt = None;
+    let mut width = 0;
+    let mut height = 1;
+    let format = loop {
+        let mut line = String::new();
+        anyhow::ensure!(reader.read_line(&mut line)? > 0, "no DATA in the header");
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next().unwrap();
+        let args: Vec<&str> = tokens.collect();
+        let parse_all = |name: &str| -> anyhow::Result<Vec<usize>> {
+            args.iter()
+                .map(|s| s.parse::<usize>())
+                .collect::<Result<_, _>>()
+                .with_context(|| format!("invalid {}", name))
+        };
+        match keyword {
+            "FIELDS" => {
+                fields = args
+                    .iter()
+                    .map(|&name| Field {
+                        name: name.to_string(),
+                        size: 4,
+                        kind: b'F',
+                        count: 1,
+                    })
+                    .collect()
+            }
+            "SIZE" | "TYPE" | "COUNT" => {
+                anyhow::ensure!(args.len() == fields.len(), "{} before FIELDS", keyword);
+                for (field, arg) in fields.iter_mut().zip(args.iter()) {
+                    match keyword {
+                        "SIZE" => field.size = arg.parse().context("invalid SIZE")?,
+                        "COUNT" => field.count = arg.parse().context("invalid COUNT")?,
+                        _ => field.kind = arg.as_bytes()[0],
+                    }
+                }
+            }
+            "WIDTH" => width = parse_all(keyword)?.first().copied().unwrap_or(0),
+            "HEIGHT" => height = parse_all(keyword)?.first().copied().unwrap_or(1),
+            "POINTS" => num_pnt = parse_all(keyword)?.first().copied(),
+            "DATA" => {
+                break match args.first().copied() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary") => Format::Binary,
+                    Some("binary_compressed") => Format::BinaryCompressed,
+                    _ => return Err(anyhow::anyhow!("unknown DATA \"{}\"", line)),
+                }
+            }
+            _ => {} // VERSION, VIEWPOINT
+        }
+    };
+    let num_pnt = match num_pnt {
+        Some(num_pnt) => num_pnt,
+        None => width.checked_mul(height).context("too many points")?,
+    };
+    anyhow::ensure!(!fields.is_empty(), "no FIELDS in the header");
+    for field in &fields {
+        let sizes: &[usize] = match field.kind {
+            b'F' => &[4, 8],
+            b'U' | b'I' => &[1, 2, 4, 8],
+            _ => &[],
+        };
+        anyhow::ensure!(
+            sizes.contains(&field.size) && field.count > 0,
+            "unsupported type {} of size {} and count {} of field \"{}\"",
+            field.kind as char,
+            field.size,
+            field.count,
+            field.name
+        );
+    }
+    // offsets of the fields in a point record
+    let mut field2offset = vec![0usize];
+    for field in &fields {
+        let offset = field
+            .size
+            .checked_mul(field.count)
+            .and_then(|n| n.checked_add(*field2offset.last().unwrap()))
+            .context("too large point record")?;
+        field2offset.push(offset);
+    }
+    let num_byte_pnt = *field2offset.last().unwrap();
+    // the data is read up to this size, so the buffers do not grow beyond the input
+    let num_byte = num_byte_pnt.checked_mul(num_pnt);
+    // bytes of each field in the column-major layout (binary formats)
+    let mut field2bytes: Vec<Vec<u8>> = vec![];
+    let mut ascii_rows: Vec<Vec<f64>> = vec![];
+    match format {
+        Format::Ascii => {
+            for line in reader.lines() {
+                let line = line?;
+                let vals = line
+                    .split_whitespace()
+                    .map(|s| match s {
+                        "nan" | "NaN" => Ok(f64::NAN),
+                        _ => s.parse::<f64>(),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .with_context(|| format!("invalid point \"{}\"", line))?;
+                if vals.is_empty() {
+                    continue;
+                }
+                ascii_rows.push(vals);
+                if ascii_rows.len() == num_pnt {
+                    break;
+                }
+            }
+            anyhow::ensure!(ascii_rows.len() == num_pnt, "too few points");
+        }
+        Format::Binary => {
+            let num_byte = num_byte.context("too many points")?;
+            let mut buf = vec![];
+            reader.take(num_byte as u64).read_to_end(&mut buf)?;
+            anyhow::ensure!(buf.len() == num_byte, "too few points");
+            // transpose to the column-major layout of binary_compressed
+            for i_field in 0..fields.len() {
+                let (o0, o1) = (field2offset[i_field], field2offset[i_field + 1]);
+                let bytes = buf
+                    .chunks(num_byte_pnt)
+                    .flat_map(|pnt| pnt[o0..o1].iter().copied())
+                    .collect();
+                field2bytes.push(bytes);
+            }
+        }
+        Format::BinaryCompressed => {
+            let mut buf = [0u8; 8];
+            reader.read_exact(&mut buf)?;
+            let size_compressed = u32::from_le_bytes(buf[..4].try_into()?) as usize;
+            let size = u32::from_le_bytes(buf[4..].try_into()?) as usize;
+            anyhow::ensure!(num_byte == Some(size), "size mismatch of data");
+            let mut compressed = vec![];
+            reader
+                .take(size_compressed as u64)
+                .read_to_end(&mut compressed)?;
+            anyhow::ensure!(
+                compressed.len() == size_compressed,
+                "truncated compressed data"
+            );
+            let data = lzf_decompress(&compressed, size)?;
+            for i_field in 0..fields.len() {
+                let (o0, o1) = (field2offset[i_field], field2offset[i_field + 1]);
+                field2bytes.push(data[o0 * num_pnt..o1 * num_pnt].to_vec());
+            }
+        }
+    }
+    // value of the first element of the field `i_field` of the point `i_pnt`
+    let ascii_col: Vec<usize> = fields
+        .iter()
+        .scan(0, |col, field| {
+            let c = *col;
+            *col += field.count;
+            Some(c)
+        })
+        .collect();
+    let value = |i_field: usize, i_pnt: usize| -> anyhow::Result<f64> {
+        let field = &fields[i_field];
+        match format {
+            Format::Ascii => ascii_rows[i_pnt]
+                .get(ascii_col[i_field])
+                .copied()
+                .context("too few values in a point"),
+            _ => {
+                let n = field.size * field.count;
+                field.value(&field2bytes[i_field][i_pnt * n..])
+            }
+        }
+    };
+    let color = |i_field: usize, i_pnt: usize| -> anyhow::Result<[u8; 3]> {
+        let field = &fields[i_field];
+        match format {
+            Format::Ascii => {
+                let v = value(i_field, i_pnt)?;
+                // the bits of float or the unsigned integer
+                let bits = match field.kind {
+                    b'F' => (v as f32).to_bits(),
+                    _ => v as u32,
+                };
+                Ok([(bits >> 16) as u8, (bits >> 8) as u8, bits as u8])
+            }
+            _ => {
+                let n = field.size * field.count;
+                field.packed_color(&field2bytes[i_field][i_pnt * n..])
+            }
+        }
+    };
+    let find = |name: &str| fields.iter().position(|f| f.name == name);
+    let mut cloud = PointCloud::<Real>::new(vec![]);
+    let field_xyz = ["x", "y", "z"].map(find);
+    let field_nrm = ["normal_x", "normal_y", "normal_z"].map(find);
+    let field_rgb = find("rgb").or_else(|| find("rgba"));
+    let field_intensity = find("intensity");
+    anyhow::ensure!(field_xyz.iter().all(|f| f.is_some()), "no x, y or z field");
+    let has_nrm = field_nrm.iter().all(|f| f.is_some());
+    for i_pnt in 0..num_pnt {
+        for i_field in field_xyz.iter().flatten() {
+            cloud.vtx2xyz.push(value(*i_field, i_pnt)?.as_());
+        }
+        if has_nrm {
+            for i_field in field_nrm.iter().flatten() {
+                cloud.vtx2nrm.push(value(*i_field, i_pnt)?.as_());
+            }
+        }
+        if let Some(i_field) = field_rgb {
+            cloud.vtx2rgb.extend_from_slice(&color(i_field, i_pnt)?);
+        }
+        if let Some(i_field) = field_intensity {
+            cloud.vtx2intensity.push(value(i_field, i_pnt)?.as_());
+        }
+    }
+    Ok(cloud)
+}
+
+pub fn load<P, Real>(path: P) -> anyhow::Result<PointCloud<Real>>
+where
+    P: AsRef<std::path::Path>,
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    read(std::io::BufReader::new(file))
+}
+
+/// write the PCD (v0.7) file. The coordinates, normals and intensity are written as `F` of
+/// the size of `Real`, and the colors as `rgb` of `U 4`
+pub fn write<W: Write, Real>(
+    writer: &mut W,
+    cloud: &PointCloud<Real>,
+    format: Format,
+) -> anyhow::Result<()>
+where
+    Real: num_traits::Float + std::fmt::Display + AsPrimitive<f64>,
+{
+    let num_pnt = cloud.num_vtx();
+    let size_real = std::mem::size_of::<Real>();
+    // (name, size, type) and the values of the points
+    let mut fields: Vec<(&str, usize, char)> = vec![];
+    let mut columns: Vec<Vec<f64>> = vec![];
+    let component = |vtx2val: &[Real], i_dim: usize| -> Vec<f64> {
+        vtx2val
+            .iter()
+            .skip(i_dim)
+            .step_by(3)
+            .map(|v| v.as_())
+            .collect()
+    };
+    for (i_dim, name) in ["x", "y", "z"].iter().enumerate() {
+        fields.push((name, size_real, 'F'));
+        columns.push(component(&cloud.vtx2xyz, i_dim));
+    }
+    if !cloud.vtx2nrm.is_empty() {
+        for (i_dim, name) in ["normal_x", "normal_y", "normal_z"].iter().enumerate() {
+            fields.push((name, size_real, 'F'));
+            columns.push(component(&cloud.vtx2nrm, i_dim));
+        }
+    }
+    if !cloud.vtx2rgb.is_empty() {
+        fields.push(("rgb", 4, 'U'));
+        let bits = cloud
+            .vtx2rgb
+            .chunks(3)
+            .map(|rgb| ((rgb[0] as u32) << 16 | (rgb[1] as u32) << 8 | rgb[2] as u32) as f64);
+        columns.push(bits.collect());
+    }
+    if !cloud.vtx2intensity.is_empty() {
+        fields.push(("intensity", size_real, 'F'));
+        columns.push(cloud.vtx2intensity.iter().map(|v| v.as_()).collect());
+    }
+    //
+    writeln!(writer, "# .PCD v0.7 - Point Cloud Data file format")?;
+    writeln!(writer, "VERSION 0.7")?;
+    let join = |f: &dyn Fn(&(&str, usize, char)) -> String| -> String {
+        fields.iter().map(f).collect::<Vec<_>>().join(" ")
+    };
+    writeln!(writer, "FIELDS {}", join(&|f| f.0.to_string()))?;
+    writeln!(writer, "SIZE {}", join(&|f| f.1.to_string()))?;
+    writeln!(writer, "TYPE {}", join(&|f| f.2.to_string()))?;
+    writeln!(writer, "COUNT {}", join(&|_| "1".to_string()))?;
+    writeln!(writer, "WIDTH {}", num_pnt)?;
+    writeln!(writer, "HEIGHT 1")?;
+    writeln!(writer, "VIEWPOINT 0 0 0 1 0 0 0")?;
+    writeln!(writer, "POINTS {}", num_pnt)?;
+    let to_bytes = |v: f64, size: usize, kind: char| -> Vec<u8> {
+        match (kind, size) {
+            ('U', _) => (v as u32).to_le_bytes().to_vec(),
+            (_, 8) => v.to_le_bytes().to_vec(),
+            _ => (v as f32).to_le_bytes().to_vec(),
+        }
+    };
+    match format {
+        Format::Ascii => {
+            writeln!(writer, "DATA ascii")?;
+            for i_pnt in 0..num_pnt {
+                let vals: Vec<String> = fields
+                    .iter()
+                    .zip(columns.iter())
+                    .map(|(f, col)| match (f.2, f.1) {
+                        ('U', _) => format!("{}", col[i_pnt] as u32),
+                        (_, 8) => format!("{}", col[i_pnt]),
+                        _ => format!("{}", col[i_pnt] as f32),
+                    })
+                    .collect();
+                writeln!(writer, "{}", vals.join(" "))?;
+            }
+        }
+        Format::Binary => {
+            writeln!(writer, "DATA binary")?;
+            let mut buf = vec![];
+            for i_pnt in 0..num_pnt {
+                for (f, col) in fields.iter().zip(columns.iter()) {
+                    buf.extend(to_bytes(col[i_pnt], f.1, f.2));
+                }
+            }
+            writer.write_all(&buf)?;
+        }
+        Format::BinaryCompressed => {
+            writeln!(writer, "DATA binary_compressed")?;
+            let mut buf = vec![];
+            for (f, col) in fields.iter().zip(columns.iter()) {
+                for &v in col {
+                    buf.extend(to_bytes(v, f.1, f.2));
+                }
+            }
+            let compressed = lzf_compress(&buf);
+            writer.write_all(&(compressed.len() as u32).to_le_bytes())?;
+            writer.write_all(&(buf.len() as u32).to_le_bytes())?;
+            writer.write_all(&compressed)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn save<P, Real>(path: P, cloud: &PointCloud<Real>, format: Format) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    Real: num_traits::Float + std::fmt::Display + AsPrimitive<f64>,
+{
+    let file = std::fs::File::create(path.as_ref())
+        .with_context(|| format!("cannot create {}", path.as_ref().display()))?;
+    let mut file = std::io::BufWriter::new(file);
+    write(&mut file, cloud, format)?;
+    file.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_read_write() -> anyhow::Result<()> {
+    // PCL style header with a padding field and the color packed in float
+    let rgb = f32::from_bits(0x00ff8001);
+    let txt = format!(
+        "# .PCD v0.7 - Point Cloud Data file format
+VERSION 0.7
+FIELDS x y z _ rgb
+SIZE 4 4 4 1 4
+TYPE F F F U F
+COUNT 1 1 1 3 1
+WIDTH 2
+HEIGHT 1
+VIEWPOINT 0 0 0 1 0 0 0
+POINTS 2
+DATA ascii
+0.5 1 2 0 0 0 {:e}
+-1 nan 3 0 0 0 {:e}
+",
+        rgb, rgb
+    );
+    let cloud = read::<_, f32>(txt.as_bytes())?;
+    assert_eq!(cloud.vtx2xyz[..3], [0.5, 1., 2.]);
+    assert!(cloud.vtx2xyz[4].is_nan());
+    assert_eq!(cloud.vtx2rgb, vec![255, 128, 1, 255, 128, 1]);
+    // LZF
+    let data: Vec<u8> = (0..1000u32).flat_map(|i| (i % 37).to_le_bytes()).collect();
+    let compressed = lzf_compress(&data);
+    assert!(compressed.len() < data.len() / 4);
+    assert_eq!(lzf_decompress(&compressed, data.len())?, data);
+    assert!(lzf_decompress(&compressed, data.len() - 1).is_err());
+    // broken headers are errors instead of the panics or the huge allocations
+    for (header, data) in [
+        ("SIZE 4 4 4\nTYPE F F F\nCOUNT 1 0 1\nPOINTS 1", "ascii"),
+        ("SIZE 4 2 4\nTYPE F F F\nCOUNT 1 1 1\nPOINTS 1", "binary"),
+        (
+            "SIZE 8 8 8\nTYPE F F F\nCOUNT 1 1 1\nPOINTS 18446744073709551615",
+            "binary",
+        ),
+        (
+            "SIZE 8 8 8\nTYPE F F F\nCOUNT 1 1 1\nPOINTS 4294967295",
+            "binary",
+        ),
+        (
+            "SIZE 8 8 8\nTYPE F F F\nCOUNT 1 1 1\nPOINTS 1",
+            "binary_compressed",
+        ),
+    ] {
+        let mut buf = format!("FIELDS x y z\n{}\nDATA {}\n", header, data).into_bytes();
+        // sizes of binary_compressed claiming 4 GiB
+        buf.extend(u32::MAX.to_le_bytes());
+        buf.extend(24u32.to_le_bytes());
+        assert!(read::<_, f32>(buf.as_slice()).is_err(), "{}", header);
+    }
+    // write and read
+    let num_pnt = 500;
+    let mut cloud = PointCloud::<f32>::new(
+        (0..num_pnt * 3)
+            .map(|i| (i as f32 * 0.37).sin() * 10.)
+            .collect(),
+    );
+    cloud.vtx2nrm = (0..num_pnt * 3).map(|i| (i % 3) as f32 * 0.5).collect();
+    cloud.vtx2rgb = (0..num_pnt * 3).map(|i| (i * 7 % 256) as u8).collect();
+    cloud.vtx2intensity = (0..num_pnt).map(|i| i as f32).collect();
+    for format in [Format::Ascii, Format::Binary, Format::BinaryCompressed] {
+        let path = format!("../target/io_pcd_{:?}.pcd", format);
+        save(&path, &cloud, format)?;
+        let cloud1 = load::<_, f32>(&path)?;
+        assert_eq!(cloud, cloud1, "{:?}", format);
+    }
+    Ok(())
+}
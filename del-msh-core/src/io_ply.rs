@@ -1,10 +1,841 @@
-use std::io::Read;
-enum Format {
+//! methods for PLY (Polygon File Format) files
+
+use anyhow::Context;
+use num_traits::AsPrimitive;
+use std::io::{BufRead, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
     Ascii,
     BinaryLittleEndian,
     BinaryBigEndian,
 }
 
+/// scalar types of the PLY properties
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarType {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+impl ScalarType {
+    /// parse both the old (e.g., `uchar`) and the sized (e.g., `uint8`) type names
+    pub fn from_name(name: &str) -> anyhow::Result<Self> {
+        let t = match name {
+            "char" | "int8" => ScalarType::Char,
+            "uchar" | "uint8" => ScalarType::UChar,
+            "short" | "int16" => ScalarType::Short,
+            "ushort" | "uint16" => ScalarType::UShort,
+            "int" | "int32" => ScalarType::Int,
+            "uint" | "uint32" => ScalarType::UInt,
+            "float" | "float32" => ScalarType::Float,
+            "double" | "float64" => ScalarType::Double,
+            _ => return Err(anyhow::anyhow!("unknown PLY scalar type \"{}\"", name)),
+        };
+        Ok(t)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScalarType::Char => "char",
+            ScalarType::UChar => "uchar",
+            ScalarType::Short => "short",
+            ScalarType::UShort => "ushort",
+            ScalarType::Int => "int",
+            ScalarType::UInt => "uint",
+            ScalarType::Float => "float",
+            ScalarType::Double => "double",
+        }
+    }
+
+    /// number of bytes in the binary format
+    pub fn size(&self) -> usize {
+        match self {
+            ScalarType::Char | ScalarType::UChar => 1,
+            ScalarType::Short | ScalarType::UShort => 2,
+            ScalarType::Int | ScalarType::UInt | ScalarType::Float => 4,
+            ScalarType::Double => 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PropertyType {
+    Scalar(ScalarType),
+    /// list property with the type of the count and the type of the items
+    List(ScalarType, ScalarType),
+}
+
+/// values of a property stored in the type declared in the header
+#[derive(Clone, Debug)]
+pub enum Values {
+    Char(Vec<i8>),
+    UChar(Vec<u8>),
+    Short(Vec<i16>),
+    UShort(Vec<u16>),
+    Int(Vec<i32>),
+    UInt(Vec<u32>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+impl Values {
+    fn new(scalar_type: ScalarType, capacity: usize) -> Self {
+        match scalar_type {
+            ScalarType::Char => Values::Char(Vec::with_capacity(capacity)),
+            ScalarType::UChar => Values::UChar(Vec::with_capacity(capacity)),
+            ScalarType::Short => Values::Short(Vec::with_capacity(capacity)),
+            ScalarType::UShort => Values::UShort(Vec::with_capacity(capacity)),
+            ScalarType::Int => Values::Int(Vec::with_capacity(capacity)),
+            ScalarType::UInt => Values::UInt(Vec::with_capacity(capacity)),
+            ScalarType::Float => Values::Float(Vec::with_capacity(capacity)),
+            ScalarType::Double => Values::Double(Vec::with_capacity(capacity)),
+        }
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        match self {
+            Values::Char(v) => v.reserve(additional),
+            Values::UChar(v) => v.reserve(additional),
+            Values::Short(v) => v.reserve(additional),
+            Values::UShort(v) => v.reserve(additional),
+            Values::Int(v) => v.reserve(additional),
+            Values::UInt(v) => v.reserve(additional),
+            Values::Float(v) => v.reserve(additional),
+            Values::Double(v) => v.reserve(additional),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Values::Char(v) => v.len(),
+            Values::UChar(v) => v.len(),
+            Values::Short(v) => v.len(),
+            Values::UShort(v) => v.len(),
+            Values::Int(v) => v.len(),
+            Values::UInt(v) => v.len(),
+            Values::Float(v) => v.len(),
+            Values::Double(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// the value is converted through `f64`, which is lossless for all the PLY scalar types
+    pub fn get<T>(&self, i: usize) -> T
+    where
+        T: Copy + 'static,
+        f64: AsPrimitive<T>,
+    {
+        let v: f64 = match self {
+            Values::Char(v) => v[i].into(),
+            Values::UChar(v) => v[i].into(),
+            Values::Short(v) => v[i].into(),
+            Values::UShort(v) => v[i].into(),
+            Values::Int(v) => v[i].into(),
+            Values::UInt(v) => v[i].into(),
+            Values::Float(v) => v[i].into(),
+            Values::Double(v) => v[i],
+        };
+        v.as_()
+    }
+
+    pub fn to_vec<T>(&self) -> Vec<T>
+    where
+        T: Copy + 'static,
+        f64: AsPrimitive<T>,
+    {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+
+    fn push_ascii(&mut self, token: &str) -> anyhow::Result<()> {
+        match self {
+            Values::Char(v) => v.push(token.parse()?),
+            Values::UChar(v) => v.push(token.parse()?),
+            Values::Short(v) => v.push(token.parse()?),
+            Values::UShort(v) => v.push(token.parse()?),
+            Values::Int(v) => v.push(token.parse()?),
+            Values::UInt(v) => v.push(token.parse()?),
+            Values::Float(v) => v.push(token.parse()?),
+            Values::Double(v) => v.push(token.parse()?),
+        }
+        Ok(())
+    }
+
+    pub fn scalar_type(&self) -> ScalarType {
+        match self {
+            Values::Char(_) => ScalarType::Char,
+            Values::UChar(_) => ScalarType::UChar,
+            Values::Short(_) => ScalarType::Short,
+            Values::UShort(_) => ScalarType::UShort,
+            Values::Int(_) => ScalarType::Int,
+            Values::UInt(_) => ScalarType::UInt,
+            Values::Float(_) => ScalarType::Float,
+            Values::Double(_) => ScalarType::Double,
+        }
+    }
+
+    fn write_ascii<W: Write>(&self, i: usize, w: &mut W) -> std::io::Result<()> {
+        match self {
+            Values::Char(v) => write!(w, "{}", v[i]),
+            Values::UChar(v) => write!(w, "{}", v[i]),
+            Values::Short(v) => write!(w, "{}", v[i]),
+            Values::UShort(v) => write!(w, "{}", v[i]),
+            Values::Int(v) => write!(w, "{}", v[i]),
+            Values::UInt(v) => write!(w, "{}", v[i]),
+            Values::Float(v) => write!(w, "{}", v[i]),
+            Values::Double(v) => write!(w, "{}", v[i]),
+        }
+    }
+
+    fn write_binary<W: Write>(&self, i: usize, w: &mut W, is_le: bool) -> std::io::Result<()> {
+        macro_rules! put {
+            ($v:expr) => {
+                if is_le {
+                    w.write_all(&$v.to_le_bytes())
+                } else {
+                    w.write_all(&$v.to_be_bytes())
+                }
+            };
+        }
+        match self {
+            Values::Char(v) => put!(v[i]),
+            Values::UChar(v) => put!(v[i]),
+            Values::Short(v) => put!(v[i]),
+            Values::UShort(v) => put!(v[i]),
+            Values::Int(v) => put!(v[i]),
+            Values::UInt(v) => put!(v[i]),
+            Values::Float(v) => put!(v[i]),
+            Values::Double(v) => put!(v[i]),
+        }
+    }
+
+    /// * `bytes` - the bytes of one value. The size should match the scalar type
+    fn push_binary(&mut self, bytes: &[u8], is_le: bool) -> anyhow::Result<()> {
+        match self {
+            Values::Char(v) => v.push(bytes[0] as i8),
+            Values::UChar(v) => v.push(bytes[0]),
+            Values::Short(v) => {
+                let b = bytes.try_into()?;
+                v.push(if is_le {
+                    i16::from_le_bytes(b)
+                } else {
+                    i16::from_be_bytes(b)
+                })
+            }
+            Values::UShort(v) => {
+                let b = bytes.try_into()?;
+                v.push(if is_le {
+                    u16::from_le_bytes(b)
+                } else {
+                    u16::from_be_bytes(b)
+                })
+            }
+            Values::Int(v) => {
+                let b = bytes.try_into()?;
+                v.push(if is_le {
+                    i32::from_le_bytes(b)
+                } else {
+                    i32::from_be_bytes(b)
+                })
+            }
+            Values::UInt(v) => {
+                let b = bytes.try_into()?;
+                v.push(if is_le {
+                    u32::from_le_bytes(b)
+                } else {
+                    u32::from_be_bytes(b)
+                })
+            }
+            Values::Float(v) => {
+                let b = bytes.try_into()?;
+                v.push(if is_le {
+                    f32::from_le_bytes(b)
+                } else {
+                    f32::from_be_bytes(b)
+                })
+            }
+            Values::Double(v) => {
+                let b = bytes.try_into()?;
+                v.push(if is_le {
+                    f64::from_le_bytes(b)
+                } else {
+                    f64::from_be_bytes(b)
+                })
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Vec<i8>> for Values {
+    fn from(v: Vec<i8>) -> Self {
+        Values::Char(v)
+    }
+}
+
+impl From<Vec<u8>> for Values {
+    fn from(v: Vec<u8>) -> Self {
+        Values::UChar(v)
+    }
+}
+
+impl From<Vec<i16>> for Values {
+    fn from(v: Vec<i16>) -> Self {
+        Values::Short(v)
+    }
+}
+
+impl From<Vec<u16>> for Values {
+    fn from(v: Vec<u16>) -> Self {
+        Values::UShort(v)
+    }
+}
+
+impl From<Vec<i32>> for Values {
+    fn from(v: Vec<i32>) -> Self {
+        Values::Int(v)
+    }
+}
+
+impl From<Vec<u32>> for Values {
+    fn from(v: Vec<u32>) -> Self {
+        Values::UInt(v)
+    }
+}
+
+impl From<Vec<f32>> for Values {
+    fn from(v: Vec<f32>) -> Self {
+        Values::Float(v)
+    }
+}
+
+impl From<Vec<f64>> for Values {
+    fn from(v: Vec<f64>) -> Self {
+        Values::Double(v)
+    }
+}
+
+pub struct Property {
+    pub name: String,
+    pub property_type: PropertyType,
+    /// offsets of the list items in `values` (i.e., `elem2idx`). Empty for scalar property
+    pub elem2idx: Vec<usize>,
+    pub values: Values,
+}
+
+impl Property {
+    pub fn new_scalar<V: Into<Values>>(name: &str, values: V) -> Self {
+        let values = values.into();
+        Property {
+            name: name.to_string(),
+            property_type: PropertyType::Scalar(values.scalar_type()),
+            elem2idx: vec![],
+            values,
+        }
+    }
+
+    /// * `count_type` - type of the number of items (typically `ScalarType::UChar`)
+    /// * `elem2idx` - offsets of the items of each element in `values`
+    pub fn new_list<V: Into<Values>>(
+        name: &str,
+        count_type: ScalarType,
+        elem2idx: Vec<usize>,
+        values: V,
+    ) -> Self {
+        let values = values.into();
+        Property {
+            name: name.to_string(),
+            property_type: PropertyType::List(count_type, values.scalar_type()),
+            elem2idx,
+            values,
+        }
+    }
+}
+
+pub struct Element {
+    pub name: String,
+    pub count: usize,
+    pub properties: Vec<Property>,
+}
+
+impl Element {
+    pub fn new(name: &str, count: usize, properties: Vec<Property>) -> Self {
+        Element {
+            name: name.to_string(),
+            count,
+            properties,
+        }
+    }
+
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    pub fn has_property(&self, name: &str) -> bool {
+        self.property(name).is_some()
+    }
+
+    /// values of a scalar property
+    pub fn scalars<T>(&self, name: &str) -> anyhow::Result<Vec<T>>
+    where
+        T: Copy + 'static,
+        f64: AsPrimitive<T>,
+    {
+        let prop = self
+            .property(name)
+            .with_context(|| format!("no property \"{}\" in element \"{}\"", name, self.name))?;
+        anyhow::ensure!(
+            matches!(prop.property_type, PropertyType::Scalar(_)),
+            "property \"{}\" is a list",
+            name
+        );
+        Ok(prop.values.to_vec())
+    }
+
+    /// interleave scalar properties (e.g., `["x", "y", "z"]` to get `vtx2xyz`)
+    pub fn interleaved<T>(&self, names: &[&str]) -> anyhow::Result<Vec<T>>
+    where
+        T: Copy + num_traits::Zero + 'static,
+        f64: AsPrimitive<T>,
+    {
+        let num_dim = names.len();
+        let mut elem2val = vec![T::zero(); self.count * num_dim];
+        for (i_dim, name) in names.iter().enumerate() {
+            let vals = self.scalars::<T>(name)?;
+            for (i_elem, val) in vals.into_iter().enumerate() {
+                elem2val[i_elem * num_dim + i_dim] = val;
+            }
+        }
+        Ok(elem2val)
+    }
+
+    /// values of a list property in the compressed format
+    /// * Returns (elem2idx, idx2val)
+    pub fn list<T>(&self, name: &str) -> anyhow::Result<(Vec<usize>, Vec<T>)>
+    where
+        T: Copy + 'static,
+        f64: AsPrimitive<T>,
+    {
+        let prop = self
+            .property(name)
+            .with_context(|| format!("no property \"{}\" in element \"{}\"", name, self.name))?;
+        anyhow::ensure!(
+            matches!(prop.property_type, PropertyType::List(_, _)),
+            "property \"{}\" is not a list",
+            name
+        );
+        Ok((prop.elem2idx.clone(), prop.values.to_vec()))
+    }
+}
+
+/// content of a PLY file
+pub struct Ply {
+    pub format: Format,
+    pub comments: Vec<String>,
+    pub obj_info: Vec<String>,
+    pub elements: Vec<Element>,
+}
+
+impl Ply {
+    pub fn element(&self, name: &str) -> Option<&Element> {
+        self.elements.iter().find(|e| e.name == name)
+    }
+
+    /// vertex coordinates and the polygon faces
+    /// * Returns (elem2idx, idx2vtx, vtx2xyz). The face is empty for point clouds
+    pub fn polygon_mesh<Index, Real>(&self) -> anyhow::Result<(Vec<usize>, Vec<Index>, Vec<Real>)>
+    where
+        Index: Copy + 'static,
+        Real: Copy + num_traits::Zero + 'static,
+        f64: AsPrimitive<Index> + AsPrimitive<Real>,
+    {
+        let vtx = self.element("vertex").context("no vertex element")?;
+        let vtx2xyz = vtx.interleaved(&["x", "y", "z"])?;
+        let Some(face) = self.element("face") else {
+            return Ok((vec![0], vec![], vtx2xyz));
+        };
+        let name = if face.has_property("vertex_indices") {
+            "vertex_indices"
+        } else {
+            "vertex_index"
+        };
+        let (elem2idx, idx2vtx) = face.list(name)?;
+        Ok((elem2idx, idx2vtx, vtx2xyz))
+    }
+}
+
+impl Ply {
+    pub fn new(format: Format, elements: Vec<Element>) -> Self {
+        Ply {
+            format,
+            comments: vec![],
+            obj_info: vec![],
+            elements,
+        }
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        let format = match self.format {
+            Format::Ascii => "ascii",
+            Format::BinaryLittleEndian => "binary_little_endian",
+            Format::BinaryBigEndian => "binary_big_endian",
+        };
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format {} 1.0", format)?;
+        for comment in &self.comments {
+            writeln!(writer, "comment {}", comment)?;
+        }
+        for obj_info in &self.obj_info {
+            writeln!(writer, "obj_info {}", obj_info)?;
+        }
+        for elem in &self.elements {
+            writeln!(writer, "element {} {}", elem.name, elem.count)?;
+            for prop in &elem.properties {
+                match prop.property_type {
+                    PropertyType::Scalar(t) => {
+                        anyhow::ensure!(
+                            prop.values.len() == elem.count,
+                            "size mismatch of property \"{}\"",
+                            prop.name
+                        );
+                        writeln!(writer, "property {} {}", t.name(), prop.name)?
+                    }
+                    PropertyType::List(t_cnt, t_item) => {
+                        anyhow::ensure!(
+                            prop.elem2idx.len() == elem.count + 1
+                                && prop.elem2idx[elem.count] == prop.values.len(),
+                            "size mismatch of list property \"{}\"",
+                            prop.name
+                        );
+                        writeln!(
+                            writer,
+                            "property list {} {} {}",
+                            t_cnt.name(),
+                            t_item.name(),
+                            prop.name
+                        )?
+                    }
+                }
+            }
+        }
+        writeln!(writer, "end_header")?;
+        let is_le = self.format == Format::BinaryLittleEndian;
+        for elem in &self.elements {
+            for i_elem in 0..elem.count {
+                for (i_prop, prop) in elem.properties.iter().enumerate() {
+                    if self.format == Format::Ascii && i_prop != 0 {
+                        write!(writer, " ")?;
+                    }
+                    let range = match prop.property_type {
+                        PropertyType::Scalar(_) => i_elem..i_elem + 1,
+                        PropertyType::List(t_cnt, _) => {
+                            let range = prop.elem2idx[i_elem]..prop.elem2idx[i_elem + 1];
+                            let mut cnt = Values::new(t_cnt, 1);
+                            cnt.push_ascii(&range.len().to_string())
+                                .with_context(|| format!("too many items in {}", prop.name))?;
+                            if self.format == Format::Ascii {
+                                cnt.write_ascii(0, writer)?;
+                                write!(writer, " ")?;
+                            } else {
+                                cnt.write_binary(0, writer, is_le)?;
+                            }
+                            range
+                        }
+                    };
+                    for (i, idx) in range.enumerate() {
+                        if self.format == Format::Ascii {
+                            if i != 0 {
+                                write!(writer, " ")?;
+                            }
+                            prop.values.write_ascii(idx, writer)?;
+                        } else {
+                            prop.values.write_binary(idx, writer, is_le)?;
+                        }
+                    }
+                }
+                if self.format == Format::Ascii {
+                    writeln!(writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path).context("file not found.")?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write(&mut file)
+    }
+}
+
+/// parse header until `end_header`. The returned elements have no values yet
+pub fn read_header<R: BufRead>(reader: &mut R) -> anyhow::Result<Ply> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    anyhow::ensure!(line.trim_end() == "ply", "not a PLY file");
+    let mut format = None;
+    let mut comments = vec![];
+    let mut obj_info = vec![];
+    let mut elements = Vec::<Element>::new();
+    for i_line in 2.. {
+        line.clear();
+        anyhow::ensure!(reader.read_line(&mut line)? != 0, "missing end_header");
+        let strs: Vec<_> = line.split_whitespace().collect();
+        if strs.is_empty() {
+            continue;
+        }
+        match strs[0] {
+            "format" => {
+                anyhow::ensure!(strs.len() == 3, "line {}: broken format", i_line);
+                format = Some(match strs[1] {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    s => return Err(anyhow::anyhow!("line {}: unknown format {}", i_line, s)),
+                });
+            }
+            "comment" => {
+                comments.push(strs[1..].join(" "));
+            }
+            "obj_info" => {
+                obj_info.push(strs[1..].join(" "));
+            }
+            "element" => {
+                anyhow::ensure!(strs.len() == 3, "line {}: broken element", i_line);
+                let count = strs[2]
+                    .parse::<usize>()
+                    .with_context(|| format!("line {}: broken element count", i_line))?;
+                elements.push(Element {
+                    name: strs[1].to_string(),
+                    count,
+                    properties: vec![],
+                });
+            }
+            "property" => {
+                let elem = elements
+                    .last_mut()
+                    .with_context(|| format!("line {}: property before element", i_line))?;
+                let (property_type, name) = if strs.len() == 5 && strs[1] == "list" {
+                    let ty_cnt = ScalarType::from_name(strs[2])?;
+                    let ty_item = ScalarType::from_name(strs[3])?;
+                    (PropertyType::List(ty_cnt, ty_item), strs[4])
+                } else if strs.len() == 3 {
+                    (
+                        PropertyType::Scalar(ScalarType::from_name(strs[1])?),
+                        strs[2],
+                    )
+                } else {
+                    return Err(anyhow::anyhow!("line {}: broken property", i_line));
+                };
+                // the count is not trusted until the body is read
+                let (elem2idx, values) = match property_type {
+                    PropertyType::Scalar(t) => (vec![], Values::new(t, 0)),
+                    PropertyType::List(_, t) => (vec![0], Values::new(t, 0)),
+                };
+                elem.properties.push(Property {
+                    name: name.to_string(),
+                    property_type,
+                    elem2idx,
+                    values,
+                });
+            }
+            "end_header" => break,
+            s => return Err(anyhow::anyhow!("line {}: unknown keyword {}", i_line, s)),
+        }
+    }
+    Ok(Ply {
+        format: format.context("missing format")?,
+        comments,
+        obj_info,
+        elements,
+    })
+}
+
+/// preallocate for the element counts in the header, bounded by the size of the body as each
+/// value takes at least one byte
+fn reserve_body(ply: &mut Ply, num_byte: usize) {
+    for elem in ply.elements.iter_mut() {
+        for prop in elem.properties.iter_mut() {
+            let num_value = match prop.property_type {
+                PropertyType::Scalar(_) => elem.count,
+                PropertyType::List(_, _) => {
+                    prop.elem2idx.reserve(elem.count.min(num_byte));
+                    elem.count.saturating_mul(3)
+                }
+            };
+            prop.values.reserve(num_value.min(num_byte));
+        }
+    }
+}
+
+fn read_body_ascii(ply: &mut Ply, buf: &str) -> anyhow::Result<()> {
+    let mut tokens = buf.split_whitespace();
+    for elem in ply.elements.iter_mut() {
+        for i_elem in 0..elem.count {
+            for prop in elem.properties.iter_mut() {
+                let mut next = || {
+                    tokens.next().with_context(|| {
+                        format!("unexpected end of data in {}[{}]", elem.name, i_elem)
+                    })
+                };
+                match prop.property_type {
+                    PropertyType::Scalar(_) => prop.values.push_ascii(next()?)?,
+                    PropertyType::List(_, _) => {
+                        let num_item = next()?.parse::<usize>()?;
+                        for _ in 0..num_item {
+                            prop.values.push_ascii(next()?)?;
+                        }
+                        prop.elem2idx.push(prop.values.len());
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_body_binary(ply: &mut Ply, buf: &[u8], is_le: bool) -> anyhow::Result<()> {
+    let mut pos = 0usize;
+    for elem in ply.elements.iter_mut() {
+        for i_elem in 0..elem.count {
+            for prop in elem.properties.iter_mut() {
+                let mut next = |size: usize| {
+                    let bytes = buf.get(pos..pos + size).with_context(|| {
+                        format!("unexpected end of data in {}[{}]", elem.name, i_elem)
+                    })?;
+                    pos += size;
+                    anyhow::Ok(bytes)
+                };
+                match prop.property_type {
+                    PropertyType::Scalar(t) => prop.values.push_binary(next(t.size())?, is_le)?,
+                    PropertyType::List(t_cnt, t_item) => {
+                        let mut cnt = Values::new(t_cnt, 1);
+                        cnt.push_binary(next(t_cnt.size())?, is_le)?;
+                        let num_item: usize = cnt.get(0);
+                        for _ in 0..num_item {
+                            prop.values.push_binary(next(t_item.size())?, is_le)?;
+                        }
+                        prop.elem2idx.push(prop.values.len());
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// read PLY data with arbitrary elements and properties
+pub fn read<R: BufRead>(reader: &mut R) -> anyhow::Result<Ply> {
+    let mut ply = read_header(reader)?;
+    match ply.format {
+        Format::Ascii => {
+            let mut buf = String::new();
+            reader.read_to_string(&mut buf)?;
+            reserve_body(&mut ply, buf.len());
+            read_body_ascii(&mut ply, &buf)?;
+        }
+        Format::BinaryLittleEndian | Format::BinaryBigEndian => {
+            let mut buf: Vec<u8> = Vec::new();
+            reader.read_to_end(&mut buf)?;
+            reserve_body(&mut ply, buf.len());
+            let is_le = ply.format == Format::BinaryLittleEndian;
+            read_body_binary(&mut ply, &buf, is_le)?;
+        }
+    }
+    Ok(ply)
+}
+
+pub fn load<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<Ply> {
+    let file = std::fs::File::open(path).context("file not found.")?;
+    let mut reader = std::io::BufReader::new(file);
+    read(&mut reader)
+}
+
+fn component<T: Copy>(vtx2val: &[T], num_dim: usize, i_dim: usize) -> Vec<T> {
+    vtx2val
+        .iter()
+        .skip(i_dim)
+        .step_by(num_dim)
+        .copied()
+        .collect()
+}
+
+/// PLY data of a triangle mesh with optional per-vertex attributes
+/// * `vtx2rgb` - vertex colors written as `red`, `green` and `blue`
+/// * `vtx2nrm` - vertex normals written as `nx`, `ny` and `nz`
+/// * `vtx2scalars` - pairs of a property name and the per-vertex values
+pub fn from_tri2vtx_vtx2xyz<Index, Real>(
+    format: Format,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+    vtx2rgb: Option<&[u8]>,
+    vtx2nrm: Option<&[Real]>,
+    vtx2scalars: &[(&str, &[Real])],
+) -> anyhow::Result<Ply>
+where
+    Index: AsPrimitive<i32>,
+    Real: Copy,
+    Vec<Real>: Into<Values>,
+{
+    let num_vtx = vtx2xyz.len() / 3;
+    let mut props = vec![
+        Property::new_scalar("x", component(vtx2xyz, 3, 0)),
+        Property::new_scalar("y", component(vtx2xyz, 3, 1)),
+        Property::new_scalar("z", component(vtx2xyz, 3, 2)),
+    ];
+    if let Some(vtx2nrm) = vtx2nrm {
+        anyhow::ensure!(vtx2nrm.len() == num_vtx * 3, "size mismatch of vtx2nrm");
+        props.push(Property::new_scalar("nx", component(vtx2nrm, 3, 0)));
+        props.push(Property::new_scalar("ny", component(vtx2nrm, 3, 1)));
+        props.push(Property::new_scalar("nz", component(vtx2nrm, 3, 2)));
+    }
+    if let Some(vtx2rgb) = vtx2rgb {
+        anyhow::ensure!(vtx2rgb.len() == num_vtx * 3, "size mismatch of vtx2rgb");
+        props.push(Property::new_scalar("red", component(vtx2rgb, 3, 0)));
+        props.push(Property::new_scalar("green", component(vtx2rgb, 3, 1)));
+        props.push(Property::new_scalar("blue", component(vtx2rgb, 3, 2)));
+    }
+    for &(name, vtx2val) in vtx2scalars {
+        anyhow::ensure!(vtx2val.len() == num_vtx, "size mismatch of {}", name);
+        props.push(Property::new_scalar(name, vtx2val.to_vec()));
+    }
+    let num_tri = tri2vtx.len() / 3;
+    let tri2idx: Vec<usize> = (0..num_tri + 1).map(|i_tri| i_tri * 3).collect();
+    let idx2vtx: Vec<i32> = tri2vtx.iter().map(|v| v.as_()).collect();
+    let face = Property::new_list("vertex_indices", ScalarType::UChar, tri2idx, idx2vtx);
+    Ok(Ply::new(
+        format,
+        vec![
+            Element::new("vertex", num_vtx, props),
+            Element::new("face", num_tri, vec![face]),
+        ],
+    ))
+}
+
+pub fn save_tri2vtx_vtx2xyz<Path, Index, Real>(
+    filepath: Path,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+    format: Format,
+) -> anyhow::Result<()>
+where
+    Path: AsRef<std::path::Path>,
+    Index: AsPrimitive<i32>,
+    Real: Copy,
+    Vec<Real>: Into<Values>,
+{
+    from_tri2vtx_vtx2xyz(format, tri2vtx, vtx2xyz, None, None, &[])?.save(filepath)
+}
+
 /* --------------------------------------*/
 // below: XyzRgb
 
@@ -25,90 +856,54 @@ pub trait XyzRgb {
     fn new(xyz: [f64; 3], rgb: [u8; 3]) -> Self;
 }
 
+/// point with a color that can be written by [`save_xyzrgb`]
+pub trait XyzRgbWrite {
+    fn xyz(&self) -> [f64; 3];
+    fn rgb(&self) -> [u8; 3];
+}
+
+/// read point cloud with the `x`, `y`, `z`, `red`, `green`, `blue` properties in any layout
 pub fn read_xyzrgb<Path: AsRef<std::path::Path>, _XyzRgb: XyzRgb>(
     path: Path,
 ) -> anyhow::Result<Vec<_XyzRgb>> {
-    // let file_path = "C:/Users/nobuy/Downloads/juice_box.ply";
-    // let file_path = "/Users/nobuyuki/project/juice_box1.ply";
-    let file = std::fs::File::open(path)?;
-    let mut reader = std::io::BufReader::new(file);
-    use std::io::BufRead;
-    let mut line = String::new();
-    {
-        // read magic number
-        let _hoge = reader.read_line(&mut line)?;
-        assert_eq!(line, "ply\n");
-        line.clear();
-    }
-    {
-        // read format
-        let _hoge = reader.read_line(&mut line)?;
-        let strs: Vec<_> = line.split_whitespace().collect();
-        assert_eq!(strs[0], "format");
-        let _format = match strs[1] {
-            "binary_little_endian" => Format::BinaryLittleEndian,
-            "binary_big_endian" => Format::BinaryBigEndian,
-            "ascii" => Format::Ascii,
-            &_ => panic!(),
-        };
-        line.clear();
-    }
-    {
-        // read comment
-        let _ = reader.read_line(&mut line)?;
-        let strs: Vec<_> = line.split_whitespace().collect();
-        assert_eq!(strs[0], "comment");
-        line.clear();
-    }
-    let num_elem = {
-        // read element number
-        let _ = reader.read_line(&mut line)?; // element vertex
-        let strs: Vec<_> = line.split_whitespace().collect();
-        assert_eq!(strs[0], "element");
-        use std::str::FromStr;
-        usize::from_str(strs[2]).unwrap()
-    };
-    {
-        // read_property
-        let _ = reader.read_line(&mut line)?; // property double x
-        let _ = reader.read_line(&mut line)?; // property double y
-        let _ = reader.read_line(&mut line)?; // property double z
-        let _ = reader.read_line(&mut line)?; // property uchar red
-        let _ = reader.read_line(&mut line)?; // property uchar green
-        let _ = reader.read_line(&mut line)?; // property uchar blue
-        line.clear();
-    }
-    {
-        // read "end header"
-        let _ = reader.read_line(&mut line)?;
-        assert_eq!(line, "end_header\n");
-    }
-    //
-    let mut buf: Vec<u8> = Vec::new();
-    reader.read_to_end(&mut buf)?;
-    let mut pnt2xyzrgb: Vec<_XyzRgb> = vec![];
-    for i_elem in 0..num_elem {
-        let i_byte: usize = i_elem * (8 * 3 + 3);
-        let x = f64::from_le_bytes(buf[i_byte..i_byte + 8].try_into()?);
-        let y = f64::from_le_bytes(buf[i_byte + 8..i_byte + 16].try_into()?);
-        let z = f64::from_le_bytes(buf[i_byte + 16..i_byte + 24].try_into()?);
-        let r = u8::from_le_bytes(buf[i_byte + 24..i_byte + 25].try_into()?);
-        let g = u8::from_le_bytes(buf[i_byte + 25..i_byte + 26].try_into()?);
-        let b = u8::from_le_bytes(buf[i_byte + 26..i_byte + 27].try_into()?);
-        pnt2xyzrgb.push(XyzRgb::new([x, y, z], [r, g, b]));
-    }
+    let ply = load(path)?;
+    let vtx = ply.element("vertex").context("no vertex element")?;
+    let vtx2xyz = vtx.interleaved::<f64>(&["x", "y", "z"])?;
+    let vtx2rgb = vtx.interleaved::<u8>(&["red", "green", "blue"])?;
+    let pnt2xyzrgb = vtx2xyz
+        .chunks(3)
+        .zip(vtx2rgb.chunks(3))
+        .map(|(xyz, rgb)| XyzRgb::new([xyz[0], xyz[1], xyz[2]], [rgb[0], rgb[1], rgb[2]]))
+        .collect();
     Ok(pnt2xyzrgb)
 }
 
-fn parse_f32<const N: usize>(buff: &[u8], i_buff: usize) -> anyhow::Result<[f32; N]> {
-    let mut a = [0f32; N];
-    for i in 0..N {
-        a[i] = f32::from_le_bytes(buff[i_buff + 4 * i..i_buff + 4 * i + 4].try_into()?);
-    }
-    Ok(a)
+/// PLY data of a point cloud with the `double` coordinates and the `uchar` colors
+pub fn from_xyzrgb<_XyzRgb: XyzRgbWrite>(format: Format, points: &[_XyzRgb]) -> Ply {
+    let xyz = |i_dim: usize| -> Vec<f64> { points.iter().map(|p| p.xyz()[i_dim]).collect() };
+    let rgb = |i_dim: usize| -> Vec<u8> { points.iter().map(|p| p.rgb()[i_dim]).collect() };
+    let props = vec![
+        Property::new_scalar("x", xyz(0)),
+        Property::new_scalar("y", xyz(1)),
+        Property::new_scalar("z", xyz(2)),
+        Property::new_scalar("red", rgb(0)),
+        Property::new_scalar("green", rgb(1)),
+        Property::new_scalar("blue", rgb(2)),
+    ];
+    Ply::new(format, vec![Element::new("vertex", points.len(), props)])
+}
+
+pub fn save_xyzrgb<Path: AsRef<std::path::Path>, _XyzRgb: XyzRgbWrite>(
+    path: Path,
+    points: &[_XyzRgb],
+    format: Format,
+) -> anyhow::Result<()> {
+    from_xyzrgb(format, points).save(path)
 }
 
 // ---------------------------
+// below: 3D Gaussian splats
+
 pub trait GaussSplat3D {
     fn new(
         xyz: [f32; 3],
@@ -120,81 +915,169 @@ pub trait GaussSplat3D {
     ) -> Self;
 }
 
+/// Gaussian splat that can be written by [`save_3d_gauss_splat`]
+pub trait GaussSplat3DWrite {
+    fn xyz(&self) -> [f32; 3];
+    fn rgb_dc(&self) -> [f32; 3];
+    fn rgb_sh(&self) -> [f32; 45];
+    fn opacity(&self) -> f32;
+    fn scale(&self) -> [f32; 3];
+    /// quaternion in the (x,y,z,w) order
+    fn quaternion(&self) -> [f32; 4];
+}
+
+/// Gaussian splat with the spherical harmonics of arbitrary degree.
+/// `rgb_sh` has `(degree+1)^2-1` coefficients for each of the three channels (channel-major)
+pub trait GaussSplat3DAnyDegree {
+    fn new(
+        xyz: [f32; 3],
+        rgb_dc: [f32; 3],
+        rgb_sh: &[f32],
+        opacity: f32,
+        scale: [f32; 3],
+        quaternion: [f32; 4],
+    ) -> Self;
+    fn xyz(&self) -> [f32; 3];
+    fn rgb_dc(&self) -> [f32; 3];
+    fn rgb_sh(&self) -> &[f32];
+    fn opacity(&self) -> f32;
+    fn scale(&self) -> [f32; 3];
+    /// quaternion in the (x,y,z,w) order
+    fn quaternion(&self) -> [f32; 4];
+}
+
+/// degree of the spherical harmonics from the number of the `f_rest_*` properties
+pub fn sh_degree_from_num_f_rest(num_f_rest: usize) -> anyhow::Result<usize> {
+    (0..=4)
+        .find(|degree| (degree + 1) * (degree + 1) * 3 - 3 == num_f_rest)
+        .with_context(|| format!("{} f_rest is not the number for any SH degree", num_f_rest))
+}
+
+/// splats in the structure-of-arrays layout. The values are after the conversions
+struct GaussSplatColumns {
+    /// number of the spherical harmonics coefficients per channel (without DC)
+    num_sh: usize,
+    xyz: Vec<f32>,
+    rgb_dc: Vec<f32>,
+    rgb_sh: Vec<f32>,
+    opacity: Vec<f32>,
+    scale: Vec<f32>,
+    quaternion: Vec<f32>,
+}
+
+impl GaussSplatColumns {
+    /// the properties are looked up by name so the order of the properties
+    /// and the extra properties (e.g., normal) does not matter
+    fn from_ply(ply: &Ply) -> anyhow::Result<Self> {
+        // let sh_c0 = 0.28209479177387814;
+        let sh_c0 = 0.5f32;
+        let vtx = ply.element("vertex").context("no vertex element")?;
+        let num_f_rest = vtx
+            .properties
+            .iter()
+            .filter(|p| p.name.starts_with("f_rest_"))
+            .count();
+        let degree = sh_degree_from_num_f_rest(num_f_rest)?;
+        let names_rest: Vec<String> = (0..num_f_rest).map(|i| format!("f_rest_{}", i)).collect();
+        let names_rest: Vec<&str> = names_rest.iter().map(|v| v.as_str()).collect();
+        let rgb_dc = vtx
+            .interleaved::<f32>(&["f_dc_0", "f_dc_1", "f_dc_2"])?
+            .iter()
+            .map(|v| (v + 0.5) * sh_c0)
+            .collect();
+        let opacity = vtx
+            .scalars::<f32>("opacity")?
+            .iter()
+            .map(|op| 1f32 / (1f32 + (-op).exp()))
+            .collect();
+        let scale = vtx
+            .interleaved::<f32>(&["scale_0", "scale_1", "scale_2"])?
+            .iter()
+            .map(|v| v.exp())
+            .collect();
+        let quaternion = vtx
+            .interleaved::<f32>(&["rot_0", "rot_1", "rot_2", "rot_3"])?
+            .chunks(4)
+            .flat_map(|q| {
+                // (w,x,y,z) -> (x,y,z,w)
+                del_geo_core::quaternion::normalized(&[q[1], q[2], q[3], q[0]])
+            })
+            .collect();
+        Ok(GaussSplatColumns {
+            num_sh: (degree + 1) * (degree + 1) - 1,
+            xyz: vtx.interleaved(&["x", "y", "z"])?,
+            rgb_dc,
+            rgb_sh: vtx.interleaved(&names_rest)?,
+            opacity,
+            scale,
+            quaternion,
+        })
+    }
+
+    /// PLY data in the layout of the original 3DGS implementation.
+    /// The conversions in `from_ply` are inverted (i.e., log scale, logit opacity,
+    /// (w,x,y,z) quaternion and DC color before the offset)
+    fn to_ply(&self, format: Format) -> Ply {
+        let sh_c0 = 0.5f32;
+        let num_splat = self.opacity.len();
+        let mut props = vec![
+            Property::new_scalar("x", component(&self.xyz, 3, 0)),
+            Property::new_scalar("y", component(&self.xyz, 3, 1)),
+            Property::new_scalar("z", component(&self.xyz, 3, 2)),
+            Property::new_scalar("nx", vec![0f32; num_splat]),
+            Property::new_scalar("ny", vec![0f32; num_splat]),
+            Property::new_scalar("nz", vec![0f32; num_splat]),
+        ];
+        for i in 0..3 {
+            let dc: Vec<f32> = component(&self.rgb_dc, 3, i)
+                .iter()
+                .map(|v| v / sh_c0 - 0.5)
+                .collect();
+            props.push(Property::new_scalar(&format!("f_dc_{}", i), dc));
+        }
+        for i in 0..self.num_sh * 3 {
+            let rest = component(&self.rgb_sh, self.num_sh * 3, i);
+            props.push(Property::new_scalar(&format!("f_rest_{}", i), rest));
+        }
+        let opacity: Vec<f32> = self
+            .opacity
+            .iter()
+            .map(|op| (op / (1. - op)).ln())
+            .collect();
+        props.push(Property::new_scalar("opacity", opacity));
+        for i in 0..3 {
+            let scale: Vec<f32> = component(&self.scale, 3, i)
+                .iter()
+                .map(|v| v.ln())
+                .collect();
+            props.push(Property::new_scalar(&format!("scale_{}", i), scale));
+        }
+        for (i, j) in [3, 0, 1, 2].into_iter().enumerate() {
+            // (x,y,z,w) -> (w,x,y,z)
+            let rot = component(&self.quaternion, 4, j);
+            props.push(Property::new_scalar(&format!("rot_{}", i), rot));
+        }
+        let mut ply = Ply::new(format, vec![Element::new("vertex", num_splat, props)]);
+        ply.comments.push("generated by del-msh".to_string());
+        ply
+    }
+}
+
+/// read 3D Gaussian splats. The spherical harmonics of degree lower than three are
+/// padded with zeros
 pub fn read_3d_gauss_splat<Path: AsRef<std::path::Path>, Splat: GaussSplat3D>(
     path: Path,
 ) -> anyhow::Result<Vec<Splat>> {
-    let file = std::fs::File::open(path)?;
-    let mut reader = std::io::BufReader::new(file);
-    use std::io::BufRead;
-    let mut line = String::new();
-    let _hoge = reader.read_line(&mut line)?;
-    assert_eq!(line, "ply\n");
-    line.clear();
-    {
-        // reading format
-        let _hoge = reader.read_line(&mut line)?;
-        let strs: Vec<_> = line.split_whitespace().collect();
-        assert_eq!(strs[0], "format");
-        let _format = match strs[1] {
-            "binary_little_endian" => Format::BinaryLittleEndian,
-            "binary_big_endian" => Format::BinaryBigEndian,
-            "ascii" => Format::Ascii,
-            &_ => panic!(),
-        };
-        line.clear();
-    }
-    {
-        // reading comment
-        let _ = reader.read_line(&mut line)?;
-        let strs: Vec<_> = line.split_whitespace().collect();
-        assert_eq!(strs[0], "comment");
-        line.clear();
-    }
-    let num_elem = {
-        let _ = reader.read_line(&mut line)?; // element vertex
-        let strs: Vec<_> = line.split_whitespace().collect();
-        assert_eq!(strs[0], "element");
-        assert_eq!(strs[1], "vertex");
-        use std::str::FromStr;
-        let num_elem = usize::from_str(strs[2]).unwrap();
-        line.clear();
-        num_elem
-    };
-    //
-    for _i in 0..62 {
-        let _ = reader.read_line(&mut line)?; // property double x
-        let strs: Vec<_> = line.split_whitespace().collect();
-        //dbg!(&line);
-        assert_eq!(strs[0], "property");
-        assert_eq!(strs[1], "float");
-        line.clear();
-    }
-    {
-        // end header
-        let _ = reader.read_line(&mut line)?;
-        assert_eq!(line, "end_header\n");
-    }
-    // let sh_c0 = 0.28209479177387814;
-    let sh_c0 = 0.5f32;
-    let mut buf: Vec<u8> = Vec::new();
-    reader.read_to_end(&mut buf)?;
-    assert_eq!(buf.len(), 62 * num_elem * 4);
-    let mut pnt2gs3: Vec<Splat> = vec![];
-    for i_elem in 0..num_elem {
-        let xyz = parse_f32::<3>(&buf, i_elem * 62 * 4)?;
-        let rgb = parse_f32::<3>(&buf, i_elem * 62 * 4 + 6 * 4)?;
-        let sh = parse_f32::<45>(&buf, i_elem * 62 * 4 + 9 * 4)?;
-        let op = parse_f32::<1>(&buf, i_elem * 62 * 4 + 54 * 4)?;
-        let scale = parse_f32::<3>(&buf, i_elem * 62 * 4 + 55 * 4)?;
-        let quaternion = parse_f32::<4>(&buf, i_elem * 62 * 4 + 58 * 4)?;
-        //
-        let rgb = [
-            (rgb[0] + 0.5) * sh_c0,
-            (rgb[1] + 0.5) * sh_c0,
-            (rgb[2] + 0.5) * sh_c0,
-        ];
-        let quaternion = [quaternion[1], quaternion[2], quaternion[3], quaternion[0]];
-        let quaternion = del_geo_core::quaternion::normalized(&quaternion);
+    let cols = GaussSplatColumns::from_ply(&load(path)?)?;
+    anyhow::ensure!(cols.num_sh <= 15, "SH degree higher than three");
+    let mut pnt2gs3: Vec<Splat> = Vec::with_capacity(cols.opacity.len());
+    for i_elem in 0..cols.opacity.len() {
+        let mut sh = [0f32; 45];
+        let rest = &cols.rgb_sh[i_elem * cols.num_sh * 3..(i_elem + 1) * cols.num_sh * 3];
+        for i_channel in 0..3 {
+            sh[i_channel * 15..i_channel * 15 + cols.num_sh]
+                .copy_from_slice(&rest[i_channel * cols.num_sh..(i_channel + 1) * cols.num_sh]);
+        }
         {
             // rotation in x direction to make the scene y-up?
             // let quat_x= del_geo_core::quaternion::from_axisangle(&[std::f32::consts::FRAC_PI_2,0.,0.]);
@@ -202,10 +1085,390 @@ pub fn read_3d_gauss_splat<Path: AsRef<std::path::Path>, Splat: GaussSplat3D>(
             // let xyz = [xyz[0], -xyz[2], xyz[1]];
             // let quaternion = del_geo_core::quaternion::mult_quaternion(&quaternion, &quat_x);
         }
-        let scale = [scale[0].exp(), scale[1].exp(), scale[2].exp()];
-        let op = op[0];
-        let op = 1f32 / (1f32 + (-op).exp());
-        pnt2gs3.push(Splat::new(xyz, rgb, sh, op, scale, quaternion));
+        pnt2gs3.push(Splat::new(
+            *arrayref::array_ref![cols.xyz, i_elem * 3, 3],
+            *arrayref::array_ref![cols.rgb_dc, i_elem * 3, 3],
+            sh,
+            cols.opacity[i_elem],
+            *arrayref::array_ref![cols.scale, i_elem * 3, 3],
+            *arrayref::array_ref![cols.quaternion, i_elem * 4, 4],
+        ));
     }
     Ok(pnt2gs3)
 }
+
+/// read 3D Gaussian splats keeping the degree of the spherical harmonics in the file
+pub fn read_3d_gauss_splat_any_degree<Path, Splat>(path: Path) -> anyhow::Result<Vec<Splat>>
+where
+    Path: AsRef<std::path::Path>,
+    Splat: GaussSplat3DAnyDegree,
+{
+    let cols = GaussSplatColumns::from_ply(&load(path)?)?;
+    let num_rest = cols.num_sh * 3;
+    let pnt2gs3 = (0..cols.opacity.len())
+        .map(|i_elem| {
+            Splat::new(
+                *arrayref::array_ref![cols.xyz, i_elem * 3, 3],
+                *arrayref::array_ref![cols.rgb_dc, i_elem * 3, 3],
+                &cols.rgb_sh[i_elem * num_rest..(i_elem + 1) * num_rest],
+                cols.opacity[i_elem],
+                *arrayref::array_ref![cols.scale, i_elem * 3, 3],
+                *arrayref::array_ref![cols.quaternion, i_elem * 4, 4],
+            )
+        })
+        .collect();
+    Ok(pnt2gs3)
+}
+
+/// PLY data of 3D Gaussian splats in the layout of the original 3DGS implementation.
+/// The conversions in `read_3d_gauss_splat` are inverted (i.e., log scale, logit opacity,
+/// (w,x,y,z) quaternion and DC color before the offset)
+pub fn from_3d_gauss_splat<Splat: GaussSplat3DWrite>(format: Format, splats: &[Splat]) -> Ply {
+    GaussSplatColumns {
+        num_sh: 15,
+        xyz: splats.iter().flat_map(|s| s.xyz()).collect(),
+        rgb_dc: splats.iter().flat_map(|s| s.rgb_dc()).collect(),
+        rgb_sh: splats.iter().flat_map(|s| s.rgb_sh()).collect(),
+        opacity: splats.iter().map(|s| s.opacity()).collect(),
+        scale: splats.iter().flat_map(|s| s.scale()).collect(),
+        quaternion: splats.iter().flat_map(|s| s.quaternion()).collect(),
+    }
+    .to_ply(format)
+}
+
+/// all the splats should have the same degree of the spherical harmonics
+pub fn from_3d_gauss_splat_any_degree<Splat: GaussSplat3DAnyDegree>(
+    format: Format,
+    splats: &[Splat],
+) -> anyhow::Result<Ply> {
+    let num_rest = splats.first().map_or(0, |s| s.rgb_sh().len());
+    let degree = sh_degree_from_num_f_rest(num_rest)?;
+    anyhow::ensure!(
+        splats.iter().all(|s| s.rgb_sh().len() == num_rest),
+        "SH degree differs among splats"
+    );
+    let ply = GaussSplatColumns {
+        num_sh: (degree + 1) * (degree + 1) - 1,
+        xyz: splats.iter().flat_map(|s| s.xyz()).collect(),
+        rgb_dc: splats.iter().flat_map(|s| s.rgb_dc()).collect(),
+        rgb_sh: splats.iter().flat_map(|s| s.rgb_sh().to_vec()).collect(),
+        opacity: splats.iter().map(|s| s.opacity()).collect(),
+        scale: splats.iter().flat_map(|s| s.scale()).collect(),
+        quaternion: splats.iter().flat_map(|s| s.quaternion()).collect(),
+    }
+    .to_ply(format);
+    Ok(ply)
+}
+
+pub fn save_3d_gauss_splat<Path: AsRef<std::path::Path>, Splat: GaussSplat3DWrite>(
+    path: Path,
+    splats: &[Splat],
+    format: Format,
+) -> anyhow::Result<()> {
+    from_3d_gauss_splat(format, splats).save(path)
+}
+
+pub fn save_3d_gauss_splat_any_degree<Path, Splat>(
+    path: Path,
+    splats: &[Splat],
+    format: Format,
+) -> anyhow::Result<()>
+where
+    Path: AsRef<std::path::Path>,
+    Splat: GaussSplat3DAnyDegree,
+{
+    from_3d_gauss_splat_any_degree(format, splats)?.save(path)
+}
+
+#[test]
+fn test_read() {
+    let header = |format: &str| {
+        format!(
+            "ply\nformat {} 1.0\ncomment made by hand\ncomment two comments\n\
+             obj_info test\nelement vertex 3\nproperty float x\nproperty float y\n\
+             property float z\nproperty uchar red\nproperty double quality\n\
+             element face 1\nproperty list uchar int vertex_indices\nend_header\n",
+            format
+        )
+    };
+    let vtx2xyz = [0f32, 0., 0., 1., 0., 0., 0., 1., 0.];
+    let check = |ply: &Ply| {
+        assert_eq!(ply.comments.len(), 2);
+        assert_eq!(ply.obj_info, vec!["test".to_string()]);
+        let (elem2idx, idx2vtx, vtx2xyz0) = ply.polygon_mesh::<usize, f32>().unwrap();
+        assert_eq!(elem2idx, vec![0, 3]);
+        assert_eq!(idx2vtx, vec![0, 1, 2]);
+        assert_eq!(vtx2xyz0, vtx2xyz);
+        let vtx = ply.element("vertex").unwrap();
+        assert_eq!(vtx.scalars::<u8>("red").unwrap(), vec![0, 1, 2]);
+        assert_eq!(vtx.scalars::<f64>("quality").unwrap(), vec![0.5, 1.5, 2.5]);
+    };
+    {
+        let mut s = header("ascii");
+        for i_vtx in 0..3 {
+            let xyz = &vtx2xyz[i_vtx * 3..i_vtx * 3 + 3];
+            let q = i_vtx as f64 + 0.5;
+            s += &format!("{} {} {} {} {}\n", xyz[0], xyz[1], xyz[2], i_vtx, q);
+        }
+        s += "3 0 1 2\n";
+        let ply = read(&mut s.as_bytes()).unwrap();
+        assert_eq!(ply.format, Format::Ascii);
+        check(&ply);
+    }
+    {
+        let mut buf = header("binary_big_endian").into_bytes();
+        for i_vtx in 0..3 {
+            for v in &vtx2xyz[i_vtx * 3..i_vtx * 3 + 3] {
+                buf.extend_from_slice(&v.to_be_bytes());
+            }
+            buf.push(i_vtx as u8);
+            buf.extend_from_slice(&(i_vtx as f64 + 0.5).to_be_bytes());
+        }
+        buf.push(3);
+        for i in 0i32..3 {
+            buf.extend_from_slice(&i.to_be_bytes());
+        }
+        let ply = read(&mut buf.as_slice()).unwrap();
+        assert_eq!(ply.format, Format::BinaryBigEndian);
+        check(&ply);
+    }
+    // the huge count in the header is not allocated
+    let s = format!(
+        "ply\nformat ascii 1.0\nelement face {}\nproperty list uchar int vertex_indices\n\
+         end_header\n3 0 1 2\n",
+        usize::MAX
+    );
+    assert!(read(&mut s.as_bytes()).is_err());
+}
+
+#[test]
+fn test_write() {
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::torus_zup::<usize, f32>(1.0, 0.3, 16, 8);
+    let num_vtx = vtx2xyz.len() / 3;
+    let vtx2rgb: Vec<u8> = (0..num_vtx * 3).map(|i| (i % 256) as u8).collect();
+    let vtx2nrm: Vec<f32> = vtx2xyz.iter().map(|v| v * 2.0).collect();
+    let vtx2val: Vec<f32> = (0..num_vtx).map(|i| i as f32).collect();
+    for format in [
+        Format::Ascii,
+        Format::BinaryLittleEndian,
+        Format::BinaryBigEndian,
+    ] {
+        let ply = from_tri2vtx_vtx2xyz(
+            format,
+            &tri2vtx,
+            &vtx2xyz,
+            Some(&vtx2rgb),
+            Some(&vtx2nrm),
+            &[("quality", &vtx2val)],
+        )
+        .unwrap();
+        let mut buf = Vec::<u8>::new();
+        ply.write(&mut buf).unwrap();
+        let ply = read(&mut buf.as_slice()).unwrap();
+        let (elem2idx, idx2vtx, vtx2xyz0) = ply.polygon_mesh::<usize, f32>().unwrap();
+        assert_eq!(elem2idx.len(), tri2vtx.len() / 3 + 1);
+        assert_eq!(idx2vtx, tri2vtx);
+        assert_eq!(vtx2xyz0, vtx2xyz);
+        let vtx = ply.element("vertex").unwrap();
+        assert_eq!(
+            vtx.interleaved::<u8>(&["red", "green", "blue"]).unwrap(),
+            vtx2rgb
+        );
+        assert_eq!(
+            vtx.interleaved::<f32>(&["nx", "ny", "nz"]).unwrap(),
+            vtx2nrm
+        );
+        assert_eq!(vtx.scalars::<f32>("quality").unwrap(), vtx2val);
+    }
+    // Gaussian splats
+    struct Splat {
+        xyz: [f32; 3],
+        rgb_dc: [f32; 3],
+        rgb_sh: [f32; 45],
+        opacity: f32,
+        scale: [f32; 3],
+        quaternion: [f32; 4],
+    }
+    impl GaussSplat3D for Splat {
+        fn new(
+            xyz: [f32; 3],
+            rgb_dc: [f32; 3],
+            rgb_sh: [f32; 45],
+            opacity: f32,
+            scale: [f32; 3],
+            quaternion: [f32; 4],
+        ) -> Self {
+            Splat {
+                xyz,
+                rgb_dc,
+                rgb_sh,
+                opacity,
+                scale,
+                quaternion,
+            }
+        }
+    }
+    impl GaussSplat3DWrite for Splat {
+        fn xyz(&self) -> [f32; 3] {
+            self.xyz
+        }
+        fn rgb_dc(&self) -> [f32; 3] {
+            self.rgb_dc
+        }
+        fn rgb_sh(&self) -> [f32; 45] {
+            self.rgb_sh
+        }
+        fn opacity(&self) -> f32 {
+            self.opacity
+        }
+        fn scale(&self) -> [f32; 3] {
+            self.scale
+        }
+        fn quaternion(&self) -> [f32; 4] {
+            self.quaternion
+        }
+    }
+    let splats0: Vec<Splat> = (0..10)
+        .map(|i| {
+            let a = i as f32 * 0.1;
+            let q = del_geo_core::quaternion::normalized(&[a, 0.2, -0.3, 1.0]);
+            Splat::new(
+                [a, -a, 2. * a],
+                [0.2, 0.5, a],
+                std::array::from_fn(|j| a * j as f32),
+                0.1 + a * 0.5,
+                [0.01 + a, 0.02, 0.03],
+                q,
+            )
+        })
+        .collect();
+    save_3d_gauss_splat("../target/splat.ply", &splats0, Format::BinaryLittleEndian).unwrap();
+    let splats1: Vec<Splat> = read_3d_gauss_splat("../target/splat.ply").unwrap();
+    assert_eq!(splats0.len(), splats1.len());
+    for (s0, s1) in splats0.iter().zip(splats1.iter()) {
+        let diff = |a: &[f32], b: &[f32]| {
+            a.iter()
+                .zip(b.iter())
+                .map(|(x, y)| (x - y).abs())
+                .fold(0f32, f32::max)
+        };
+        assert!(diff(&s0.xyz, &s1.xyz) < 1.0e-6);
+        assert!(diff(&s0.rgb_dc, &s1.rgb_dc) < 1.0e-6);
+        assert!(diff(&s0.rgb_sh, &s1.rgb_sh) < 1.0e-6);
+        assert!((s0.opacity - s1.opacity).abs() < 1.0e-5);
+        assert!(diff(&s0.scale, &s1.scale) < 1.0e-5);
+        assert!(diff(&s0.quaternion, &s1.quaternion) < 1.0e-5);
+    }
+}
+
+#[test]
+fn test_gauss_splat_any_degree() {
+    struct Splat {
+        xyz: [f32; 3],
+        rgb_dc: [f32; 3],
+        rgb_sh: Vec<f32>,
+        opacity: f32,
+        scale: [f32; 3],
+        quaternion: [f32; 4],
+    }
+    impl GaussSplat3DAnyDegree for Splat {
+        fn new(
+            xyz: [f32; 3],
+            rgb_dc: [f32; 3],
+            rgb_sh: &[f32],
+            opacity: f32,
+            scale: [f32; 3],
+            quaternion: [f32; 4],
+        ) -> Self {
+            Splat {
+                xyz,
+                rgb_dc,
+                rgb_sh: rgb_sh.to_vec(),
+                opacity,
+                scale,
+                quaternion,
+            }
+        }
+        fn xyz(&self) -> [f32; 3] {
+            self.xyz
+        }
+        fn rgb_dc(&self) -> [f32; 3] {
+            self.rgb_dc
+        }
+        fn rgb_sh(&self) -> &[f32] {
+            &self.rgb_sh
+        }
+        fn opacity(&self) -> f32 {
+            self.opacity
+        }
+        fn scale(&self) -> [f32; 3] {
+            self.scale
+        }
+        fn quaternion(&self) -> [f32; 4] {
+            self.quaternion
+        }
+    }
+    for degree in 0..4 {
+        let num_rest = ((degree + 1) * (degree + 1) - 1) * 3;
+        let splats0: Vec<Splat> = (0..5)
+            .map(|i| {
+                let a = i as f32 * 0.1;
+                let sh: Vec<f32> = (0..num_rest).map(|j| a + j as f32).collect();
+                Splat::new(
+                    [a, 0., 0.],
+                    [0.3, 0.3, a],
+                    &sh,
+                    0.5,
+                    [0.1, 0.2, 0.3],
+                    [0., 0., 0., 1.],
+                )
+            })
+            .collect();
+        let path = format!("../target/splat_degree{}.ply", degree);
+        save_3d_gauss_splat_any_degree(&path, &splats0, Format::BinaryLittleEndian).unwrap();
+        {
+            // reorder the properties and add an extra one
+            let mut ply = load(&path).unwrap();
+            ply.elements[0].properties.reverse();
+            let prop = Property::new_scalar("confidence", vec![1f64; 5]);
+            ply.elements[0].properties.insert(3, prop);
+            ply.format = Format::Ascii;
+            ply.save(&path).unwrap();
+        }
+        let splats1: Vec<Splat> = read_3d_gauss_splat_any_degree(&path).unwrap();
+        for (s0, s1) in splats0.iter().zip(splats1.iter()) {
+            assert_eq!(s1.rgb_sh.len(), num_rest);
+            assert_eq!(s0.rgb_sh, s1.rgb_sh);
+            assert!((s0.xyz[0] - s1.xyz[0]).abs() < 1.0e-6);
+            assert!((s0.scale[2] - s1.scale[2]).abs() < 1.0e-6);
+        }
+        // padded to degree three
+        struct Splat3(Vec<f32>);
+        impl GaussSplat3D for Splat3 {
+            fn new(
+                _xyz: [f32; 3],
+                _rgb_dc: [f32; 3],
+                rgb_sh: [f32; 45],
+                _opacity: f32,
+                _scale: [f32; 3],
+                _quaternion: [f32; 4],
+            ) -> Self {
+                Splat3(rgb_sh.to_vec())
+            }
+        }
+        let splats3: Vec<Splat3> = read_3d_gauss_splat(&path).unwrap();
+        let num_sh = num_rest / 3;
+        for (s0, s3) in splats0.iter().zip(splats3.iter()) {
+            for i_channel in 0..3 {
+                for i_sh in 0..15 {
+                    let v3 = s3.0[i_channel * 15 + i_sh];
+                    if i_sh < num_sh {
+                        assert_eq!(v3, s0.rgb_sh[i_channel * num_sh + i_sh]);
+                    } else {
+                        assert_eq!(v3, 0.);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,321 @@
+//! methods for STL files (both ASCII and binary)
+
+use anyhow::Context;
+use num_traits::AsPrimitive;
+use std::io::{BufRead, Write};
+
+/// triangle mesh loaded from a STL file
+pub struct StlTriMesh<Index, Real> {
+    pub tri2vtx: Vec<Index>,
+    pub vtx2xyz: Vec<Real>,
+    /// facet normals as written in the file (three values per triangle)
+    pub tri2nrm: Vec<Real>,
+    /// "attribute byte count" of binary STL. Zero for ASCII STL
+    pub tri2attr: Vec<u16>,
+    /// name after `solid` for ASCII STL or the 80-byte header for binary STL
+    pub name: String,
+}
+
+/// check the STL data is in binary format from the size of the data
+fn is_binary(buf: &[u8]) -> bool {
+    if buf.len() < 84 {
+        return false;
+    }
+    let num_tri = u32::from_le_bytes(buf[80..84].try_into().unwrap()) as usize;
+    84 + num_tri * 50 == buf.len()
+}
+
+/// triangle soup in the STL file.
+/// * Returns (tri2xyz, tri2nrm, tri2attr, name)
+#[allow(clippy::type_complexity)]
+fn read_triangle_soup_binary(buf: &[u8]) -> anyhow::Result<(Vec<f32>, Vec<f32>, Vec<u16>, String)> {
+    let name = String::from_utf8_lossy(&buf[0..80])
+        .trim_end_matches(['\0', ' '])
+        .to_string();
+    let num_tri = u32::from_le_bytes(buf[80..84].try_into()?) as usize;
+    let mut tri2xyz = Vec::<f32>::with_capacity(num_tri * 9);
+    let mut tri2nrm = Vec::<f32>::with_capacity(num_tri * 3);
+    let mut tri2attr = Vec::<u16>::with_capacity(num_tri);
+    for facet in buf[84..].chunks_exact(50) {
+        for (i_val, val) in facet[0..48].chunks_exact(4).enumerate() {
+            let v = f32::from_le_bytes(val.try_into()?);
+            if i_val < 3 {
+                tri2nrm.push(v);
+            } else {
+                tri2xyz.push(v);
+            }
+        }
+        tri2attr.push(u16::from_le_bytes(facet[48..50].try_into()?));
+    }
+    Ok((tri2xyz, tri2nrm, tri2attr, name))
+}
+
+#[allow(clippy::type_complexity)]
+fn read_triangle_soup_ascii(buf: &[u8]) -> anyhow::Result<(Vec<f32>, Vec<f32>, Vec<u16>, String)> {
+    let mut tri2xyz = Vec::<f32>::new();
+    let mut tri2nrm = Vec::<f32>::new();
+    let mut name = String::new();
+    let mut num_vtx_in_facet = 0;
+    for (i_line, line) in buf.lines().enumerate() {
+        let line = line?;
+        let strs: Vec<_> = line.split_whitespace().collect();
+        if strs.is_empty() {
+            continue;
+        }
+        let parse3 = |strs: &[&str]| -> anyhow::Result<[f32; 3]> {
+            anyhow::ensure!(strs.len() == 3, "line {}: expected 3 values", i_line + 1);
+            let mut v = [0f32; 3];
+            for (v, s) in v.iter_mut().zip(strs) {
+                *v = s
+                    .parse::<f32>()
+                    .with_context(|| format!("line {}: invalid number", i_line + 1))?;
+            }
+            Ok(v)
+        };
+        match strs[0] {
+            "solid" => {
+                name = strs[1..].join(" ");
+            }
+            "facet" => {
+                anyhow::ensure!(
+                    strs.len() == 5 && strs[1] == "normal",
+                    "line {}: expected \"facet normal nx ny nz\"",
+                    i_line + 1
+                );
+                tri2nrm.extend_from_slice(&parse3(&strs[2..])?);
+                num_vtx_in_facet = 0;
+            }
+            "vertex" => {
+                tri2xyz.extend_from_slice(&parse3(&strs[1..])?);
+                num_vtx_in_facet += 1;
+            }
+            "endfacet" => {
+                anyhow::ensure!(
+                    num_vtx_in_facet == 3,
+                    "line {}: facet should have 3 vertices",
+                    i_line + 1
+                );
+            }
+            _ => {} // "outer loop", "endloop", "endsolid"
+        }
+    }
+    anyhow::ensure!(tri2xyz.len() == tri2nrm.len() * 3, "broken facet");
+    let tri2attr = vec![0u16; tri2nrm.len() / 3];
+    Ok((tri2xyz, tri2nrm, tri2attr, name))
+}
+
+/// merge the vertices of a triangle soup that have exactly the same coordinates
+/// * `tri2xyz` - coordinates of the corners of triangles (nine values per triangle)
+/// * Returns (tri2vtx, vtx2xyz)
+pub fn unify_vertices_of_triangle_soup<Index>(tri2xyz: &[f32]) -> (Vec<Index>, Vec<f32>)
+where
+    Index: num_traits::PrimInt + 'static,
+    usize: AsPrimitive<Index>,
+{
+    let (corner2vtx, num_vtx) = crate::trimesh_cleanup::weld_vertices(tri2xyz, 3, 0f32);
+    let vtx2xyz = crate::map_idx::map_vertex_attibute_to(tri2xyz, 3, &corner2vtx, num_vtx);
+    let tri2vtx = corner2vtx.iter().map(|&i_vtx| i_vtx.as_()).collect();
+    (tri2vtx, vtx2xyz)
+}
+
+/// load STL from a reader. The format (ASCII or binary) is detected from the content
+pub fn read<R, Index, Real>(reader: &mut R) -> anyhow::Result<StlTriMesh<Index, Real>>
+where
+    R: std::io::Read,
+    Index: num_traits::PrimInt + 'static,
+    Real: Copy + 'static,
+    usize: AsPrimitive<Index>,
+    f32: AsPrimitive<Real>,
+{
+    let mut buf = Vec::<u8>::new();
+    reader.read_to_end(&mut buf)?;
+    let (tri2xyz, tri2nrm, tri2attr, name) = if is_binary(&buf) {
+        read_triangle_soup_binary(&buf)?
+    } else if buf.starts_with(b"solid") {
+        read_triangle_soup_ascii(&buf)?
+    } else {
+        return Err(anyhow::anyhow!("neither ASCII nor binary STL"));
+    };
+    let (tri2vtx, vtx2xyz) = unify_vertices_of_triangle_soup(&tri2xyz);
+    Ok(StlTriMesh {
+        tri2vtx,
+        vtx2xyz: vtx2xyz.iter().map(|v| v.as_()).collect(),
+        tri2nrm: tri2nrm.iter().map(|v| v.as_()).collect(),
+        tri2attr,
+        name,
+    })
+}
+
+/// load STL file
+pub fn load<P, Index, Real>(filepath: P) -> anyhow::Result<StlTriMesh<Index, Real>>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + 'static,
+    Real: Copy + 'static,
+    usize: AsPrimitive<Index>,
+    f32: AsPrimitive<Real>,
+{
+    let file = std::fs::File::open(filepath).context("file not found.")?;
+    let mut reader = std::io::BufReader::new(file);
+    read(&mut reader)
+}
+
+/// load STL file as triangle mesh with shared vertices
+/// * Returns (tri2vtx, vtx2xyz)
+pub fn load_tri_mesh<P, Index, Real>(filepath: P) -> anyhow::Result<(Vec<Index>, Vec<Real>)>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + 'static,
+    Real: Copy + 'static,
+    usize: AsPrimitive<Index>,
+    f32: AsPrimitive<Real>,
+{
+    let stl = load(filepath)?;
+    Ok((stl.tri2vtx, stl.vtx2xyz))
+}
+
+fn unit_normal<Index, Real>(tri2vtx: &[Index], vtx2xyz: &[Real], i_tri: usize) -> [Real; 3]
+where
+    Index: AsPrimitive<usize>,
+    Real: num_traits::Float,
+{
+    let n = crate::trimesh3::to_tri3(tri2vtx, vtx2xyz, i_tri).normal();
+    let len = del_geo_core::vec3::norm(&n);
+    if len.is_zero() {
+        return [Real::zero(); 3];
+    }
+    del_geo_core::vec3::scale(&n, Real::one() / len)
+}
+
+/// write ASCII STL with the facet normals computed from the vertices
+pub fn write_tri2vtx_vtx2xyz_ascii<W, Index, Real>(
+    writer: &mut W,
+    name: &str,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+) -> anyhow::Result<()>
+where
+    W: Write,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display,
+{
+    writeln!(writer, "solid {}", name)?;
+    for (i_tri, node2vtx) in tri2vtx.chunks(3).enumerate() {
+        let n = unit_normal(tri2vtx, vtx2xyz, i_tri);
+        writeln!(writer, "  facet normal {} {} {}", n[0], n[1], n[2])?;
+        writeln!(writer, "    outer loop")?;
+        for i_vtx in node2vtx.iter().map(|v| v.as_()) {
+            writeln!(
+                writer,
+                "      vertex {} {} {}",
+                vtx2xyz[i_vtx * 3],
+                vtx2xyz[i_vtx * 3 + 1],
+                vtx2xyz[i_vtx * 3 + 2]
+            )?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+    writeln!(writer, "endsolid {}", name)?;
+    Ok(())
+}
+
+/// write binary STL with the facet normals computed from the vertices
+/// * `tri2attr` - "attribute byte count" for each triangle. Zeros are written if None
+pub fn write_tri2vtx_vtx2xyz_binary<W, Index, Real>(
+    writer: &mut W,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+    tri2attr: Option<&[u16]>,
+) -> anyhow::Result<()>
+where
+    W: Write,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + AsPrimitive<f32>,
+{
+    let num_tri = tri2vtx.len() / 3;
+    if let Some(tri2attr) = tri2attr {
+        anyhow::ensure!(tri2attr.len() == num_tri, "size mismatch of tri2attr");
+    }
+    let num_tri_u32: u32 = num_tri.try_into().context("too many triangles for STL")?;
+    let mut header = [0u8; 80];
+    header[..14].copy_from_slice(b"binary del-msh");
+    writer.write_all(&header)?;
+    writer.write_all(&num_tri_u32.to_le_bytes())?;
+    for (i_tri, node2vtx) in tri2vtx.chunks(3).enumerate() {
+        let n = unit_normal(tri2vtx, vtx2xyz, i_tri);
+        for v in n {
+            writer.write_all(&v.as_().to_le_bytes())?;
+        }
+        for i_vtx in node2vtx.iter().map(|v| v.as_()) {
+            for &v in &vtx2xyz[i_vtx * 3..i_vtx * 3 + 3] {
+                writer.write_all(&v.as_().to_le_bytes())?;
+            }
+        }
+        let attr = tri2attr.map_or(0u16, |v| v[i_tri]);
+        writer.write_all(&attr.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// save the triangle mesh as ASCII STL
+pub fn save_tri2vtx_vtx2xyz_ascii<Path, Index, Real>(
+    filepath: Path,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+) -> anyhow::Result<()>
+where
+    Path: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + std::fmt::Display,
+{
+    let file = std::fs::File::create(filepath).context("file not found.")?;
+    let mut file = std::io::BufWriter::new(file);
+    write_tri2vtx_vtx2xyz_ascii(&mut file, "del-msh", tri2vtx, vtx2xyz)
+}
+
+/// save the triangle mesh as binary STL
+pub fn save_tri2vtx_vtx2xyz_binary<Path, Index, Real>(
+    filepath: Path,
+    tri2vtx: &[Index],
+    vtx2xyz: &[Real],
+) -> anyhow::Result<()>
+where
+    Path: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + AsPrimitive<usize>,
+    Real: num_traits::Float + AsPrimitive<f32>,
+{
+    let file = std::fs::File::create(filepath).context("file not found.")?;
+    let mut file = std::io::BufWriter::new(file);
+    write_tri2vtx_vtx2xyz_binary(&mut file, tri2vtx, vtx2xyz, None)
+}
+
+#[test]
+fn test_save_load() {
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::torus_zup::<usize, f32>(1.0, 0.3, 16, 8);
+    let num_vtx = vtx2xyz.len() / 3;
+    for is_binary in [false, true] {
+        let path = if is_binary {
+            "../target/torus_binary.stl"
+        } else {
+            "../target/torus_ascii.stl"
+        };
+        if is_binary {
+            save_tri2vtx_vtx2xyz_binary(path, &tri2vtx, &vtx2xyz).unwrap();
+        } else {
+            save_tri2vtx_vtx2xyz_ascii(path, &tri2vtx, &vtx2xyz).unwrap();
+        }
+        let stl = load::<_, usize, f32>(path).unwrap();
+        assert_eq!(stl.tri2vtx.len(), tri2vtx.len());
+        assert_eq!(stl.vtx2xyz.len(), num_vtx * 3);
+        assert_eq!(stl.tri2nrm.len(), tri2vtx.len());
+        assert_eq!(stl.tri2attr.len(), tri2vtx.len() / 3);
+        for (&i_vtx0, &i_vtx1) in tri2vtx.iter().zip(stl.tri2vtx.iter()) {
+            for i_dim in 0..3 {
+                let v0 = vtx2xyz[i_vtx0 * 3 + i_dim];
+                let v1 = stl.vtx2xyz[i_vtx1 * 3 + i_dim];
+                assert!((v0 - v1).abs() < 1.0e-6);
+            }
+        }
+    }
+}
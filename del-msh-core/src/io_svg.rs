@@ -1,205 +1,338 @@
-pub fn svg_outline_path_from_shape(s0: &str) -> Vec<String> {
-    let s0 = s0.as_bytes();
-    let mut imark = 0;
-    let mut strs = Vec::<String>::new();
-    for i in 0..s0.len() {
-        if s0[i].is_ascii_digit() {
-            continue;
+//! methods for SVG files (path data and the basic shapes)
+
+use anyhow::Context;
+
+type Vec2 = nalgebra::Vector2<f32>;
+
+/// poly-Bézier curve as the control points, the index of the end point of each segment
+/// (`seg2vtx[0]` is zero), and whether the curve is closed
+pub type PolyBezier = (Vec<nalgebra::Vector2<f32>>, Vec<usize>, bool);
+
+fn is_path_command(c: u8) -> bool {
+    b"MmZzLlHhVvCcSsQqTtAa".contains(&c)
+}
+
+/// scan a number such as `-1.5`, `.5` or `1e-3`. Return `None` if there is no number at `i`
+fn scan_number(b: &[u8], i: &mut usize) -> Option<(usize, usize)> {
+    let start = *i;
+    let mut j = start;
+    if j < b.len() && (b[j] == b'+' || b[j] == b'-') {
+        j += 1;
+    }
+    let mut num_digit = 0;
+    while j < b.len() && b[j].is_ascii_digit() {
+        j += 1;
+        num_digit += 1;
+    }
+    if j < b.len() && b[j] == b'.' {
+        j += 1;
+        while j < b.len() && b[j].is_ascii_digit() {
+            j += 1;
+            num_digit += 1;
         }
-        if s0[i] == b',' {
-            strs.push(std::str::from_utf8(&s0[imark..i]).unwrap().to_string());
-            imark = i + 1; // mark should be the beginning position of the string so move next
+    }
+    if num_digit == 0 {
+        return None;
+    }
+    if j < b.len() && (b[j] == b'e' || b[j] == b'E') {
+        let mut k = j + 1;
+        if k < b.len() && (b[k] == b'+' || b[k] == b'-') {
+            k += 1;
         }
-        if s0[i] == b' ' {
-            // sometimes the space act as delimiter in the SVG (inkscape version)
-            if i > imark {
-                strs.push(std::str::from_utf8(&s0[imark..i]).unwrap().to_string());
+        if k < b.len() && b[k].is_ascii_digit() {
+            while k < b.len() && b[k].is_ascii_digit() {
+                k += 1;
             }
-            imark = i + 1; // mark should be the beginning position of the string so move next
+            j = k;
         }
-        if s0[i] == b'-' {
-            if i > imark {
-                strs.push(std::str::from_utf8(&s0[imark..i]).unwrap().to_string());
-            }
-            imark = i;
+    }
+    *i = j;
+    Some((start, j))
+}
+
+fn skip_separator(b: &[u8], i: &mut usize) {
+    while *i < b.len() && (b[*i].is_ascii_whitespace() || b[*i] == b',') {
+        *i += 1;
+    }
+}
+
+/// numbers separated by white spaces or commas (e.g., `points` of `<polygon>`)
+fn parse_numbers(s: &str) -> anyhow::Result<Vec<f32>> {
+    let b = s.as_bytes();
+    let mut i = 0;
+    let mut res = vec![];
+    loop {
+        skip_separator(b, &mut i);
+        if i == b.len() {
+            break;
+        }
+        let Some((start, end)) = scan_number(b, &mut i) else {
+            return Err(anyhow::anyhow!("cannot parse number in \"{}\"", s));
+        };
+        res.push(s[start..end].parse::<f32>()?);
+    }
+    Ok(res)
+}
+
+/// split the path data (`d` attribute) into the commands and the numbers
+pub fn svg_outline_path_from_shape(s0: &str) -> anyhow::Result<Vec<String>> {
+    let b = s0.as_bytes();
+    let mut i = 0;
+    let mut cmd = 0u8;
+    let mut i_param = 0;
+    let mut strs = Vec::<String>::new();
+    loop {
+        skip_separator(b, &mut i);
+        if i == b.len() {
+            break;
+        }
+        if is_path_command(b[i]) {
+            cmd = b[i];
+            i_param = 0;
+            strs.push((b[i] as char).to_string());
+            i += 1;
+            continue;
         }
-        if s0[i].is_ascii_alphabetic() {
-            if i > imark {
-                strs.push(std::str::from_utf8(&s0[imark..i]).unwrap().to_string());
+        if (cmd == b'A' || cmd == b'a') && (i_param % 7 == 3 || i_param % 7 == 4) {
+            // the flags of an arc may not be separated (e.g., `a1 1 0 0050 50`)
+            if b[i] != b'0' && b[i] != b'1' {
+                return Err(anyhow::anyhow!("invalid arc flag at {} in path data", i));
             }
-            strs.push(std::str::from_utf8(&[s0[i]]).unwrap().to_string()); // push tag
-            imark = i + 1;
+            strs.push((b[i] as char).to_string());
+            i += 1;
+            i_param += 1;
+            continue;
         }
+        let Some((start, end)) = scan_number(b, &mut i) else {
+            return Err(anyhow::anyhow!(
+                "unexpected character '{}' at {} in path data",
+                b[i] as char,
+                i
+            ));
+        };
+        strs.push(s0[start..end].to_string());
+        i_param += 1;
     }
-    if s0.len() > imark {
-        strs.push(
-            std::str::from_utf8(&s0[imark..s0.len()])
-                .unwrap()
-                .to_string(),
-        );
+    Ok(strs)
+}
+
+/// building poly-Bézier curves from the path commands
+struct PathBuilder {
+    loops: Vec<PolyBezier>,
+    vtxl2xy: Vec<Vec2>,
+    seg2vtxl: Vec<usize>,
+    pos_cur: Vec2,
+    pos_start: Vec2,
+}
+
+impl PathBuilder {
+    /// push the current curve if it has any segment
+    fn flush(&mut self, is_close: bool) {
+        if self.seg2vtxl.len() > 1 {
+            let vtxl2xy = std::mem::take(&mut self.vtxl2xy);
+            let seg2vtxl = std::mem::replace(&mut self.seg2vtxl, vec![0]);
+            self.loops.push((vtxl2xy, seg2vtxl, is_close));
+        }
+        self.vtxl2xy.clear();
+        self.seg2vtxl = vec![0];
+    }
+
+    fn move_to(&mut self, p: Vec2) {
+        self.flush(false);
+        self.pos_cur = p;
+        self.pos_start = p;
+    }
+
+    /// append a segment with the control points and the end point
+    fn segment_to(&mut self, ctrls: &[Vec2], p: Vec2) {
+        if self.vtxl2xy.is_empty() {
+            self.vtxl2xy.push(self.pos_cur);
+        }
+        self.vtxl2xy.extend_from_slice(ctrls);
+        self.seg2vtxl.push(self.vtxl2xy.len());
+        self.vtxl2xy.push(p);
+        self.pos_cur = p;
+    }
+
+    fn close(&mut self) {
+        self.flush(true);
+        self.pos_cur = self.pos_start;
+    }
+}
+
+/// cubic Bézier curves (two control points and the end point) approximating an elliptical arc.
+/// See the implementation notes of SVG 1.1 (F.6.5)
+fn arc_to_cubic_beziers(
+    p0: Vec2,
+    radius: Vec2,
+    phi_degree: f32,
+    is_large_arc: bool,
+    is_sweep: bool,
+    p1: Vec2,
+) -> Vec<[Vec2; 3]> {
+    let (mut rx, mut ry) = (radius.x.abs(), radius.y.abs());
+    if p0 == p1 {
+        return vec![];
+    }
+    if rx == 0. || ry == 0. {
+        return vec![[p0, p1, p1]]; // straight line
+    }
+    let (sin, cos) = phi_degree.to_radians().sin_cos();
+    let rot = |v: Vec2| Vec2::new(cos * v.x - sin * v.y, sin * v.x + cos * v.y);
+    let d = (p0 - p1) * 0.5;
+    let x1 = cos * d.x + sin * d.y;
+    let y1 = -sin * d.x + cos * d.y;
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1. {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if is_large_arc == is_sweep { -1. } else { 1. };
+    let coeff = sign * (num / den).max(0.).sqrt();
+    let cp = Vec2::new(coeff * rx * y1 / ry, -coeff * ry * x1 / rx);
+    let center = rot(cp) + (p0 + p1) * 0.5;
+    let angle = |u: Vec2, v: Vec2| (u.x * v.y - u.y * v.x).atan2(u.dot(&v));
+    let u = Vec2::new((x1 - cp.x) / rx, (y1 - cp.y) / ry);
+    let v = Vec2::new((-x1 - cp.x) / rx, (-y1 - cp.y) / ry);
+    let theta0 = angle(Vec2::new(1., 0.), u);
+    let mut dtheta = angle(u, v);
+    if !is_sweep && dtheta > 0. {
+        dtheta -= 2. * std::f32::consts::PI;
+    } else if is_sweep && dtheta < 0. {
+        dtheta += 2. * std::f32::consts::PI;
     }
-    strs
+    // each piece spans at most a quarter of the ellipse
+    let num_piece = (dtheta.abs() / std::f32::consts::FRAC_PI_2 - 1.0e-3)
+        .ceil()
+        .max(1.) as usize;
+    let delta = dtheta / num_piece as f32;
+    let k = 4. / 3. * (delta / 4.).tan();
+    let pos = |t: f32| center + rot(Vec2::new(rx * t.cos(), ry * t.sin()));
+    let tangent = |t: f32| rot(Vec2::new(-rx * t.sin(), ry * t.cos()));
+    (0..num_piece)
+        .map(|i| {
+            let t0 = theta0 + delta * i as f32;
+            let t1 = t0 + delta;
+            let pe = if i + 1 == num_piece { p1 } else { pos(t1) };
+            [pos(t0) + tangent(t0) * k, pe - tangent(t1) * k, pe]
+        })
+        .collect()
 }
 
-#[allow(clippy::identity_op)]
-pub fn svg_loops_from_outline_path(
-    strs: &[String],
-) -> Vec<(Vec<nalgebra::Vector2<f32>>, Vec<usize>, bool)> {
-    type Vec3 = nalgebra::Vector2<f32>;
-    let hoge =
-        |s0: &str, s1: &str| Vec3::new(s0.parse::<f32>().unwrap(), s1.parse::<f32>().unwrap());
-    let mut loops: Vec<(Vec<Vec3>, Vec<usize>, bool)> = vec![];
-    let mut vtxl2xy: Vec<Vec3> = vec![];
-    let mut seg2vtxl: Vec<usize> = vec![0];
-    assert!(strs[0] == "M" || strs[0] == "m");
-    // assert!(strs[strs.len() - 1] == "Z" || strs[strs.len() - 1] == "z");
-    let mut pos_cur = nalgebra::Vector2::<f32>::zeros();
+/// interpret the tokens of the path data as poly-Bézier curves
+pub fn svg_loops_from_outline_path(strs: &[String]) -> anyhow::Result<Vec<PolyBezier>> {
+    let mut path = PathBuilder {
+        loops: vec![],
+        vtxl2xy: vec![],
+        seg2vtxl: vec![0],
+        pos_cur: Vec2::zeros(),
+        pos_start: Vec2::zeros(),
+    };
+    // the second control point of the previous cubic or the control point of the previous quadratic curve
+    let mut ctrl_cubic: Option<Vec2> = None;
+    let mut ctrl_quad: Option<Vec2> = None;
+    let mut cmd = 0u8;
     let mut is = 0;
-    loop {
-        if strs[is] == "M" {
-            // start absolute
-            is += 1;
-            pos_cur = hoge(&strs[is + 0], &strs[is + 1]);
-            vtxl2xy.push(pos_cur);
-            is += 2;
-        } else if strs[is] == "m" {
-            // start relative
-            is += 1;
-            pos_cur += hoge(&strs[is + 0], &strs[is + 1]);
-            vtxl2xy.push(pos_cur);
-            is += 2;
-        } else if strs[is] == "l" {
-            // line relative
-            is += 1;
-            loop {
-                seg2vtxl.push(vtxl2xy.len());
-                let p1 = pos_cur + hoge(&strs[is + 0], &strs[is + 1]);
-                vtxl2xy.push(p1);
-                pos_cur = p1;
-                is += 2;
-                if strs[is].as_bytes()[0].is_ascii_alphabetic() {
-                    break;
+    while is < strs.len() {
+        let res: anyhow::Result<()> = (|| {
+            if strs[is].as_bytes()[0].is_ascii_alphabetic() {
+                cmd = strs[is].as_bytes()[0];
+                is += 1;
+                if cmd == b'Z' || cmd == b'z' {
+                    path.close();
+                    return Ok(());
                 }
+            } else if cmd == 0 || cmd == b'Z' || cmd == b'z' {
+                return Err(anyhow::anyhow!("number without command"));
             }
-        } else if strs[is] == "L" {
-            // line absolute
-            is += 1;
-            loop {
-                seg2vtxl.push(vtxl2xy.len());
-                let p1 = hoge(&strs[is + 0], &strs[is + 1]);
-                vtxl2xy.push(p1);
-                pos_cur = p1;
-                is += 2;
-                if strs[is].as_bytes()[0].is_ascii_alphabetic() {
-                    break;
-                }
+            let num_param = match cmd.to_ascii_uppercase() {
+                b'H' | b'V' => 1,
+                b'M' | b'L' | b'T' => 2,
+                b'S' | b'Q' => 4,
+                b'C' => 6,
+                b'A' => 7,
+                _ => return Err(anyhow::anyhow!("unknown command")),
+            };
+            if is + num_param > strs.len() {
+                return Err(anyhow::anyhow!("missing parameters"));
             }
-        } else if strs[is] == "v" {
-            // vertical relative
-            seg2vtxl.push(vtxl2xy.len());
-            let p1 =
-                pos_cur + nalgebra::Vector2::<f32>::new(0., strs[is + 1].parse::<f32>().unwrap());
-            vtxl2xy.push(p1);
-            pos_cur = p1;
-            is += 2;
-        } else if strs[is] == "V" {
-            // vertical absolute
-            seg2vtxl.push(vtxl2xy.len());
-            let p1 =
-                nalgebra::Vector2::<f32>::new(pos_cur[0], strs[is + 1].parse::<f32>().unwrap());
-            vtxl2xy.push(p1);
-            pos_cur = p1;
-            is += 2;
-        } else if strs[is] == "H" {
-            // horizontal absolute
-            seg2vtxl.push(vtxl2xy.len());
-            let p1 = nalgebra::Vector2::new(strs[is + 1].parse::<f32>().unwrap(), pos_cur[1]);
-            vtxl2xy.push(p1);
-            pos_cur = p1;
-            is += 2;
-        } else if strs[is] == "h" {
-            // horizontal relative
-            seg2vtxl.push(vtxl2xy.len());
-            let dh = strs[is + 1].parse::<f32>().unwrap();
-            let p1 = pos_cur + nalgebra::Vector2::<f32>::new(dh, 0.);
-            vtxl2xy.push(p1);
-            pos_cur = p1;
-            is += 2;
-        } else if strs[is] == "q" {
-            // relative
-            is += 1;
-            loop {
-                // loop for poly quadratic Bézeir curve
-                let pm0 = pos_cur + hoge(&strs[is + 0], &strs[is + 1]);
-                let p1 = pos_cur + hoge(&strs[is + 2], &strs[is + 3]);
-                vtxl2xy.push(pm0);
-                seg2vtxl.push(vtxl2xy.len());
-                vtxl2xy.push(p1);
-                pos_cur = p1;
-                is += 4;
-                if is == strs.len() {
-                    loops.push((vtxl2xy.clone(), seg2vtxl.clone(), false));
-                    break;
+            let mut v = [0f32; 7];
+            for i in 0..num_param {
+                v[i] = strs[is + i]
+                    .parse::<f32>()
+                    .with_context(|| format!("cannot parse \"{}\"", strs[is + i]))?;
+            }
+            is += num_param;
+            let is_relative = cmd.is_ascii_lowercase();
+            let origin = if is_relative {
+                path.pos_cur
+            } else {
+                Vec2::zeros()
+            };
+            let p = |i: usize| origin + Vec2::new(v[i], v[i + 1]);
+            let reflect = |c: Option<Vec2>| c.map_or(path.pos_cur, |c| path.pos_cur * 2. - c);
+            let (mut next_cubic, mut next_quad) = (None, None);
+            match cmd.to_ascii_uppercase() {
+                b'M' => {
+                    path.move_to(p(0));
+                    // the following pairs are the implicit line-to
+                    cmd = if is_relative { b'l' } else { b'L' };
                 }
-                if strs[is].as_bytes()[0].is_ascii_alphabetic() {
-                    break;
+                b'L' => path.segment_to(&[], p(0)),
+                b'H' => {
+                    let x = if is_relative {
+                        path.pos_cur.x + v[0]
+                    } else {
+                        v[0]
+                    };
+                    path.segment_to(&[], Vec2::new(x, path.pos_cur.y));
                 }
-            }
-        } else if strs[is] == "Q" {
-            // absolute
-            is += 1;
-            loop {
-                // loop for poly-Bezeir curve
-                let pm0 = hoge(&strs[is + 0], &strs[is + 1]);
-                let p1 = hoge(&strs[is + 2], &strs[is + 3]);
-                vtxl2xy.push(pm0);
-                seg2vtxl.push(vtxl2xy.len());
-                vtxl2xy.push(p1);
-                pos_cur = p1;
-                is += 4;
-                if strs[is].as_bytes()[0].is_ascii_alphabetic() {
-                    break;
+                b'V' => {
+                    let y = if is_relative {
+                        path.pos_cur.y + v[0]
+                    } else {
+                        v[0]
+                    };
+                    path.segment_to(&[], Vec2::new(path.pos_cur.x, y));
                 }
-            }
-        } else if strs[is] == "c" {
-            // relative
-            is += 1;
-            loop {
-                // loop for poly cubic Bézeir curve
-                let pm0 = pos_cur + hoge(&strs[is + 0], &strs[is + 1]);
-                let pm1 = pos_cur + hoge(&strs[is + 2], &strs[is + 3]);
-                let p1 = pos_cur + hoge(&strs[is + 4], &strs[is + 5]);
-                vtxl2xy.push(pm0);
-                vtxl2xy.push(pm1);
-                seg2vtxl.push(vtxl2xy.len());
-                vtxl2xy.push(p1);
-                pos_cur = p1;
-                is += 6;
-                if is == strs.len() {
-                    loops.push((vtxl2xy.clone(), seg2vtxl.clone(), false));
-                    break;
+                b'C' => {
+                    next_cubic = Some(p(2));
+                    path.segment_to(&[p(0), p(2)], p(4));
+                }
+                b'S' => {
+                    let c0 = reflect(ctrl_cubic);
+                    next_cubic = Some(p(0));
+                    path.segment_to(&[c0, p(0)], p(2));
+                }
+                b'Q' => {
+                    next_quad = Some(p(0));
+                    path.segment_to(&[p(0)], p(2));
                 }
-                if strs[is].as_bytes()[0].is_ascii_alphabetic() {
-                    break;
+                b'T' => {
+                    let c = reflect(ctrl_quad);
+                    next_quad = Some(c);
+                    path.segment_to(&[c], p(0));
+                }
+                _ => {
+                    let p0 = path.pos_cur;
+                    let radius = Vec2::new(v[0], v[1]);
+                    let pieces =
+                        arc_to_cubic_beziers(p0, radius, v[2], v[3] != 0., v[4] != 0., p(5));
+                    for [c0, c1, pe] in pieces {
+                        path.segment_to(&[c0, c1], pe);
+                    }
                 }
             }
-        } else if strs[is] == "z" || strs[is] == "Z" {
-            let pe = vtxl2xy[0];
-            let ps = vtxl2xy[vtxl2xy.len() - 1];
-            let _dist0 = (ps - pe).norm();
-            loops.push((vtxl2xy.clone(), seg2vtxl.clone(), true));
-            vtxl2xy.clear();
-            seg2vtxl = vec![0];
-            is += 1;
-        } else {
-            dbg!("error!--> ", &strs[is]);
-            break;
-        }
-        if is == strs.len() {
-            break;
-        }
+            ctrl_cubic = next_cubic;
+            ctrl_quad = next_quad;
+            Ok(())
+        })();
+        res.with_context(|| format!("path command '{}' at token {}", cmd as char, is))?;
     }
-    loops
+    path.flush(false);
+    Ok(path.loops)
 }
 
 pub fn polybezier2polyloop(
@@ -208,6 +341,19 @@ pub fn polybezier2polyloop(
     is_close: bool,
     edge_length: f32,
 ) -> Vec<nalgebra::Vector2<f32>> {
+    // a line shorter than `edge_length` still keeps its start point
+    let line = |ret: &mut Vec<Vec2>, ps: &Vec2, pe: &Vec2| {
+        let len = (pe - ps).norm();
+        if len == 0. {
+            return;
+        }
+        let ndiv = ((len / edge_length) as usize).max(1);
+        for i in 0..ndiv {
+            let r = i as f32 / ndiv as f32;
+            let p = ps.scale(1f32 - r) + pe.scale(r);
+            ret.push(p);
+        }
+    };
     let mut ret: Vec<nalgebra::Vector2<f32>> = vec![];
     let num_seg = seg2vtx.len() - 1;
     for i_seg in 0..num_seg {
@@ -215,13 +361,7 @@ pub fn polybezier2polyloop(
         let ps = &vtx2xy[is_vtx];
         let pe = &vtx2xy[ie_vtx];
         if ie_vtx - is_vtx == 1 {
-            let len = (pe - ps).norm();
-            let ndiv = (len / edge_length) as usize;
-            for i in 0..ndiv {
-                let r = i as f32 / ndiv as f32;
-                let p = ps.scale(1f32 - r) + pe.scale(r);
-                ret.push(p);
-            }
+            line(&mut ret, ps, pe);
         } else if ie_vtx - is_vtx == 2 {
             // quadratic bezier
             let pc = &vtx2xy[is_vtx + 1];
@@ -251,19 +391,307 @@ pub fn polybezier2polyloop(
         }
     }
     if is_close {
-        let ps = &vtx2xy[vtx2xy.len() - 1];
-        let pe = &vtx2xy[0];
-        let len = (pe - ps).norm();
-        let ndiv = (len / edge_length) as usize;
-        for i in 0..ndiv {
-            let r = i as f32 / ndiv as f32;
-            let p = ps.scale(1f32 - r) + pe.scale(r);
-            ret.push(p);
-        }
+        line(&mut ret, &vtx2xy[vtx2xy.len() - 1], &vtx2xy[0]);
+    } else if let Some(p) = vtx2xy.last() {
+        ret.push(*p);
     }
     ret
 }
 
+// ---------------------------
+// document
+
+/// affine transformation `[a, b, c, d, e, f]` as in `matrix(a b c d e f)`
+type Affine = [f32; 6];
+
+const IDENTITY: Affine = [1., 0., 0., 1., 0., 0.];
+
+/// `m0 * m1`, i.e., `m1` is applied first
+fn mult_affine(m0: &Affine, m1: &Affine) -> Affine {
+    [
+        m0[0] * m1[0] + m0[2] * m1[1],
+        m0[1] * m1[0] + m0[3] * m1[1],
+        m0[0] * m1[2] + m0[2] * m1[3],
+        m0[1] * m1[2] + m0[3] * m1[3],
+        m0[0] * m1[4] + m0[2] * m1[5] + m0[4],
+        m0[1] * m1[4] + m0[3] * m1[5] + m0[5],
+    ]
+}
+
+fn transform_point(m: &Affine, p: &Vec2) -> Vec2 {
+    Vec2::new(
+        m[0] * p.x + m[2] * p.y + m[4],
+        m[1] * p.x + m[3] * p.y + m[5],
+    )
+}
+
+/// parse `transform` attribute such as `translate(10,20) rotate(30)`
+fn parse_transform(s: &str) -> anyhow::Result<Affine> {
+    let mut m = IDENTITY;
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        let (name, args) = rest
+            .split_once('(')
+            .with_context(|| format!("invalid transform \"{}\"", s))?;
+        let (args, tail) = args
+            .split_once(')')
+            .with_context(|| format!("invalid transform \"{}\"", s))?;
+        let v = parse_numbers(args)?;
+        let arg = |i: usize| v.get(i).copied();
+        let name = name.trim_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        let m1 = match (name, v.len()) {
+            ("matrix", 6) => [v[0], v[1], v[2], v[3], v[4], v[5]],
+            ("translate", 1 | 2) => [1., 0., 0., 1., v[0], arg(1).unwrap_or(0.)],
+            ("scale", 1 | 2) => [v[0], 0., 0., arg(1).unwrap_or(v[0]), 0., 0.],
+            ("rotate", 1 | 3) => {
+                let (sin, cos) = v[0].to_radians().sin_cos();
+                let r = [cos, sin, -sin, cos, 0., 0.];
+                let (cx, cy) = (arg(1).unwrap_or(0.), arg(2).unwrap_or(0.));
+                let m = mult_affine(&[1., 0., 0., 1., cx, cy], &r);
+                mult_affine(&m, &[1., 0., 0., 1., -cx, -cy])
+            }
+            ("skewX", 1) => [1., 0., v[0].to_radians().tan(), 1., 0., 0.],
+            ("skewY", 1) => [1., v[0].to_radians().tan(), 0., 1., 0., 0.],
+            _ => return Err(anyhow::anyhow!("invalid transform \"{}\"", s)),
+        };
+        m = mult_affine(&m, &m1);
+        rest = tail.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+    }
+    Ok(m)
+}
+
+/// start or end tag of an XML element
+struct XmlTag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    is_end: bool,
+    is_self_closing: bool,
+}
+
+impl XmlTag {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.0 == name)
+            .map(|a| a.1.as_str())
+    }
+
+    /// attribute as a length. The unit `px` is ignored
+    fn length(&self, name: &str) -> anyhow::Result<f32> {
+        let Some(s) = self.attribute(name) else {
+            return Ok(0.);
+        };
+        let s = s.trim().trim_end_matches("px");
+        s.parse::<f32>()
+            .with_context(|| format!("cannot parse {}=\"{}\" of <{}>", name, s, self.name))
+    }
+}
+
+/// tags in the document. Comments, processing instructions and CDATA are skipped
+fn xml_tags(doc: &str) -> anyhow::Result<Vec<XmlTag>> {
+    let mut tags = vec![];
+    let mut rest = doc;
+    while let Some(i) = rest.find('<') {
+        rest = &rest[i..];
+        let skip_to = |rest: &str, end: &str| -> anyhow::Result<usize> {
+            rest.find(end)
+                .map(|i| i + end.len())
+                .with_context(|| format!("\"{}\" is not found", end))
+        };
+        if rest.starts_with("<!--") {
+            rest = &rest[skip_to(rest, "-->")?..];
+            continue;
+        }
+        if rest.starts_with("<![CDATA[") {
+            rest = &rest[skip_to(rest, "]]>")?..];
+            continue;
+        }
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest = &rest[skip_to(rest, ">")?..];
+            continue;
+        }
+        let is_end = rest.starts_with("</");
+        rest = &rest[if is_end { 2 } else { 1 }..];
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+            .context("unterminated tag")?;
+        let mut tag = XmlTag {
+            name: rest[..name_end].to_string(),
+            attributes: vec![],
+            is_end,
+            is_self_closing: false,
+        };
+        rest = &rest[name_end..];
+        loop {
+            rest = rest.trim_start();
+            if let Some(r) = rest.strip_prefix("/>") {
+                tag.is_self_closing = true;
+                rest = r;
+                break;
+            }
+            if let Some(r) = rest.strip_prefix('>') {
+                rest = r;
+                break;
+            }
+            let (name, r) = rest
+                .split_once('=')
+                .with_context(|| format!("invalid attribute in <{}>", tag.name))?;
+            let r = r.trim_start();
+            let quote = r.chars().next().context("unterminated tag")?;
+            if quote != '"' && quote != '\'' {
+                return Err(anyhow::anyhow!("unquoted attribute in <{}>", tag.name));
+            }
+            let (value, r) = r[1..]
+                .split_once(quote)
+                .with_context(|| format!("unterminated attribute in <{}>", tag.name))?;
+            tag.attributes
+                .push((name.trim().to_string(), value.to_string()));
+            rest = r;
+        }
+        tags.push(tag);
+    }
+    Ok(tags)
+}
+
+/// path data of the basic shapes
+fn path_data_of_shape(tag: &XmlTag) -> anyhow::Result<Option<String>> {
+    let path = match tag.name.as_str() {
+        "path" => tag.attribute("d").unwrap_or("").to_string(),
+        "polygon" | "polyline" => {
+            let v = parse_numbers(tag.attribute("points").unwrap_or(""))?;
+            let mut d = String::new();
+            for (i, xy) in v.chunks_exact(2).enumerate() {
+                d += &format!("{} {} {} ", if i == 0 { "M" } else { "L" }, xy[0], xy[1]);
+            }
+            if tag.name == "polygon" {
+                d += "Z";
+            }
+            d
+        }
+        "rect" => {
+            let (x, y) = (tag.length("x")?, tag.length("y")?);
+            let (w, h) = (tag.length("width")?, tag.length("height")?);
+            // a missing radius is the same as the other
+            let (rx, ry) = match (tag.attribute("rx"), tag.attribute("ry")) {
+                (None, None) => (0., 0.),
+                (Some(_), None) => (tag.length("rx")?, tag.length("rx")?),
+                (None, Some(_)) => (tag.length("ry")?, tag.length("ry")?),
+                _ => (tag.length("rx")?, tag.length("ry")?),
+            };
+            let (rx, ry) = (rx.min(w * 0.5), ry.min(h * 0.5));
+            format!(
+                "M {} {} H {} A {rx} {ry} 0 0 1 {} {} V {} A {rx} {ry} 0 0 1 {} {} H {} \
+                 A {rx} {ry} 0 0 1 {} {} V {} A {rx} {ry} 0 0 1 {} {} Z",
+                x + rx,
+                y,
+                x + w - rx,
+                x + w,
+                y + ry,
+                y + h - ry,
+                x + w - rx,
+                y + h,
+                x + rx,
+                x,
+                y + h - ry,
+                y + ry,
+                x + rx,
+                y,
+            )
+        }
+        "circle" | "ellipse" => {
+            let (cx, cy) = (tag.length("cx")?, tag.length("cy")?);
+            let (rx, ry) = if tag.name == "circle" {
+                (tag.length("r")?, tag.length("r")?)
+            } else {
+                (tag.length("rx")?, tag.length("ry")?)
+            };
+            format!(
+                "M {} {cy} A {rx} {ry} 0 0 1 {cx} {} A {rx} {ry} 0 0 1 {} {cy} \
+                 A {rx} {ry} 0 0 1 {cx} {} A {rx} {ry} 0 0 1 {} {cy} Z",
+                cx + rx,
+                cy + ry,
+                cx - rx,
+                cy - ry,
+                cx + rx,
+            )
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(path))
+}
+
+/// poly-Bézier curves of `<path>`, `<polygon>`, `<polyline>`, `<rect>`, `<circle>` and `<ellipse>`
+/// in the document with the `transform` of the ancestors applied.
+/// The elements in `<defs>`, `<clipPath>`, `<mask>`, `<pattern>` and `<symbol>` are skipped
+pub fn polybeziers_from_svg_document(doc: &str) -> anyhow::Result<Vec<PolyBezier>> {
+    let mut stack = vec![IDENTITY];
+    let mut depth_skip = 0;
+    let mut loops = vec![];
+    for tag in xml_tags(doc)? {
+        let is_skipped = matches!(
+            tag.name.as_str(),
+            "defs" | "clipPath" | "mask" | "pattern" | "symbol"
+        );
+        if tag.is_end {
+            if stack.len() > 1 {
+                stack.pop();
+            }
+            if is_skipped {
+                depth_skip -= 1;
+            }
+            continue;
+        }
+        let transform = match tag.attribute("transform") {
+            Some(s) => parse_transform(s)?,
+            None => IDENTITY,
+        };
+        let m = mult_affine(stack.last().unwrap(), &transform);
+        if !tag.is_self_closing {
+            stack.push(m);
+            if is_skipped {
+                depth_skip += 1;
+            }
+        }
+        if depth_skip > 0 || is_skipped {
+            continue;
+        }
+        let Some(path) = path_data_of_shape(&tag)? else {
+            continue;
+        };
+        let strs = svg_outline_path_from_shape(&path).with_context(|| format!("<{}>", tag.name))?;
+        let shape =
+            svg_loops_from_outline_path(&strs).with_context(|| format!("<{}>", tag.name))?;
+        for (vtx2xy, seg2vtx, is_close) in shape {
+            let vtx2xy = vtx2xy.iter().map(|p| transform_point(&m, p)).collect();
+            loops.push((vtx2xy, seg2vtx, is_close));
+        }
+    }
+    Ok(loops)
+}
+
+/// closed curves in the document as flat polyloops (e.g., for `trimesh2_dynamic::meshing_from_polyloop2`).
+/// Note that the y-axis of SVG points downward
+pub fn polyloops_from_svg_document(doc: &str, edge_length: f32) -> anyhow::Result<Vec<Vec<f32>>> {
+    let loops = polybeziers_from_svg_document(doc)?
+        .iter()
+        .filter(|polybezier| polybezier.2)
+        .map(|(vtx2xy, seg2vtx, is_close)| {
+            let vtxl2xy = polybezier2polyloop(vtx2xy, seg2vtx, *is_close, edge_length);
+            crate::vtx2xdim::from_array_of_nalgebra(&vtxl2xy)
+        })
+        .collect();
+    Ok(loops)
+}
+
+pub fn load_polyloops<P: AsRef<std::path::Path>>(
+    path: P,
+    edge_length: f32,
+) -> anyhow::Result<Vec<Vec<f32>>> {
+    let doc = std::fs::read_to_string(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    polyloops_from_svg_document(&doc, edge_length)
+}
+
 #[test]
 fn hoge() {
     let str2 = "M 457.60409,474.77081 H 347.66161 L 208.25942,282.21963 \
@@ -279,8 +707,9 @@ fn hoge() {
     M 165.13281,263.08599 q 5.77046,0 10.02238,0.30371 4.25192,0 6.9853,0 \
     58.91944,0 88.68288,-25.51151 30.06714,-25.51152 30.06714,-64.99362 0,-38.57098 \
     -24.29668,-62.56395 -23.99297,-24.296679 -63.77879,-24.296679 -17.61509,0 -47.68223,5.770461 z";
-    let strs = svg_outline_path_from_shape(str2);
-    let loops = svg_loops_from_outline_path(&strs);
+    let strs = svg_outline_path_from_shape(str2).unwrap();
+    let loops = svg_loops_from_outline_path(&strs).unwrap();
+    assert_eq!(loops.len(), 2);
     // dbg!(&loops[0].0);
     for polybezier in loops {
         polybezier2polyloop(&polybezier.0, &polybezier.1, polybezier.2, 5.0);
@@ -314,8 +743,8 @@ fn hoge2() {
     -1298 629 -116 29 -289 57 -507 82 -229 26 -799 26 -1000 0 -265 -35 -499 -87 \
     -714 -159 l-124 -42 -35 44 c-75 95 -259 267 -350 328 -157 105 -323 175 -500 \
     212 -114 24 -350 34 -460 19z";
-    let strs = svg_outline_path_from_shape(str0);
-    let loops = svg_loops_from_outline_path(&strs);
+    let strs = svg_outline_path_from_shape(str0).unwrap();
+    let loops = svg_loops_from_outline_path(&strs).unwrap();
     assert_eq!(loops.len(), 1);
     let polyline = polybezier2polyloop(&loops[0].0, &loops[0].1, loops[0].2, 10.0);
     let polyline = crate::vtx2xdim::from_array_of_nalgebra(&polyline);
@@ -339,12 +768,83 @@ fn hoge3() {
     27 167 24 487 -6 660 -33 189 -64 249 -150 289 -46 21 -51 21 -846 21 -440 0 \
     -828 -3 -861 -7 l-62 -7 -32 86 c-54 143 -194 412 -289 554 -479 720 -1201 \
     1178 -2040 1295 -101 14 -496 27 -571 18z";
-    let outline_path = svg_outline_path_from_shape(str_path);
+    let outline_path = svg_outline_path_from_shape(str_path).unwrap();
     // dbg!(&outline_path);
-    let loops = svg_loops_from_outline_path(&outline_path);
+    let loops = svg_loops_from_outline_path(&outline_path).unwrap();
     let vtxl2xy = polybezier2polyloop(&loops[0].0, &loops[0].1, loops[0].2, 600.);
     let vtxl2xy =
         crate::vtx2vec::normalize2(&vtxl2xy, &nalgebra::Vector2::<f32>::new(0.5, 0.5), 1.0);
     crate::io_obj::save_vtx2vecn_as_polyloop("../target/duck_curve.obj", &vtxl2xy).unwrap();
     crate::vtx2xdim::from_array_of_nalgebra(&vtxl2xy);
 }
+
+#[test]
+fn test_path_grammar() -> anyhow::Result<()> {
+    assert_eq!(
+        svg_outline_path_from_shape("M.5.5l1e-3-2E+1a25,25 -30 0150-25z")?,
+        vec![
+            "M", ".5", ".5", "l", "1e-3", "-2E+1", "a", "25", "25", "-30", "0", "1", "50", "-25",
+            "z"
+        ]
+    );
+    assert!(svg_outline_path_from_shape("M 0 0 L 1 x").is_err());
+    // smooth curves reflect the previous control point
+    let strs = svg_outline_path_from_shape(
+        "M0 0 C 0 1 1 1 1 0 S 2 -1 2 0 Q 2.5 1 3 0 T 4 0 H 5 V 1 h -5 z",
+    )?;
+    let loops = svg_loops_from_outline_path(&strs)?;
+    assert_eq!(loops.len(), 1);
+    let (vtx2xy, seg2vtx, is_close) = &loops[0];
+    assert!(is_close);
+    assert_eq!(seg2vtx, &vec![0, 3, 6, 8, 10, 11, 12, 13]);
+    assert_eq!(vtx2xy[4], Vec2::new(1., -1.));
+    assert_eq!(vtx2xy[9], Vec2::new(3.5, -1.));
+    assert_eq!(vtx2xy[13], Vec2::new(0., 1.));
+    // a circle made of two arcs
+    let strs = svg_outline_path_from_shape("M 3 2 a 2 2 0 1 1 -4 0 A 2 2 0 1 1 3 2 Z")?;
+    let loops = svg_loops_from_outline_path(&strs)?;
+    assert_eq!(loops[0].1.len(), 5);
+    let polyloop = polybezier2polyloop(&loops[0].0, &loops[0].1, loops[0].2, 0.1);
+    for p in polyloop.iter() {
+        assert!(((p - Vec2::new(1., 2.)).norm() - 2.).abs() < 2.0e-3);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_document() -> anyhow::Result<()> {
+    let doc = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- comment with <rect> -->
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100">
+  <defs><rect x="0" y="0" width="1" height="1"/></defs>
+  <g transform="translate(10, 20) scale(2)">
+    <rect x="0" y="0" width="10" height="5" rx="1"/>
+    <g transform="rotate(90)">
+      <polygon points="0,0 1,0 1,1"/>
+    </g>
+    <circle cx="5" cy="5" r="2"/>
+  </g>
+  <polyline points="0 0 5 5 10 0"/>
+  <path d="M 0 0 L 10 0 L 10 10 Z M 2 2 L 8 2 L 8 8 z"/>
+</svg>"#;
+    let loops = polybeziers_from_svg_document(doc)?;
+    assert_eq!(loops.len(), 6);
+    // rounded rectangle: four lines and four arcs
+    assert_eq!(loops[0].1.len(), 9);
+    assert_eq!(loops[0].0[0], Vec2::new(12., 20.));
+    // rotated and transformed polygon
+    let p = loops[1].0[1];
+    assert!((p - Vec2::new(10., 22.)).norm() < 1.0e-5);
+    assert!(!loops[3].2);
+    //
+    let polyloops = polyloops_from_svg_document(doc, 0.5)?;
+    assert_eq!(polyloops.len(), 5);
+    for p in polyloops[2].chunks(2) {
+        let d = Vec2::new(p[0], p[1]) - Vec2::new(20., 30.);
+        assert!((d.norm() - 4.).abs() < 1.0e-2);
+    }
+    let (tri2vtx, vtx2xy) =
+        crate::trimesh2_dynamic::meshing_from_polyloop2::<usize, f32>(&polyloops[0], 0.5, 0.5);
+    assert!(!tri2vtx.is_empty() && !vtx2xy.is_empty());
+    Ok(())
+}
@@ -1,24 +1,74 @@
 //! method for VTK files
 
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum VtkElementType {
+    VERTEX = 1,
+    POLY_VERTEX = 2,
+    LINE = 3,
+    POLY_LINE = 4,
     TRIANGLE = 5,
+    TRIANGLE_STRIP = 6,
+    POLYGON = 7,
+    PIXEL = 8,
     QUAD = 9,
     TETRA = 10,
+    VOXEL = 11,
     HEXAHEDRON = 12,
-    WEDGE,
-    PYRAMID,
+    WEDGE = 13,
+    PYRAMID = 14,
 }
 
-pub fn write_vtk_points<T>(
-    file: &mut std::fs::File,
+impl VtkElementType {
+    /// element type from the VTK cell type id
+    pub fn from_id(id: usize) -> Option<Self> {
+        use VtkElementType::*;
+        let elem_type = match id {
+            1 => VERTEX,
+            2 => POLY_VERTEX,
+            3 => LINE,
+            4 => POLY_LINE,
+            5 => TRIANGLE,
+            6 => TRIANGLE_STRIP,
+            7 => POLYGON,
+            8 => PIXEL,
+            9 => QUAD,
+            10 => TETRA,
+            11 => VOXEL,
+            12 => HEXAHEDRON,
+            13 => WEDGE,
+            14 => PYRAMID,
+            _ => return None,
+        };
+        Some(elem_type)
+    }
+
+    /// number of nodes of the element. `None` for the types with variable number of nodes
+    pub fn num_node(&self) -> Option<usize> {
+        use VtkElementType::*;
+        match self {
+            VERTEX => Some(1),
+            LINE => Some(2),
+            TRIANGLE => Some(3),
+            PIXEL | QUAD | TETRA => Some(4),
+            PYRAMID => Some(5),
+            WEDGE => Some(6),
+            VOXEL | HEXAHEDRON => Some(8),
+            POLY_VERTEX | POLY_LINE | TRIANGLE_STRIP | POLYGON => None,
+        }
+    }
+}
+
+pub fn write_vtk_points<W, T>(
+    file: &mut W,
     name: &str,
     vtx2xyz: &[T],
     ndim: usize,
 ) -> std::io::Result<()>
 where
+    W: std::io::Write,
     T: std::fmt::Display,
 {
-    use std::io::Write;
     let np = vtx2xyz.len() / ndim;
     writeln!(file, "# vtk DataFile Version 2.0")?;
     writeln!(file, "{}", name)?;
@@ -39,22 +89,15 @@ where
     Ok(())
 }
 
-pub fn write_vtk_cells(
-    file: &mut std::fs::File,
+pub fn write_vtk_cells<W: std::io::Write>(
+    file: &mut W,
     vtk_elem_type: VtkElementType,
     elem2vtx: &[usize],
 ) -> std::io::Result<()> {
-    let num_node = match vtk_elem_type {
-        VtkElementType::TRIANGLE => 3,
-        VtkElementType::QUAD => 4,
-        VtkElementType::TETRA => 4,
-        VtkElementType::HEXAHEDRON => 8,
-        _ => {
-            panic!();
-        }
+    let Some(num_node) = vtk_elem_type.num_node() else {
+        panic!("element type with variable number of nodes");
     };
     let nelem = elem2vtx.len() / num_node;
-    use std::io::Write;
     writeln!(file, "CELLS {} {}", nelem, nelem * (num_node + 1))?;
     for av in elem2vtx.chunks(num_node) {
         write!(file, "{}", num_node)?;
@@ -73,16 +116,16 @@ pub fn write_vtk_cells(
     Ok(())
 }
 
-pub fn write_vtk_data_point_scalar<T>(
-    file: &mut std::fs::File,
+pub fn write_vtk_data_point_scalar<W, T>(
+    file: &mut W,
     vtx2data: &[T],
     num_vtx: usize,
     num_stride: usize,
 ) -> std::io::Result<()>
 where
+    W: std::io::Write,
     T: std::fmt::Display,
 {
-    use std::io::Write;
     writeln!(file, "SCALARS pointvalue float 1")?;
     writeln!(file, "LOOKUP_TABLE default")?;
     for ip in 0..num_vtx {
@@ -91,9 +134,936 @@ where
     Ok(())
 }
 
+/// cells of the mixed types. The nodes of the `i_elem`-th cell are
+/// `idx2vtx[elem2idx[i_elem]..elem2idx[i_elem+1]]`
+pub fn write_vtk_cells_mixed<W: std::io::Write>(
+    file: &mut W,
+    elem2idx: &[usize],
+    idx2vtx: &[usize],
+    elem2type: &[VtkElementType],
+) -> std::io::Result<()> {
+    let nelem = elem2type.len();
+    assert_eq!(elem2idx.len(), nelem + 1);
+    writeln!(file, "CELLS {} {}", nelem, nelem + elem2idx[nelem])?;
+    for idx in elem2idx.windows(2) {
+        write!(file, "{}", idx[1] - idx[0])?;
+        for v in &idx2vtx[idx[0]..idx[1]] {
+            write!(file, " {}", v)?;
+        }
+        writeln!(file)?;
+    }
+    writeln!(file, "CELL_TYPES {}", nelem)?;
+    for &elem_type in elem2type {
+        writeln!(file, "{}", elem_type as usize)?;
+    }
+    Ok(())
+}
+
+/// attribute of the points with `num_comp` values per point. `POINT_DATA` needs to be written
+/// before the first attribute. The colors of `ColorScalars` are in [0,1]
+pub fn write_vtk_data_point_attribute<W, T>(
+    file: &mut W,
+    attribute: VtkAttribute,
+    name: &str,
+    vtx2data: &[T],
+    num_comp: usize,
+) -> std::io::Result<()>
+where
+    W: std::io::Write,
+    T: std::fmt::Display,
+{
+    match attribute {
+        VtkAttribute::Scalars => {
+            writeln!(file, "SCALARS {} float {}", name, num_comp)?;
+            writeln!(file, "LOOKUP_TABLE default")?;
+        }
+        VtkAttribute::ColorScalars => writeln!(file, "COLOR_SCALARS {} {}", name, num_comp)?,
+        VtkAttribute::Vectors => writeln!(file, "VECTORS {} float", name)?,
+        VtkAttribute::Normals => writeln!(file, "NORMALS {} float", name)?,
+        VtkAttribute::TextureCoordinates => {
+            writeln!(file, "TEXTURE_COORDINATES {} {} float", name, num_comp)?
+        }
+        VtkAttribute::Tensors => writeln!(file, "TENSORS {} float", name)?,
+        VtkAttribute::Field => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "FIELD is not supported",
+            ))
+        }
+    }
+    for vals in vtx2data.chunks(num_comp) {
+        for (i_comp, v) in vals.iter().enumerate() {
+            if i_comp != 0 {
+                write!(file, " ")?;
+            }
+            write!(file, "{}", v)?;
+        }
+        writeln!(file)?;
+    }
+    Ok(())
+}
+
+// ------------------------------
+// below: VTK XML writer
+
+/// encoding of the appended data of the VTK XML files
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VtkXmlEncoding {
+    Raw,
+    Base64,
+}
+
+/// named array for the VTK XML writers.
+/// `num_comp` is one for scalars, three for vectors and nine for tensors
+pub struct VtkXmlArray<'a, Real> {
+    pub name: &'a str,
+    pub num_comp: usize,
+    pub values: &'a [Real],
+}
+
+/// mesh with mixed element types and the data on its points and cells
+pub struct VtkXmlPiece<'a, Index, Real> {
+    /// three dimensional coordinates
+    pub vtx2xyz: &'a [Real],
+    pub elem2idx: &'a [Index],
+    pub idx2vtx: &'a [Index],
+    pub elem2type: &'a [VtkElementType],
+    pub point_data: Vec<VtkXmlArray<'a, Real>>,
+    pub cell_data: Vec<VtkXmlArray<'a, Real>>,
+}
+
+pub(crate) fn encode_base64(bytes: &[u8]) -> Vec<u8> {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut res = Vec::<u8>::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                res.push(TABLE[(n >> (18 - 6 * i) & 63) as usize]);
+            } else {
+                res.push(b'=');
+            }
+        }
+    }
+    res
+}
+
+/// binary blocks of `<AppendedData>`. Each block is the byte count (UInt64) followed by the data
+struct AppendedData {
+    encoding: VtkXmlEncoding,
+    buf: Vec<u8>,
+}
+
+impl AppendedData {
+    /// append the block and return its offset
+    fn push(&mut self, data: &[u8]) -> usize {
+        let offset = self.buf.len();
+        let header = (data.len() as u64).to_le_bytes();
+        match self.encoding {
+            VtkXmlEncoding::Raw => {
+                self.buf.extend_from_slice(&header);
+                self.buf.extend_from_slice(data);
+            }
+            VtkXmlEncoding::Base64 => {
+                // header and data are encoded separately as VTK does
+                self.buf.extend(encode_base64(&header));
+                self.buf.extend(encode_base64(data));
+            }
+        }
+        offset
+    }
+}
+
+fn real_bytes<Real>(vals: &[Real]) -> (&'static str, Vec<u8>)
+where
+    Real: num_traits::AsPrimitive<f32> + num_traits::AsPrimitive<f64>,
+{
+    use num_traits::AsPrimitive;
+    if std::mem::size_of::<Real>() == 4 {
+        let bytes = vals
+            .iter()
+            .flat_map(|&v| AsPrimitive::<f32>::as_(v).to_le_bytes())
+            .collect();
+        ("Float32", bytes)
+    } else {
+        let bytes = vals
+            .iter()
+            .flat_map(|&v| AsPrimitive::<f64>::as_(v).to_le_bytes())
+            .collect();
+        ("Float64", bytes)
+    }
+}
+
+fn write_data_array_tag<W: std::io::Write>(
+    writer: &mut W,
+    data_type: &str,
+    name: &str,
+    num_comp: usize,
+    offset: usize,
+) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "        <DataArray type=\"{}\" Name=\"{}\" NumberOfComponents=\"{}\" format=\"appended\" offset=\"{}\"/>",
+        data_type, name, num_comp, offset
+    )
+}
+
+impl<'a, Index, Real> VtkXmlPiece<'a, Index, Real>
+where
+    Index: num_traits::PrimInt + num_traits::AsPrimitive<usize>,
+    Real: num_traits::AsPrimitive<f32> + num_traits::AsPrimitive<f64>,
+{
+    pub fn new(
+        vtx2xyz: &'a [Real],
+        elem2idx: &'a [Index],
+        idx2vtx: &'a [Index],
+        elem2type: &'a [VtkElementType],
+    ) -> Self {
+        VtkXmlPiece {
+            vtx2xyz,
+            elem2idx,
+            idx2vtx,
+            elem2type,
+            point_data: vec![],
+            cell_data: vec![],
+        }
+    }
+
+    fn num_vtx(&self) -> usize {
+        self.vtx2xyz.len() / 3
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        let num_elem = self.elem2type.len();
+        if self.elem2idx.len() != num_elem + 1 {
+            return Err(anyhow::anyhow!(
+                "the sizes of elem2idx and elem2type do not match"
+            ));
+        }
+        for i_elem in 0..num_elem {
+            let num_node = self.elem2idx[i_elem + 1].as_() - self.elem2idx[i_elem].as_();
+            if let Some(n) = self.elem2type[i_elem].num_node() {
+                if n != num_node {
+                    return Err(anyhow::anyhow!(
+                        "element {} of type {:?} has {} nodes",
+                        i_elem,
+                        self.elem2type[i_elem],
+                        num_node
+                    ));
+                }
+            }
+        }
+        for (data, num) in [
+            (&self.point_data, self.num_vtx()),
+            (&self.cell_data, num_elem),
+        ] {
+            for array in data {
+                if array.values.len() != array.num_comp * num {
+                    return Err(anyhow::anyhow!(
+                        "the size of the array \"{}\" does not match",
+                        array.name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `<PointData>`, `<CellData>` and `<Points>`. The cell data is ordered by `elem2elem_out`
+    fn write_data<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        appended: &mut AppendedData,
+        elem2elem_out: &[usize],
+    ) -> anyhow::Result<()> {
+        writeln!(writer, "      <PointData>")?;
+        for array in &self.point_data {
+            let (data_type, bytes) = real_bytes(array.values);
+            let offset = appended.push(&bytes);
+            write_data_array_tag(writer, data_type, array.name, array.num_comp, offset)?;
+        }
+        writeln!(writer, "      </PointData>")?;
+        writeln!(writer, "      <CellData>")?;
+        for array in &self.cell_data {
+            let n = array.num_comp;
+            let values: Vec<Real> = elem2elem_out
+                .iter()
+                .flat_map(|&i_elem| array.values[i_elem * n..i_elem * n + n].iter().copied())
+                .collect();
+            let (data_type, bytes) = real_bytes(&values);
+            let offset = appended.push(&bytes);
+            write_data_array_tag(writer, data_type, array.name, n, offset)?;
+        }
+        writeln!(writer, "      </CellData>")?;
+        writeln!(writer, "      <Points>")?;
+        let (data_type, bytes) = real_bytes(self.vtx2xyz);
+        let offset = appended.push(&bytes);
+        write_data_array_tag(writer, data_type, "Points", 3, offset)?;
+        writeln!(writer, "      </Points>")?;
+        Ok(())
+    }
+
+    /// connectivity and offsets of the elements in `elems`
+    fn write_cells<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        appended: &mut AppendedData,
+        elems: &[usize],
+    ) -> anyhow::Result<()> {
+        let mut connectivity = Vec::<u8>::new();
+        let mut offsets = Vec::<u8>::new();
+        let mut num_idx = 0i64;
+        for &i_elem in elems {
+            let idx0 = self.elem2idx[i_elem].as_();
+            let idx1 = self.elem2idx[i_elem + 1].as_();
+            for &i_vtx in &self.idx2vtx[idx0..idx1] {
+                connectivity.extend((i_vtx.as_() as i64).to_le_bytes());
+            }
+            num_idx += (idx1 - idx0) as i64;
+            offsets.extend(num_idx.to_le_bytes());
+        }
+        let offset = appended.push(&connectivity);
+        write_data_array_tag(writer, "Int64", "connectivity", 1, offset)?;
+        let offset = appended.push(&offsets);
+        write_data_array_tag(writer, "Int64", "offsets", 1, offset)?;
+        Ok(())
+    }
+
+    fn write_appended<W: std::io::Write>(
+        writer: &mut W,
+        appended: &AppendedData,
+    ) -> anyhow::Result<()> {
+        let encoding = match appended.encoding {
+            VtkXmlEncoding::Raw => "raw",
+            VtkXmlEncoding::Base64 => "base64",
+        };
+        writeln!(writer, "  <AppendedData encoding=\"{}\">", encoding)?;
+        write!(writer, "_")?;
+        writer.write_all(&appended.buf)?;
+        writeln!(writer)?;
+        writeln!(writer, "  </AppendedData>")?;
+        writeln!(writer, "</VTKFile>")?;
+        Ok(())
+    }
+
+    /// write VTK XML UnstructuredGrid (.vtu) file
+    pub fn write_vtu<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        encoding: VtkXmlEncoding,
+    ) -> anyhow::Result<()> {
+        self.check()?;
+        let num_elem = self.elem2type.len();
+        let elems: Vec<usize> = (0..num_elem).collect();
+        let mut appended = AppendedData {
+            encoding,
+            buf: vec![],
+        };
+        writeln!(writer, "<?xml version=\"1.0\"?>")?;
+        writeln!(
+            writer,
+            "<VTKFile type=\"UnstructuredGrid\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\">"
+        )?;
+        writeln!(writer, "  <UnstructuredGrid>")?;
+        writeln!(
+            writer,
+            "    <Piece NumberOfPoints=\"{}\" NumberOfCells=\"{}\">",
+            self.num_vtx(),
+            num_elem
+        )?;
+        self.write_data(writer, &mut appended, &elems)?;
+        writeln!(writer, "      <Cells>")?;
+        self.write_cells(writer, &mut appended, &elems)?;
+        let types: Vec<u8> = self.elem2type.iter().map(|&t| t as u8).collect();
+        let offset = appended.push(&types);
+        write_data_array_tag(writer, "UInt8", "types", 1, offset)?;
+        writeln!(writer, "      </Cells>")?;
+        writeln!(writer, "    </Piece>")?;
+        writeln!(writer, "  </UnstructuredGrid>")?;
+        Self::write_appended(writer, &appended)
+    }
+
+    /// write VTK XML PolyData (.vtp) file. Only vertices, lines, polygons and triangle strips
+    /// are allowed. The cell data is reordered as the VTK orders the cells of PolyData
+    pub fn write_vtp<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        encoding: VtkXmlEncoding,
+    ) -> anyhow::Result<()> {
+        use VtkElementType::*;
+        self.check()?;
+        // elements of `Verts`, `Lines`, `Polys` and `Strips`
+        let mut sec2elems: [Vec<usize>; 4] = Default::default();
+        for (i_elem, elem_type) in self.elem2type.iter().enumerate() {
+            let i_sec = match elem_type {
+                VERTEX | POLY_VERTEX => 0,
+                LINE | POLY_LINE => 1,
+                TRIANGLE | QUAD | POLYGON => 2,
+                TRIANGLE_STRIP => 3,
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "element type {:?} is not allowed in PolyData",
+                        elem_type
+                    ))
+                }
+            };
+            sec2elems[i_sec].push(i_elem);
+        }
+        let elem2elem_out = sec2elems.concat();
+        let mut appended = AppendedData {
+            encoding,
+            buf: vec![],
+        };
+        writeln!(writer, "<?xml version=\"1.0\"?>")?;
+        writeln!(
+            writer,
+            "<VTKFile type=\"PolyData\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\">"
+        )?;
+        writeln!(writer, "  <PolyData>")?;
+        writeln!(
+            writer,
+            "    <Piece NumberOfPoints=\"{}\" NumberOfVerts=\"{}\" NumberOfLines=\"{}\" NumberOfPolys=\"{}\" NumberOfStrips=\"{}\">",
+            self.num_vtx(),
+            sec2elems[0].len(),
+            sec2elems[1].len(),
+            sec2elems[2].len(),
+            sec2elems[3].len()
+        )?;
+        self.write_data(writer, &mut appended, &elem2elem_out)?;
+        for (i_sec, tag) in ["Verts", "Lines", "Polys", "Strips"].iter().enumerate() {
+            writeln!(writer, "      <{}>", tag)?;
+            self.write_cells(writer, &mut appended, &sec2elems[i_sec])?;
+            writeln!(writer, "      </{}>", tag)?;
+        }
+        writeln!(writer, "    </Piece>")?;
+        writeln!(writer, "  </PolyData>")?;
+        Self::write_appended(writer, &appended)
+    }
+}
+
+/// write ParaView collection (.pvd) file listing the pairs of the time and the data file
+pub fn write_pvd<W: std::io::Write>(writer: &mut W, frames: &[(f64, &str)]) -> anyhow::Result<()> {
+    writeln!(writer, "<?xml version=\"1.0\"?>")?;
+    writeln!(writer, "<VTKFile type=\"Collection\" version=\"1.0\">")?;
+    writeln!(writer, "  <Collection>")?;
+    for (time, file) in frames {
+        writeln!(
+            writer,
+            "    <DataSet timestep=\"{}\" group=\"\" part=\"0\" file=\"{}\"/>",
+            time, file
+        )?;
+    }
+    writeln!(writer, "  </Collection>")?;
+    writeln!(writer, "</VTKFile>")?;
+    Ok(())
+}
+
+// ------------------------------
+// below: legacy VTK reader
+
+/// kind of the attribute in `POINT_DATA` or `CELL_DATA`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VtkAttribute {
+    Scalars,
+    ColorScalars,
+    Vectors,
+    Normals,
+    TextureCoordinates,
+    Tensors,
+    Field,
+}
+
+/// named data array of `POINT_DATA` or `CELL_DATA` with `num_comp` values per point (or cell)
+#[derive(Clone, Debug)]
+pub struct VtkDataArray<Real> {
+    pub name: String,
+    pub attribute: VtkAttribute,
+    pub num_comp: usize,
+    pub values: Vec<Real>,
+}
+
+/// points, cells and data arrays in a legacy VTK file
+#[derive(Clone, Debug)]
+pub struct VtkMesh<Index, Real> {
+    pub title: String,
+    /// coordinates of the points (always three dimensional)
+    pub vtx2xyz: Vec<Real>,
+    pub elem2idx: Vec<Index>,
+    pub idx2vtx: Vec<Index>,
+    pub elem2type: Vec<VtkElementType>,
+    pub point_data: Vec<VtkDataArray<Real>>,
+    pub cell_data: Vec<VtkDataArray<Real>>,
+}
+
+impl<Index, Real> VtkMesh<Index, Real>
+where
+    Index: num_traits::PrimInt + num_traits::AsPrimitive<usize>,
+{
+    /// flattened connectivity of the cells of the given type (e.g., `tri2vtx` for `TRIANGLE`)
+    pub fn elem2vtx_of_type(&self, elem_type: VtkElementType) -> Vec<Index> {
+        let mut elem2vtx = vec![];
+        for (i_elem, &t) in self.elem2type.iter().enumerate() {
+            if t != elem_type {
+                continue;
+            }
+            let idx0 = self.elem2idx[i_elem].as_();
+            let idx1 = self.elem2idx[i_elem + 1].as_();
+            elem2vtx.extend_from_slice(&self.idx2vtx[idx0..idx1]);
+        }
+        elem2vtx
+    }
+
+    pub fn point_data(&self, name: &str) -> Option<&VtkDataArray<Real>> {
+        self.point_data.iter().find(|a| a.name == name)
+    }
+
+    pub fn cell_data(&self, name: &str) -> Option<&VtkDataArray<Real>> {
+        self.cell_data.iter().find(|a| a.name == name)
+    }
+}
+
+/// cursor over the whole file. Keywords are read line by line and the data either as
+/// whitespace-separated tokens (ASCII) or as big-endian values (BINARY)
+struct LegacyCursor {
+    buf: Vec<u8>,
+    pos: usize,
+    is_binary: bool,
+}
+
+impl LegacyCursor {
+    fn raw_line(&mut self) -> Option<String> {
+        if self.pos >= self.buf.len() {
+            return None;
+        }
+        let end = self.buf[self.pos..]
+            .iter()
+            .position(|&c| c == b'\n')
+            .map_or(self.buf.len(), |i| self.pos + i);
+        let line = String::from_utf8_lossy(&self.buf[self.pos..end])
+            .trim()
+            .to_string();
+        self.pos = (end + 1).min(self.buf.len());
+        Some(line)
+    }
+
+    /// next non-empty line
+    fn line(&mut self) -> Option<String> {
+        loop {
+            let line = self.raw_line()?;
+            if !line.is_empty() {
+                return Some(line);
+            }
+        }
+    }
+
+    /// next non-empty line without consuming it
+    fn peek_line(&mut self) -> Option<String> {
+        let pos = self.pos;
+        let line = self.line();
+        self.pos = pos;
+        line
+    }
+
+    fn token(&mut self) -> anyhow::Result<&str> {
+        while self.pos < self.buf.len() && self.buf[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        let start = self.pos;
+        while self.pos < self.buf.len() && !self.buf[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(anyhow::anyhow!("unexpected end of file"));
+        }
+        Ok(std::str::from_utf8(&self.buf[start..self.pos])?)
+    }
+
+    /// read `num` values of the VTK data type `data_type` as f64
+    fn values(&mut self, num: usize, data_type: &str) -> anyhow::Result<Vec<f64>> {
+        if !self.is_binary {
+            // each value takes at least one byte, so the count is not trusted beyond the rest
+            let mut vals = Vec::with_capacity(num.min(self.buf.len() - self.pos));
+            for _ in 0..num {
+                let token = self.token()?;
+                let val = token
+                    .parse::<f64>()
+                    .map_err(|_| anyhow::anyhow!("cannot parse \"{}\" as a number", token))?;
+                vals.push(val);
+            }
+            return Ok(vals);
+        }
+        let size = match data_type {
+            "bit" => return Err(anyhow::anyhow!("binary \"bit\" data is not supported")),
+            "unsigned_char" | "char" => 1,
+            "unsigned_short" | "short" => 2,
+            "unsigned_int" | "int" | "float" => 4,
+            "unsigned_long" | "long" | "double" | "vtktypeint64" | "vtktypeuint64"
+            | "vtkIdType" => 8,
+            _ => return Err(anyhow::anyhow!("unknown data type \"{}\"", data_type)),
+        };
+        let Some(end) = num
+            .checked_mul(size)
+            .and_then(|n| n.checked_add(self.pos))
+            .filter(|&end| end <= self.buf.len())
+        else {
+            return Err(anyhow::anyhow!("unexpected end of binary data"));
+        };
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        let vals = bytes
+            .chunks(size)
+            .map(|b| match data_type {
+                "unsigned_char" => b[0] as f64,
+                "char" => b[0] as i8 as f64,
+                "unsigned_short" => u16::from_be_bytes([b[0], b[1]]) as f64,
+                "short" => i16::from_be_bytes([b[0], b[1]]) as f64,
+                "unsigned_int" => u32::from_be_bytes(b.try_into().unwrap()) as f64,
+                "int" => i32::from_be_bytes(b.try_into().unwrap()) as f64,
+                "float" => f32::from_be_bytes(b.try_into().unwrap()) as f64,
+                "double" => f64::from_be_bytes(b.try_into().unwrap()),
+                "unsigned_long" | "vtktypeuint64" => {
+                    u64::from_be_bytes(b.try_into().unwrap()) as f64
+                }
+                _ => i64::from_be_bytes(b.try_into().unwrap()) as f64,
+            })
+            .collect();
+        Ok(vals)
+    }
+
+    fn indices(&mut self, num: usize, data_type: &str) -> anyhow::Result<Vec<usize>> {
+        self.values(num, data_type)?
+            .into_iter()
+            .map(|v| {
+                if v < 0. {
+                    Err(anyhow::anyhow!("negative index {}", v))
+                } else {
+                    Ok(v as usize)
+                }
+            })
+            .collect()
+    }
+
+    /// cell lists in either `n i0 i1 ... n i0 ...` form or `OFFSETS`/`CONNECTIVITY` form (v5)
+    fn cell_lists(
+        &mut self,
+        num_a: usize,
+        num_b: usize,
+    ) -> anyhow::Result<(Vec<usize>, Vec<usize>)> {
+        if self.peek_line().is_some_and(|l| l.starts_with("OFFSETS")) {
+            let line = self.line().unwrap();
+            let data_type = line.split_whitespace().nth(1).unwrap_or("vtktypeint64");
+            let elem2idx = self.indices(num_a, data_type)?;
+            let line = self.line().unwrap_or_default();
+            if !line.starts_with("CONNECTIVITY") {
+                return Err(anyhow::anyhow!("missing CONNECTIVITY after OFFSETS"));
+            }
+            let data_type = line.split_whitespace().nth(1).unwrap_or("vtktypeint64");
+            let idx2vtx = self.indices(num_b, data_type)?;
+            if elem2idx.last().is_some_and(|&idx| idx != idx2vtx.len()) {
+                return Err(anyhow::anyhow!("OFFSETS does not match CONNECTIVITY"));
+            }
+            return Ok((elem2idx, idx2vtx));
+        }
+        let vals = self.indices(num_b, "int")?;
+        let mut elem2idx = vec![0];
+        let mut idx2vtx = Vec::with_capacity(num_b.saturating_sub(num_a));
+        let mut pos = 0;
+        for _ in 0..num_a {
+            let num_node = *vals
+                .get(pos)
+                .ok_or_else(|| anyhow::anyhow!("cell list is shorter than declared"))?;
+            let nodes = num_node
+                .checked_add(pos + 1)
+                .and_then(|end| vals.get(pos + 1..end))
+                .ok_or_else(|| anyhow::anyhow!("cell list is shorter than declared"))?;
+            idx2vtx.extend_from_slice(nodes);
+            elem2idx.push(idx2vtx.len());
+            pos += 1 + num_node;
+        }
+        Ok((elem2idx, idx2vtx))
+    }
+}
+
+/// number of the values of `num_tuple` tuples with `num_comp` components
+fn num_value(num_tuple: usize, num_comp: usize) -> anyhow::Result<usize> {
+    num_tuple
+        .checked_mul(num_comp)
+        .ok_or_else(|| anyhow::anyhow!("too many values"))
+}
+
+fn parse_count(tokens: &[&str], i: usize) -> anyhow::Result<usize> {
+    let Some(s) = tokens.get(i) else {
+        return Err(anyhow::anyhow!("missing count"));
+    };
+    s.parse::<usize>()
+        .map_err(|_| anyhow::anyhow!("cannot parse \"{}\" as a count", s))
+}
+
+/// `SCALARS`, `COLOR_SCALARS`, `VECTORS`, `NORMALS`, `TEXTURE_COORDINATES` or `TENSORS`
+fn read_attribute<Real>(
+    cur: &mut LegacyCursor,
+    tokens: &[&str],
+    num_tuple: usize,
+) -> anyhow::Result<VtkDataArray<Real>>
+where
+    Real: Copy + 'static,
+    f64: num_traits::AsPrimitive<Real>,
+{
+    use num_traits::AsPrimitive;
+    let Some(name) = tokens.get(1) else {
+        return Err(anyhow::anyhow!("missing name"));
+    };
+    let data_type = |i: usize| tokens.get(i).copied().unwrap_or("float");
+    let (attribute, num_comp, data_type) = match tokens[0] {
+        "SCALARS" => {
+            let num_comp = match tokens.get(3) {
+                Some(_) => parse_count(tokens, 3)?,
+                None => 1,
+            };
+            (VtkAttribute::Scalars, num_comp, data_type(2))
+        }
+        "COLOR_SCALARS" => (
+            VtkAttribute::ColorScalars,
+            parse_count(tokens, 2)?,
+            "unsigned_char",
+        ),
+        "VECTORS" => (VtkAttribute::Vectors, 3, data_type(2)),
+        "NORMALS" => (VtkAttribute::Normals, 3, data_type(2)),
+        "TENSORS" => (VtkAttribute::Tensors, 9, data_type(2)),
+        _ => (
+            VtkAttribute::TextureCoordinates,
+            parse_count(tokens, 2)?,
+            data_type(3),
+        ),
+    };
+    if attribute == VtkAttribute::Scalars
+        && cur
+            .peek_line()
+            .is_some_and(|l| l.starts_with("LOOKUP_TABLE"))
+    {
+        cur.line();
+    }
+    let mut values = cur.values(num_value(num_tuple, num_comp)?, data_type)?;
+    if attribute == VtkAttribute::ColorScalars && cur.is_binary {
+        values.iter_mut().for_each(|v| *v /= 255.);
+    }
+    Ok(VtkDataArray {
+        name: name.to_string(),
+        attribute,
+        num_comp,
+        values: values.into_iter().map(|v| v.as_()).collect(),
+    })
+}
+
+/// read ASCII or binary legacy VTK file with `DATASET UNSTRUCTURED_GRID` or `DATASET POLYDATA`
+pub fn read_legacy<R: std::io::Read, Index, Real>(
+    mut reader: R,
+) -> anyhow::Result<VtkMesh<Index, Real>>
+where
+    Index: num_traits::PrimInt + 'static,
+    Real: Copy + 'static,
+    usize: num_traits::AsPrimitive<Index>,
+    f64: num_traits::AsPrimitive<Real>,
+{
+    use anyhow::Context;
+    use num_traits::AsPrimitive;
+    let mut buf = vec![];
+    reader.read_to_end(&mut buf)?;
+    let mut cur = LegacyCursor {
+        buf,
+        pos: 0,
+        is_binary: false,
+    };
+    let version = cur.raw_line().unwrap_or_default();
+    if !version.starts_with("# vtk DataFile") {
+        return Err(anyhow::anyhow!("not a legacy VTK file"));
+    }
+    let title = cur.raw_line().unwrap_or_default();
+    match cur.line().as_deref() {
+        Some("ASCII") => {}
+        Some("BINARY") => cur.is_binary = true,
+        _ => return Err(anyhow::anyhow!("expected ASCII or BINARY")),
+    }
+    let dataset = cur.line().unwrap_or_default();
+    match dataset.split_whitespace().nth(1) {
+        Some("UNSTRUCTURED_GRID") | Some("POLYDATA") => {}
+        _ => return Err(anyhow::anyhow!("unsupported dataset \"{}\"", dataset)),
+    };
+    let mut vtx2xyz = Vec::<Real>::new();
+    // cells of UNSTRUCTURED_GRID, or cells of POLYDATA in the order of
+    // VERTICES, LINES, POLYGONS and TRIANGLE_STRIPS
+    let mut sec2cells: [(Vec<usize>, Vec<usize>, Vec<VtkElementType>); 4] = Default::default();
+    let mut point_data = vec![];
+    let mut cell_data = vec![];
+    // `Some(true)` in CELL_DATA, `Some(false)` in POINT_DATA, `None` before either
+    let mut is_cell_data: Option<bool> = None;
+    let mut num_tuple = 0;
+    while let Some(line) = cur.line() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let keyword = tokens[0];
+        let res: anyhow::Result<()> = (|| {
+            match keyword {
+                "POINTS" => {
+                    let num_vtx = parse_count(&tokens, 1)?;
+                    let data_type = tokens.get(2).unwrap_or(&"float");
+                    vtx2xyz = cur
+                        .values(num_value(num_vtx, 3)?, data_type)?
+                        .into_iter()
+                        .map(|v| v.as_())
+                        .collect();
+                }
+                "CELLS" => {
+                    let (elem2idx, idx2vtx) =
+                        cur.cell_lists(parse_count(&tokens, 1)?, parse_count(&tokens, 2)?)?;
+                    sec2cells[0].0 = elem2idx;
+                    sec2cells[0].1 = idx2vtx;
+                }
+                "CELL_TYPES" => {
+                    let num_elem = parse_count(&tokens, 1)?;
+                    sec2cells[0].2 = cur
+                        .indices(num_elem, "int")?
+                        .into_iter()
+                        .map(|id| {
+                            VtkElementType::from_id(id)
+                                .ok_or_else(|| anyhow::anyhow!("unsupported cell type {}", id))
+                        })
+                        .collect::<anyhow::Result<_>>()?;
+                }
+                "VERTICES" | "LINES" | "POLYGONS" | "TRIANGLE_STRIPS" => {
+                    let i_sec = ["VERTICES", "LINES", "POLYGONS", "TRIANGLE_STRIPS"]
+                        .iter()
+                        .position(|&k| k == keyword)
+                        .unwrap();
+                    let (elem2idx, idx2vtx) =
+                        cur.cell_lists(parse_count(&tokens, 1)?, parse_count(&tokens, 2)?)?;
+                    let elem2type = elem2idx
+                        .windows(2)
+                        .map(|w| {
+                            use VtkElementType::*;
+                            match (i_sec, w[1] - w[0]) {
+                                (0, 1) => VERTEX,
+                                (0, _) => POLY_VERTEX,
+                                (1, 2) => LINE,
+                                (1, _) => POLY_LINE,
+                                (2, 3) => TRIANGLE,
+                                (2, 4) => QUAD,
+                                (2, _) => POLYGON,
+                                _ => TRIANGLE_STRIP,
+                            }
+                        })
+                        .collect();
+                    sec2cells[i_sec] = (elem2idx, idx2vtx, elem2type);
+                }
+                "POINT_DATA" | "CELL_DATA" => {
+                    is_cell_data = Some(keyword == "CELL_DATA");
+                    num_tuple = parse_count(&tokens, 1)?;
+                }
+                "SCALARS"
+                | "COLOR_SCALARS"
+                | "VECTORS"
+                | "NORMALS"
+                | "TENSORS"
+                | "TEXTURE_COORDINATES" => {
+                    let array = read_attribute(&mut cur, &tokens, num_tuple)?;
+                    match is_cell_data {
+                        Some(true) => cell_data.push(array),
+                        Some(false) => point_data.push(array),
+                        None => return Err(anyhow::anyhow!("attribute before POINT_DATA")),
+                    }
+                }
+                "FIELD" => {
+                    let num_array = parse_count(&tokens, 2)?;
+                    for _ in 0..num_array {
+                        let line = cur
+                            .line()
+                            .ok_or_else(|| anyhow::anyhow!("unexpected end of file"))?;
+                        let t: Vec<&str> = line.split_whitespace().collect();
+                        let num_comp = parse_count(&t, 1)?;
+                        let num_tuple = parse_count(&t, 2)?;
+                        let data_type = t.get(3).unwrap_or(&"float");
+                        let values = cur.values(num_value(num_tuple, num_comp)?, data_type)?;
+                        let array = VtkDataArray {
+                            name: t[0].to_string(),
+                            attribute: VtkAttribute::Field,
+                            num_comp,
+                            values: values.into_iter().map(|v| v.as_()).collect(),
+                        };
+                        // field data of the dataset itself (e.g., TIME) is skipped
+                        match is_cell_data {
+                            Some(true) => cell_data.push(array),
+                            Some(false) => point_data.push(array),
+                            None => {}
+                        }
+                    }
+                }
+                "LOOKUP_TABLE" => {
+                    let num = parse_count(&tokens, 2)?;
+                    cur.values(num_value(num, 4)?, "unsigned_char")?;
+                }
+                "METADATA" => {
+                    // metadata block ends with an empty line
+                    while cur.raw_line().is_some_and(|l| !l.is_empty()) {}
+                }
+                _ => return Err(anyhow::anyhow!("unknown keyword")),
+            }
+            Ok(())
+        })();
+        res.with_context(|| format!("while reading \"{}\"", line))?;
+    }
+    if sec2cells[0].0.len().max(1) - 1 != sec2cells[0].2.len() {
+        return Err(anyhow::anyhow!(
+            "the numbers of CELLS and CELL_TYPES differ"
+        ));
+    }
+    let num_vtx = vtx2xyz.len() / 3;
+    let mut elem2idx: Vec<Index> = vec![Index::zero()];
+    let mut idx2vtx: Vec<Index> = vec![];
+    let mut elem2type = vec![];
+    for (sec2idx, sec2vtx, sec2type) in sec2cells.iter() {
+        for w in sec2idx.windows(2) {
+            for &i_vtx in &sec2vtx[w[0]..w[1]] {
+                if i_vtx >= num_vtx {
+                    return Err(anyhow::anyhow!("point index {} is out of range", i_vtx));
+                }
+                idx2vtx.push(i_vtx.as_());
+            }
+            elem2idx.push(idx2vtx.len().as_());
+        }
+        elem2type.extend_from_slice(sec2type);
+    }
+    Ok(VtkMesh {
+        title,
+        vtx2xyz,
+        elem2idx,
+        idx2vtx,
+        elem2type,
+        point_data,
+        cell_data,
+    })
+}
+
+pub fn load_legacy<P, Index, Real>(path: P) -> anyhow::Result<VtkMesh<Index, Real>>
+where
+    P: AsRef<std::path::Path>,
+    Index: num_traits::PrimInt + 'static,
+    Real: Copy + 'static,
+    usize: num_traits::AsPrimitive<Index>,
+    f64: num_traits::AsPrimitive<Real>,
+{
+    use anyhow::Context;
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    read_legacy(std::io::BufReader::new(file))
+}
+
 #[cfg(test)]
 mod test {
-    use crate::io_vtk::VtkElementType;
+    use crate::io_vtk::{VtkAttribute, VtkElementType};
 
     #[test]
     fn trimesh3_scalardata() {
@@ -114,4 +1084,176 @@ mod test {
         let _ =
             crate::io_vtk::write_vtk_data_point_scalar(&mut file, &vtx2data, vtx2xyz.len() / 3, 1);
     }
+
+    #[test]
+    fn legacy_read() -> anyhow::Result<()> {
+        let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::hemisphere_zup::<f64>(1., 8, 16);
+        let vtx2data: Vec<f64> = vtx2xyz.chunks(3).map(|v| v[2] * 2.).collect();
+        {
+            let mut file = std::fs::File::create("../target/io_vtk_legacy_read.vtk")?;
+            crate::io_vtk::write_vtk_points(&mut file, "hemisphere", &vtx2xyz, 3)?;
+            crate::io_vtk::write_vtk_cells(&mut file, VtkElementType::TRIANGLE, &tri2vtx)?;
+            use std::io::Write;
+            writeln!(file, "POINT_DATA {}", vtx2xyz.len() / 3)?;
+            crate::io_vtk::write_vtk_data_point_scalar(&mut file, &vtx2data, vtx2data.len(), 1)?;
+        }
+        let mesh = crate::io_vtk::load_legacy::<_, usize, f64>("../target/io_vtk_legacy_read.vtk")?;
+        assert_eq!(mesh.title, "hemisphere");
+        assert_eq!(mesh.vtx2xyz, vtx2xyz);
+        assert_eq!(mesh.elem2vtx_of_type(VtkElementType::TRIANGLE), tri2vtx);
+        assert_eq!(mesh.point_data("pointvalue").unwrap().values, vtx2data);
+        // binary POLYDATA with a line and a quad, and cell data
+        let mut buf = Vec::<u8>::new();
+        buf.extend(b"# vtk DataFile Version 3.0\n\nBINARY\nDATASET POLYDATA\n");
+        buf.extend(b"POINTS 4 float\n");
+        for v in [0f32, 0., 0., 1., 0., 0., 1., 1., 0., 0., 1., 0.] {
+            buf.extend(v.to_be_bytes());
+        }
+        buf.extend(b"\nPOLYGONS 1 5\n");
+        for i in [4i32, 0, 1, 2, 3] {
+            buf.extend(i.to_be_bytes());
+        }
+        buf.extend(b"\nLINES 1 3\n");
+        for i in [2i32, 0, 2] {
+            buf.extend(i.to_be_bytes());
+        }
+        buf.extend(b"\nCELL_DATA 2\nFIELD FieldData 1\nid 1 2 int\n");
+        for i in [7i32, 8] {
+            buf.extend(i.to_be_bytes());
+        }
+        buf.extend(b"\nVECTORS dir double\n");
+        for v in [1f64, 0., 0., 0., 1., 0.] {
+            buf.extend(v.to_be_bytes());
+        }
+        let mesh = crate::io_vtk::read_legacy::<_, u32, f32>(buf.as_slice())?;
+        assert_eq!(mesh.title, "");
+        assert_eq!(
+            mesh.elem2type,
+            vec![VtkElementType::LINE, VtkElementType::QUAD]
+        );
+        assert_eq!(mesh.elem2idx, vec![0, 2, 6]);
+        assert_eq!(mesh.idx2vtx, vec![0, 2, 0, 1, 2, 3]);
+        assert_eq!(mesh.cell_data("id").unwrap().values, vec![7., 8.]);
+        let dir = mesh.cell_data("dir").unwrap();
+        assert_eq!(dir.num_comp, 3);
+        assert_eq!(dir.values, vec![1., 0., 0., 0., 1., 0.]);
+        let mut buf = Vec::<u8>::new();
+        assert!(crate::io_vtk::write_vtk_data_point_attribute(
+            &mut buf,
+            VtkAttribute::Field,
+            "id",
+            &[0f32],
+            1
+        )
+        .is_err());
+        // counts beyond the data are errors
+        for (format, body) in [
+            ("ASCII", "POINTS 6148914691236517206 float\n0 0 0\n"),
+            ("BINARY", "POINTS 6148914691236517206 float\n0000"),
+            (
+                "ASCII",
+                "POINTS 1 float\n0 0 0\nCELLS 1 2\n18446744073709551615 0\n",
+            ),
+        ] {
+            let txt = format!(
+                "# vtk DataFile Version 3.0\n\n{}\nDATASET UNSTRUCTURED_GRID\n{}",
+                format, body
+            );
+            assert!(crate::io_vtk::read_legacy::<_, u32, f32>(txt.as_bytes()).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn xml_write() -> anyhow::Result<()> {
+        use crate::io_vtk::{VtkXmlArray, VtkXmlEncoding, VtkXmlPiece};
+        // a tetrahedron, a wedge and a pyramid sharing the points
+        let vtx2xyz = [
+            0f32, 0., 0., 1., 0., 0., 0., 1., 0., 0., 0., 1., 1., 0., 1., 0., 1., 1., 1., 1., 0.,
+        ];
+        let elem2idx = [0usize, 4, 10, 15];
+        let idx2vtx = [0usize, 1, 2, 3, 0, 1, 2, 3, 4, 5, 0, 1, 6, 2, 3];
+        let elem2type = [
+            VtkElementType::TETRA,
+            VtkElementType::WEDGE,
+            VtkElementType::PYRAMID,
+        ];
+        let vtx2vec: Vec<f32> = vtx2xyz.iter().map(|v| v * 2.).collect();
+        let elem2tensor: Vec<f32> = (0..27).map(|v| v as f32).collect();
+        let mut piece = VtkXmlPiece::new(&vtx2xyz, &elem2idx, &idx2vtx, &elem2type);
+        piece.point_data.push(VtkXmlArray {
+            name: "velocity",
+            num_comp: 3,
+            values: &vtx2vec,
+        });
+        piece.cell_data.push(VtkXmlArray {
+            name: "stress",
+            num_comp: 9,
+            values: &elem2tensor,
+        });
+        let mut frames = vec![];
+        for (i_frame, encoding) in [VtkXmlEncoding::Raw, VtkXmlEncoding::Base64]
+            .into_iter()
+            .enumerate()
+        {
+            let path = format!("../target/io_vtk_xml_write_{}.vtu", i_frame);
+            let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+            piece.write_vtu(&mut file, encoding)?;
+            frames.push((i_frame as f64 * 0.1, path));
+        }
+        let frames: Vec<(f64, &str)> = frames.iter().map(|(t, p)| (*t, p.as_str())).collect();
+        let mut file = std::fs::File::create("../target/io_vtk_xml_write.pvd")?;
+        crate::io_vtk::write_pvd(&mut file, &frames)?;
+        // wrong number of nodes
+        let elem2type = [
+            VtkElementType::TETRA,
+            VtkElementType::PYRAMID,
+            VtkElementType::WEDGE,
+        ];
+        let piece = VtkXmlPiece::<usize, f32>::new(&vtx2xyz, &elem2idx, &idx2vtx, &elem2type);
+        assert!(piece
+            .write_vtu(&mut Vec::<u8>::new(), VtkXmlEncoding::Raw)
+            .is_err());
+        // triangles and a line in PolyData
+        let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::sphere_yup::<usize, f64>(1.0, 8, 8);
+        let num_tri = tri2vtx.len() / 3;
+        let mut elem2idx: Vec<usize> = (0..num_tri + 1).map(|i| i * 3).collect();
+        let mut idx2vtx = tri2vtx.clone();
+        let mut elem2type = vec![VtkElementType::TRIANGLE; num_tri];
+        idx2vtx.extend([0, 1]);
+        elem2idx.push(idx2vtx.len());
+        elem2type.push(VtkElementType::LINE);
+        let elem2id: Vec<f64> = (0..num_tri + 1).map(|i| i as f64).collect();
+        let mut piece = VtkXmlPiece::new(&vtx2xyz, &elem2idx, &idx2vtx, &elem2type);
+        piece.cell_data.push(VtkXmlArray {
+            name: "id",
+            num_comp: 1,
+            values: &elem2id,
+        });
+        let mut buf = Vec::<u8>::new();
+        piece.write_vtp(&mut buf, VtkXmlEncoding::Raw)?;
+        std::fs::write("../target/io_vtk_xml_write.vtp", &buf)?;
+        let txt = String::from_utf8_lossy(&buf);
+        let tag = format!("NumberOfLines=\"1\" NumberOfPolys=\"{}\"", num_tri);
+        assert!(txt.contains(&tag));
+        // the line comes first in the cell data
+        let i_start = txt.find("\n_").unwrap() + 2;
+        assert_eq!(
+            buf[i_start..i_start + 8],
+            ((num_tri + 1) as u64 * 8).to_le_bytes()
+        );
+        assert_eq!(
+            buf[i_start + 8..i_start + 16],
+            (num_tri as f64).to_le_bytes()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(crate::io_vtk::encode_base64(b""), b"");
+        assert_eq!(crate::io_vtk::encode_base64(b"f"), b"Zg==");
+        assert_eq!(crate::io_vtk::encode_base64(b"fo"), b"Zm8=");
+        assert_eq!(crate::io_vtk::encode_base64(b"foobar"), b"Zm9vYmFy");
+    }
 }
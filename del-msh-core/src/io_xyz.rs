@@ -0,0 +1,248 @@
+//! methods for point clouds in text files (`.xyz` and Leica `.pts`)
+
+use crate::vtx2point::PointCloud;
+use anyhow::Context;
+use num_traits::AsPrimitive;
+use std::io::{BufRead, Write};
+
+/// meaning of a column of the text
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XyzColumn {
+    X,
+    Y,
+    Z,
+    Nx,
+    Ny,
+    Nz,
+    Red,
+    Green,
+    Blue,
+    Intensity,
+    Skip,
+}
+
+/// columns guessed from the values. The coordinates are followed by the optional intensity
+/// (if the number of columns is four, seven or ten), the colors (if the values are the integers
+/// in [0,255] and not the unit vectors) and the normals
+fn guess_columns(row2vals: &[Vec<f64>]) -> anyhow::Result<Vec<XyzColumn>> {
+    use XyzColumn::*;
+    let num_col = row2vals.first().map_or(3, |vals| vals.len());
+    anyhow::ensure!(
+        num_col >= 3,
+        "{} columns are too few for the coordinates",
+        num_col
+    );
+    // integral axis-aligned normals look like colors
+    let is_unit = |i_col: usize| -> bool {
+        row2vals.iter().all(|vals| {
+            let sq: f64 = vals[i_col..i_col + 3].iter().map(|&v| v * v).sum();
+            (sq - 1.).abs() < 1.0e-3
+        })
+    };
+    let is_color = |i_col: usize| -> bool {
+        row2vals.iter().all(|vals| {
+            vals[i_col..i_col + 3]
+                .iter()
+                .all(|&v| v.fract() == 0. && (0. ..=255.).contains(&v))
+        })
+    };
+    let mut columns = vec![X, Y, Z];
+    let mut i_col = 3;
+    if matches!(num_col, 4 | 7 | 10) {
+        columns.push(Intensity);
+        i_col += 1;
+    }
+    match num_col - i_col {
+        0 => {}
+        3 if is_color(i_col) && !is_unit(i_col) => columns.extend([Red, Green, Blue]),
+        3 => columns.extend([Nx, Ny, Nz]),
+        6 if is_color(i_col) && !is_unit(i_col) => columns.extend([Red, Green, Blue, Nx, Ny, Nz]),
+        6 => columns.extend([Nx, Ny, Nz, Red, Green, Blue]),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "cannot guess the meaning of {} columns",
+                num_col
+            ))
+        }
+    }
+    Ok(columns)
+}
+
+/// read the point cloud in a text file where each line is a point. The values are separated by
+/// white spaces, commas or semicolons, and the lines starting with `#` or `//` are skipped.
+/// The first line of `.pts` with the number of points is also skipped
+/// * `columns` - meaning of the columns. Guessed from the values if `None`
+pub fn read<R: BufRead, Real>(
+    reader: R,
+    columns: Option<&[XyzColumn]>,
+) -> anyhow::Result<PointCloud<Real>>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let mut row2vals = vec![];
+    for (i_line, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        let vals = line
+            .split(|c: char| c.is_ascii_whitespace() || c == ',' || c == ';')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .with_context(|| format!("line {}: {}", i_line + 1, line))?;
+        if row2vals.is_empty() && vals.len() == 1 {
+            continue; // number of points in `.pts`
+        }
+        if let Some(vals0) = row2vals.first() {
+            let vals0: &Vec<f64> = vals0;
+            anyhow::ensure!(
+                vals.len() == vals0.len(),
+                "line {}: {} columns while the first point has {}",
+                i_line + 1,
+                vals.len(),
+                vals0.len()
+            );
+        }
+        row2vals.push(vals);
+    }
+    let columns = match columns {
+        Some(columns) => columns.to_vec(),
+        None => guess_columns(&row2vals)?,
+    };
+    if let Some(vals) = row2vals.first() {
+        anyhow::ensure!(
+            vals.len() >= columns.len(),
+            "{} columns are given for {} values",
+            columns.len(),
+            vals.len()
+        );
+    }
+    let has = |col: XyzColumn| columns.contains(&col);
+    let mut cloud = PointCloud::<Real>::new(vec![]);
+    for vals in &row2vals {
+        let mut xyz = [Real::zero(); 3];
+        let mut nrm = [Real::zero(); 3];
+        let mut rgb = [0u8; 3];
+        for (&col, &val) in columns.iter().zip(vals.iter()) {
+            use XyzColumn::*;
+            match col {
+                X | Y | Z => xyz[col as usize - X as usize] = val.as_(),
+                Nx | Ny | Nz => nrm[col as usize - Nx as usize] = val.as_(),
+                Red | Green | Blue => {
+                    rgb[col as usize - Red as usize] = val.round().clamp(0., 255.) as u8
+                }
+                Intensity => cloud.vtx2intensity.push(val.as_()),
+                Skip => {}
+            }
+        }
+        cloud.vtx2xyz.extend_from_slice(&xyz);
+        if has(XyzColumn::Nx) {
+            cloud.vtx2nrm.extend_from_slice(&nrm);
+        }
+        if has(XyzColumn::Red) {
+            cloud.vtx2rgb.extend_from_slice(&rgb);
+        }
+    }
+    Ok(cloud)
+}
+
+pub fn load<P, Real>(path: P, columns: Option<&[XyzColumn]>) -> anyhow::Result<PointCloud<Real>>
+where
+    P: AsRef<std::path::Path>,
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let file = std::fs::File::open(path.as_ref())
+        .with_context(|| format!("file not found: {}", path.as_ref().display()))?;
+    read(std::io::BufReader::new(file), columns)
+}
+
+/// write the points as `x y z [intensity] [r g b] [nx ny nz]` that `read` can guess
+/// * `is_pts` - write the number of points in the first line as `.pts`
+pub fn write<W: Write, Real>(
+    writer: &mut W,
+    cloud: &PointCloud<Real>,
+    is_pts: bool,
+) -> anyhow::Result<()>
+where
+    Real: num_traits::Float + std::fmt::Display,
+{
+    let num_vtx = cloud.num_vtx();
+    if is_pts {
+        writeln!(writer, "{}", num_vtx)?;
+    }
+    for i_vtx in 0..num_vtx {
+        let xyz = &cloud.vtx2xyz[i_vtx * 3..i_vtx * 3 + 3];
+        write!(writer, "{} {} {}", xyz[0], xyz[1], xyz[2])?;
+        if let Some(intensity) = cloud.vtx2intensity.get(i_vtx) {
+            write!(writer, " {}", intensity)?;
+        }
+        if let Some(rgb) = cloud.vtx2rgb.get(i_vtx * 3..i_vtx * 3 + 3) {
+            write!(writer, " {} {} {}", rgb[0], rgb[1], rgb[2])?;
+        }
+        if let Some(nrm) = cloud.vtx2nrm.get(i_vtx * 3..i_vtx * 3 + 3) {
+            write!(writer, " {} {} {}", nrm[0], nrm[1], nrm[2])?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// save the point cloud. The number of points is written in the first line if the extension is `.pts`
+pub fn save<P, Real>(path: P, cloud: &PointCloud<Real>) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    Real: num_traits::Float + std::fmt::Display,
+{
+    let path = path.as_ref();
+    let is_pts = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pts"));
+    let file =
+        std::fs::File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+    let mut file = std::io::BufWriter::new(file);
+    write(&mut file, cloud, is_pts)?;
+    file.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_read_write() -> anyhow::Result<()> {
+    use XyzColumn::*;
+    // Leica PTS: count, then x y z intensity r g b
+    let txt = "2\n0.5 1.5 -2 -1024 255 0 10\n1 2 3 100 0 128 255\n";
+    let cloud = read::<_, f64>(txt.as_bytes(), None)?;
+    assert_eq!(cloud.vtx2xyz, vec![0.5, 1.5, -2., 1., 2., 3.]);
+    assert_eq!(cloud.vtx2intensity, vec![-1024., 100.]);
+    assert_eq!(cloud.vtx2rgb, vec![255, 0, 10, 0, 128, 255]);
+    assert!(cloud.vtx2nrm.is_empty());
+    // comma separated with normals, and the explicit columns
+    let txt = "# x,y,z,nx,ny,nz\n0,0,0,0,0,1\n1,0,0,0.6,0,0.8\n";
+    let cloud = read::<_, f32>(txt.as_bytes(), None)?;
+    assert_eq!(cloud.vtx2nrm, vec![0., 0., 1., 0.6, 0., 0.8]);
+    let cloud1 = read::<_, f32>(txt.as_bytes(), Some(&[Skip, X, Y, Z]))?;
+    assert_eq!(cloud1.vtx2xyz, vec![0., 0., 0., 0., 0., 0.6]);
+    assert!(read::<_, f32>("1 2 3\n4 5\n".as_bytes(), None).is_err());
+    assert!(read::<_, f32>("1 2\n3 4\n".as_bytes(), None).is_err());
+    // axis-aligned normals are not colors
+    let cloud = read::<_, f32>("0 0 0 0 0 1\n1 0 0 -1 0 0\n".as_bytes(), None)?;
+    assert_eq!(cloud.vtx2nrm, vec![0., 0., 1., -1., 0., 0.]);
+    let cloud = read::<_, f32>("0 0 0 0 0 1\n1 0 0 1 0 0\n".as_bytes(), None)?;
+    assert_eq!(cloud.vtx2nrm, vec![0., 0., 1., 1., 0., 0.]);
+    // write and read with all the attributes
+    let mut cloud = PointCloud::<f32>::new(vec![0., 1., 2., 3., 4., 5.]);
+    cloud.vtx2nrm = vec![0., 0., 1., 0.5, 0.5, 0.70710677];
+    cloud.vtx2rgb = vec![1, 2, 3, 4, 5, 6];
+    cloud.vtx2intensity = vec![0.25, 7.];
+    for path in ["../target/io_xyz.xyz", "../target/io_xyz.pts"] {
+        save(path, &cloud)?;
+        let cloud1 = load::<_, f32>(path, None)?;
+        assert_eq!(cloud, cloud1);
+    }
+    let aabb = crate::vtx2point::aabb3_from_points(&cloud.to_points());
+    assert_eq!(aabb, [0., 1., 2., 3., 4., 5.]);
+    Ok(())
+}
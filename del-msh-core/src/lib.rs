@@ -2,6 +2,8 @@
 pub mod edge2elem;
 pub mod edge2vtx;
 pub mod elem2elem;
+pub mod halfedge;
+pub mod mesh_check;
 pub mod tri2vtx;
 pub mod unify_index;
 pub mod vtx2elem;
@@ -17,6 +19,8 @@ pub mod polyloop3;
 pub mod quadmesh;
 pub mod trimesh2;
 pub mod trimesh3;
+pub mod trimesh3_fill_hole;
+pub mod trimesh3_line_drawing;
 pub mod trimesh3_primitive;
 pub mod trimesh3_search_bruteforce;
 pub mod vtx2point;
@@ -35,12 +39,22 @@ pub mod transform;
 pub mod unindex;
 
 // io
+pub mod io;
 pub mod io_nas;
+pub mod io_dxf;
+pub mod io_gltf;
+pub mod io_las;
+pub mod io_medit;
+pub mod io_msh;
+pub mod io_mtl;
 pub mod io_obj;
 pub mod io_off;
+pub mod io_pcd;
 pub mod io_ply;
+pub mod io_stl;
 pub mod io_svg;
 pub mod io_vtk;
+pub mod io_xyz;
 
 // search
 pub mod bvhnode2aabb2;
@@ -61,7 +75,9 @@ pub mod trimesh3_proximity;
 // misc
 pub mod polygon_mesh;
 pub mod trimesh;
+pub mod trimesh_cleanup;
 pub mod trimesh2_dynamic;
+pub mod trimesh_orientation;
 pub mod trimesh_topology;
 pub mod uniform_mesh;
 pub mod vtx2group;
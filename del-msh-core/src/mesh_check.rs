@@ -0,0 +1,420 @@
+//! validity check of triangle and polygon meshes
+
+use num_traits::AsPrimitive;
+
+/// thresholds of the geometric checks
+#[derive(Clone, Debug)]
+pub struct Tolerance {
+    /// the element is zero-area if the ratio of its area to the squared diagonal of the
+    /// bounding box is below this
+    pub area: f64,
+    /// the triangle is a needle if the ratio of its shortest edge to the longest is below this
+    pub needle: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            area: 1.0e-12,
+            needle: 1.0e-2,
+        }
+    }
+}
+
+/// result of the check. The elements with the broken index range, the indices out of range
+/// or the repeated vertices are excluded from the other checks
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub num_vtx: usize,
+    pub num_elem: usize,
+    /// elements whose range in `elem2idx` is decreasing or goes past the end of `idx2vtx`
+    pub elem_idx_broken: Vec<usize>,
+    /// elements referring to the vertices out of range
+    pub elem_out_of_range: Vec<usize>,
+    /// elements with less than three vertices or the same vertex twice
+    pub elem_degenerate: Vec<usize>,
+    /// edges shared by more than two elements
+    pub edge_non_manifold: Vec<[usize; 2]>,
+    /// vertices where the elements around do not form a single fan
+    pub vtx_non_manifold: Vec<usize>,
+    /// pairs of the adjacent elements going along the shared edge in the same direction
+    pub elem_pair_inconsistent: Vec<[usize; 2]>,
+    pub elem_zero_area: Vec<usize>,
+    /// triangles with a short edge, excluding the zero-area ones
+    pub tri_needle: Vec<usize>,
+    /// pairs of the element and the preceding element with the same set of vertices
+    pub elem_duplicate: Vec<[usize; 2]>,
+    pub vtx_unreferenced: Vec<usize>,
+    /// connected components of the elements sharing edges
+    pub num_component: usize,
+    /// connected components of the boundary edges
+    pub num_boundary_loop: usize,
+}
+
+impl Report {
+    /// no error is found. The needles and the unreferenced vertices are reported but allowed
+    pub fn is_valid(&self) -> bool {
+        self.elem_idx_broken.is_empty()
+            && self.elem_out_of_range.is_empty()
+            && self.elem_degenerate.is_empty()
+            && self.edge_non_manifold.is_empty()
+            && self.vtx_non_manifold.is_empty()
+            && self.elem_pair_inconsistent.is_empty()
+            && self.elem_zero_area.is_empty()
+            && self.elem_duplicate.is_empty()
+    }
+
+    /// returns the error with the diagnosis if the mesh is not valid
+    pub fn ensure_valid(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(self.is_valid(), "invalid mesh: {}", self);
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} vertices, {} elements, {} connected components, {} boundary loops",
+            self.num_vtx, self.num_elem, self.num_component, self.num_boundary_loop
+        )?;
+        let lists = [
+            (
+                "elements with broken index range",
+                summary(&self.elem_idx_broken),
+            ),
+            (
+                "elements with vertex index out of range",
+                summary(&self.elem_out_of_range),
+            ),
+            ("degenerate elements", summary(&self.elem_degenerate)),
+            ("non-manifold edges", summary(&self.edge_non_manifold)),
+            ("non-manifold vertices", summary(&self.vtx_non_manifold)),
+            (
+                "pairs of inconsistently oriented elements",
+                summary(&self.elem_pair_inconsistent),
+            ),
+            ("zero-area elements", summary(&self.elem_zero_area)),
+            ("needle triangles", summary(&self.tri_needle)),
+            ("duplicate elements", summary(&self.elem_duplicate)),
+            ("unreferenced vertices", summary(&self.vtx_unreferenced)),
+        ];
+        for (name, (num, items)) in lists {
+            if num > 0 {
+                write!(f, "\n  {} {}: {}", num, name, items)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// number of the items and the first few of them
+fn summary<T: std::fmt::Debug>(items: &[T]) -> (usize, String) {
+    const MAX_ITEM: usize = 10;
+    let mut strs: Vec<String> = items
+        .iter()
+        .take(MAX_ITEM)
+        .map(|i| format!("{:?}", i))
+        .collect();
+    if items.len() > MAX_ITEM {
+        strs.push("...".to_string());
+    }
+    (items.len(), strs.join(", "))
+}
+
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+fn unite(parent: &mut [usize], i: usize, j: usize) {
+    let (ri, rj) = (find_root(parent, i), find_root(parent, j));
+    if ri != rj {
+        parent[ri.max(rj)] = ri.min(rj);
+    }
+}
+
+/// check the connectivity of the polygon mesh
+pub fn check_polygon_mesh_topology(
+    elem2idx: &[usize],
+    idx2vtx: &[usize],
+    num_vtx: usize,
+) -> Report {
+    let num_elem = elem2idx.len().saturating_sub(1);
+    let mut report = Report {
+        num_vtx,
+        num_elem,
+        ..Default::default()
+    };
+    let mut elem2valid = vec![true; num_elem];
+    let mut vtx2used = vec![false; num_vtx];
+    for i_elem in 0..num_elem {
+        let Some(elem2vtx) = idx2vtx.get(elem2idx[i_elem]..elem2idx[i_elem + 1]) else {
+            report.elem_idx_broken.push(i_elem);
+            elem2valid[i_elem] = false;
+            continue;
+        };
+        if elem2vtx.iter().any(|&i_vtx| i_vtx >= num_vtx) {
+            report.elem_out_of_range.push(i_elem);
+            elem2valid[i_elem] = false;
+        } else if elem2vtx.len() < 3
+            || (1..elem2vtx.len()).any(|i| elem2vtx[..i].contains(&elem2vtx[i]))
+        {
+            report.elem_degenerate.push(i_elem);
+            elem2valid[i_elem] = false;
+        }
+        for &i_vtx in elem2vtx.iter().filter(|&&i_vtx| i_vtx < num_vtx) {
+            vtx2used[i_vtx] = true;
+        }
+    }
+    report.vtx_unreferenced = (0..num_vtx).filter(|&i_vtx| !vtx2used[i_vtx]).collect();
+    // (smaller vertex, larger vertex, element, is the direction from smaller to larger,
+    //  corner of the smaller vertex, corner of the larger vertex)
+    let mut edges = Vec::<(usize, usize, usize, bool, usize, usize)>::new();
+    for i_elem in (0..num_elem).filter(|&i_elem| elem2valid[i_elem]) {
+        let (idx0, idx1) = (elem2idx[i_elem], elem2idx[i_elem + 1]);
+        for idx in idx0..idx1 {
+            let jdx = if idx + 1 == idx1 { idx0 } else { idx + 1 };
+            let (i0, i1) = (idx2vtx[idx], idx2vtx[jdx]);
+            if i0 < i1 {
+                edges.push((i0, i1, i_elem, true, idx, jdx));
+            } else {
+                edges.push((i1, i0, i_elem, false, jdx, idx));
+            }
+        }
+    }
+    edges.sort_unstable();
+    // union-find of the elements, the corners around each vertex and the boundary vertices
+    let mut elem2parent: Vec<usize> = (0..num_elem).collect();
+    let mut idx2parent: Vec<usize> = (0..idx2vtx.len()).collect();
+    let mut vtx2parent: Vec<usize> = (0..num_vtx).collect();
+    let mut vtx2boundary = vec![false; num_vtx];
+    for group in edges.chunk_by(|a, b| (a.0, a.1) == (b.0, b.1)) {
+        let (i0, i1) = (group[0].0, group[0].1);
+        for e in &group[1..] {
+            unite(&mut elem2parent, group[0].2, e.2);
+            unite(&mut idx2parent, group[0].4, e.4);
+            unite(&mut idx2parent, group[0].5, e.5);
+        }
+        match group.len() {
+            1 => {
+                unite(&mut vtx2parent, i0, i1);
+                vtx2boundary[i0] = true;
+                vtx2boundary[i1] = true;
+            }
+            2 if group[0].3 == group[1].3 => {
+                report.elem_pair_inconsistent.push([group[0].2, group[1].2]);
+            }
+            2 => {}
+            _ => report.edge_non_manifold.push([i0, i1]),
+        }
+    }
+    let mut vtx2root = vec![usize::MAX; num_vtx];
+    let mut vtx2non_manifold = vec![false; num_vtx];
+    for i_elem in (0..num_elem).filter(|&i_elem| elem2valid[i_elem]) {
+        let (idx0, idx1) = (elem2idx[i_elem], elem2idx[i_elem + 1]);
+        for (idx, &i_vtx) in (idx0..idx1).zip(&idx2vtx[idx0..idx1]) {
+            let root = find_root(&mut idx2parent, idx);
+            if vtx2root[i_vtx] == usize::MAX {
+                vtx2root[i_vtx] = root;
+            } else if vtx2root[i_vtx] != root {
+                vtx2non_manifold[i_vtx] = true;
+            }
+        }
+    }
+    report.vtx_non_manifold = (0..num_vtx).filter(|&i| vtx2non_manifold[i]).collect();
+    report.num_component = (0..num_elem)
+        .filter(|&i_elem| elem2valid[i_elem] && find_root(&mut elem2parent, i_elem) == i_elem)
+        .count();
+    report.num_boundary_loop = (0..num_vtx)
+        .filter(|&i_vtx| vtx2boundary[i_vtx] && find_root(&mut vtx2parent, i_vtx) == i_vtx)
+        .count();
+    // duplicate elements
+    let mut elem2sorted: Vec<(Vec<usize>, usize)> = (0..num_elem)
+        .filter(|&i_elem| elem2valid[i_elem])
+        .map(|i_elem| {
+            let mut vtxs = idx2vtx[elem2idx[i_elem]..elem2idx[i_elem + 1]].to_vec();
+            vtxs.sort_unstable();
+            (vtxs, i_elem)
+        })
+        .collect();
+    elem2sorted.sort_unstable();
+    for group in elem2sorted.chunk_by(|a, b| a.0 == b.0) {
+        for e in &group[1..] {
+            report.elem_duplicate.push([e.1, group[0].1]);
+        }
+    }
+    report.elem_duplicate.sort_unstable();
+    report
+}
+
+/// check the connectivity and the shapes of the elements of the polygon mesh
+/// * `num_dim` - dimension of the coordinates (2 or 3)
+pub fn check_polygon_mesh<Real>(
+    elem2idx: &[usize],
+    idx2vtx: &[usize],
+    vtx2xyz: &[Real],
+    num_dim: usize,
+    tolerance: &Tolerance,
+) -> Report
+where
+    Real: AsPrimitive<f64>,
+{
+    assert!(num_dim == 2 || num_dim == 3);
+    let num_vtx = vtx2xyz.len() / num_dim;
+    let mut report = check_polygon_mesh_topology(elem2idx, idx2vtx, num_vtx);
+    let xyz = |i_vtx: usize| -> [f64; 3] {
+        let p = &vtx2xyz[i_vtx * num_dim..(i_vtx + 1) * num_dim];
+        [
+            p[0].as_(),
+            p[1].as_(),
+            if num_dim == 3 { p[2].as_() } else { 0. },
+        ]
+    };
+    let sub = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let norm = |a: [f64; 3]| (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt();
+    let mut min = [f64::MAX; 3];
+    let mut max = [f64::MIN; 3];
+    for i_vtx in 0..num_vtx {
+        let p = xyz(i_vtx);
+        for i_dim in 0..3 {
+            min[i_dim] = min[i_dim].min(p[i_dim]);
+            max[i_dim] = max[i_dim].max(p[i_dim]);
+        }
+    }
+    let diagonal = if num_vtx == 0 {
+        0.
+    } else {
+        norm(sub(max, min))
+    };
+    let mut elem2skip = vec![false; report.num_elem];
+    for &i_elem in report
+        .elem_idx_broken
+        .iter()
+        .chain(&report.elem_out_of_range)
+        .chain(&report.elem_degenerate)
+    {
+        elem2skip[i_elem] = true;
+    }
+    for i_elem in (0..report.num_elem).filter(|&i_elem| !elem2skip[i_elem]) {
+        let elem2vtx = &idx2vtx[elem2idx[i_elem]..elem2idx[i_elem + 1]];
+        // area by the Newell's method
+        let mut n = [0f64; 3];
+        for (i_node, &i_vtx) in elem2vtx.iter().enumerate() {
+            let p0 = xyz(i_vtx);
+            let p1 = xyz(elem2vtx[(i_node + 1) % elem2vtx.len()]);
+            n[0] += (p0[1] - p1[1]) * (p0[2] + p1[2]);
+            n[1] += (p0[2] - p1[2]) * (p0[0] + p1[0]);
+            n[2] += (p0[0] - p1[0]) * (p0[1] + p1[1]);
+        }
+        let area = 0.5 * norm(n);
+        if area <= tolerance.area * diagonal * diagonal {
+            report.elem_zero_area.push(i_elem);
+            continue;
+        }
+        if elem2vtx.len() == 3 {
+            let lens = [0, 1, 2].map(|i| norm(sub(xyz(elem2vtx[(i + 1) % 3]), xyz(elem2vtx[i]))));
+            let len_min = lens[0].min(lens[1]).min(lens[2]);
+            let len_max = lens[0].max(lens[1]).max(lens[2]);
+            if len_min < tolerance.needle * len_max {
+                report.tri_needle.push(i_elem);
+            }
+        }
+    }
+    report
+}
+
+pub fn check_triangle_mesh<Real>(
+    tri2vtx: &[usize],
+    vtx2xyz: &[Real],
+    num_dim: usize,
+    tolerance: &Tolerance,
+) -> Report
+where
+    Real: AsPrimitive<f64>,
+{
+    let tri2idx: Vec<usize> = (0..=tri2vtx.len() / 3).map(|i_tri| i_tri * 3).collect();
+    check_polygon_mesh(&tri2idx, tri2vtx, vtx2xyz, num_dim, tolerance)
+}
+
+#[test]
+fn test_check() -> anyhow::Result<()> {
+    let tolerance = Tolerance::default();
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::torus_zup::<usize, f32>(1.0, 0.3, 16, 8);
+    let report = check_triangle_mesh(&tri2vtx, &vtx2xyz, 3, &tolerance);
+    report.ensure_valid()?;
+    assert_eq!((report.num_component, report.num_boundary_loop), (1, 0));
+    // quad grid
+    let (quad2vtx, vtx2xy) = crate::quadmesh::from_grid::<f32>(4, 3);
+    let quad2idx: Vec<usize> = (0..=quad2vtx.len() / 4).map(|i| i * 4).collect();
+    let report = check_polygon_mesh(&quad2idx, &quad2vtx, &vtx2xy, 2, &tolerance);
+    report.ensure_valid()?;
+    assert_eq!((report.num_component, report.num_boundary_loop), (1, 1));
+    // mesh with problems in separate components
+    let tri2vtx = vec![
+        0, 1, 2, // 0
+        1, 2, 3, // 1: inconsistent with 0
+        4, 5, 6, // 2
+        5, 4, 7, // 3
+        4, 5, 8, // 4: the edge (4, 5) is non-manifold
+        9, 10, 11, // 5
+        9, 12, 13, // 6: touches 5 only at the vertex 9
+        14, 15, 16, // 7: zero area
+        17, 18, 19, // 8: needle
+        20, 21, 22, // 9
+        22, 21, 20, // 10: duplicate of 9
+        0, 1, 99, // 11: out of range
+        3, 3, 2, // 12: degenerate
+    ];
+    let vtx2xyz: Vec<f32> = [
+        [0., 0., 0.],
+        [1., 0., 0.],
+        [0., 1., 0.],
+        [1., 1., 0.],
+        [0., 0., 1.],
+        [1., 0., 1.],
+        [0., 1., 1.],
+        [0., -1., 1.],
+        [0., 0., 2.],
+        [0., 0., 3.],
+        [1., 0., 3.],
+        [0., 1., 3.],
+        [-1., 0., 3.],
+        [0., -1., 3.],
+        [0., 0., 4.],
+        [1., 0., 4.],
+        [2., 0., 4.],
+        [0., 0., 5.],
+        [1., 0., 5.],
+        [1., 0.001, 5.],
+        [0., 0., 6.],
+        [1., 0., 6.],
+        [0., 1., 6.],
+        [9., 9., 9.], // unreferenced
+    ]
+    .concat();
+    let report = check_triangle_mesh(&tri2vtx, &vtx2xyz, 3, &tolerance);
+    assert_eq!(report.elem_out_of_range, vec![11]);
+    assert_eq!(report.elem_degenerate, vec![12]);
+    assert_eq!(report.edge_non_manifold, vec![[4, 5]]);
+    assert_eq!(report.vtx_non_manifold, vec![9]);
+    assert_eq!(report.elem_pair_inconsistent, vec![[0, 1]]);
+    assert_eq!(report.elem_zero_area, vec![7]);
+    assert_eq!(report.tri_needle, vec![8]);
+    assert_eq!(report.elem_duplicate, vec![[10, 9]]);
+    assert_eq!(report.vtx_unreferenced, vec![23]);
+    assert_eq!(report.num_component, 7);
+    let msg = format!("{}", report.ensure_valid().unwrap_err());
+    assert!(msg.contains("1 non-manifold edges: [4, 5]"), "{}", msg);
+    assert!(msg.contains("1 duplicate elements: [10, 9]"), "{}", msg);
+    // broken index ranges are reported instead of panicking
+    let report = check_polygon_mesh_topology(&[], &[], 3);
+    assert_eq!(report.num_elem, 0);
+    let report = check_polygon_mesh_topology(&[0, 3, 2, 6, 9], &[0, 1, 2, 0, 2, 1], 3);
+    assert_eq!(report.elem_idx_broken, vec![1, 3]);
+    assert!(!report.is_valid());
+    Ok(())
+}
@@ -0,0 +1,540 @@
+//! filling the holes of a triangle mesh following
+//! P. Liepa, "Filling Holes in Meshes", SGP 2003.
+//! The hole is triangulated by the dynamic programming, then the patch is refined to match the
+//! density of the surrounding mesh and faired
+
+use num_traits::AsPrimitive;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// weight minimized by the triangulation of a hole
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weight {
+    /// sum of the triangle areas
+    Area,
+    /// the largest dihedral angle, then the sum of the triangle areas
+    DihedralArea,
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub weight: Weight,
+    /// insert the vertices so that the density of the patch matches the surrounding mesh
+    pub is_refine: bool,
+    /// density control factor of the refinement. A larger value inserts more vertices
+    pub density: f64,
+    /// number of the smoothing iterations applied to the inserted vertices
+    pub num_iter_fair: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            weight: Weight::DihedralArea,
+            is_refine: true,
+            density: std::f64::consts::SQRT_2,
+            num_iter_fair: 100,
+        }
+    }
+}
+
+/// the mesh around a boundary loop. The vertices are indexed by the position in the loop
+struct Rim<Real> {
+    vtx2xyz: Vec<[Real; 3]>,
+    /// vertex of the mesh triangle adjacent to the loop edge (i, i+1)
+    edge2opp: Vec<[Real; 3]>,
+    /// mesh edges between the loop vertices as the sorted pairs
+    edges: HashSet<[usize; 2]>,
+    /// average length of the mesh edges around the loop vertex
+    vtx2scale: Vec<Real>,
+}
+
+impl<Real> Rim<Real>
+where
+    Real: num_traits::Float + 'static,
+    usize: AsPrimitive<Real>,
+{
+    fn new(tri2vtx: &[usize], vtx2xyz: &[Real], loop2vtx: &[usize]) -> anyhow::Result<Self> {
+        let num_vtx_loop = loop2vtx.len();
+        anyhow::ensure!(num_vtx_loop >= 3, "the loop has less than three vertices");
+        let mut vtx2pos = HashMap::<usize, usize>::new();
+        for (i_pos, &i_vtx) in loop2vtx.iter().enumerate() {
+            anyhow::ensure!(
+                vtx2pos.insert(i_vtx, i_pos).is_none(),
+                "the vertex {} appears twice in the loop",
+                i_vtx
+            );
+        }
+        let xyz = |i_vtx: usize| *arrayref::array_ref!(vtx2xyz, i_vtx * 3, 3);
+        let mut edge2opp = vec![None; num_vtx_loop];
+        let mut edges = HashSet::new();
+        let mut pos2length = vec![(Real::zero(), 0); num_vtx_loop];
+        for node2vtx in tri2vtx.chunks(3) {
+            for i_node in 0..3 {
+                let i0 = node2vtx[i_node];
+                let i1 = node2vtx[(i_node + 1) % 3];
+                let (i_pos0, i_pos1) = (vtx2pos.get(&i0).copied(), vtx2pos.get(&i1).copied());
+                if i_pos0.is_none() && i_pos1.is_none() {
+                    continue;
+                }
+                let len = del_geo_core::vec3::distance(&xyz(i0), &xyz(i1));
+                for i_pos in [i_pos0, i_pos1].into_iter().flatten() {
+                    pos2length[i_pos].0 = pos2length[i_pos].0 + len;
+                    pos2length[i_pos].1 += 1;
+                }
+                let (Some(i_pos0), Some(i_pos1)) = (i_pos0, i_pos1) else {
+                    continue;
+                };
+                edges.insert([i_pos0.min(i_pos1), i_pos0.max(i_pos1)]);
+                if (i_pos0 + 1) % num_vtx_loop == i_pos1 {
+                    edge2opp[i_pos0] = Some(xyz(node2vtx[(i_node + 2) % 3]));
+                }
+            }
+        }
+        let edge2opp = edge2opp
+            .iter()
+            .enumerate()
+            .map(|(i_pos, opp)| {
+                opp.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "the loop edge ({}, {}) is not an edge of the mesh",
+                        loop2vtx[i_pos],
+                        loop2vtx[(i_pos + 1) % num_vtx_loop]
+                    )
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Rim {
+            vtx2xyz: loop2vtx.iter().map(|&i_vtx| xyz(i_vtx)).collect(),
+            edge2opp,
+            edges,
+            vtx2scale: pos2length
+                .iter()
+                .map(|&(sum, num)| sum / num.as_())
+                .collect(),
+        })
+    }
+}
+
+/// cost of a partial triangulation
+#[derive(Clone, Copy)]
+struct Cost<Real> {
+    /// number of the triangulation edges that already exist in the mesh
+    num_dup: usize,
+    angle: Real,
+    area: Real,
+}
+
+impl<Real> Cost<Real>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    fn zero() -> Self {
+        Cost {
+            num_dup: 0,
+            angle: Real::zero(),
+            area: Real::zero(),
+        }
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Cost {
+            num_dup: self.num_dup + other.num_dup,
+            angle: self.angle.max(other.angle),
+            area: self.area + other.area,
+        }
+    }
+
+    fn is_less(&self, other: &Self, weight: Weight) -> bool {
+        if self.num_dup != other.num_dup {
+            return self.num_dup < other.num_dup;
+        }
+        // ignore the difference of the angles within the numerical noise
+        if weight == Weight::DihedralArea && (self.angle - other.angle).abs() > 1.0e-3.as_() {
+            return self.angle < other.angle;
+        }
+        self.area < other.area
+    }
+}
+
+/// angle between the normals of the triangles
+fn dihedral_angle<Real>(n0: &[Real; 3], n1: &[Real; 3]) -> Real
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use del_geo_core::vec3;
+    let c = vec3::dot(n0, n1) / (vec3::norm(n0) * vec3::norm(n1));
+    if c.is_finite() {
+        c.max(-Real::one()).min(Real::one()).acos()
+    } else {
+        std::f64::consts::PI.as_()
+    }
+}
+
+/// triangulate the polygon (n-1, n-2, ..., 0), which goes around the hole in the direction
+/// opposite to the loop. The returned triangles are indexed by the position in the loop
+fn triangulate_rim<Real>(rim: &Rim<Real>, weight: Weight) -> Vec<usize>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use del_geo_core::tri3;
+    let n = rim.vtx2xyz.len();
+    let pos = |i: usize| n - 1 - i;
+    let xyz = |i: usize| &rim.vtx2xyz[pos(i)];
+    // normal of the mesh triangle adjacent to the polygon edge (i, i+1)
+    let normal_mesh = |i: usize| {
+        let i_edge = (2 * n - 2 - i) % n;
+        tri3::normal(xyz((i + 1) % n), xyz(i), &rim.edge2opp[i_edge])
+    };
+    let mut ik2cost = vec![Cost::zero(); n * n];
+    let mut ik2apex = vec![usize::MAX; n * n];
+    for len in 2..n {
+        for i in 0..n - len {
+            let k = i + len;
+            let is_closing = i == 0 && k == n - 1;
+            let is_dup = !is_closing && rim.edges.contains(&[pos(k), pos(i)]);
+            for m in i + 1..k {
+                let nrm = tri3::normal(xyz(i), xyz(m), xyz(k));
+                let mut angle = Real::zero();
+                if weight == Weight::DihedralArea {
+                    let nrm_im = match m == i + 1 {
+                        true => normal_mesh(i),
+                        false => tri3::normal(xyz(i), xyz(ik2apex[i * n + m]), xyz(m)),
+                    };
+                    let nrm_mk = match k == m + 1 {
+                        true => normal_mesh(m),
+                        false => tri3::normal(xyz(m), xyz(ik2apex[m * n + k]), xyz(k)),
+                    };
+                    angle = dihedral_angle(&nrm, &nrm_im).max(dihedral_angle(&nrm, &nrm_mk));
+                    if is_closing {
+                        angle = angle.max(dihedral_angle(&nrm, &normal_mesh(n - 1)));
+                    }
+                }
+                let cost = Cost {
+                    num_dup: usize::from(is_dup),
+                    angle,
+                    area: tri3::area(xyz(i), xyz(m), xyz(k)),
+                };
+                let cost = cost.add(&ik2cost[i * n + m]).add(&ik2cost[m * n + k]);
+                if ik2apex[i * n + k] == usize::MAX || cost.is_less(&ik2cost[i * n + k], weight) {
+                    ik2cost[i * n + k] = cost;
+                    ik2apex[i * n + k] = m;
+                }
+            }
+        }
+    }
+    let mut tri2vtx = Vec::with_capacity((n - 2) * 3);
+    let mut stack = vec![(0, n - 1)];
+    while let Some((i, k)) = stack.pop() {
+        if k == i + 1 {
+            continue;
+        }
+        let m = ik2apex[i * n + k];
+        tri2vtx.extend([pos(i), pos(m), pos(k)]);
+        stack.push((i, m));
+        stack.push((m, k));
+    }
+    tri2vtx
+}
+
+/// flip the edges of the patch until the sum of the opposite angles is less than pi.
+/// The loop edges and the `fixed_edges` are not created nor flipped
+fn relax<Real>(tri2vtx: &mut [usize], vtx2xyz: &[[Real; 3]], fixed_edges: &HashSet<[usize; 2]>)
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use del_geo_core::vec3;
+    let angle = |i0: usize, i1: usize, i2: usize| {
+        let (p0, p1, p2) = (&vtx2xyz[i0], &vtx2xyz[i1], &vtx2xyz[i2]);
+        let (l01, l02, l12) = (
+            vec3::distance(p0, p1),
+            vec3::distance(p0, p2),
+            vec3::distance(p1, p2),
+        );
+        let two = Real::one() + Real::one();
+        ((l01 * l01 + l02 * l02 - l12 * l12) / (two * l01 * l02))
+            .max(-Real::one())
+            .min(Real::one())
+            .acos()
+    };
+    let num_tri = tri2vtx.len() / 3;
+    for _iter in 0..100 {
+        let mut edge2tri = BTreeMap::<[usize; 2], Vec<usize>>::new();
+        for (i_tri, node2vtx) in tri2vtx.chunks(3).enumerate() {
+            for i_node in 0..3 {
+                let (i0, i1) = (node2vtx[i_node], node2vtx[(i_node + 1) % 3]);
+                edge2tri
+                    .entry([i0.min(i1), i0.max(i1)])
+                    .or_default()
+                    .push(i_tri);
+            }
+        }
+        let mut tri2flag = vec![false; num_tri];
+        let mut new_edges = HashSet::new();
+        for (edge, tris) in &edge2tri {
+            let &[i_tri0, i_tri1] = tris.as_slice() else {
+                continue;
+            };
+            if tri2flag[i_tri0] || tri2flag[i_tri1] {
+                continue;
+            }
+            // i_tri0 is (a, b, c) and i_tri1 is (b, a, d)
+            let node0 = (0..3)
+                .find(|&i_node| !edge.contains(&tri2vtx[i_tri0 * 3 + i_node]))
+                .unwrap();
+            let node1 = (0..3)
+                .find(|&i_node| !edge.contains(&tri2vtx[i_tri1 * 3 + i_node]))
+                .unwrap();
+            let c = tri2vtx[i_tri0 * 3 + node0];
+            let a = tri2vtx[i_tri0 * 3 + (node0 + 1) % 3];
+            let b = tri2vtx[i_tri0 * 3 + (node0 + 2) % 3];
+            let d = tri2vtx[i_tri1 * 3 + node1];
+            let new_edge = [c.min(d), c.max(d)];
+            if c == d
+                || edge2tri.contains_key(&new_edge)
+                || new_edges.contains(&new_edge)
+                || fixed_edges.contains(&new_edge)
+            {
+                continue;
+            }
+            let pi: Real = std::f64::consts::PI.as_();
+            if angle(c, a, b) + angle(d, b, a) <= pi + 1.0e-6.as_() {
+                continue;
+            }
+            tri2vtx[i_tri0 * 3..i_tri0 * 3 + 3].copy_from_slice(&[c, a, d]);
+            tri2vtx[i_tri1 * 3..i_tri1 * 3 + 3].copy_from_slice(&[d, b, c]);
+            tri2flag[i_tri0] = true;
+            tri2flag[i_tri1] = true;
+            new_edges.insert(new_edge);
+        }
+        if new_edges.is_empty() {
+            break;
+        }
+    }
+}
+
+/// split the triangles of the patch at the centroid while the centroid is far from the
+/// corners compared to the local edge length, relaxing the edges after each pass
+fn refine<Real>(
+    tri2vtx: &mut Vec<usize>,
+    vtx2xyz: &mut Vec<[Real; 3]>,
+    vtx2scale: &mut Vec<Real>,
+    fixed_edges: &HashSet<[usize; 2]>,
+    density: Real,
+) where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    use del_geo_core::vec3;
+    let one_third: Real = (1. / 3.).as_();
+    loop {
+        let mut is_split = false;
+        for i_tri in 0..tri2vtx.len() / 3 {
+            let node2vtx = *arrayref::array_ref!(tri2vtx, i_tri * 3, 3);
+            let center = vec3::scale(
+                &vec3::add(
+                    &vec3::add(&vtx2xyz[node2vtx[0]], &vtx2xyz[node2vtx[1]]),
+                    &vtx2xyz[node2vtx[2]],
+                ),
+                one_third,
+            );
+            let scale = node2vtx
+                .iter()
+                .fold(Real::zero(), |sum, &i_vtx| sum + vtx2scale[i_vtx])
+                * one_third;
+            let is_coarse = node2vtx.iter().all(|&i_vtx| {
+                let dist = density * vec3::distance(&center, &vtx2xyz[i_vtx]);
+                dist > scale && dist > vtx2scale[i_vtx]
+            });
+            if !is_coarse {
+                continue;
+            }
+            let i_vtx_new = vtx2xyz.len();
+            vtx2xyz.push(center);
+            vtx2scale.push(scale);
+            let [i0, i1, i2] = node2vtx;
+            tri2vtx[i_tri * 3 + 2] = i_vtx_new;
+            tri2vtx.extend([i1, i2, i_vtx_new, i2, i0, i_vtx_new]);
+            is_split = true;
+        }
+        if !is_split {
+            break;
+        }
+        relax(tri2vtx, vtx2xyz, fixed_edges);
+    }
+}
+
+/// triangulate the hole bounded by `loop2vtx` without adding vertices. The loop goes along the
+/// boundary edges as returned by [`crate::trimesh_topology::boundary_loops`], and the returned
+/// triangles are oriented consistently with `tri2vtx`. The cost is cubic to the loop length
+pub fn triangulate_hole<Real>(
+    tri2vtx: &[usize],
+    vtx2xyz: &[Real],
+    loop2vtx: &[usize],
+    weight: Weight,
+) -> anyhow::Result<Vec<usize>>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+    usize: AsPrimitive<Real>,
+{
+    let rim = Rim::new(tri2vtx, vtx2xyz, loop2vtx)?;
+    let patch2vtx = triangulate_rim(&rim, weight);
+    Ok(patch2vtx.iter().map(|&i_pos| loop2vtx[i_pos]).collect())
+}
+
+/// fill the hole bounded by `loop2vtx`, adding the triangles to `tri2vtx` and the inserted
+/// vertices to the end of `vtx2xyz`
+pub fn fill_hole<Real>(
+    tri2vtx: &mut Vec<usize>,
+    vtx2xyz: &mut Vec<Real>,
+    loop2vtx: &[usize],
+    options: &Options,
+) -> anyhow::Result<()>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+    usize: AsPrimitive<Real>,
+{
+    let rim = Rim::new(tri2vtx, vtx2xyz, loop2vtx)?;
+    let num_pos_loop = loop2vtx.len();
+    let mut pos2xyz = rim.vtx2xyz.clone();
+    let mut pos2scale = rim.vtx2scale.clone();
+    let mut patch2pos = triangulate_rim(&rim, options.weight);
+    if options.is_refine {
+        relax(&mut patch2pos, &pos2xyz, &rim.edges);
+        refine(
+            &mut patch2pos,
+            &mut pos2xyz,
+            &mut pos2scale,
+            &rim.edges,
+            options.density.as_(),
+        );
+    }
+    let num_pos = pos2xyz.len();
+    if num_pos > num_pos_loop {
+        // membrane fairing with the loop vertices fixed
+        let (pos2jdx, jdx2pos) =
+            crate::vtx2vtx::from_uniform_mesh::<usize>(&patch2pos, 3, num_pos, false);
+        for _iter in 0..options.num_iter_fair {
+            for i_pos in num_pos_loop..num_pos {
+                let mut sum = [Real::zero(); 3];
+                for &j_pos in &jdx2pos[pos2jdx[i_pos]..pos2jdx[i_pos + 1]] {
+                    sum = del_geo_core::vec3::add(&sum, &pos2xyz[j_pos]);
+                }
+                let num_adj: Real = (pos2jdx[i_pos + 1] - pos2jdx[i_pos]).as_();
+                pos2xyz[i_pos] = del_geo_core::vec3::scale(&sum, Real::one() / num_adj);
+            }
+        }
+    }
+    let num_vtx = vtx2xyz.len() / 3;
+    for xyz in &pos2xyz[num_pos_loop..] {
+        vtx2xyz.extend(xyz);
+    }
+    tri2vtx.extend(patch2pos.iter().map(|&i_pos| {
+        if i_pos < num_pos_loop {
+            loop2vtx[i_pos]
+        } else {
+            num_vtx + i_pos - num_pos_loop
+        }
+    }));
+    Ok(())
+}
+
+/// fill the boundary loops for which `is_fill` returns true given the vertices of the loop.
+/// Returns the number of the filled holes
+pub fn fill_holes<Real, F>(
+    tri2vtx: &mut Vec<usize>,
+    vtx2xyz: &mut Vec<Real>,
+    options: &Options,
+    is_fill: F,
+) -> anyhow::Result<usize>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+    usize: AsPrimitive<Real>,
+    F: Fn(&[usize]) -> bool,
+{
+    let (loop2idx, idx2vtx) = crate::trimesh_topology::boundary_loops(tri2vtx, vtx2xyz.len() / 3)?;
+    let mut num_filled = 0;
+    for loop2vtx in loop2idx.windows(2).map(|r| &idx2vtx[r[0]..r[1]]) {
+        if !is_fill(loop2vtx) {
+            continue;
+        }
+        fill_hole(tri2vtx, vtx2xyz, loop2vtx, options)?;
+        num_filled += 1;
+    }
+    Ok(num_filled)
+}
+
+#[test]
+fn test_fill_holes() -> anyhow::Result<()> {
+    let (tri2vtx0, vtx2xyz0) = crate::trimesh3_primitive::sphere_yup::<usize, f64>(1.0, 32, 32);
+    let volume = |tri2vtx: &[usize], vtx2xyz: &[f64]| {
+        crate::trimesh_orientation::group2volume(tri2vtx, vtx2xyz, 1, &vec![0; tri2vtx.len() / 3])
+            [0]
+    };
+    let volume0 = volume(&tri2vtx0, &vtx2xyz0);
+    // open a hole at the top and a smaller one at the side
+    let tri2vtx1: Vec<usize> = tri2vtx0
+        .chunks(3)
+        .filter(|node2vtx| {
+            let c = [0, 1, 2].map(|i_dim| {
+                node2vtx
+                    .iter()
+                    .map(|&i_vtx| vtx2xyz0[i_vtx * 3 + i_dim])
+                    .sum::<f64>()
+                    / 3.
+            });
+            c[1] < 0.9 && c[0] < 0.95
+        })
+        .flatten()
+        .copied()
+        .collect();
+    let (loop2idx, idx2vtx) =
+        crate::trimesh_topology::boundary_loops(&tri2vtx1, vtx2xyz0.len() / 3)?;
+    assert_eq!(loop2idx.len(), 3);
+    // the loop goes along the triangles
+    for loop2vtx in loop2idx.windows(2).map(|r| &idx2vtx[r[0]..r[1]]) {
+        for i in 0..loop2vtx.len() {
+            let (i0, i1) = (loop2vtx[i], loop2vtx[(i + 1) % loop2vtx.len()]);
+            assert!(tri2vtx1.chunks(3).any(|node2vtx| (0..3)
+                .any(|i_node| node2vtx[i_node] == i0 && node2vtx[(i_node + 1) % 3] == i1)));
+        }
+    }
+    // triangulation without the refinement
+    let loop2vtx = &idx2vtx[loop2idx[0]..loop2idx[1]];
+    for weight in [Weight::Area, Weight::DihedralArea] {
+        let patch2vtx = triangulate_hole(&tri2vtx1, &vtx2xyz0, loop2vtx, weight)?;
+        assert_eq!(patch2vtx.len(), (loop2vtx.len() - 2) * 3);
+        let tri2vtx = [tri2vtx1.clone(), patch2vtx].concat();
+        let (_, idx2vtx) = crate::trimesh_topology::boundary_loops(&tri2vtx, vtx2xyz0.len() / 3)?;
+        assert_eq!(idx2vtx.len(), loop2idx[2] - loop2idx[1]);
+    }
+    // fill only the smaller hole
+    let (mut tri2vtx, mut vtx2xyz) = (tri2vtx1.clone(), vtx2xyz0.clone());
+    let num_filled = fill_holes(&mut tri2vtx, &mut vtx2xyz, &Options::default(), |l| {
+        l.len() < 32
+    })?;
+    assert_eq!(num_filled, 1);
+    // fill all the holes
+    let (mut tri2vtx, mut vtx2xyz) = (tri2vtx1.clone(), vtx2xyz0.clone());
+    let num_filled = fill_holes(&mut tri2vtx, &mut vtx2xyz, &Options::default(), |_| true)?;
+    assert_eq!(num_filled, 2);
+    assert!(vtx2xyz.len() > vtx2xyz0.len());
+    crate::io_obj::save_tri2vtx_vtx2xyz("../target/trimesh3_fill_hole.obj", &tri2vtx, &vtx2xyz, 3)?;
+    let report = crate::mesh_check::check_triangle_mesh(&tri2vtx, &vtx2xyz, 3, &Default::default());
+    assert!(report.is_valid(), "{}", report);
+    assert_eq!(report.num_boundary_loop, 0);
+    let volume1 = volume(&tri2vtx, &vtx2xyz);
+    assert!(
+        (volume1 - volume0).abs() < volume0 * 0.02,
+        "{} {}",
+        volume0,
+        volume1
+    );
+    Ok(())
+}
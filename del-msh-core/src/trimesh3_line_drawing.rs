@@ -0,0 +1,401 @@
+//! vector line drawing of the feature lines of a 3D triangle mesh with the hidden lines removed
+
+use anyhow::Context;
+use std::io::Write;
+
+/// style of the SVG strokes
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineStyle {
+    /// SVG color (e.g., `black`, `#ff0000`)
+    pub color: String,
+    /// stroke width in pixels
+    pub width: f32,
+    /// `stroke-dasharray` (e.g., `4 2`)
+    pub dasharray: Option<String>,
+}
+
+impl LineStyle {
+    pub fn new(color: &str, width: f32) -> Self {
+        LineStyle {
+            color: color.to_string(),
+            width,
+            dasharray: None,
+        }
+    }
+
+    pub fn dashed(color: &str, width: f32, dasharray: &str) -> Self {
+        LineStyle {
+            color: color.to_string(),
+            width,
+            dasharray: Some(dasharray.to_string()),
+        }
+    }
+
+    fn to_svg_attributes(&self) -> String {
+        let mut s = format!(
+            "fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"",
+            self.color, self.width
+        );
+        if let Some(dasharray) = &self.dasharray {
+            s += &format!(" stroke-dasharray=\"{}\"", dasharray);
+        }
+        s
+    }
+}
+
+/// category of lines (e.g., boundary, crease, silhouette or occluding contour) drawn in the same style
+pub struct LineCategory<'a> {
+    /// used as the `id` of the SVG group
+    pub name: &'a str,
+    pub edge2vtx: &'a [usize],
+    pub style: LineStyle,
+    /// style of the hidden part. The hidden part is removed if `None`
+    pub hidden_style: Option<LineStyle>,
+}
+
+/// chain the edges into polylines of the vertex indices. A closed polyline ends with its first vertex.
+/// The polylines are split at the vertices shared by other than two edges
+pub fn polylines_from_edge2vtx(edge2vtx: &[usize]) -> Vec<Vec<usize>> {
+    let num_vtx = edge2vtx.iter().map(|&i| i + 1).max().unwrap_or(0);
+    let (vtx2idx, idx2edge) = crate::vtx2elem::from_uniform_mesh(edge2vtx, 2, num_vtx);
+    let num_edge = edge2vtx.len() / 2;
+    let mut edge2flag = vec![false; num_edge];
+    let mut polylines = vec![];
+    let degree = |i_vtx: usize| vtx2idx[i_vtx + 1] - vtx2idx[i_vtx];
+    // trace from the end points first so that the open polylines are not split
+    let start_vtxs = (0..num_vtx)
+        .filter(|&i_vtx| degree(i_vtx) != 2)
+        .chain(0..num_vtx);
+    for i_vtx_start in start_vtxs {
+        for &i_edge_start in &idx2edge[vtx2idx[i_vtx_start]..vtx2idx[i_vtx_start + 1]] {
+            if edge2flag[i_edge_start] {
+                continue;
+            }
+            let mut polyline = vec![i_vtx_start];
+            let (mut i_vtx, mut i_edge) = (i_vtx_start, i_edge_start);
+            loop {
+                edge2flag[i_edge] = true;
+                let j_vtx = edge2vtx[i_edge * 2] + edge2vtx[i_edge * 2 + 1] - i_vtx;
+                polyline.push(j_vtx);
+                if degree(j_vtx) != 2 {
+                    break;
+                }
+                let next = idx2edge[vtx2idx[j_vtx]..vtx2idx[j_vtx + 1]]
+                    .iter()
+                    .find(|&&j_edge| !edge2flag[j_edge]);
+                let Some(&j_edge) = next else {
+                    break; // closed
+                };
+                (i_vtx, i_edge) = (j_vtx, j_edge);
+            }
+            polylines.push(polyline);
+        }
+    }
+    polylines
+}
+
+/// projection of the lines on a triangle mesh to the SVG canvas
+pub struct LineDrawing<'a> {
+    pub tri2vtx: &'a [usize],
+    pub vtx2xyz: &'a [f32],
+    pub bvhnodes: &'a [usize],
+    pub bvhnode2aabb: &'a [f32],
+    /// view-projection transformation (column major)
+    pub transform_world2ndc: &'a [f32; 16],
+    /// size of the canvas in pixels
+    pub width: f32,
+    pub height: f32,
+    /// interval of the visibility tests along the lines in pixels
+    pub sample_spacing: f32,
+}
+
+impl LineDrawing<'_> {
+    /// position on the canvas. The y-axis of SVG points downward
+    fn to_canvas(&self, ndc: &[f32; 3]) -> [f32; 2] {
+        [
+            (ndc[0] + 1.) * 0.5 * self.width,
+            (1. - ndc[1]) * 0.5 * self.height,
+        ]
+    }
+
+    /// whether the point on the edge `(i0_vtx, i1_vtx)` is not occluded by the mesh
+    fn is_visible(
+        &self,
+        pos: &[f32; 3],
+        i0_vtx: usize,
+        i1_vtx: usize,
+        transform_ndc2world: &[f32; 16],
+    ) -> bool {
+        let (_, ray_dir) = del_geo_core::mat4_col_major::ray_from_transform_world2ndc(
+            self.transform_world2ndc,
+            pos,
+            transform_ndc2world,
+        );
+        let mut hits = vec![];
+        crate::search_bvh3::intersections_ray(
+            &mut hits,
+            pos,
+            &ray_dir,
+            &crate::search_bvh3::TriMeshWithBvh {
+                tri2vtx: self.tri2vtx,
+                vtx2xyz: self.vtx2xyz,
+                bvhnodes: self.bvhnodes,
+                bvhnode2aabb: self.bvhnode2aabb,
+            },
+            0,
+        );
+        // the triangles around the edge trivially intersect the ray at its origin
+        !hits.iter().any(|&(t, i_tri)| {
+            let node2vtx = &self.tri2vtx[i_tri * 3..i_tri * 3 + 3];
+            t > 1.0e-5 && !node2vtx.contains(&i0_vtx) && !node2vtx.contains(&i1_vtx)
+        })
+    }
+
+    /// visible and hidden parts of the edges as the polylines on the canvas
+    #[allow(clippy::type_complexity)]
+    pub fn project(
+        &self,
+        edge2vtx: &[usize],
+    ) -> anyhow::Result<(Vec<Vec<[f32; 2]>>, Vec<Vec<[f32; 2]>>)> {
+        use del_geo_core::mat4_col_major;
+        let transform_ndc2world = mat4_col_major::try_inverse(self.transform_world2ndc)
+            .context("the view-projection transformation is singular")?;
+        let project = |p: &[f32; 3]| -> anyhow::Result<[f32; 3]> {
+            mat4_col_major::transform_homogeneous(self.transform_world2ndc, p)
+                .context("point at infinity")
+        };
+        let mut visible = vec![];
+        let mut hidden = vec![];
+        for polyline in polylines_from_edge2vtx(edge2vtx) {
+            // samples on the polyline with the visibility
+            let mut samples: Vec<([f32; 2], bool)> = vec![];
+            for (i_seg, node2vtx) in polyline.windows(2).enumerate() {
+                let (i0_vtx, i1_vtx) = (node2vtx[0], node2vtx[1]);
+                let p0 = arrayref::array_ref![self.vtx2xyz, i0_vtx * 3, 3];
+                let p1 = arrayref::array_ref![self.vtx2xyz, i1_vtx * 3, 3];
+                let (q0, q1) = (self.to_canvas(&project(p0)?), self.to_canvas(&project(p1)?));
+                let len = ((q1[0] - q0[0]).powi(2) + (q1[1] - q0[1]).powi(2)).sqrt();
+                let num_div = ((len / self.sample_spacing).ceil() as usize).max(1);
+                let i_start = if i_seg == 0 { 0 } else { 1 };
+                for i_div in i_start..=num_div {
+                    let r = i_div as f32 / num_div as f32;
+                    let p: [f32; 3] = std::array::from_fn(|i| p0[i] * (1. - r) + p1[i] * r);
+                    let is_visible = self.is_visible(&p, i0_vtx, i1_vtx, &transform_ndc2world);
+                    samples.push((self.to_canvas(&project(&p)?), is_visible));
+                }
+            }
+            // split at the middle of the samples with different visibility
+            let mut run = vec![samples[0].0];
+            for (s0, s1) in samples.iter().zip(samples.iter().skip(1)) {
+                if s0.1 == s1.1 {
+                    run.push(s1.0);
+                    continue;
+                }
+                let mid = [(s0.0[0] + s1.0[0]) * 0.5, (s0.0[1] + s1.0[1]) * 0.5];
+                run.push(mid);
+                let run_done = std::mem::replace(&mut run, vec![mid, s1.0]);
+                if s0.1 {
+                    visible.push(run_done);
+                } else {
+                    hidden.push(run_done);
+                }
+            }
+            if samples.last().unwrap().1 {
+                visible.push(run);
+            } else {
+                hidden.push(run);
+            }
+        }
+        Ok((visible, hidden))
+    }
+
+    /// write the categories as the SVG groups. The hidden lines are drawn below the visible ones
+    pub fn write_svg<W: Write>(
+        &self,
+        writer: &mut W,
+        categories: &[LineCategory],
+    ) -> anyhow::Result<()> {
+        writeln!(
+            writer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+            self.width, self.height, self.width, self.height
+        )?;
+        let category2lines = categories
+            .iter()
+            .map(|c| self.project(c.edge2vtx))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let write_group = |writer: &mut W,
+                           id: &str,
+                           style: &LineStyle,
+                           polylines: &[Vec<[f32; 2]>]|
+         -> anyhow::Result<()> {
+            writeln!(writer, "<g id=\"{}\" {}>", id, style.to_svg_attributes())?;
+            for polyline in polylines {
+                let points: Vec<String> = polyline
+                    .iter()
+                    .map(|p| format!("{:.2},{:.2}", p[0], p[1]))
+                    .collect();
+                writeln!(writer, "<polyline points=\"{}\"/>", points.join(" "))?;
+            }
+            writeln!(writer, "</g>")?;
+            Ok(())
+        };
+        for (category, (_, hidden)) in categories.iter().zip(category2lines.iter()) {
+            if let Some(hidden_style) = &category.hidden_style {
+                write_group(
+                    writer,
+                    &format!("{}_hidden", category.name),
+                    hidden_style,
+                    hidden,
+                )?;
+            }
+        }
+        for (category, (visible, _)) in categories.iter().zip(category2lines.iter()) {
+            write_group(writer, category.name, &category.style, visible)?;
+        }
+        writeln!(writer, "</svg>")?;
+        Ok(())
+    }
+
+    pub fn save_svg<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        categories: &[LineCategory],
+    ) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path.as_ref())
+            .with_context(|| format!("cannot create {}", path.as_ref().display()))?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write_svg(&mut file, categories)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_line_drawing() -> anyhow::Result<()> {
+    use del_geo_core::mat4_col_major;
+    let (tri2vtx, vtx2xyz) = crate::trimesh3_primitive::torus_zup::<usize, f32>(0.8, 0.3, 32, 16);
+    let transform_world2ndc = {
+        let ez = del_geo_core::vec3::normalized(&[0f32, -1.0, 1.0]);
+        let (ex, ey) = del_geo_core::vec3::basis_xy_from_basis_z(&ez);
+        let m3 = del_geo_core::mat3_col_major::from_column_vectors(
+            &del_geo_core::vec3::scale(&ex, 0.8),
+            &del_geo_core::vec3::scale(&ey, 0.8),
+            &del_geo_core::vec3::scale(&ez, 0.8),
+        );
+        let t = mat4_col_major::from_mat3_col_major_adding_w(&m3);
+        mat4_col_major::transpose(&t)
+    };
+    let bvhnodes = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx, &vtx2xyz, 3);
+    let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh(
+        0,
+        &bvhnodes,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let num_vtx = vtx2xyz.len() / 3;
+    let edge2vtx = crate::edge2vtx::from_triangle_mesh(&tri2vtx, num_vtx);
+    let edge2tri = crate::edge2elem::from_edge2vtx_of_tri2vtx(&edge2vtx, &tri2vtx, num_vtx);
+    assert!(crate::edge2vtx::boundary_for_triangle_mesh(&edge2vtx, &edge2tri).is_empty());
+    let edge2vtx_silhouette = crate::edge2vtx::silhouette_for_triangle_mesh(
+        &tri2vtx,
+        &vtx2xyz,
+        &transform_world2ndc,
+        &edge2vtx,
+        &edge2tri,
+        &bvhnodes,
+        &bvhnode2aabb,
+    );
+    let edge2vtx_crease =
+        crate::edge2vtx::crease_for_triangle_mesh(&tri2vtx, &vtx2xyz, &edge2vtx, &edge2tri, 0.3);
+    // the cross-sectional circles of the torus
+    let edge2vtx_ring: Vec<usize> = (0..16).flat_map(|i| [i, (i + 1) % 16]).collect();
+    let polylines = polylines_from_edge2vtx(&edge2vtx_ring);
+    assert_eq!(polylines.len(), 1);
+    assert_eq!(polylines[0].len(), 17);
+    assert_eq!(polylines[0][0], polylines[0][16]);
+    let polylines = polylines_from_edge2vtx(&[3, 4, 1, 2, 2, 3, 2, 5]);
+    assert_eq!(polylines.len(), 3);
+    //
+    let drawing = LineDrawing {
+        tri2vtx: &tri2vtx,
+        vtx2xyz: &vtx2xyz,
+        bvhnodes: &bvhnodes,
+        bvhnode2aabb: &bvhnode2aabb,
+        transform_world2ndc: &transform_world2ndc,
+        width: 400.,
+        height: 400.,
+        sample_spacing: 2.,
+    };
+    let (visible, hidden) = drawing.project(&edge2vtx)?;
+    assert!(!visible.is_empty() && !hidden.is_empty());
+    drawing.save_svg(
+        "../target/trimesh3_line_drawing.svg",
+        &[
+            LineCategory {
+                name: "crease",
+                edge2vtx: &edge2vtx_crease,
+                style: LineStyle::new("gray", 0.5),
+                hidden_style: None,
+            },
+            LineCategory {
+                name: "ring",
+                edge2vtx: &edge2vtx_ring,
+                style: LineStyle::new("red", 1.0),
+                hidden_style: Some(LineStyle::dashed("red", 0.5, "2 2")),
+            },
+            LineCategory {
+                name: "silhouette",
+                edge2vtx: &edge2vtx_silhouette,
+                style: LineStyle::new("black", 1.5),
+                hidden_style: None,
+            },
+        ],
+    )?;
+    // the result can be read back as polylines
+    let doc = std::fs::read_to_string("../target/trimesh3_line_drawing.svg")?;
+    let loops = crate::io_svg::polybeziers_from_svg_document(&doc)?;
+    assert!(!loops.is_empty());
+    // the middle of the upper edge of the back quad is hidden by the front quad
+    let vtx2xyz = [
+        [-0.5, -0.5, -0.5],
+        [0.5, -0.5, -0.5],
+        [0.5, 0.5, -0.5],
+        [-0.5, 0.5, -0.5],
+        [-0.8, -0.2, 0.5],
+        [0.8, -0.2, 0.5],
+        [0.8, 0.2, 0.5],
+        [-0.8, 0.2, 0.5],
+    ]
+    .concat();
+    let tri2vtx = [0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7];
+    let bvhnodes = crate::bvhnodes_morton::from_triangle_mesh(&tri2vtx, &vtx2xyz, 3);
+    let bvhnode2aabb = crate::bvhnode2aabb3::from_uniform_mesh_with_bvh(
+        0,
+        &bvhnodes,
+        Some((&tri2vtx, 3)),
+        &vtx2xyz,
+        None,
+    );
+    let transform_world2ndc: [f32; 16] = std::array::from_fn(|i| (i % 5 == 0) as i32 as f32);
+    let drawing = LineDrawing {
+        tri2vtx: &tri2vtx,
+        vtx2xyz: &vtx2xyz,
+        bvhnodes: &bvhnodes,
+        bvhnode2aabb: &bvhnode2aabb,
+        transform_world2ndc: &transform_world2ndc,
+        width: 400.,
+        height: 400.,
+        sample_spacing: 2.,
+    };
+    let (visible, hidden) = drawing.project(&[6, 7])?;
+    assert_eq!((visible.len(), hidden.len()), (2, 1));
+    let (p0, p1) = (hidden[0][0], *hidden[0].last().unwrap());
+    assert!((p0[0] - 300.).abs() < 3. && (p1[0] - 100.).abs() < 3.);
+    assert!(hidden[0].iter().all(|p| (p[1] - 160.).abs() < 1.0e-3));
+    // the edge of the front quad is not hidden by the back quad
+    let (visible, hidden) = drawing.project(&[2, 3])?;
+    assert_eq!((visible.len(), hidden.len()), (1, 0));
+    Ok(())
+}
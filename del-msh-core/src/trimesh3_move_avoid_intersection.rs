@@ -189,8 +189,10 @@ pub fn match_vtx2xyz_while_avoid_collision(
                 println!("# of intersecting tripairs  {:}", tripairs.len());
                 if !tripairs.is_empty() {
                     dbg!("something is wrong");
-                    crate::io_off::save_tri_mesh("target/cand0.off", tri2vtx, &vtx2xyz);
-                    crate::io_off::save_tri_mesh("target/cand1.off", tri2vtx, &vtx2xyz_cand);
+                    // the debug dump is best effort
+                    let _ = crate::io_off::save_tri_mesh("target/cand0.off", tri2vtx, &vtx2xyz);
+                    let _ =
+                        crate::io_off::save_tri_mesh("target/cand1.off", tri2vtx, &vtx2xyz_cand);
                     //panic!();
                 }
                 // assert_eq!(tripairs.len(),0);
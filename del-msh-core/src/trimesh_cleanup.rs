@@ -0,0 +1,198 @@
+//! methods to weld the coincident vertices and to remove the broken triangles
+
+use std::collections::HashMap;
+
+/// merge the vertices within the distance `epsilon` using the uniform grid of the cell size
+/// `epsilon`. Each vertex is merged to the nearest earlier vertex within `epsilon` that is not
+/// merged, so the vertices are not merged by chaining. `epsilon = 0` merges the vertices with the
+/// same coordinates
+/// * Returns (vtxa2vtxb, num_vtxb). The new vertices are numbered in the order of appearance
+pub fn weld_vertices<Real>(vtx2xyz: &[Real], num_dim: usize, epsilon: Real) -> (Vec<usize>, usize)
+where
+    Real: num_traits::Float,
+{
+    assert!(num_dim <= 3);
+    let num_vtxa = vtx2xyz.len() / num_dim;
+    let mut vtxa2vtxb = vec![usize::MAX; num_vtxa];
+    if epsilon <= Real::zero() {
+        let mut key2vtxb = HashMap::<[u64; 3], usize>::new();
+        for (i_vtxa, xyz) in vtx2xyz.chunks(num_dim).enumerate() {
+            let mut key = [0u64; 3];
+            for i_dim in 0..num_dim {
+                // `+ 0.0` identifies the negative zero with the positive zero
+                key[i_dim] = (xyz[i_dim].to_f64().unwrap() + 0.0).to_bits();
+            }
+            let num_vtxb = key2vtxb.len();
+            vtxa2vtxb[i_vtxa] = *key2vtxb.entry(key).or_insert(num_vtxb);
+        }
+        return (vtxa2vtxb, key2vtxb.len());
+    }
+    let cell = |xyz: &[Real]| {
+        let mut cell = [0i64; 3];
+        for i_dim in 0..num_dim {
+            cell[i_dim] = (xyz[i_dim] / epsilon).floor().to_i64().unwrap_or(i64::MAX);
+        }
+        cell
+    };
+    let squared_epsilon = epsilon * epsilon;
+    let mut cell2vtxb = HashMap::<[i64; 3], Vec<usize>>::new();
+    let mut vtxb2vtxa = vec![];
+    for (i_vtxa, xyz) in vtx2xyz.chunks(num_dim).enumerate() {
+        let cell0 = cell(xyz);
+        let mut nearest: Option<(Real, usize)> = None;
+        for i_neighbor in 0..3usize.pow(num_dim as u32) {
+            let mut cell1 = cell0;
+            let mut i_digit = i_neighbor;
+            for c in cell1.iter_mut().take(num_dim) {
+                *c = c.wrapping_add((i_digit % 3) as i64 - 1);
+                i_digit /= 3;
+            }
+            let Some(vtxbs) = cell2vtxb.get(&cell1) else {
+                continue;
+            };
+            for &i_vtxb in vtxbs {
+                let j_vtxa = vtxb2vtxa[i_vtxb];
+                let squared_dist = (0..num_dim).fold(Real::zero(), |sum, i_dim| {
+                    let d = xyz[i_dim] - vtx2xyz[j_vtxa * num_dim + i_dim];
+                    sum + d * d
+                });
+                if squared_dist <= squared_epsilon
+                    && nearest.is_none_or(|(dist, _)| squared_dist < dist)
+                {
+                    nearest = Some((squared_dist, i_vtxb));
+                }
+            }
+        }
+        vtxa2vtxb[i_vtxa] = match nearest {
+            Some((_, i_vtxb)) => i_vtxb,
+            None => {
+                let i_vtxb = vtxb2vtxa.len();
+                vtxb2vtxa.push(i_vtxa);
+                cell2vtxb.entry(cell0).or_default().push(i_vtxb);
+                i_vtxb
+            }
+        };
+    }
+    (vtxa2vtxb, vtxb2vtxa.len())
+}
+
+/// remove the triangles with a repeated vertex and the triangles with the same vertices as an
+/// earlier triangle regardless of the orientation
+/// * Returns (tria2trib, num_trib). The removed triangles are mapped to `usize::MAX`
+pub fn remove_degenerate_and_duplicate_triangles(tri2vtx: &[usize]) -> (Vec<usize>, usize) {
+    let mut key2trib = HashMap::<[usize; 3], usize>::new();
+    let mut tria2trib = vec![usize::MAX; tri2vtx.len() / 3];
+    for (i_tria, node2vtx) in tri2vtx.chunks(3).enumerate() {
+        let mut key = [node2vtx[0], node2vtx[1], node2vtx[2]];
+        key.sort();
+        if key[0] == key[1] || key[1] == key[2] {
+            continue;
+        }
+        let num_trib = key2trib.len();
+        if let std::collections::hash_map::Entry::Vacant(e) = key2trib.entry(key) {
+            e.insert(num_trib);
+            tria2trib[i_tria] = num_trib;
+        }
+    }
+    (tria2trib, key2trib.len())
+}
+
+/// result of [`cleanup`]
+#[derive(Clone, Debug)]
+pub struct Cleanup<Real> {
+    pub tri2vtx: Vec<usize>,
+    pub vtx2xyz: Vec<Real>,
+    /// new index of the input vertex. `usize::MAX` if the vertex is removed
+    pub vtxa2vtxb: Vec<usize>,
+    pub num_vtxb: usize,
+    /// new index of the input triangle. `usize::MAX` if the triangle is removed
+    pub tria2trib: Vec<usize>,
+    pub num_trib: usize,
+}
+
+/// weld the vertices within `epsilon`, remove the degenerate and duplicate triangles, and then
+/// remove the unreferenced vertices. The coordinates of a welded vertex are taken from one of
+/// the merged vertices. The attributes of the input vertices and triangles can be carried with
+/// [`crate::map_idx::map_vertex_attibute_to`] using `vtxa2vtxb` and `tria2trib`
+pub fn cleanup<Real>(
+    tri2vtx: &[usize],
+    vtx2xyz: &[Real],
+    num_dim: usize,
+    epsilon: Real,
+) -> Cleanup<Real>
+where
+    Real: num_traits::Float,
+{
+    let (vtxa2vtxw, num_vtxw) = weld_vertices(vtx2xyz, num_dim, epsilon);
+    let tri2vtxw = crate::map_idx::map_elem_index(tri2vtx, &vtxa2vtxw);
+    let (tria2trib, num_trib) = remove_degenerate_and_duplicate_triangles(&tri2vtxw);
+    let tri2vtxw: Vec<usize> = tri2vtxw
+        .chunks(3)
+        .zip(tria2trib.iter())
+        .filter(|(_, &i_trib)| i_trib != usize::MAX)
+        .flat_map(|(node2vtx, _)| node2vtx.iter().copied())
+        .collect();
+    let (vtxw2vtxb, num_vtxb) =
+        crate::map_idx::from_remove_unreferenced_vertices(&tri2vtxw, num_vtxw);
+    let vtxa2vtxb: Vec<usize> = vtxa2vtxw.iter().map(|&i_vtxw| vtxw2vtxb[i_vtxw]).collect();
+    Cleanup {
+        tri2vtx: crate::map_idx::map_elem_index(&tri2vtxw, &vtxw2vtxb),
+        vtx2xyz: crate::map_idx::map_vertex_attibute_to(vtx2xyz, num_dim, &vtxa2vtxb, num_vtxb),
+        vtxa2vtxb,
+        num_vtxb,
+        tria2trib,
+        num_trib,
+    }
+}
+
+#[test]
+fn test_cleanup() {
+    use rand::{Rng, SeedableRng};
+    let mut rng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    let (tri2vtx0, vtx2xyz0) = crate::trimesh3_primitive::sphere_yup::<usize, f64>(1.0, 16, 16);
+    let num_tri0 = tri2vtx0.len() / 3;
+    // triangle soup with small noise
+    let mut tri2vtx: Vec<usize> = (0..tri2vtx0.len()).collect();
+    let mut vtx2xyz: Vec<f64> = crate::map_idx::map_vertex_attibute_from(&vtx2xyz0, 3, &tri2vtx0)
+        .iter()
+        .map(|&v| v + (rng.gen::<f64>() - 0.5) * 1.0e-6)
+        .collect();
+    // duplicated triangle with the opposite orientation
+    let num_vtx = vtx2xyz.len() / 3;
+    vtx2xyz.extend_from_within(0..9);
+    tri2vtx.extend([num_vtx, num_vtx + 2, num_vtx + 1]);
+    // degenerate triangle after the welding
+    vtx2xyz.extend_from_within(0..6);
+    let xyz = [vtx2xyz[0] + 1.0e-7, vtx2xyz[1], vtx2xyz[2]];
+    vtx2xyz.extend(xyz);
+    tri2vtx.extend([num_vtx + 3, num_vtx + 4, num_vtx + 5]);
+    // unreferenced vertex
+    vtx2xyz.extend([2., 0., 0.]);
+    let res = cleanup(&tri2vtx, &vtx2xyz, 3, 1.0e-5);
+    assert_eq!(res.num_vtxb, vtx2xyz0.len() / 3);
+    assert_eq!(res.num_trib, num_tri0);
+    assert_eq!(res.tri2vtx.len(), num_tri0 * 3);
+    assert_eq!(res.vtx2xyz.len(), res.num_vtxb * 3);
+    assert_eq!(res.tria2trib[num_tri0..], [usize::MAX, usize::MAX]);
+    assert_eq!(*res.vtxa2vtxb.last().unwrap(), usize::MAX);
+    let report =
+        crate::mesh_check::check_triangle_mesh(&res.tri2vtx, &res.vtx2xyz, 3, &Default::default());
+    assert!(report.is_valid(), "{}", report);
+    assert_eq!(report.num_boundary_loop, 0);
+    // carry the attributes
+    for (i_vtxa, &i_vtxb) in res.vtxa2vtxb.iter().enumerate() {
+        if i_vtxb == usize::MAX {
+            continue;
+        }
+        let p0 = arrayref::array_ref!(vtx2xyz, i_vtxa * 3, 3);
+        let p1 = arrayref::array_ref!(res.vtx2xyz, i_vtxb * 3, 3);
+        assert!(del_geo_core::vec3::distance(p0, p1) < 1.0e-5);
+    }
+    let tria2id: Vec<usize> = (0..tri2vtx.len() / 3).collect();
+    let trib2id = crate::map_idx::map_vertex_attibute_to(&tria2id, 1, &res.tria2trib, res.num_trib);
+    assert_eq!(trib2id, (0..num_tri0).collect::<Vec<_>>());
+    // exact welding
+    let vtx2xyz = crate::map_idx::map_vertex_attibute_from(&vtx2xyz0, 3, &tri2vtx0);
+    let (_, num_vtx) = weld_vertices(&vtx2xyz, 3, 0.);
+    assert_eq!(num_vtx, vtx2xyz0.len() / 3);
+}
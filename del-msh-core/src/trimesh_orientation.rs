@@ -0,0 +1,205 @@
+//! methods to make the orientation of the triangles consistent
+
+use num_traits::AsPrimitive;
+
+/// result of the orientation repair
+#[derive(Clone, Debug, PartialEq)]
+pub struct Orientation {
+    pub num_group: usize,
+    /// connected component of the triangle
+    pub tri2group: Vec<usize>,
+    /// triangles flipped by the repair
+    pub tri_flipped: Vec<usize>,
+    /// the group does not have a consistent orientation (e.g., Möbius strip). Such a group is
+    /// oriented consistently except around the edges where the propagation collides
+    pub group2non_orientable: Vec<bool>,
+    /// the group does not have a boundary edge
+    pub group2closed: Vec<bool>,
+}
+
+impl Orientation {
+    pub fn non_orientable_groups(&self) -> Vec<usize> {
+        (0..self.num_group)
+            .filter(|&i_group| self.group2non_orientable[i_group])
+            .collect()
+    }
+}
+
+/// flip the triangles so that the adjacent triangles go along the shared edge in the opposite
+/// directions. The orientation is propagated from the triangle with the smallest index in each
+/// connected component
+pub fn orient_consistently(tri2vtx: &mut [usize], num_vtx: usize) -> Orientation {
+    let num_tri = tri2vtx.len() / 3;
+    let (face2idx, idx2node) = crate::elem2elem::face2node_of_simplex_element(3);
+    let tri2tri = crate::elem2elem::from_uniform_mesh(tri2vtx, 3, &face2idx, &idx2node, num_vtx);
+    let (num_group, tri2group) =
+        crate::elem2group::from_uniform_mesh_with_elem2elem(tri2vtx, 3, &tri2tri);
+    let mut group2non_orientable = vec![false; num_group];
+    let mut group2closed = vec![true; num_group];
+    // 0: not visited, 1: keep, 2: flip
+    let mut tri2flag = vec![0u8; num_tri];
+    let mut stack = vec![];
+    for i_tri0 in 0..num_tri {
+        if tri2flag[i_tri0] != 0 {
+            continue;
+        }
+        let i_group = tri2group[i_tri0];
+        tri2flag[i_tri0] = 1;
+        stack.push(i_tri0);
+        while let Some(i_tri) = stack.pop() {
+            let is_flip = tri2flag[i_tri] == 2;
+            for i_node in 0..3 {
+                let j_tri = tri2tri[i_tri * 3 + i_node];
+                if j_tri == usize::MAX {
+                    group2closed[i_group] = false;
+                    continue;
+                }
+                // edge opposite to the node in the original orientation
+                let i0 = tri2vtx[i_tri * 3 + (i_node + 1) % 3];
+                let i1 = tri2vtx[i_tri * 3 + (i_node + 2) % 3];
+                let is_same_direction = (0..3).any(|j_node| {
+                    tri2vtx[j_tri * 3 + j_node] == i0 && tri2vtx[j_tri * 3 + (j_node + 1) % 3] == i1
+                });
+                let flag = if is_same_direction != is_flip { 2 } else { 1 };
+                if tri2flag[j_tri] == 0 {
+                    tri2flag[j_tri] = flag;
+                    stack.push(j_tri);
+                } else if tri2flag[j_tri] != flag {
+                    group2non_orientable[i_group] = true;
+                }
+            }
+        }
+    }
+    let tri_flipped: Vec<usize> = (0..num_tri).filter(|&i_tri| tri2flag[i_tri] == 2).collect();
+    for &i_tri in &tri_flipped {
+        tri2vtx.swap(i_tri * 3 + 1, i_tri * 3 + 2);
+    }
+    Orientation {
+        num_group,
+        tri2group,
+        tri_flipped,
+        group2non_orientable,
+        group2closed,
+    }
+}
+
+/// signed volume of each group. It is the enclosed volume if the group is closed and its
+/// triangles are counter-clockwise seen from outside
+pub fn group2volume<Real>(
+    tri2vtx: &[usize],
+    vtx2xyz: &[Real],
+    num_group: usize,
+    tri2group: &[usize],
+) -> Vec<Real>
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let mut group2volume = vec![Real::zero(); num_group];
+    for (i_tri, node2vtx) in tri2vtx.chunks(3).enumerate() {
+        let p = [0, 1, 2].map(|i_node| arrayref::array_ref!(vtx2xyz, node2vtx[i_node] * 3, 3));
+        let v = p[0][0] * (p[1][1] * p[2][2] - p[1][2] * p[2][1])
+            + p[0][1] * (p[1][2] * p[2][0] - p[1][0] * p[2][2])
+            + p[0][2] * (p[1][0] * p[2][1] - p[1][1] * p[2][0]);
+        let i_group = tri2group[i_tri];
+        group2volume[i_group] = group2volume[i_group] + v * (1. / 6.).as_();
+    }
+    group2volume
+}
+
+/// make the orientation consistent, then flip every closed and orientable group whose signed
+/// volume is negative so that its triangles face outward
+pub fn orient_outward<Real>(tri2vtx: &mut [usize], vtx2xyz: &[Real]) -> Orientation
+where
+    Real: num_traits::Float + 'static,
+    f64: AsPrimitive<Real>,
+{
+    let mut orientation = orient_consistently(tri2vtx, vtx2xyz.len() / 3);
+    let group2volume = group2volume(
+        tri2vtx,
+        vtx2xyz,
+        orientation.num_group,
+        &orientation.tri2group,
+    );
+    let group2flip: Vec<bool> = (0..orientation.num_group)
+        .map(|i_group| {
+            orientation.group2closed[i_group]
+                && !orientation.group2non_orientable[i_group]
+                && group2volume[i_group] < Real::zero()
+        })
+        .collect();
+    let num_tri = tri2vtx.len() / 3;
+    let mut tri2flipped = vec![false; num_tri];
+    for &i_tri in &orientation.tri_flipped {
+        tri2flipped[i_tri] = true;
+    }
+    for i_tri in 0..num_tri {
+        if group2flip[orientation.tri2group[i_tri]] {
+            tri2vtx.swap(i_tri * 3 + 1, i_tri * 3 + 2);
+            tri2flipped[i_tri] = !tri2flipped[i_tri];
+        }
+    }
+    orientation.tri_flipped = (0..num_tri).filter(|&i_tri| tri2flipped[i_tri]).collect();
+    orientation
+}
+
+#[test]
+fn test_orient() {
+    use rand::{Rng, SeedableRng};
+    let mut rng = rand_chacha::ChaChaRng::seed_from_u64(0);
+    // torus and sphere as separate components
+    let (tri2vtx0, vtx2xyz0) = crate::trimesh3_primitive::torus_zup::<usize, f64>(1.0, 0.3, 16, 8);
+    let (tri2vtx1, vtx2xyz1) = crate::trimesh3_primitive::sphere_yup::<usize, f64>(0.5, 8, 8);
+    let num_vtx0 = vtx2xyz0.len() / 3;
+    let tri2vtx_ref: Vec<usize> = tri2vtx0
+        .iter()
+        .copied()
+        .chain(tri2vtx1.iter().map(|&i_vtx| i_vtx + num_vtx0))
+        .collect();
+    let vtx2xyz = [vtx2xyz0, vtx2xyz1].concat();
+    let num_tri = tri2vtx_ref.len() / 3;
+    let mut tri2vtx = tri2vtx_ref.clone();
+    for i_tri in 0..num_tri {
+        // flip randomly, and the sphere entirely
+        if rng.gen::<bool>() != (i_tri >= tri2vtx0.len() / 3) {
+            tri2vtx.swap(i_tri * 3 + 1, i_tri * 3 + 2);
+        }
+    }
+    let orientation = orient_outward(&mut tri2vtx, &vtx2xyz);
+    assert_eq!(orientation.num_group, 2);
+    assert!(orientation.non_orientable_groups().is_empty());
+    assert_eq!(orientation.group2closed, vec![true, true]);
+    assert_eq!(tri2vtx, tri2vtx_ref);
+    let report = crate::mesh_check::check_triangle_mesh(&tri2vtx, &vtx2xyz, 3, &Default::default());
+    assert!(report.elem_pair_inconsistent.is_empty());
+    let volume = group2volume(&tri2vtx, &vtx2xyz, 2, &orientation.tri2group);
+    assert!(volume[0] > 0. && volume[1] > 0.);
+    // Möbius strip
+    let num_div = 16;
+    let vtx2xyz: Vec<f64> = (0..num_div)
+        .flat_map(|i| {
+            let t = i as f64 / num_div as f64 * std::f64::consts::PI * 2.;
+            let (c, s) = ((t * 0.5).cos() * 0.2, (t * 0.5).sin() * 0.2);
+            [
+                (1. + c) * t.cos(),
+                (1. + c) * t.sin(),
+                s,
+                (1. - c) * t.cos(),
+                (1. - c) * t.sin(),
+                -s,
+            ]
+        })
+        .collect();
+    let mut tri2vtx = vec![];
+    for i in 0..num_div {
+        let (a0, b0) = (i * 2, i * 2 + 1);
+        let (a1, b1) = match i + 1 == num_div {
+            true => (1, 0), // half twist
+            false => (i * 2 + 2, i * 2 + 3),
+        };
+        tri2vtx.extend([a0, a1, b0, b0, a1, b1]);
+    }
+    let orientation = orient_outward(&mut tri2vtx, &vtx2xyz);
+    assert_eq!(orientation.non_orientable_groups(), vec![0]);
+    assert_eq!(orientation.group2closed, vec![false]);
+}
@@ -513,3 +513,91 @@ pub fn boundaryedge2vtx(tri2vtx: &[usize], num_vtx: usize) -> (Vec<usize>, Vec<u
     }
     (bedge2vtx, tri2tri)
 }
+
+/// Chain the boundary edges into the loops. The loops go along the boundary edges in the same
+/// direction as the triangles, so the mesh is on the left side of the loop seen from the front.
+/// At a vertex with several boundary edges, the loop goes to the next boundary edge of the same
+/// fan of triangles around the vertex. A vertex shared by several loops (e.g., the vertex of a
+/// bow-tie) appears in each of them
+///
+/// # Return
+/// (loop2idx, idx2vtx)
+pub fn boundary_loops(
+    tri2vtx: &[usize],
+    num_vtx: usize,
+) -> anyhow::Result<(Vec<usize>, Vec<usize>)> {
+    let num_tri = tri2vtx.len() / 3;
+    let (bedge2vtx, tri2tri) = boundaryedge2vtx(tri2vtx, num_vtx);
+    let num_bedge = bedge2vtx.len() / 2;
+    let mut bedge2tri = vec![0; num_bedge];
+    for (i_tri, node2tri) in tri2tri.chunks(3).enumerate() {
+        for &j_tri in node2tri {
+            if j_tri >= num_tri {
+                bedge2tri[j_tri - num_tri] = i_tri;
+            }
+        }
+    }
+    //
+    let mut bedge2flag = vec![false; num_bedge];
+    let mut loop2idx = vec![0];
+    let mut idx2vtx = vec![];
+    for i_bedge0 in 0..num_bedge {
+        if bedge2flag[i_bedge0] {
+            continue;
+        }
+        let mut i_bedge = i_bedge0;
+        loop {
+            bedge2flag[i_bedge] = true;
+            idx2vtx.push(bedge2vtx[i_bedge * 2]);
+            let i_vtx = bedge2vtx[i_bedge * 2 + 1];
+            // go around the vertex through the triangles until the next boundary edge
+            let mut i_tri = bedge2tri[i_bedge];
+            let mut j_bedge = usize::MAX;
+            for _ in 0..num_tri {
+                let i_node = find_node(i_vtx, tri2vtx, i_tri);
+                if i_node == usize::MAX {
+                    break;
+                }
+                let j_tri = tri2tri[i_tri * 3 + (i_node + 2) % 3];
+                if j_tri >= num_tri {
+                    j_bedge = j_tri - num_tri;
+                    break;
+                }
+                i_tri = j_tri;
+            }
+            if j_bedge == i_bedge0 {
+                break;
+            }
+            if j_bedge == usize::MAX || bedge2vtx[j_bedge * 2] != i_vtx || bedge2flag[j_bedge] {
+                return Err(anyhow::anyhow!(
+                    "the triangles around the vertex {} are not oriented consistently",
+                    i_vtx
+                ));
+            }
+            i_bedge = j_bedge;
+        }
+        loop2idx.push(idx2vtx.len());
+    }
+    Ok((loop2idx, idx2vtx))
+}
+
+#[test]
+fn test_boundary_loops() -> anyhow::Result<()> {
+    // bow-tie of two fans sharing the vertex 0. The edges out of the vertex 0 are listed so that
+    // taking any unvisited one would chain the two fans into one loop
+    let tri2vtx = [0, 1, 2, 0, 2, 3, 0, 4, 5, 0, 5, 6];
+    let (loop2idx, idx2vtx) = boundary_loops(&tri2vtx, 7)?;
+    assert_eq!(loop2idx, vec![0, 4, 8]);
+    for node2idx in loop2idx.windows(2) {
+        let idx2vtx = &idx2vtx[node2idx[0]..node2idx[1]];
+        assert_eq!(idx2vtx.iter().filter(|&&i_vtx| i_vtx == 0).count(), 1);
+        let mut vtxs = idx2vtx.to_vec();
+        vtxs.sort();
+        assert!(vtxs == [0, 1, 2, 3] || vtxs == [0, 4, 5, 6]);
+    }
+    // single triangle, and the inconsistent orientation
+    let (loop2idx, idx2vtx) = boundary_loops(&[0, 1, 2], 3)?;
+    assert_eq!((loop2idx, idx2vtx), (vec![0, 3], vec![1, 2, 0]));
+    assert!(boundary_loops(&[0, 1, 2, 0, 1, 3], 4).is_err());
+    Ok(())
+}
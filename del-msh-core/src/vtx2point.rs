@@ -19,3 +19,71 @@ where
     assert!(aabb[2] <= aabb[5]);
     aabb
 }
+
+/// point cloud with the optional per-point attributes, which are empty if absent
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointCloud<Real> {
+    pub vtx2xyz: Vec<Real>,
+    pub vtx2nrm: Vec<Real>,
+    /// colors in [0,255]
+    pub vtx2rgb: Vec<u8>,
+    pub vtx2intensity: Vec<Real>,
+}
+
+impl<Real> PointCloud<Real>
+where
+    Real: num_traits::Float,
+{
+    pub fn new(vtx2xyz: Vec<Real>) -> Self {
+        PointCloud {
+            vtx2xyz,
+            vtx2nrm: vec![],
+            vtx2rgb: vec![],
+            vtx2intensity: vec![],
+        }
+    }
+
+    pub fn num_vtx(&self) -> usize {
+        self.vtx2xyz.len() / 3
+    }
+
+    /// array of the points (e.g., for `aabb3_from_points`). The absent attributes are zero
+    pub fn to_points(&self) -> Vec<Point<Real>> {
+        let attr3 = |vtx2val: &[Real], i_vtx: usize| -> [Real; 3] {
+            match vtx2val.is_empty() {
+                true => [Real::zero(); 3],
+                false => *arrayref::array_ref![vtx2val, i_vtx * 3, 3],
+            }
+        };
+        (0..self.num_vtx())
+            .map(|i_vtx| Point {
+                xyz: attr3(&self.vtx2xyz, i_vtx),
+                nrm: attr3(&self.vtx2nrm, i_vtx),
+                rgb: match self.vtx2rgb.is_empty() {
+                    true => [0; 3],
+                    false => *arrayref::array_ref![self.vtx2rgb, i_vtx * 3, 3],
+                },
+                intensity: self
+                    .vtx2intensity
+                    .get(i_vtx)
+                    .copied()
+                    .unwrap_or(Real::zero()),
+            })
+            .collect()
+    }
+}
+
+/// point with the attributes in `PointCloud`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point<Real> {
+    pub xyz: [Real; 3],
+    pub nrm: [Real; 3],
+    pub rgb: [u8; 3],
+    pub intensity: Real,
+}
+
+impl<Real> HasXyz<Real> for Point<Real> {
+    fn xyz(&self) -> &[Real; 3] {
+        &self.xyz
+    }
+}
@@ -98,6 +98,13 @@ def read_material(path: str):
     return dict_mtl
 
 
+def load_material(path: str):
+    """all the properties of the materials in the MTL file. Unlike `read_material`, the colors
+    are lists and the texture maps are included"""
+    from .del_msh import load_wavefront_mtl
+    return load_wavefront_mtl(path)
+
+
 def save(path: str, elem2vtx, vtx2xyz):
     from .del_msh import save_wavefront_obj_for_uniform_mesh
     if vtx2xyz.dtype != numpy.float32:
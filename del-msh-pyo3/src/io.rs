@@ -8,6 +8,7 @@ pub fn add_functions(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     use pyo3::prelude::PyModuleMethods;
     m.add_function(wrap_pyfunction!(load_wavefront_obj, m)?)?;
     m.add_function(wrap_pyfunction!(load_wavefront_obj_as_triangle_mesh, m)?)?;
+    m.add_function(wrap_pyfunction!(load_wavefront_mtl, m)?)?;
     m.add_function(wrap_pyfunction!(load_nastran_as_triangle_mesh, m)?)?;
     m.add_function(wrap_pyfunction!(load_off_as_triangle_mesh, m)?)?;
     m.add_function(wrap_pyfunction!(save_wavefront_obj_for_uniform_mesh, m)?)?;
@@ -78,36 +79,71 @@ pub fn load_wavefront_obj_as_triangle_mesh(
     )
 }
 
+/// materials in the MTL file as a dictionary from the material name to its properties
+#[pyfunction]
+pub fn load_wavefront_mtl(py: Python, path_file: String) -> PyResult<PyObject> {
+    let mtls = del_msh_core::io_mtl::load(&path_file)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("{:#}", e)))?;
+    let mut name2mtl = std::collections::HashMap::<String, _>::new();
+    for mtl in mtls {
+        let mut key2val = std::collections::HashMap::<&str, PyObject>::new();
+        key2val.insert("Ka", mtl.ka.to_object(py));
+        key2val.insert("Kd", mtl.kd.to_object(py));
+        key2val.insert("Ks", mtl.ks.to_object(py));
+        key2val.insert("Ke", mtl.ke.to_object(py));
+        key2val.insert("Ns", mtl.ns.to_object(py));
+        key2val.insert("Ni", mtl.ni.to_object(py));
+        key2val.insert("d", mtl.d.to_object(py));
+        key2val.insert("illum", mtl.illum.to_object(py));
+        for (key, map) in [
+            ("map_Ka", &mtl.map_ka),
+            ("map_Kd", &mtl.map_kd),
+            ("map_Ks", &mtl.map_ks),
+            ("map_Ns", &mtl.map_ns),
+            ("map_d", &mtl.map_d),
+            ("map_Bump", &mtl.map_bump),
+        ] {
+            if let Some(map) = map {
+                key2val.insert(key, map.path.to_object(py));
+            }
+        }
+        name2mtl.insert(mtl.name, key2val.to_object(py));
+    }
+    Ok(name2mtl.to_object(py))
+}
+
 #[pyfunction]
 pub fn load_nastran_as_triangle_mesh(
     py: Python,
     path_file: String,
-) -> (Bound<PyArray2<usize>>, Bound<PyArray2<f32>>) {
-    let (tri2vtx, vtx2xyz) = del_msh_core::io_nas::load_tri_mesh(path_file);
-    (
+) -> PyResult<(Bound<PyArray2<usize>>, Bound<PyArray2<f32>>)> {
+    let (tri2vtx, vtx2xyz) = del_msh_core::io_nas::load_tri_mesh::<_, usize, f32>(path_file)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("{:#}", e)))?;
+    Ok((
         numpy::ndarray::Array2::from_shape_vec((tri2vtx.len() / 3, 3), tri2vtx)
             .unwrap()
             .into_pyarray_bound(py),
         numpy::ndarray::Array2::from_shape_vec((vtx2xyz.len() / 3, 3), vtx2xyz)
             .unwrap()
             .into_pyarray_bound(py),
-    )
+    ))
 }
 
 #[pyfunction]
 pub fn load_off_as_triangle_mesh(
     py: Python,
     path_file: String,
-) -> (Bound<PyArray2<usize>>, Bound<PyArray2<f32>>) {
-    let (tri2vtx, vtx2xyz) = del_msh_core::io_off::load_as_tri_mesh(path_file);
-    (
+) -> PyResult<(Bound<PyArray2<usize>>, Bound<PyArray2<f32>>)> {
+    let (tri2vtx, vtx2xyz) = del_msh_core::io_off::load_as_tri_mesh(path_file)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("{:#}", e)))?;
+    Ok((
         numpy::ndarray::Array2::from_shape_vec((tri2vtx.len() / 3, 3), tri2vtx)
             .unwrap()
             .into_pyarray_bound(py),
         numpy::ndarray::Array2::from_shape_vec((vtx2xyz.len() / 3, 3), vtx2xyz)
             .unwrap()
             .into_pyarray_bound(py),
-    )
+    ))
 }
 
 #[pyfunction]